@@ -0,0 +1,24 @@
+//! Generates the gRPC bindings from `proto/` when the `grpc` feature is enabled.
+
+fn main() {
+    #[cfg(feature = "grpc")]
+    compile_protos().expect("Failed to compile protobuf definitions");
+}
+
+#[cfg(feature = "grpc")]
+fn compile_protos() -> Result<(), Box<dyn std::error::Error>> {
+    // Use the bundled protoc and well-known types so no protobuf toolchain is needed
+    let mut config = tonic_prost_build::Config::new();
+    config.protoc_executable(protoc_bin_vendored::protoc_bin_path()?);
+    let includes = [
+        std::path::PathBuf::from("proto"),
+        protoc_bin_vendored::include_path()?,
+    ];
+
+    tonic_prost_build::configure().compile_with_config(
+        config,
+        &[std::path::PathBuf::from("proto/tasks.proto")],
+        &includes,
+    )?;
+    Ok(())
+}
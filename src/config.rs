@@ -1,11 +1,41 @@
 use serde::Deserialize;
 use std::env;
 
-#[derive(Debug, Clone, Deserialize)]
+/// How requests without credentials are treated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthMode {
+    /// Every protected request needs credentials; the server refuses to start without JWT keys
+    #[default]
+    Jwt,
+    /// Requests without credentials run as an unprivileged anonymous principal; meant for
+    /// local development only
+    Anonymous,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
 pub struct Config {
     pub host: String,
     pub port: u16,
-//    pub log_level: String,
+    /// Port of the gRPC server, when built with the `grpc` feature; no server without it
+    pub grpc_port: Option<u16>,
+    //    pub log_level: String,
+    pub auth_mode: AuthMode,
+    pub jwt_secret: Option<String>,
+    pub jwt_public_key_path: Option<String>,
+    pub jwt_jwks_path: Option<String>,
+    pub jwt_issuer: Option<String>,
+    pub jwt_audience: Option<String>,
+    pub tenant_max_projects: Option<usize>,
+    pub tenant_max_tasks: Option<usize>,
+    pub tenant_quotas: Option<String>,
+    pub webhook_max_attempts: Option<u32>,
+    pub webhook_retry_base_secs: Option<u64>,
+    pub webhook_retry_max_secs: Option<u64>,
+    pub webhook_timeout_secs: Option<u64>,
+    pub webhook_retention_days: Option<u32>,
+    pub trash_retention_days: Option<u32>,
+    pub reminder_lead_hours: Option<u32>,
 }
 
 impl Config {
@@ -18,11 +48,76 @@ impl Config {
                 .unwrap_or_else(|_| "8080".to_string())
                 .parse()
                 .expect("PORT must be a valid number"),
+            grpc_port: env::var("GRPC_PORT")
+                .ok()
+                .map(|value| value.parse().expect("GRPC_PORT must be a valid number")),
             //log_level: env::var("RUST_LOG").unwrap_or_else(|_| "info".to_string()),
+            auth_mode: match env::var("AUTH_MODE").as_deref() {
+                Err(_) | Ok("jwt") => AuthMode::Jwt,
+                Ok("anonymous") => AuthMode::Anonymous,
+                Ok(other) => panic!("AUTH_MODE must be 'jwt' or 'anonymous', got '{}'", other),
+            },
+            jwt_secret: env::var("JWT_SECRET").ok(),
+            jwt_public_key_path: env::var("JWT_PUBLIC_KEY_PATH").ok(),
+            jwt_jwks_path: env::var("JWT_JWKS_PATH").ok(),
+            jwt_issuer: env::var("JWT_ISSUER").ok(),
+            jwt_audience: env::var("JWT_AUDIENCE").ok(),
+            tenant_max_projects: env::var("TENANT_MAX_PROJECTS").ok().map(|value| {
+                value
+                    .parse()
+                    .expect("TENANT_MAX_PROJECTS must be a valid number")
+            }),
+            tenant_max_tasks: env::var("TENANT_MAX_TASKS").ok().map(|value| {
+                value
+                    .parse()
+                    .expect("TENANT_MAX_TASKS must be a valid number")
+            }),
+            tenant_quotas: env::var("TENANT_QUOTAS").ok(),
+            webhook_max_attempts: env::var("WEBHOOK_MAX_ATTEMPTS").ok().map(|value| {
+                value
+                    .parse()
+                    .expect("WEBHOOK_MAX_ATTEMPTS must be a valid number")
+            }),
+            webhook_retry_base_secs: env::var("WEBHOOK_RETRY_BASE_SECS").ok().map(|value| {
+                value
+                    .parse()
+                    .expect("WEBHOOK_RETRY_BASE_SECS must be a valid number")
+            }),
+            webhook_retry_max_secs: env::var("WEBHOOK_RETRY_MAX_SECS").ok().map(|value| {
+                value
+                    .parse()
+                    .expect("WEBHOOK_RETRY_MAX_SECS must be a valid number")
+            }),
+            webhook_timeout_secs: env::var("WEBHOOK_TIMEOUT_SECS").ok().map(|value| {
+                value
+                    .parse()
+                    .expect("WEBHOOK_TIMEOUT_SECS must be a valid number")
+            }),
+            webhook_retention_days: env::var("WEBHOOK_RETENTION_DAYS").ok().map(|value| {
+                value
+                    .parse()
+                    .expect("WEBHOOK_RETENTION_DAYS must be a valid number")
+            }),
+            trash_retention_days: env::var("TRASH_RETENTION_DAYS").ok().map(|value| {
+                value
+                    .parse()
+                    .expect("TRASH_RETENTION_DAYS must be a valid number")
+            }),
+            reminder_lead_hours: env::var("REMINDER_LEAD_HOURS").ok().map(|value| {
+                value
+                    .parse()
+                    .expect("REMINDER_LEAD_HOURS must be a valid number")
+            }),
         }
     }
 
     pub fn address(&self) -> String {
         format!("{}:{}", self.host, self.port)
     }
+
+    /// Where the gRPC server listens, if `GRPC_PORT` is set.
+    pub fn grpc_address(&self) -> Option<String> {
+        self.grpc_port
+            .map(|grpc_port| format!("{}:{}", self.host, grpc_port))
+    }
 }
@@ -0,0 +1,69 @@
+use crate::models::{ApiKeyCreate, Principal};
+use crate::services::ApiKeyService;
+use crate::views::{ApiError, ApiResponse};
+use ntex::web::types::{Json, Path, State};
+use ntex::web::HttpResponse;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post,
+    path = "/api/v1/admin/api-keys",
+    tag = "api-keys",
+    request_body = ApiKeyCreate,
+    responses(
+        (status = 201, description = "API key created, plaintext key is only shown once", body = ApiResponse<ApiKeyCreated>),
+        (status = 400, description = "Invalid request", body = ApiResponse<()>),
+        (status = 401, description = "Missing credentials", body = ApiResponse<()>),
+        (status = 403, description = "Admin scope required", body = ApiResponse<()>)
+    )
+))]
+pub async fn create_api_key(
+    service: State<Arc<ApiKeyService>>,
+    principal: Principal,
+    body: Json<ApiKeyCreate>,
+) -> Result<HttpResponse, ApiError> {
+    let created = service.create_key(&principal, body.into_inner()).await?;
+    Ok(HttpResponse::Created().json(&ApiResponse::success(created)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/admin/api-keys",
+    tag = "api-keys",
+    responses(
+        (status = 200, description = "List of API keys", body = ApiResponse<Vec<ApiKey>>),
+        (status = 401, description = "Missing credentials", body = ApiResponse<()>),
+        (status = 403, description = "Admin scope required", body = ApiResponse<()>)
+    )
+))]
+pub async fn list_api_keys(
+    service: State<Arc<ApiKeyService>>,
+    principal: Principal,
+) -> Result<HttpResponse, ApiError> {
+    let keys = service.list_keys(&principal).await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(keys)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    delete,
+    path = "/api/v1/admin/api-keys/{id}",
+    tag = "api-keys",
+    params(
+        ("id" = Uuid, Path, description = "API key ID")
+    ),
+    responses(
+        (status = 200, description = "API key revoked", body = ApiResponse<ApiKey>),
+        (status = 401, description = "Missing credentials", body = ApiResponse<()>),
+        (status = 403, description = "Admin scope required", body = ApiResponse<()>),
+        (status = 404, description = "API key not found", body = ApiResponse<()>)
+    )
+))]
+pub async fn revoke_api_key(
+    service: State<Arc<ApiKeyService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    let key = service.revoke_key(&principal, id.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(key)))
+}
@@ -0,0 +1,56 @@
+use crate::models::{AuditFilter, Principal};
+use crate::services::AuditService;
+use crate::views::{ApiError, ApiResponse};
+use ntex::web::types::{Query, State};
+use ntex::web::HttpResponse;
+use std::sync::Arc;
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/audit",
+    tag = "audit",
+    params(AuditFilter),
+    responses(
+        (status = 200, description = "Matching audit entries, oldest first", body = ApiResponse<Vec<AuditEntry>>),
+        (status = 400, description = "Invalid filter", body = ApiResponse<()>),
+        (status = 403, description = "Admin scope required", body = ApiResponse<()>)
+    )
+))]
+pub async fn list_audit_entries(
+    service: State<Arc<AuditService>>,
+    principal: Principal,
+    filter: Query<AuditFilter>,
+) -> Result<HttpResponse, ApiError> {
+    let entries = service.list_entries(&principal, &filter).await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(entries)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/audit/export",
+    tag = "audit",
+    params(AuditFilter),
+    responses(
+        (status = 200, description = "Matching audit entries as newline-delimited JSON", content_type = "application/x-ndjson", body = String),
+        (status = 400, description = "Invalid filter", body = ApiResponse<()>),
+        (status = 403, description = "Admin scope required", body = ApiResponse<()>)
+    )
+))]
+pub async fn export_audit_entries(
+    service: State<Arc<AuditService>>,
+    principal: Principal,
+    filter: Query<AuditFilter>,
+) -> Result<HttpResponse, ApiError> {
+    let entries = service.list_entries(&principal, &filter).await?;
+
+    let mut body = String::new();
+    for entry in &entries {
+        let line = serde_json::to_string(entry).map_err(|_| ApiError::InternalServerError)?;
+        body.push_str(&line);
+        body.push('\n');
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/x-ndjson")
+        .body(body))
+}
@@ -0,0 +1,16 @@
+use crate::models::Principal;
+use crate::views::{ApiError, ApiResponse};
+use ntex::web::HttpResponse;
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/auth/me",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Authenticated principal", body = ApiResponse<Principal>),
+        (status = 401, description = "Missing or invalid credentials", body = ApiResponse<()>)
+    )
+))]
+pub async fn current_principal(principal: Principal) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(principal)))
+}
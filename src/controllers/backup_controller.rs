@@ -0,0 +1,70 @@
+use crate::models::{BackupFormat, BackupQuery, Principal, RestoreQuery};
+use crate::services::access::require_admin;
+use crate::services::BackupService;
+use crate::views::{ApiError, ApiResponse};
+use ntex::web::types::{Payload, Query, State};
+use ntex::web::HttpResponse;
+use std::sync::Arc;
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/admin/backup",
+    tag = "backup",
+    params(BackupQuery),
+    responses(
+        (status = 200, description = "The tenant's projects, tasks, members, recurring tasks and time entries; NDJSON starts with a header line", content_type = "application/x-ndjson", body = String),
+        (status = 200, description = "The same archive as one JSON document, with `format=json`", content_type = "application/json", body = Backup),
+        (status = 403, description = "Admin scope required", body = ApiResponse<()>)
+    )
+))]
+pub async fn export_backup(
+    service: State<Arc<BackupService>>,
+    principal: Principal,
+    query: Query<BackupQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let backup = service.export(&principal).await?;
+
+    let (body, content_type, extension) = match query.format {
+        BackupFormat::Ndjson => (backup.to_ndjson(), "application/x-ndjson", "ndjson"),
+        BackupFormat::Json => (serde_json::to_string(&backup), "application/json", "json"),
+    };
+    let body = body.map_err(|_| ApiError::InternalServerError)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(content_type)
+        .header(
+            "Content-Disposition",
+            format!(
+                "attachment; filename=\"backup-{}.{}\"",
+                backup.created_at.format("%Y%m%dT%H%M%SZ"),
+                extension
+            ),
+        )
+        .body(body))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post,
+    path = "/api/v1/admin/restore",
+    tag = "backup",
+    params(RestoreQuery),
+    request_body(content = String, content_type = "application/x-ndjson", description = "An archive from the backup endpoint, in the format given by `format`"),
+    responses(
+        (status = 200, description = "Archive restored", body = ApiResponse<RestoreReport>),
+        (status = 400, description = "Archive could not be parsed or failed its integrity checks", body = ApiResponse<()>),
+        (status = 403, description = "Admin scope required", body = ApiResponse<()>),
+        (status = 409, description = "Restoring would exceed a tenant quota", body = ApiResponse<()>)
+    )
+))]
+pub async fn restore_backup(
+    service: State<Arc<BackupService>>,
+    principal: Principal,
+    query: Query<RestoreQuery>,
+    body: Payload,
+) -> Result<HttpResponse, ApiError> {
+    // Refused before the upload is read
+    require_admin(&principal)?;
+    let backup = BackupService::read_backup(body, query.format).await?;
+    let report = service.restore(&principal, backup, query.mode).await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(report)))
+}
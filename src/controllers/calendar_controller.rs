@@ -0,0 +1,117 @@
+use crate::models::{CalendarQuery, CalendarTokenCreate, Principal};
+use crate::services::CalendarService;
+use crate::views::{task_calendar, ApiError, ApiResponse};
+use chrono::Utc;
+use ntex::web::types::{Json, Path, Query, State};
+use ntex::web::HttpResponse;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post,
+    path = "/api/v1/calendar/tokens",
+    tag = "calendar",
+    request_body = CalendarTokenCreate,
+    responses(
+        (status = 201, description = "Token created; the plaintext token and feed URL are only returned here", body = ApiResponse<CalendarTokenCreated>),
+        (status = 400, description = "Invalid request", body = ApiResponse<()>)
+    )
+))]
+pub async fn create_calendar_token(
+    service: State<Arc<CalendarService>>,
+    principal: Principal,
+    body: Json<CalendarTokenCreate>,
+) -> Result<HttpResponse, ApiError> {
+    let created = service.create_token(&principal, body.into_inner()).await?;
+    Ok(HttpResponse::Created().json(&ApiResponse::success(created)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/calendar/tokens",
+    tag = "calendar",
+    responses(
+        (status = 200, description = "The caller's calendar tokens", body = ApiResponse<Vec<CalendarToken>>)
+    )
+))]
+pub async fn list_calendar_tokens(
+    service: State<Arc<CalendarService>>,
+    principal: Principal,
+) -> Result<HttpResponse, ApiError> {
+    let tokens = service.list_tokens(&principal).await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(tokens)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    delete,
+    path = "/api/v1/calendar/tokens/{id}",
+    tag = "calendar",
+    params(
+        ("id" = Uuid, Path, description = "Calendar token ID")
+    ),
+    responses(
+        (status = 204, description = "Token deleted; feeds using it stop working"),
+        (status = 404, description = "Calendar token not found", body = ApiResponse<()>)
+    )
+))]
+pub async fn delete_calendar_token(
+    service: State<Arc<CalendarService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    service.delete_token(&principal, id.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/calendar.ics",
+    tag = "calendar",
+    params(CalendarQuery),
+    responses(
+        (status = 200, description = "Tasks with due dates in the caller's active projects", content_type = "text/calendar", body = String),
+        (status = 401, description = "Invalid calendar token", body = ApiResponse<()>)
+    )
+))]
+pub async fn user_calendar(
+    service: State<Arc<CalendarService>>,
+    principal: Principal,
+    query: Query<CalendarQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let tasks = service.user_calendar(&principal).await?;
+    let calendar = task_calendar("Tasks", &tasks, query.component, Utc::now());
+    Ok(calendar_response(calendar))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/projects/{id}/calendar.ics",
+    tag = "calendar",
+    params(
+        ("id" = Uuid, Path, description = "Project ID"),
+        CalendarQuery
+    ),
+    responses(
+        (status = 200, description = "The project's tasks with due dates", content_type = "text/calendar", body = String),
+        (status = 401, description = "Invalid calendar token", body = ApiResponse<()>),
+        (status = 404, description = "Project not found", body = ApiResponse<()>)
+    )
+))]
+pub async fn project_calendar(
+    service: State<Arc<CalendarService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+    query: Query<CalendarQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let (project, tasks) = service
+        .project_calendar(&principal, id.into_inner())
+        .await?;
+    let calendar = task_calendar(&project.name, &tasks, query.component, Utc::now());
+    Ok(calendar_response(calendar))
+}
+
+fn calendar_response(calendar: String) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/calendar; charset=utf-8")
+        .body(calendar)
+}
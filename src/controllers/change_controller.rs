@@ -0,0 +1,37 @@
+use crate::models::Principal;
+use crate::services::ChangeService;
+use crate::views::{ApiError, ApiResponse};
+use ntex::web::types::{Query, State};
+use ntex::web::HttpResponse;
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+pub struct ChangeQuery {
+    /// Return changes after this sequence number; 0 or absent for a full sync
+    pub since: Option<u64>,
+    /// Maximum number of changes, 1 to 1000 (default 100)
+    pub limit: Option<usize>,
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/changes",
+    tag = "changes",
+    params(ChangeQuery),
+    responses(
+        (status = 200, description = "Upserts and deletes after `since`, in sequence order", body = ApiResponse<ChangeFeed>),
+        (status = 400, description = "Invalid limit", body = ApiResponse<()>)
+    )
+))]
+pub async fn list_changes(
+    service: State<Arc<ChangeService>>,
+    principal: Principal,
+    query: Query<ChangeQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let feed = service
+        .list_changes(&principal, query.since.unwrap_or_default(), query.limit)
+        .await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(feed)))
+}
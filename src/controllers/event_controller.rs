@@ -0,0 +1,110 @@
+use crate::models::{EventFilter, EventKind, Principal};
+use crate::services::{EventStream, StreamItem};
+use crate::views::ApiError;
+use futures::stream;
+use ntex::time::{sleep, Millis};
+use ntex::util::Bytes;
+use ntex::web::types::{Query, State};
+use ntex::web::{HttpRequest, HttpResponse};
+use serde::Deserialize;
+use std::convert::Infallible;
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub const LAST_EVENT_ID_HEADER: &str = "last-event-id";
+
+/// Idle time after which a comment is sent so proxies keep the connection open.
+const HEARTBEAT_INTERVAL: Millis = Millis(15_000);
+
+#[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+pub struct EventStreamQuery {
+    /// Only stream events of this project
+    pub project_id: Option<Uuid>,
+    /// Comma-separated event types, e.g. `task_created,task_updated`
+    pub event_types: Option<String>,
+    /// Resume after this event id, for clients that cannot send `Last-Event-ID`
+    pub last_event_id: Option<u64>,
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/events",
+    tag = "events",
+    params(
+        EventStreamQuery,
+        ("Last-Event-ID" = Option<u64>, Header, description = "Resume after this event id")
+    ),
+    responses(
+        (status = 200, description = "Server-Sent Events stream of project and task changes", content_type = "text/event-stream", body = String),
+        (status = 400, description = "Invalid filter", body = ApiResponse<()>),
+        (status = 403, description = "Not a member of the project", body = ApiResponse<()>)
+    )
+))]
+pub async fn stream_events(
+    service: State<Arc<EventStream>>,
+    principal: Principal,
+    query: Query<EventStreamQuery>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let query = query.into_inner();
+    let event_types = query
+        .event_types
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| name.parse::<EventKind>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| ApiError::validation_error(&e))?;
+
+    let last_event_id = match req.headers().get(LAST_EVENT_ID_HEADER) {
+        Some(value) => {
+            let value = value.to_str().unwrap_or_default().trim();
+            // An empty id is how a client forgets its position after a reset.
+            if value.is_empty() {
+                None
+            } else {
+                Some(
+                    value
+                        .parse()
+                        .map_err(|_| ApiError::bad_request("Invalid Last-Event-ID header"))?,
+                )
+            }
+        }
+        None => query.last_event_id,
+    };
+
+    let filter = EventFilter {
+        project_id: query.project_id,
+        event_types,
+    };
+    let subscription = service.subscribe(&principal, filter, last_event_id)?;
+
+    let frames = stream::unfold(subscription, |mut subscription| async move {
+        let frame = tokio::select! {
+            item = subscription.recv() => match item? {
+                StreamItem::Event(event) => {
+                    let data = serde_json::to_string(event.envelope.as_ref()).ok()?;
+                    format!(
+                        "id: {}\nevent: {}\ndata: {}\n\n",
+                        event.id,
+                        event.envelope.event.kind().as_str(),
+                        data
+                    )
+                }
+                // Clearing the id keeps a reconnecting client from asking for the gap again.
+                StreamItem::Reset => "id:\nevent: reset\ndata: {}\n\n".to_string(),
+            },
+            _ = sleep(HEARTBEAT_INTERVAL) => ": heartbeat\n\n".to_string(),
+        };
+        Some((Ok::<_, Infallible>(Bytes::from(frame)), subscription))
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .header("cache-control", "no-cache")
+        .header("x-accel-buffering", "no")
+        .streaming(Box::pin(frames)))
+}
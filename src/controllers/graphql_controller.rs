@@ -0,0 +1,25 @@
+use crate::graphql::{ApiSchema, ProjectTasksLoader};
+use crate::models::Principal;
+use crate::services::TaskService;
+use crate::views::ApiError;
+use async_graphql::dataloader::DataLoader;
+use ntex::web::types::{Json, State};
+use ntex::web::HttpResponse;
+use std::sync::Arc;
+
+/// Runs one GraphQL operation as the authenticated caller. Failures are reported in the
+/// response's `errors`, each with the REST error code in `extensions.code`.
+pub async fn graphql(
+    schema: State<ApiSchema>,
+    tasks: State<Arc<TaskService>>,
+    principal: Principal,
+    body: Json<async_graphql::Request>,
+) -> Result<HttpResponse, ApiError> {
+    let loader = DataLoader::new(
+        ProjectTasksLoader::new(tasks.get_ref().clone(), principal.clone()),
+        ntex::rt::spawn,
+    );
+    let request = body.into_inner().data(principal).data(loader);
+    let response = schema.execute(request).await;
+    Ok(HttpResponse::Ok().json(&response))
+}
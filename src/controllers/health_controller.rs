@@ -18,7 +18,8 @@ pub struct HealthResponse {
     tag = "health",
     responses(
         (status = 200, description = "Service is healthy", body = HealthResponse)
-    )
+    ),
+    security(())
 ))]
 pub async fn health_check() -> HttpResponse {
     let response = HealthResponse {
@@ -1,7 +1,37 @@
+pub mod api_key_controller;
+pub mod audit_controller;
+pub mod auth_controller;
+pub mod backup_controller;
+pub mod calendar_controller;
+pub mod change_controller;
+pub mod event_controller;
+#[cfg(feature = "graphql")]
+pub mod graphql_controller;
 pub mod health_controller;
 pub mod project_controller;
+pub mod recurring_task_controller;
+pub mod reminder_controller;
 pub mod task_controller;
+pub mod time_controller;
+pub mod trash_controller;
+pub mod webhook_controller;
+pub mod websocket_controller;
 
+pub use api_key_controller::*;
+pub use audit_controller::*;
+pub use auth_controller::*;
+pub use backup_controller::*;
+pub use calendar_controller::*;
+pub use change_controller::*;
+pub use event_controller::*;
+#[cfg(feature = "graphql")]
+pub use graphql_controller::*;
 pub use health_controller::*;
 pub use project_controller::*;
+pub use recurring_task_controller::*;
+pub use reminder_controller::*;
 pub use task_controller::*;
+pub use time_controller::*;
+pub use trash_controller::*;
+pub use webhook_controller::*;
+pub use websocket_controller::*;
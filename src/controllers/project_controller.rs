@@ -1,9 +1,21 @@
-use crate::views::ApiError;
+use crate::models::{
+    ColumnDefinition, Principal, ProjectCreate, ProjectMemberUpdate, ProjectStatsQuery,
+    ProjectUpdate, RevisionDiffQuery,
+};
+use crate::services::ProjectService;
+use crate::views::{ApiError, ApiResponse, Negotiated};
+use ntex::web::types::{Json, Path, Query, State};
 use ntex::web::HttpResponse;
-use serde_json;
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
 
-#[cfg(feature = "openapi")]
-use utoipa;
+#[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+pub struct ProjectListQuery {
+    /// Also return archived projects
+    pub include_archived: Option<bool>,
+}
 
 #[cfg_attr(feature = "openapi", utoipa::path(
     post,
@@ -15,10 +27,15 @@ use utoipa;
         (status = 400, description = "Invalid request", body = ApiResponse<()>)
     )
 ))]
-pub async fn create_project() -> Result<HttpResponse, ApiError> {
-    // For now, return a placeholder response
-    let response = serde_json::json!({"message": "create_project endpoint"});
-    Ok(HttpResponse::Created().json(&response))
+pub async fn create_project(
+    service: State<Arc<ProjectService>>,
+    principal: Principal,
+    body: Negotiated<ProjectCreate>,
+) -> Result<HttpResponse, ApiError> {
+    let project = service
+        .create_project(&principal, body.into_inner())
+        .await?;
+    Ok(HttpResponse::Created().json(&ApiResponse::success(project)))
 }
 
 #[cfg_attr(feature = "openapi", utoipa::path(
@@ -33,22 +50,33 @@ pub async fn create_project() -> Result<HttpResponse, ApiError> {
         (status = 404, description = "Project not found", body = ApiResponse<()>)
     )
 ))]
-pub async fn get_project() -> Result<HttpResponse, ApiError> {
-    let response = serde_json::json!({"message": "get_project endpoint"});
-    Ok(HttpResponse::Ok().json(&response))
+pub async fn get_project(
+    service: State<Arc<ProjectService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    let project = service.get_project(&principal, id.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(project)))
 }
 
 #[cfg_attr(feature = "openapi", utoipa::path(
     get,
     path = "/api/v1/projects",
     tag = "projects",
+    params(ProjectListQuery),
     responses(
         (status = 200, description = "List of projects", body = ApiResponse<Vec<Project>>)
     )
 ))]
-pub async fn list_projects() -> Result<HttpResponse, ApiError> {
-    let response = serde_json::json!({"message": "list_projects endpoint"});
-    Ok(HttpResponse::Ok().json(&response))
+pub async fn list_projects(
+    service: State<Arc<ProjectService>>,
+    principal: Principal,
+    query: Query<ProjectListQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let projects = service
+        .list_projects(&principal, query.include_archived.unwrap_or_default())
+        .await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(projects)))
 }
 
 #[cfg_attr(feature = "openapi", utoipa::path(
@@ -65,9 +93,16 @@ pub async fn list_projects() -> Result<HttpResponse, ApiError> {
         (status = 400, description = "Invalid request", body = ApiResponse<()>)
     )
 ))]
-pub async fn update_project() -> Result<HttpResponse, ApiError> {
-    let response = serde_json::json!({"message": "update_project endpoint"});
-    Ok(HttpResponse::Ok().json(&response))
+pub async fn update_project(
+    service: State<Arc<ProjectService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+    body: Json<ProjectUpdate>,
+) -> Result<HttpResponse, ApiError> {
+    let project = service
+        .update_project(&principal, id.into_inner(), body.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(project)))
 }
 
 #[cfg_attr(feature = "openapi", utoipa::path(
@@ -79,9 +114,306 @@ pub async fn update_project() -> Result<HttpResponse, ApiError> {
     ),
     responses(
         (status = 204, description = "Project deleted successfully"),
+        (status = 403, description = "Only owners can delete a project", body = ApiResponse<()>),
+        (status = 404, description = "Project not found", body = ApiResponse<()>)
+    )
+))]
+pub async fn delete_project(
+    service: State<Arc<ProjectService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    service.delete_project(&principal, id.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/projects/{id}/stats",
+    tag = "projects",
+    params(
+        ("id" = Uuid, Path, description = "Project ID"),
+        ProjectStatsQuery
+    ),
+    responses(
+        (status = 200, description = "Task counts and a daily burndown", body = ApiResponse<ProjectStats>),
+        (status = 400, description = "Invalid range", body = ApiResponse<()>),
+        (status = 404, description = "Project not found", body = ApiResponse<()>)
+    )
+))]
+pub async fn project_stats(
+    service: State<Arc<ProjectService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+    query: Query<ProjectStatsQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let stats = service
+        .project_stats(&principal, id.into_inner(), &query)
+        .await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(stats)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/projects/{id}/columns",
+    tag = "projects",
+    params(
+        ("id" = Uuid, Path, description = "Project ID")
+    ),
+    responses(
+        (status = 200, description = "Board columns in display order", body = ApiResponse<Vec<Column>>),
+        (status = 404, description = "Project not found", body = ApiResponse<()>)
+    )
+))]
+pub async fn list_project_columns(
+    service: State<Arc<ProjectService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    let columns = service.list_columns(&principal, id.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(columns)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    put,
+    path = "/api/v1/projects/{id}/columns",
+    tag = "projects",
+    params(
+        ("id" = Uuid, Path, description = "Project ID")
+    ),
+    request_body = Vec<ColumnDefinition>,
+    responses(
+        (status = 200, description = "Board replaced", body = ApiResponse<Vec<Column>>),
+        (status = 400, description = "Invalid board definition", body = ApiResponse<()>),
+        (status = 404, description = "Project not found", body = ApiResponse<()>),
+        (status = 409, description = "A removed column still has tasks, or the project is archived", body = ApiResponse<()>)
+    )
+))]
+pub async fn set_project_columns(
+    service: State<Arc<ProjectService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+    definitions: Json<Vec<ColumnDefinition>>,
+) -> Result<HttpResponse, ApiError> {
+    let columns = service
+        .set_columns(&principal, id.into_inner(), definitions.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(columns)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post,
+    path = "/api/v1/projects/{id}/archive",
+    tag = "projects",
+    params(
+        ("id" = Uuid, Path, description = "Project ID")
+    ),
+    responses(
+        (status = 200, description = "Project archived; it and its tasks are now read-only", body = ApiResponse<Project>),
+        (status = 403, description = "Only owners can archive a project", body = ApiResponse<()>),
+        (status = 404, description = "Project not found", body = ApiResponse<()>)
+    )
+))]
+pub async fn archive_project(
+    service: State<Arc<ProjectService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    let project = service.archive_project(&principal, id.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(project)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post,
+    path = "/api/v1/projects/{id}/unarchive",
+    tag = "projects",
+    params(
+        ("id" = Uuid, Path, description = "Project ID")
+    ),
+    responses(
+        (status = 200, description = "Project unarchived", body = ApiResponse<Project>),
+        (status = 403, description = "Only owners can unarchive a project", body = ApiResponse<()>),
+        (status = 404, description = "Project not found", body = ApiResponse<()>)
+    )
+))]
+pub async fn unarchive_project(
+    service: State<Arc<ProjectService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    let project = service
+        .unarchive_project(&principal, id.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(project)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/projects/{id}/members",
+    tag = "projects",
+    params(
+        ("id" = Uuid, Path, description = "Project ID")
+    ),
+    responses(
+        (status = 200, description = "Project members", body = ApiResponse<Vec<ProjectMember>>),
+        (status = 403, description = "Not a member of the project", body = ApiResponse<()>),
         (status = 404, description = "Project not found", body = ApiResponse<()>)
     )
 ))]
-pub async fn delete_project() -> Result<HttpResponse, ApiError> {
+pub async fn list_project_members(
+    service: State<Arc<ProjectService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    let members = service.list_members(&principal, id.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(members)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    put,
+    path = "/api/v1/projects/{id}/members/{subject}",
+    tag = "projects",
+    params(
+        ("id" = Uuid, Path, description = "Project ID"),
+        ("subject" = String, Path, description = "Principal subject")
+    ),
+    request_body = ProjectMemberUpdate,
+    responses(
+        (status = 200, description = "Member role set", body = ApiResponse<ProjectMember>),
+        (status = 403, description = "Only owners can manage members", body = ApiResponse<()>),
+        (status = 404, description = "Project not found", body = ApiResponse<()>),
+        (status = 409, description = "Project would be left without an owner", body = ApiResponse<()>)
+    )
+))]
+pub async fn set_project_member(
+    service: State<Arc<ProjectService>>,
+    principal: Principal,
+    path: Path<(Uuid, String)>,
+    body: Json<ProjectMemberUpdate>,
+) -> Result<HttpResponse, ApiError> {
+    let (id, subject) = path.into_inner();
+    let member = service
+        .set_member(&principal, id, subject, body.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(member)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    delete,
+    path = "/api/v1/projects/{id}/members/{subject}",
+    tag = "projects",
+    params(
+        ("id" = Uuid, Path, description = "Project ID"),
+        ("subject" = String, Path, description = "Principal subject")
+    ),
+    responses(
+        (status = 204, description = "Member removed"),
+        (status = 403, description = "Only owners can manage members", body = ApiResponse<()>),
+        (status = 404, description = "Project or member not found", body = ApiResponse<()>),
+        (status = 409, description = "Project would be left without an owner", body = ApiResponse<()>)
+    )
+))]
+pub async fn remove_project_member(
+    service: State<Arc<ProjectService>>,
+    principal: Principal,
+    path: Path<(Uuid, String)>,
+) -> Result<HttpResponse, ApiError> {
+    let (id, subject) = path.into_inner();
+    service.remove_member(&principal, id, &subject).await?;
     Ok(HttpResponse::NoContent().finish())
 }
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/projects/{id}/revisions",
+    tag = "projects",
+    params(
+        ("id" = Uuid, Path, description = "Project ID")
+    ),
+    responses(
+        (status = 200, description = "Every version of the project, oldest first", body = ApiResponse<Vec<Revision>>),
+        (status = 404, description = "Project not found", body = ApiResponse<()>)
+    )
+))]
+pub async fn list_project_revisions(
+    service: State<Arc<ProjectService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    let revisions = service
+        .list_project_revisions(&principal, id.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(revisions)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/projects/{id}/revisions/{number}",
+    tag = "projects",
+    params(
+        ("id" = Uuid, Path, description = "Project ID"),
+        ("number" = u32, Path, description = "Revision number, starting at 1")
+    ),
+    responses(
+        (status = 200, description = "The project as of this revision", body = ApiResponse<Revision>),
+        (status = 404, description = "Project or revision not found", body = ApiResponse<()>)
+    )
+))]
+pub async fn get_project_revision(
+    service: State<Arc<ProjectService>>,
+    principal: Principal,
+    path: Path<(Uuid, u32)>,
+) -> Result<HttpResponse, ApiError> {
+    let (id, number) = path.into_inner();
+    let revision = service.get_project_revision(&principal, id, number).await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(revision)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/projects/{id}/revisions/diff",
+    tag = "projects",
+    params(
+        ("id" = Uuid, Path, description = "Project ID"),
+        RevisionDiffQuery
+    ),
+    responses(
+        (status = 200, description = "Fields that changed between the two revisions", body = ApiResponse<RevisionDiff>),
+        (status = 404, description = "Project or revision not found", body = ApiResponse<()>)
+    )
+))]
+pub async fn diff_project_revisions(
+    service: State<Arc<ProjectService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+    query: Query<RevisionDiffQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let diff = service
+        .diff_project_revisions(&principal, id.into_inner(), query.from, query.to)
+        .await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(diff)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post,
+    path = "/api/v1/projects/{id}/revisions/{number}/revert",
+    tag = "projects",
+    params(
+        ("id" = Uuid, Path, description = "Project ID"),
+        ("number" = u32, Path, description = "Revision to go back to")
+    ),
+    responses(
+        (status = 200, description = "Project reverted; the result is stored as a new revision", body = ApiResponse<Project>),
+        (status = 403, description = "Requires editor role on the project", body = ApiResponse<()>),
+        (status = 404, description = "Project or revision not found", body = ApiResponse<()>)
+    )
+))]
+pub async fn revert_project(
+    service: State<Arc<ProjectService>>,
+    principal: Principal,
+    path: Path<(Uuid, u32)>,
+) -> Result<HttpResponse, ApiError> {
+    let (id, number) = path.into_inner();
+    let project = service.revert_project(&principal, id, number).await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(project)))
+}
@@ -0,0 +1,92 @@
+use crate::models::{Principal, RecurringTaskCreate};
+use crate::services::RecurringTaskService;
+use crate::views::{ApiError, ApiResponse};
+use ntex::web::types::{Json, Path, State};
+use ntex::web::HttpResponse;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post,
+    path = "/api/v1/recurring-tasks",
+    tag = "recurring-tasks",
+    request_body = RecurringTaskCreate,
+    responses(
+        (status = 201, description = "Recurring task created", body = ApiResponse<RecurringTask>),
+        (status = 400, description = "Invalid template or RRULE", body = ApiResponse<()>),
+        (status = 404, description = "Project not found", body = ApiResponse<()>),
+        (status = 409, description = "The project is archived", body = ApiResponse<()>)
+    )
+))]
+pub async fn create_recurring_task(
+    service: State<Arc<RecurringTaskService>>,
+    principal: Principal,
+    create_data: Json<RecurringTaskCreate>,
+) -> Result<HttpResponse, ApiError> {
+    let recurring_task = service
+        .create_recurring_task(&principal, create_data.into_inner())
+        .await?;
+    Ok(HttpResponse::Created().json(&ApiResponse::success(recurring_task)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/recurring-tasks",
+    tag = "recurring-tasks",
+    responses(
+        (status = 200, description = "Recurring tasks in the caller's projects", body = ApiResponse<Vec<RecurringTask>>)
+    )
+))]
+pub async fn list_recurring_tasks(
+    service: State<Arc<RecurringTaskService>>,
+    principal: Principal,
+) -> Result<HttpResponse, ApiError> {
+    let recurring_tasks = service.list_recurring_tasks(&principal).await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(recurring_tasks)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/recurring-tasks/{id}",
+    tag = "recurring-tasks",
+    params(
+        ("id" = Uuid, Path, description = "Recurring task ID")
+    ),
+    responses(
+        (status = 200, description = "Recurring task found", body = ApiResponse<RecurringTask>),
+        (status = 404, description = "Recurring task not found", body = ApiResponse<()>)
+    )
+))]
+pub async fn get_recurring_task(
+    service: State<Arc<RecurringTaskService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    let recurring_task = service
+        .get_recurring_task(&principal, id.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(recurring_task)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    delete,
+    path = "/api/v1/recurring-tasks/{id}",
+    tag = "recurring-tasks",
+    params(
+        ("id" = Uuid, Path, description = "Recurring task ID")
+    ),
+    responses(
+        (status = 204, description = "Recurring task deleted; tasks already created are kept"),
+        (status = 404, description = "Recurring task not found", body = ApiResponse<()>)
+    )
+))]
+pub async fn delete_recurring_task(
+    service: State<Arc<RecurringTaskService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    service
+        .delete_recurring_task(&principal, id.into_inner())
+        .await?;
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,44 @@
+use crate::controllers::TaskListQuery;
+use crate::models::Principal;
+use crate::services::ReminderService;
+use crate::views::{ApiError, ApiResponse};
+use ntex::web::types::{Query, State};
+use ntex::web::HttpResponse;
+use std::sync::Arc;
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/tasks/overdue",
+    tag = "tasks",
+    params(TaskListQuery),
+    responses(
+        (status = 200, description = "Open tasks past their due date, most overdue first", body = ApiResponse<Vec<Task>>),
+        (status = 404, description = "Project not found", body = ApiResponse<()>)
+    )
+))]
+pub async fn list_overdue_tasks(
+    service: State<Arc<ReminderService>>,
+    principal: Principal,
+    query: Query<TaskListQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let tasks = service
+        .list_overdue_tasks(&principal, query.project_id)
+        .await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(tasks)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/tasks/overdue/counts",
+    tag = "tasks",
+    responses(
+        (status = 200, description = "Overdue tasks per project", body = ApiResponse<Vec<OverdueCount>>)
+    )
+))]
+pub async fn overdue_counts(
+    service: State<Arc<ReminderService>>,
+    principal: Principal,
+) -> Result<HttpResponse, ApiError> {
+    let counts = service.overdue_counts(&principal).await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(counts)))
+}
@@ -1,9 +1,21 @@
-use crate::views::ApiError;
+use crate::models::{
+    task_csv_row, Principal, RevisionDiffQuery, TaskCreate, TaskImportQuery, TaskMove, TaskUpdate,
+    TASK_CSV_COLUMNS,
+};
+use crate::services::TaskService;
+use crate::views::{csv_record, ApiError, ApiResponse, Negotiated};
+use ntex::web::types::{Json, Path, Payload, Query, State};
 use ntex::web::HttpResponse;
-use serde_json;
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
 
-#[cfg(feature = "openapi")]
-use utoipa;
+#[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+pub struct TaskListQuery {
+    /// Only return tasks belonging to this project
+    pub project_id: Option<Uuid>,
+}
 
 #[cfg_attr(feature = "openapi", utoipa::path(
     post,
@@ -15,9 +27,13 @@ use utoipa;
         (status = 400, description = "Invalid request", body = ApiResponse<()>)
     )
 ))]
-pub async fn create_task() -> Result<HttpResponse, ApiError> {
-    let response = serde_json::json!({"message": "create_task endpoint"});
-    Ok(HttpResponse::Created().json(&response))
+pub async fn create_task(
+    service: State<Arc<TaskService>>,
+    principal: Principal,
+    body: Negotiated<TaskCreate>,
+) -> Result<HttpResponse, ApiError> {
+    let task = service.create_task(&principal, body.into_inner()).await?;
+    Ok(HttpResponse::Created().json(&ApiResponse::success(task)))
 }
 
 #[cfg_attr(feature = "openapi", utoipa::path(
@@ -32,22 +48,39 @@ pub async fn create_task() -> Result<HttpResponse, ApiError> {
         (status = 404, description = "Task not found", body = ApiResponse<()>)
     )
 ))]
-pub async fn get_task() -> Result<HttpResponse, ApiError> {
-    let response = serde_json::json!({"message": "get_task endpoint"});
-    Ok(HttpResponse::Ok().json(&response))
+pub async fn get_task(
+    service: State<Arc<TaskService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    let task = service.get_task(&principal, id.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(task)))
 }
 
 #[cfg_attr(feature = "openapi", utoipa::path(
     get,
     path = "/api/v1/tasks",
     tag = "tasks",
+    params(TaskListQuery),
     responses(
-        (status = 200, description = "List of tasks", body = ApiResponse<Vec<Task>>)
+        (status = 200, description = "List of tasks", body = ApiResponse<Vec<Task>>),
+        (status = 404, description = "Project not found", body = ApiResponse<()>)
     )
 ))]
-pub async fn list_tasks() -> Result<HttpResponse, ApiError> {
-    let response = serde_json::json!({"message": "list_tasks endpoint"});
-    Ok(HttpResponse::Ok().json(&response))
+pub async fn list_tasks(
+    service: State<Arc<TaskService>>,
+    principal: Principal,
+    query: Query<TaskListQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let tasks = match query.project_id {
+        Some(project_id) => {
+            service
+                .list_tasks_by_project(&principal, project_id)
+                .await?
+        }
+        None => service.list_tasks(&principal).await?,
+    };
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(tasks)))
 }
 
 #[cfg_attr(feature = "openapi", utoipa::path(
@@ -60,13 +93,21 @@ pub async fn list_tasks() -> Result<HttpResponse, ApiError> {
     request_body = TaskUpdate,
     responses(
         (status = 200, description = "Task updated successfully", body = ApiResponse<Task>),
+        (status = 403, description = "Viewers cannot modify tasks", body = ApiResponse<()>),
         (status = 404, description = "Task not found", body = ApiResponse<()>),
         (status = 400, description = "Invalid request", body = ApiResponse<()>)
     )
 ))]
-pub async fn update_task() -> Result<HttpResponse, ApiError> {
-    let response = serde_json::json!({"message": "update_task endpoint"});
-    Ok(HttpResponse::Ok().json(&response))
+pub async fn update_task(
+    service: State<Arc<TaskService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+    body: Json<TaskUpdate>,
+) -> Result<HttpResponse, ApiError> {
+    let task = service
+        .update_task(&principal, id.into_inner(), body.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(task)))
 }
 
 #[cfg_attr(feature = "openapi", utoipa::path(
@@ -81,6 +122,194 @@ pub async fn update_task() -> Result<HttpResponse, ApiError> {
         (status = 404, description = "Task not found", body = ApiResponse<()>)
     )
 ))]
-pub async fn delete_task() -> Result<HttpResponse, ApiError> {
+pub async fn delete_task(
+    service: State<Arc<TaskService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    service.delete_task(&principal, id.into_inner()).await?;
     Ok(HttpResponse::NoContent().finish())
 }
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post,
+    path = "/api/v1/tasks/{id}/move",
+    tag = "tasks",
+    params(
+        ("id" = Uuid, Path, description = "Task ID")
+    ),
+    request_body = TaskMove,
+    responses(
+        (status = 200, description = "Task moved; its new rank is returned", body = ApiResponse<Task>),
+        (status = 400, description = "Neighbours missing, not adjacent or in another project", body = ApiResponse<()>),
+        (status = 404, description = "Task not found", body = ApiResponse<()>)
+    )
+))]
+pub async fn move_task(
+    service: State<Arc<TaskService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+    body: Json<TaskMove>,
+) -> Result<HttpResponse, ApiError> {
+    let task = service
+        .move_task(&principal, id.into_inner(), body.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(task)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/tasks/{id}/revisions",
+    tag = "tasks",
+    params(
+        ("id" = Uuid, Path, description = "Task ID")
+    ),
+    responses(
+        (status = 200, description = "Every version of the task, oldest first", body = ApiResponse<Vec<Revision>>),
+        (status = 404, description = "Task not found", body = ApiResponse<()>)
+    )
+))]
+pub async fn list_task_revisions(
+    service: State<Arc<TaskService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    let revisions = service
+        .list_task_revisions(&principal, id.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(revisions)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/tasks/{id}/revisions/{number}",
+    tag = "tasks",
+    params(
+        ("id" = Uuid, Path, description = "Task ID"),
+        ("number" = u32, Path, description = "Revision number, starting at 1")
+    ),
+    responses(
+        (status = 200, description = "The task as of this revision", body = ApiResponse<Revision>),
+        (status = 404, description = "Task or revision not found", body = ApiResponse<()>)
+    )
+))]
+pub async fn get_task_revision(
+    service: State<Arc<TaskService>>,
+    principal: Principal,
+    path: Path<(Uuid, u32)>,
+) -> Result<HttpResponse, ApiError> {
+    let (id, number) = path.into_inner();
+    let revision = service.get_task_revision(&principal, id, number).await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(revision)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/tasks/{id}/revisions/diff",
+    tag = "tasks",
+    params(
+        ("id" = Uuid, Path, description = "Task ID"),
+        RevisionDiffQuery
+    ),
+    responses(
+        (status = 200, description = "Fields that changed between the two revisions", body = ApiResponse<RevisionDiff>),
+        (status = 404, description = "Task or revision not found", body = ApiResponse<()>)
+    )
+))]
+pub async fn diff_task_revisions(
+    service: State<Arc<TaskService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+    query: Query<RevisionDiffQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let diff = service
+        .diff_task_revisions(&principal, id.into_inner(), query.from, query.to)
+        .await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(diff)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post,
+    path = "/api/v1/tasks/{id}/revisions/{number}/revert",
+    tag = "tasks",
+    params(
+        ("id" = Uuid, Path, description = "Task ID"),
+        ("number" = u32, Path, description = "Revision to go back to")
+    ),
+    responses(
+        (status = 200, description = "Task reverted; the result is stored as a new revision", body = ApiResponse<Task>),
+        (status = 403, description = "Requires editor role on the project", body = ApiResponse<()>),
+        (status = 404, description = "Task or revision not found", body = ApiResponse<()>)
+    )
+))]
+pub async fn revert_task(
+    service: State<Arc<TaskService>>,
+    principal: Principal,
+    path: Path<(Uuid, u32)>,
+) -> Result<HttpResponse, ApiError> {
+    let (id, number) = path.into_inner();
+    let task = service.revert_task(&principal, id, number).await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(task)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/projects/{id}/tasks.csv",
+    tag = "tasks",
+    params(
+        ("id" = Uuid, Path, description = "Project ID")
+    ),
+    responses(
+        (status = 200, description = "The project's tasks in order as CSV with id, title, description, done, column, due_at, created_at and updated_at columns", content_type = "text/csv", body = String),
+        (status = 404, description = "Project not found", body = ApiResponse<()>)
+    )
+))]
+pub async fn export_project_tasks(
+    service: State<Arc<TaskService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    let (project, tasks) = service.export_tasks(&principal, id.into_inner()).await?;
+
+    let mut body = csv_record(&TASK_CSV_COLUMNS);
+    for task in &tasks {
+        body.push_str(&csv_record(&task_csv_row(&project, task)));
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/csv; charset=utf-8")
+        .header(
+            "Content-Disposition",
+            format!("attachment; filename=\"tasks-{}.csv\"", project.id),
+        )
+        .body(body))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post,
+    path = "/api/v1/projects/{id}/tasks.csv",
+    tag = "tasks",
+    params(
+        ("id" = Uuid, Path, description = "Project ID"),
+        TaskImportQuery
+    ),
+    request_body(content = String, content_type = "text/csv", description = "A header row naming the title, description, column and due_at columns, then one task per row"),
+    responses(
+        (status = 200, description = "Rows imported, or validated on a dry run, with the errors of failed rows", body = ApiResponse<TaskImportReport>),
+        (status = 400, description = "The header has no title column", body = ApiResponse<()>),
+        (status = 403, description = "Requires editor role on the project", body = ApiResponse<()>),
+        (status = 404, description = "Project not found", body = ApiResponse<()>)
+    )
+))]
+pub async fn import_project_tasks(
+    service: State<Arc<TaskService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+    query: Query<TaskImportQuery>,
+    body: Payload,
+) -> Result<HttpResponse, ApiError> {
+    let report = service
+        .import_tasks(&principal, id.into_inner(), body, query.dry_run)
+        .await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(report)))
+}
@@ -0,0 +1,181 @@
+use crate::models::{Principal, TimeEntryCreate, TimeEntryFilter, TimeReportQuery, TimerStart};
+use crate::services::TimeService;
+use crate::views::{csv_record, ApiError, ApiResponse};
+use ntex::web::types::{Json, Path, Query, State};
+use ntex::web::HttpResponse;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post,
+    path = "/api/v1/time/entries",
+    tag = "time",
+    request_body = TimeEntryCreate,
+    responses(
+        (status = 201, description = "Time entry booked", body = ApiResponse<TimeEntry>),
+        (status = 400, description = "Invalid times or note", body = ApiResponse<()>),
+        (status = 404, description = "Task not found", body = ApiResponse<()>),
+        (status = 409, description = "The task's project is archived", body = ApiResponse<()>)
+    )
+))]
+pub async fn create_time_entry(
+    service: State<Arc<TimeService>>,
+    principal: Principal,
+    create_data: Json<TimeEntryCreate>,
+) -> Result<HttpResponse, ApiError> {
+    let entry = service
+        .create_entry(&principal, create_data.into_inner())
+        .await?;
+    Ok(HttpResponse::Created().json(&ApiResponse::success(entry)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/time/entries",
+    tag = "time",
+    params(TimeEntryFilter),
+    responses(
+        (status = 200, description = "Matching time entries, oldest first", body = ApiResponse<Vec<TimeEntry>>),
+        (status = 400, description = "Invalid range", body = ApiResponse<()>)
+    )
+))]
+pub async fn list_time_entries(
+    service: State<Arc<TimeService>>,
+    principal: Principal,
+    filter: Query<TimeEntryFilter>,
+) -> Result<HttpResponse, ApiError> {
+    let entries = service.list_entries(&principal, &filter).await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(entries)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    delete,
+    path = "/api/v1/time/entries/{id}",
+    tag = "time",
+    params(
+        ("id" = Uuid, Path, description = "Time entry ID")
+    ),
+    responses(
+        (status = 204, description = "Time entry deleted"),
+        (status = 403, description = "Only project owners can delete other users' entries", body = ApiResponse<()>),
+        (status = 404, description = "Time entry not found", body = ApiResponse<()>)
+    )
+))]
+pub async fn delete_time_entry(
+    service: State<Arc<TimeService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    service.delete_entry(&principal, id.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/time/timer",
+    tag = "time",
+    responses(
+        (status = 200, description = "The caller's running timer, or null", body = ApiResponse<TimeEntry>)
+    )
+))]
+pub async fn get_timer(
+    service: State<Arc<TimeService>>,
+    principal: Principal,
+) -> Result<HttpResponse, ApiError> {
+    let timer = service.current_timer(&principal).await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(timer)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post,
+    path = "/api/v1/time/timer/start",
+    tag = "time",
+    request_body = TimerStart,
+    responses(
+        (status = 201, description = "Timer started", body = ApiResponse<TimeEntry>),
+        (status = 404, description = "Task not found", body = ApiResponse<()>),
+        (status = 409, description = "A timer is already running, or the project is archived", body = ApiResponse<()>)
+    )
+))]
+pub async fn start_timer(
+    service: State<Arc<TimeService>>,
+    principal: Principal,
+    start_data: Json<TimerStart>,
+) -> Result<HttpResponse, ApiError> {
+    let timer = service
+        .start_timer(&principal, start_data.into_inner())
+        .await?;
+    Ok(HttpResponse::Created().json(&ApiResponse::success(timer)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post,
+    path = "/api/v1/time/timer/stop",
+    tag = "time",
+    responses(
+        (status = 200, description = "Timer stopped; the finished entry", body = ApiResponse<TimeEntry>),
+        (status = 404, description = "No timer running", body = ApiResponse<()>)
+    )
+))]
+pub async fn stop_timer(
+    service: State<Arc<TimeService>>,
+    principal: Principal,
+) -> Result<HttpResponse, ApiError> {
+    let entry = service.stop_timer(&principal).await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(entry)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/time/report",
+    tag = "time",
+    params(TimeReportQuery),
+    responses(
+        (status = 200, description = "Hours per project, task or user, most first", body = ApiResponse<TimeReport>),
+        (status = 400, description = "Invalid range", body = ApiResponse<()>)
+    )
+))]
+pub async fn time_report(
+    service: State<Arc<TimeService>>,
+    principal: Principal,
+    query: Query<TimeReportQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let report = service.report(&principal, &query).await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(report)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/time/report.csv",
+    tag = "time",
+    params(TimeReportQuery),
+    responses(
+        (status = 200, description = "The report as CSV with key, name, hours and entries columns", content_type = "text/csv", body = String),
+        (status = 400, description = "Invalid range", body = ApiResponse<()>)
+    )
+))]
+pub async fn export_time_report(
+    service: State<Arc<TimeService>>,
+    principal: Principal,
+    query: Query<TimeReportQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let report = service.report(&principal, &query).await?;
+
+    let mut body = csv_record(&["key", "name", "hours", "entries"]);
+    for row in &report.rows {
+        body.push_str(&csv_record(&[
+            row.key.clone(),
+            row.name.clone(),
+            format!("{:.2}", row.hours),
+            row.entries.to_string(),
+        ]));
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/csv; charset=utf-8")
+        .header(
+            "Content-Disposition",
+            "attachment; filename=\"time-report.csv\"",
+        )
+        .body(body))
+}
@@ -0,0 +1,113 @@
+use crate::models::Principal;
+use crate::services::TrashService;
+use crate::views::{ApiError, ApiResponse};
+use ntex::web::types::{Path, State};
+use ntex::web::HttpResponse;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/trash",
+    tag = "trash",
+    responses(
+        (status = 200, description = "Deleted projects and tasks the caller can see", body = ApiResponse<Trash>)
+    )
+))]
+pub async fn list_trash(
+    service: State<Arc<TrashService>>,
+    principal: Principal,
+) -> Result<HttpResponse, ApiError> {
+    let trash = service.list_trash(&principal).await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(trash)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post,
+    path = "/api/v1/trash/projects/{id}/restore",
+    tag = "trash",
+    params(
+        ("id" = Uuid, Path, description = "Project ID")
+    ),
+    responses(
+        (status = 200, description = "Project and the tasks deleted with it restored", body = ApiResponse<Project>),
+        (status = 403, description = "Only owners can restore a project", body = ApiResponse<()>),
+        (status = 404, description = "Project not in the trash", body = ApiResponse<()>),
+        (status = 409, description = "Restoring would exceed a tenant quota", body = ApiResponse<()>)
+    )
+))]
+pub async fn restore_project(
+    service: State<Arc<TrashService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    let project = service.restore_project(&principal, id.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(project)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    delete,
+    path = "/api/v1/trash/projects/{id}",
+    tag = "trash",
+    params(
+        ("id" = Uuid, Path, description = "Project ID")
+    ),
+    responses(
+        (status = 204, description = "Project, its tasks and memberships permanently deleted"),
+        (status = 403, description = "Only owners can purge a project", body = ApiResponse<()>),
+        (status = 404, description = "Project not in the trash", body = ApiResponse<()>)
+    )
+))]
+pub async fn purge_project(
+    service: State<Arc<TrashService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    service.purge_project(&principal, id.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post,
+    path = "/api/v1/trash/tasks/{id}/restore",
+    tag = "trash",
+    params(
+        ("id" = Uuid, Path, description = "Task ID")
+    ),
+    responses(
+        (status = 200, description = "Task restored", body = ApiResponse<Task>),
+        (status = 403, description = "Requires editor role on the project", body = ApiResponse<()>),
+        (status = 404, description = "Task not in the trash", body = ApiResponse<()>),
+        (status = 409, description = "Project is in the trash or task quota reached", body = ApiResponse<()>)
+    )
+))]
+pub async fn restore_task(
+    service: State<Arc<TrashService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    let task = service.restore_task(&principal, id.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(task)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    delete,
+    path = "/api/v1/trash/tasks/{id}",
+    tag = "trash",
+    params(
+        ("id" = Uuid, Path, description = "Task ID")
+    ),
+    responses(
+        (status = 204, description = "Task permanently deleted"),
+        (status = 403, description = "Requires editor role on the project", body = ApiResponse<()>),
+        (status = 404, description = "Task not in the trash", body = ApiResponse<()>)
+    )
+))]
+pub async fn purge_task(
+    service: State<Arc<TrashService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    service.purge_task(&principal, id.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,177 @@
+use crate::models::{DeliveryStatus, Principal, WebhookCreate, WebhookUpdate};
+use crate::services::WebhookService;
+use crate::views::{ApiError, ApiResponse};
+use ntex::web::types::{Json, Path, Query, State};
+use ntex::web::HttpResponse;
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+pub struct DeliveryListQuery {
+    /// Only return deliveries in this state
+    pub status: Option<DeliveryStatus>,
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post,
+    path = "/api/v1/webhooks",
+    tag = "webhooks",
+    request_body = WebhookCreate,
+    responses(
+        (status = 201, description = "Webhook created, the signing secret is only shown once", body = ApiResponse<WebhookCreated>),
+        (status = 400, description = "Invalid request", body = ApiResponse<()>),
+        (status = 403, description = "Admin scope required", body = ApiResponse<()>),
+        (status = 404, description = "Project not found", body = ApiResponse<()>)
+    )
+))]
+pub async fn create_webhook(
+    service: State<Arc<WebhookService>>,
+    principal: Principal,
+    body: Json<WebhookCreate>,
+) -> Result<HttpResponse, ApiError> {
+    let created = service
+        .create_webhook(&principal, body.into_inner())
+        .await?;
+    Ok(HttpResponse::Created().json(&ApiResponse::success(created)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/webhooks",
+    tag = "webhooks",
+    responses(
+        (status = 200, description = "List of webhooks", body = ApiResponse<Vec<Webhook>>),
+        (status = 403, description = "Admin scope required", body = ApiResponse<()>)
+    )
+))]
+pub async fn list_webhooks(
+    service: State<Arc<WebhookService>>,
+    principal: Principal,
+) -> Result<HttpResponse, ApiError> {
+    let webhooks = service.list_webhooks(&principal).await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(webhooks)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/webhooks/{id}",
+    tag = "webhooks",
+    params(
+        ("id" = Uuid, Path, description = "Webhook ID")
+    ),
+    responses(
+        (status = 200, description = "Webhook found", body = ApiResponse<Webhook>),
+        (status = 403, description = "Admin scope required", body = ApiResponse<()>),
+        (status = 404, description = "Webhook not found", body = ApiResponse<()>)
+    )
+))]
+pub async fn get_webhook(
+    service: State<Arc<WebhookService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    let webhook = service.get_webhook(&principal, id.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(webhook)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    put,
+    path = "/api/v1/webhooks/{id}",
+    tag = "webhooks",
+    params(
+        ("id" = Uuid, Path, description = "Webhook ID")
+    ),
+    request_body = WebhookUpdate,
+    responses(
+        (status = 200, description = "Webhook updated", body = ApiResponse<Webhook>),
+        (status = 400, description = "Invalid request", body = ApiResponse<()>),
+        (status = 403, description = "Admin scope required", body = ApiResponse<()>),
+        (status = 404, description = "Webhook not found", body = ApiResponse<()>)
+    )
+))]
+pub async fn update_webhook(
+    service: State<Arc<WebhookService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+    body: Json<WebhookUpdate>,
+) -> Result<HttpResponse, ApiError> {
+    let webhook = service
+        .update_webhook(&principal, id.into_inner(), body.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(webhook)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    delete,
+    path = "/api/v1/webhooks/{id}",
+    tag = "webhooks",
+    params(
+        ("id" = Uuid, Path, description = "Webhook ID")
+    ),
+    responses(
+        (status = 204, description = "Webhook deleted"),
+        (status = 403, description = "Admin scope required", body = ApiResponse<()>),
+        (status = 404, description = "Webhook not found", body = ApiResponse<()>)
+    )
+))]
+pub async fn delete_webhook(
+    service: State<Arc<WebhookService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    service.delete_webhook(&principal, id.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/webhooks/{id}/deliveries",
+    tag = "webhooks",
+    params(
+        ("id" = Uuid, Path, description = "Webhook ID"),
+        DeliveryListQuery
+    ),
+    responses(
+        (status = 200, description = "Delivery log, newest first", body = ApiResponse<Vec<WebhookDelivery>>),
+        (status = 403, description = "Admin scope required", body = ApiResponse<()>),
+        (status = 404, description = "Webhook not found", body = ApiResponse<()>)
+    )
+))]
+pub async fn list_webhook_deliveries(
+    service: State<Arc<WebhookService>>,
+    principal: Principal,
+    id: Path<Uuid>,
+    query: Query<DeliveryListQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let deliveries = service
+        .list_deliveries(&principal, id.into_inner(), query.status)
+        .await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(deliveries)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post,
+    path = "/api/v1/webhooks/{id}/deliveries/{delivery_id}/retry",
+    tag = "webhooks",
+    params(
+        ("id" = Uuid, Path, description = "Webhook ID"),
+        ("delivery_id" = Uuid, Path, description = "Delivery ID")
+    ),
+    responses(
+        (status = 200, description = "Delivery queued again", body = ApiResponse<WebhookDelivery>),
+        (status = 403, description = "Admin scope required", body = ApiResponse<()>),
+        (status = 404, description = "Delivery not found", body = ApiResponse<()>),
+        (status = 409, description = "Delivery is not dead-lettered", body = ApiResponse<()>)
+    )
+))]
+pub async fn retry_webhook_delivery(
+    service: State<Arc<WebhookService>>,
+    principal: Principal,
+    path: Path<(Uuid, Uuid)>,
+) -> Result<HttpResponse, ApiError> {
+    let (id, delivery_id) = path.into_inner();
+    let delivery = service.retry_delivery(&principal, id, delivery_id).await?;
+    Ok(HttpResponse::Ok().json(&ApiResponse::success(delivery)))
+}
@@ -0,0 +1,224 @@
+use crate::models::{DomainEvent, EventEnvelope, EventFilter, Principal, TaskCreate, TaskUpdate};
+use crate::services::{EventStream, ProjectService, StreamItem, TaskService};
+use crate::views::{ApiError, ErrorResponse};
+use ntex::service::{fn_factory_with_config, fn_service};
+use ntex::web::types::State;
+use ntex::web::ws::{Frame, Message, WsSink};
+use ntex::web::{self, HttpRequest, HttpResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::io;
+use std::rc::Rc;
+use std::sync::Arc;
+use tracing::warn;
+use uuid::Uuid;
+
+/// A request from the client; `id` is echoed on the matching `ack` or `error` frame.
+#[derive(Debug, Deserialize)]
+pub struct ClientFrame {
+    pub id: Option<String>,
+    #[serde(flatten)]
+    pub message: ClientMessage,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Subscribe { project_id: Uuid },
+    Unsubscribe { project_id: Uuid },
+    CreateTask { task: TaskCreate },
+    UpdateTask { task_id: Uuid, changes: TaskUpdate },
+    DeleteTask { task_id: Uuid },
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerFrame<'a> {
+    Ack {
+        id: Option<String>,
+        data: Value,
+    },
+    Error {
+        id: Option<String>,
+        error: ErrorResponse,
+    },
+    /// A change in a subscribed project.
+    Event {
+        event_id: u64,
+        event: &'a EventEnvelope,
+    },
+    /// Notifications were missed; reload subscribed projects.
+    Reset,
+}
+
+impl ServerFrame<'_> {
+    fn to_message(&self) -> Message {
+        Message::Text(serde_json::to_string(self).unwrap_or_default().into())
+    }
+}
+
+struct Session {
+    principal: Principal,
+    projects: Arc<ProjectService>,
+    tasks: Arc<TaskService>,
+    subscriptions: Rc<RefCell<HashSet<Uuid>>>,
+}
+
+impl Session {
+    async fn handle(&self, frame: Frame) -> Option<Message> {
+        match frame {
+            Frame::Text(text) => {
+                let reply = match serde_json::from_slice::<ClientFrame>(&text) {
+                    Ok(frame) => match self.dispatch(frame.message).await {
+                        Ok(data) => ServerFrame::Ack { id: frame.id, data },
+                        Err(error) => ServerFrame::Error {
+                            id: frame.id,
+                            error: error.to_error_response(),
+                        },
+                    },
+                    Err(e) => ServerFrame::Error {
+                        id: None,
+                        error: ApiError::bad_request(&format!("Invalid message: {}", e))
+                            .to_error_response(),
+                    },
+                };
+                Some(reply.to_message())
+            }
+            Frame::Binary(_) => Some(
+                ServerFrame::Error {
+                    id: None,
+                    error: ApiError::bad_request("Binary frames are not supported")
+                        .to_error_response(),
+                }
+                .to_message(),
+            ),
+            Frame::Ping(payload) => Some(Message::Pong(payload)),
+            Frame::Close(reason) => Some(Message::Close(reason)),
+            Frame::Pong(_) | Frame::Continuation(_) => None,
+        }
+    }
+
+    async fn dispatch(&self, message: ClientMessage) -> Result<Value, ApiError> {
+        let principal = &self.principal;
+        match message {
+            ClientMessage::Subscribe { project_id } => {
+                self.projects.get_project(principal, project_id).await?;
+                self.subscriptions.borrow_mut().insert(project_id);
+                Ok(serde_json::json!({ "project_id": project_id }))
+            }
+            ClientMessage::Unsubscribe { project_id } => {
+                self.subscriptions.borrow_mut().remove(&project_id);
+                Ok(serde_json::json!({ "project_id": project_id }))
+            }
+            ClientMessage::CreateTask { task } => {
+                to_value(self.tasks.create_task(principal, task).await?)
+            }
+            ClientMessage::UpdateTask { task_id, changes } => {
+                to_value(self.tasks.update_task(principal, task_id, changes).await?)
+            }
+            ClientMessage::DeleteTask { task_id } => {
+                self.tasks.delete_task(principal, task_id).await?;
+                Ok(serde_json::json!({ "task_id": task_id }))
+            }
+        }
+    }
+}
+
+fn to_value<T: Serialize>(value: T) -> Result<Value, ApiError> {
+    serde_json::to_value(value).map_err(|_| ApiError::InternalServerError)
+}
+
+/// Sends events of subscribed projects until the connection closes.
+async fn forward_events(
+    stream: Arc<EventStream>,
+    principal: Principal,
+    subscriptions: Rc<RefCell<HashSet<Uuid>>>,
+    sink: WsSink,
+) {
+    let mut subscription = match stream.subscribe(&principal, EventFilter::default(), None) {
+        Ok(subscription) => subscription,
+        Err(e) => {
+            warn!("WebSocket event subscription failed: {}", e);
+            return;
+        }
+    };
+    let mut disconnected = sink.on_disconnect();
+
+    loop {
+        let item = tokio::select! {
+            _ = &mut disconnected => return,
+            item = subscription.recv() => item,
+        };
+        let frame = match item {
+            Some(StreamItem::Event(event)) => {
+                let project_id = event.envelope.event.project_id();
+                let mut subscriptions = subscriptions.borrow_mut();
+                if !subscriptions.contains(&project_id) {
+                    continue;
+                }
+                if let DomainEvent::ProjectDeleted { .. } = event.envelope.event {
+                    subscriptions.remove(&project_id);
+                }
+                ServerFrame::Event {
+                    event_id: event.id,
+                    event: &event.envelope,
+                }
+                .to_message()
+            }
+            Some(StreamItem::Reset) => ServerFrame::Reset.to_message(),
+            None => return,
+        };
+        if sink.send(frame).await.is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/ws",
+    tag = "events",
+    responses(
+        (status = 101, description = "Switched to the WebSocket protocol"),
+        (status = 400, description = "Not a WebSocket handshake", body = ApiResponse<()>)
+    )
+))]
+pub async fn connect_websocket(
+    req: HttpRequest,
+    principal: Principal,
+    projects: State<Arc<ProjectService>>,
+    tasks: State<Arc<TaskService>>,
+    stream: State<Arc<EventStream>>,
+) -> Result<HttpResponse, web::Error> {
+    let projects = projects.get_ref().clone();
+    let tasks = tasks.get_ref().clone();
+    let stream = stream.get_ref().clone();
+
+    web::ws::start::<_, _, web::Error>(
+        req,
+        fn_factory_with_config(move |sink: WsSink| {
+            let session = Rc::new(Session {
+                principal: principal.clone(),
+                projects: projects.clone(),
+                tasks: tasks.clone(),
+                subscriptions: Rc::default(),
+            });
+            ntex::rt::spawn(forward_events(
+                stream.clone(),
+                principal.clone(),
+                session.subscriptions.clone(),
+                sink,
+            ));
+
+            async move {
+                Ok::<_, web::Error>(fn_service(move |frame: Frame| {
+                    let session = session.clone();
+                    async move { Ok::<_, io::Error>(session.handle(frame).await) }
+                }))
+            }
+        }),
+    )
+    .await
+}
@@ -0,0 +1,13 @@
+use crate::views::ApiError;
+use async_graphql::{Error, ErrorExtensions};
+
+/// GraphQL errors carry the REST error code, such as `NOT_FOUND` or `FORBIDDEN`, in
+/// `extensions.code`.
+impl ErrorExtensions for ApiError {
+    fn extend(&self) -> Error {
+        let response = self.to_error_response();
+        Error::new(response.message).extend_with(|_, extensions| {
+            extensions.set("code", response.code);
+        })
+    }
+}
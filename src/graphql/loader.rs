@@ -0,0 +1,32 @@
+use crate::models::{Principal, Task};
+use crate::services::TaskService;
+use async_graphql::{Error, ErrorExtensions};
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Loads the tasks of the projects in a query together, so resolving `tasks` on a list of
+/// projects reads the task store once rather than once per project. Created per request
+/// for the caller.
+pub struct ProjectTasksLoader {
+    service: Arc<TaskService>,
+    principal: Principal,
+}
+
+impl ProjectTasksLoader {
+    pub fn new(service: Arc<TaskService>, principal: Principal) -> Self {
+        Self { service, principal }
+    }
+}
+
+impl async_graphql::dataloader::Loader<Uuid> for ProjectTasksLoader {
+    type Value = Vec<Task>;
+    type Error = Error;
+
+    async fn load(&self, project_ids: &[Uuid]) -> Result<HashMap<Uuid, Vec<Task>>, Error> {
+        self.service
+            .list_tasks_by_projects(&self.principal, project_ids)
+            .await
+            .map_err(|e| e.extend())
+    }
+}
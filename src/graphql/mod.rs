@@ -0,0 +1,6 @@
+pub mod error;
+pub mod loader;
+pub mod schema;
+
+pub use loader::*;
+pub use schema::*;
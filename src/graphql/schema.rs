@@ -0,0 +1,198 @@
+use crate::graphql::ProjectTasksLoader;
+use crate::models::{
+    Principal, Project, ProjectCreate, ProjectUpdate, Task, TaskCreate, TaskUpdate,
+};
+use crate::services::{ProjectService, TaskService};
+use crate::views::ApiError;
+use async_graphql::dataloader::DataLoader;
+use async_graphql::{
+    ComplexObject, Context, EmptySubscription, ErrorExtensions, InputObject, Object, Result, Schema,
+};
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub type ApiSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+
+/// Deepest field nesting a query may use; GraphiQL's introspection query needs 13 levels.
+pub const MAX_QUERY_DEPTH: usize = 16;
+/// Most fields a query may select, counting each alias and fragment use separately.
+pub const MAX_QUERY_COMPLEXITY: usize = 500;
+
+/// The schema over the shared project and task services. Each request must carry the
+/// caller's [`Principal`] and a [`ProjectTasksLoader`] for them as data. Queries over
+/// [`MAX_QUERY_DEPTH`] or [`MAX_QUERY_COMPLEXITY`] are rejected before they run.
+pub fn build_schema(projects: Arc<ProjectService>, tasks: Arc<TaskService>) -> ApiSchema {
+    Schema::build(QueryRoot, MutationRoot, EmptySubscription)
+        .data(projects)
+        .data(tasks)
+        .limit_depth(MAX_QUERY_DEPTH)
+        .limit_complexity(MAX_QUERY_COMPLEXITY)
+        .finish()
+}
+
+/// Criteria for the `tasks` query; all given criteria must match.
+#[derive(Debug, Default, InputObject)]
+pub struct TaskFilter {
+    pub project_id: Option<Uuid>,
+    pub done: Option<bool>,
+    pub column_id: Option<Uuid>,
+    /// Only tasks due before this instant
+    pub due_before: Option<DateTime<Utc>>,
+}
+
+impl TaskFilter {
+    fn matches(&self, task: &Task) -> bool {
+        self.done.is_none_or(|done| task.done == done)
+            && self
+                .column_id
+                .is_none_or(|column_id| task.column_id == Some(column_id))
+            && self
+                .due_before
+                .is_none_or(|cutoff| task.due_at.is_some_and(|due_at| due_at < cutoff))
+    }
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Projects the caller can see
+    async fn projects(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = false)] include_archived: bool,
+    ) -> Result<Vec<Project>> {
+        let (service, principal) = (projects(ctx)?, principal(ctx)?);
+        service
+            .list_projects(principal, include_archived)
+            .await
+            .map_err(|e| e.extend())
+    }
+
+    /// A single project, or null when there is none with this id
+    async fn project(&self, ctx: &Context<'_>, id: Uuid) -> Result<Option<Project>> {
+        let (service, principal) = (projects(ctx)?, principal(ctx)?);
+        found(service.get_project(principal, id).await)
+    }
+
+    /// Tasks the caller can see, in rank order when filtered by project
+    async fn tasks(&self, ctx: &Context<'_>, filter: Option<TaskFilter>) -> Result<Vec<Task>> {
+        let (service, principal) = (tasks(ctx)?, principal(ctx)?);
+        let filter = filter.unwrap_or_default();
+        let tasks = match filter.project_id {
+            Some(project_id) => service.list_tasks_by_project(principal, project_id).await,
+            None => service.list_tasks(principal).await,
+        }
+        .map_err(|e| e.extend())?;
+        Ok(tasks
+            .into_iter()
+            .filter(|task| filter.matches(task))
+            .collect())
+    }
+
+    /// A single task, or null when there is none with this id
+    async fn task(&self, ctx: &Context<'_>, id: Uuid) -> Result<Option<Task>> {
+        let (service, principal) = (tasks(ctx)?, principal(ctx)?);
+        found(service.get_task(principal, id).await)
+    }
+}
+
+#[ComplexObject]
+impl Project {
+    /// Live tasks in rank order, loaded together for all projects in the response
+    async fn tasks(&self, ctx: &Context<'_>, done: Option<bool>) -> Result<Vec<Task>> {
+        let tasks = ctx
+            .data::<DataLoader<ProjectTasksLoader>>()?
+            .load_one(self.id)
+            .await?
+            .unwrap_or_default();
+        Ok(tasks
+            .into_iter()
+            .filter(|task| done.is_none_or(|done| task.done == done))
+            .collect())
+    }
+}
+
+pub struct MutationRoot;
+
+#[Object]
+impl MutationRoot {
+    async fn create_project(&self, ctx: &Context<'_>, input: ProjectCreate) -> Result<Project> {
+        let (service, principal) = (projects(ctx)?, principal(ctx)?);
+        service
+            .create_project(principal, input)
+            .await
+            .map_err(|e| e.extend())
+    }
+
+    async fn update_project(
+        &self,
+        ctx: &Context<'_>,
+        id: Uuid,
+        input: ProjectUpdate,
+    ) -> Result<Project> {
+        let (service, principal) = (projects(ctx)?, principal(ctx)?);
+        service
+            .update_project(principal, id, input)
+            .await
+            .map_err(|e| e.extend())
+    }
+
+    /// Moves the project and its tasks to the trash
+    async fn delete_project(&self, ctx: &Context<'_>, id: Uuid) -> Result<bool> {
+        let (service, principal) = (projects(ctx)?, principal(ctx)?);
+        service
+            .delete_project(principal, id)
+            .await
+            .map_err(|e| e.extend())?;
+        Ok(true)
+    }
+
+    async fn create_task(&self, ctx: &Context<'_>, input: TaskCreate) -> Result<Task> {
+        let (service, principal) = (tasks(ctx)?, principal(ctx)?);
+        service
+            .create_task(principal, input)
+            .await
+            .map_err(|e| e.extend())
+    }
+
+    async fn update_task(&self, ctx: &Context<'_>, id: Uuid, input: TaskUpdate) -> Result<Task> {
+        let (service, principal) = (tasks(ctx)?, principal(ctx)?);
+        service
+            .update_task(principal, id, input)
+            .await
+            .map_err(|e| e.extend())
+    }
+
+    /// Moves the task to the trash
+    async fn delete_task(&self, ctx: &Context<'_>, id: Uuid) -> Result<bool> {
+        let (service, principal) = (tasks(ctx)?, principal(ctx)?);
+        service
+            .delete_task(principal, id)
+            .await
+            .map_err(|e| e.extend())?;
+        Ok(true)
+    }
+}
+
+fn principal<'a>(ctx: &Context<'a>) -> Result<&'a Principal> {
+    ctx.data::<Principal>()
+}
+
+fn projects<'a>(ctx: &Context<'a>) -> Result<&'a Arc<ProjectService>> {
+    ctx.data::<Arc<ProjectService>>()
+}
+
+fn tasks<'a>(ctx: &Context<'a>) -> Result<&'a Arc<TaskService>> {
+    ctx.data::<Arc<TaskService>>()
+}
+
+/// Lookups answer null for a missing record instead of an error.
+fn found<T>(result: Result<T, ApiError>) -> Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(ApiError::NotFound { .. }) => Ok(None),
+        Err(err) => Err(err.extend()),
+    }
+}
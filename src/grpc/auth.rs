@@ -0,0 +1,79 @@
+use crate::config::AuthMode;
+use crate::middleware::{
+    parse_bearer, select_tenant, JwtVerifier, API_KEY_HEADER, REQUEST_ID_HEADER, TENANT_HEADER,
+};
+use crate::models::Principal;
+use crate::services::ApiKeyService;
+use crate::views::ApiError;
+use std::sync::Arc;
+use tonic::metadata::MetadataMap;
+use uuid::Uuid;
+
+/// Authenticates gRPC calls from their metadata with the same schemes and keys as HTTP
+/// requests: an `x-api-key` first, otherwise a bearer token when JWT verification is
+/// configured. Calls without credentials only run, as the unprivileged
+/// [`Principal::anonymous`], under [`AuthMode::Anonymous`].
+#[derive(Clone)]
+pub struct GrpcAuth {
+    mode: AuthMode,
+    verifier: Option<Arc<JwtVerifier>>,
+    api_keys: Arc<ApiKeyService>,
+}
+
+impl GrpcAuth {
+    pub fn new(
+        mode: AuthMode,
+        verifier: Option<Arc<JwtVerifier>>,
+        api_keys: Arc<ApiKeyService>,
+    ) -> Self {
+        Self {
+            mode,
+            verifier,
+            api_keys,
+        }
+    }
+
+    pub async fn authenticate(&self, metadata: &MetadataMap) -> Result<Principal, ApiError> {
+        let requested = match metadata.get(TENANT_HEADER) {
+            Some(value) => Some(
+                value
+                    .to_str()
+                    .map_err(|_| ApiError::bad_request("Invalid x-tenant-id metadata"))?,
+            ),
+            None => None,
+        };
+
+        let mut principal = if let Some(key) = metadata.get(API_KEY_HEADER) {
+            let key = key
+                .to_str()
+                .map_err(|_| ApiError::unauthorized("Invalid API key"))?;
+            let principal = self.api_keys.authenticate(key.trim()).await?;
+            select_tenant(requested, Some(&principal.tenant_id))?;
+            principal
+        } else if let Some(ref verifier) = self.verifier {
+            let token = metadata
+                .get("authorization")
+                .and_then(|value| value.to_str().ok())
+                .and_then(parse_bearer)
+                .ok_or_else(|| ApiError::unauthorized("Missing bearer token"))?;
+            let claims = verifier.verify(token)?;
+            let tenant_id = select_tenant(requested, Some(claims.tenant_id()))?;
+            claims.into_principal(tenant_id)
+        } else if self.mode == AuthMode::Anonymous {
+            Principal::anonymous(select_tenant(requested, None)?)
+        } else {
+            return Err(ApiError::unauthorized("Missing bearer token"));
+        };
+
+        principal.request_id = Some(
+            metadata
+                .get(REQUEST_ID_HEADER)
+                .and_then(|value| value.to_str().ok())
+                .map(str::trim)
+                .filter(|value| !value.is_empty() && value.len() <= 128)
+                .map(str::to_string)
+                .unwrap_or_else(|| Uuid::new_v4().to_string()),
+        );
+        Ok(principal)
+    }
+}
@@ -0,0 +1,93 @@
+use crate::grpc::proto;
+use crate::models::{Column, Project, Task};
+use crate::views::ApiError;
+use chrono::{DateTime, Utc};
+use prost_types::Timestamp;
+use tonic::{Code, Status};
+use uuid::Uuid;
+
+/// Metadata key carrying the REST error code, such as `NOT_FOUND`, of a failed call.
+pub const ERROR_CODE_METADATA: &str = "x-error-code";
+
+impl From<ApiError> for Status {
+    fn from(err: ApiError) -> Self {
+        let code = match err {
+            ApiError::NotFound { .. } => Code::NotFound,
+            ApiError::BadRequest { .. }
+            | ApiError::ValidationError { .. }
+            | ApiError::NotAcceptable { .. }
+            | ApiError::UnsupportedMediaType { .. } => Code::InvalidArgument,
+            ApiError::Conflict { .. } => Code::FailedPrecondition,
+            ApiError::Unauthorized { .. } => Code::Unauthenticated,
+            ApiError::Forbidden { .. } => Code::PermissionDenied,
+            ApiError::InternalServerError | ApiError::RepositoryError { .. } => Code::Internal,
+        };
+        let body = err.to_error_response();
+        let mut status = Status::new(code, body.message);
+        if let Ok(value) = body.code.parse() {
+            status.metadata_mut().insert(ERROR_CODE_METADATA, value);
+        }
+        status
+    }
+}
+
+pub fn timestamp(at: DateTime<Utc>) -> Timestamp {
+    Timestamp {
+        seconds: at.timestamp(),
+        nanos: at.timestamp_subsec_nanos() as i32,
+    }
+}
+
+pub fn datetime(field: &str, timestamp: Timestamp) -> Result<DateTime<Utc>, ApiError> {
+    u32::try_from(timestamp.nanos)
+        .ok()
+        .and_then(|nanos| DateTime::from_timestamp(timestamp.seconds, nanos))
+        .ok_or_else(|| ApiError::bad_request(&format!("Invalid {}: out of range", field)))
+}
+
+pub fn parse_id(field: &str, value: &str) -> Result<Uuid, ApiError> {
+    Uuid::parse_str(value)
+        .map_err(|_| ApiError::bad_request(&format!("Invalid {}: '{}'", field, value)))
+}
+
+impl From<Column> for proto::Column {
+    fn from(column: Column) -> Self {
+        Self {
+            id: column.id.to_string(),
+            name: column.name,
+            wip_limit: column.wip_limit.map(|limit| limit as u64),
+            done: column.done,
+        }
+    }
+}
+
+impl From<Project> for proto::Project {
+    fn from(project: Project) -> Self {
+        Self {
+            id: project.id.to_string(),
+            name: project.name,
+            description: project.description,
+            archived: project.archived,
+            columns: project.columns.into_iter().map(Into::into).collect(),
+            created_at: Some(timestamp(project.created_at)),
+            updated_at: Some(timestamp(project.updated_at)),
+        }
+    }
+}
+
+impl From<Task> for proto::Task {
+    fn from(task: Task) -> Self {
+        Self {
+            id: task.id.to_string(),
+            project_id: task.project_id.to_string(),
+            title: task.title,
+            description: task.description,
+            done: task.done,
+            rank: task.rank,
+            column_id: task.column_id.map(|id| id.to_string()),
+            due_at: task.due_at.map(timestamp),
+            created_at: Some(timestamp(task.created_at)),
+            updated_at: Some(timestamp(task.updated_at)),
+        }
+    }
+}
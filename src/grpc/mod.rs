@@ -0,0 +1,14 @@
+pub mod auth;
+pub mod convert;
+pub mod server;
+pub mod service;
+
+pub use auth::*;
+pub use convert::*;
+pub use server::*;
+pub use service::*;
+
+/// Messages and service stubs generated from `proto/tasks.proto`.
+pub mod proto {
+    tonic::include_proto!("tasks.v1");
+}
@@ -0,0 +1,67 @@
+use crate::grpc::proto::projects_server::ProjectsServer;
+use crate::grpc::proto::tasks_server::TasksServer;
+use crate::grpc::{GrpcAuth, GrpcProjects, GrpcTasks};
+use crate::services::{EventStream, ProjectService, TaskService};
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::thread;
+use tonic::transport::server::TcpIncoming;
+use tonic::transport::Server;
+
+/// Serves the gRPC API on its own port, next to the HTTP server and over the same services.
+pub struct GrpcServer {
+    projects: GrpcProjects,
+    tasks: GrpcTasks,
+}
+
+impl GrpcServer {
+    pub fn new(
+        projects: Arc<ProjectService>,
+        tasks: Arc<TaskService>,
+        events: Arc<EventStream>,
+        auth: GrpcAuth,
+    ) -> Self {
+        Self {
+            projects: GrpcProjects::new(projects, auth.clone()),
+            tasks: GrpcTasks::new(tasks, events, auth),
+        }
+    }
+
+    /// Serves connections from `listener` until the runtime shuts down.
+    pub async fn serve(self, listener: tokio::net::TcpListener) -> Result<(), String> {
+        Server::builder()
+            .add_service(ProjectsServer::new(self.projects))
+            .add_service(TasksServer::new(self.tasks))
+            .serve_with_incoming(TcpIncoming::from(listener))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    /// Binds `address` and serves it from a thread with its own Tokio runtime, as tonic
+    /// needs one and the HTTP server runs on ntex's. Returns the bound address; a port that
+    /// is already taken fails here rather than on the server thread.
+    pub fn spawn(self, address: &str) -> io::Result<SocketAddr> {
+        let listener = std::net::TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        let local_addr = listener.local_addr()?;
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .thread_name("grpc")
+            .enable_all()
+            .build()?;
+
+        thread::Builder::new()
+            .name("grpc-server".to_string())
+            .spawn(move || {
+                let result = runtime.block_on(async move {
+                    let listener =
+                        tokio::net::TcpListener::from_std(listener).map_err(|e| e.to_string())?;
+                    self.serve(listener).await
+                });
+                if let Err(e) = result {
+                    tracing::error!("gRPC server stopped: {}", e);
+                }
+            })?;
+        Ok(local_addr)
+    }
+}
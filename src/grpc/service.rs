@@ -0,0 +1,300 @@
+use crate::grpc::proto::projects_server::Projects;
+use crate::grpc::proto::tasks_server::Tasks;
+use crate::grpc::{datetime, parse_id, proto, timestamp, GrpcAuth};
+use crate::models::{
+    DomainEvent, EventFilter, EventKind, ProjectCreate, ProjectUpdate, TaskCreate, TaskUpdate,
+};
+use crate::services::{EventStream, ProjectService, StreamEvent, StreamItem, TaskService};
+use futures::Stream;
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+
+/// Event types `WatchTasks` streams; project deletes and restores are sent as changes to the
+/// tasks they cascaded to.
+const TASK_EVENTS: [EventKind; 8] = [
+    EventKind::ProjectDeleted,
+    EventKind::ProjectRestored,
+    EventKind::TaskCreated,
+    EventKind::TaskUpdated,
+    EventKind::TaskDeleted,
+    EventKind::TaskRestored,
+    EventKind::TaskDueSoon,
+    EventKind::TaskOverdue,
+];
+
+/// The `tasks.v1.Projects` service over the shared [`ProjectService`].
+pub struct GrpcProjects {
+    projects: Arc<ProjectService>,
+    auth: GrpcAuth,
+}
+
+impl GrpcProjects {
+    pub fn new(projects: Arc<ProjectService>, auth: GrpcAuth) -> Self {
+        Self { projects, auth }
+    }
+}
+
+#[tonic::async_trait]
+impl Projects for GrpcProjects {
+    async fn list_projects(
+        &self,
+        request: Request<proto::ListProjectsRequest>,
+    ) -> Result<Response<proto::ListProjectsResponse>, Status> {
+        let principal = self.auth.authenticate(request.metadata()).await?;
+        let projects = self
+            .projects
+            .list_projects(&principal, request.get_ref().include_archived)
+            .await?;
+        Ok(Response::new(proto::ListProjectsResponse {
+            projects: projects.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn get_project(
+        &self,
+        request: Request<proto::GetProjectRequest>,
+    ) -> Result<Response<proto::Project>, Status> {
+        let principal = self.auth.authenticate(request.metadata()).await?;
+        let id = parse_id("id", &request.get_ref().id)?;
+        let project = self.projects.get_project(&principal, id).await?;
+        Ok(Response::new(project.into()))
+    }
+
+    async fn create_project(
+        &self,
+        request: Request<proto::CreateProjectRequest>,
+    ) -> Result<Response<proto::Project>, Status> {
+        let principal = self.auth.authenticate(request.metadata()).await?;
+        let request = request.into_inner();
+        let project = self
+            .projects
+            .create_project(
+                &principal,
+                ProjectCreate {
+                    name: request.name,
+                    description: request.description,
+                },
+            )
+            .await?;
+        Ok(Response::new(project.into()))
+    }
+
+    async fn update_project(
+        &self,
+        request: Request<proto::UpdateProjectRequest>,
+    ) -> Result<Response<proto::Project>, Status> {
+        let principal = self.auth.authenticate(request.metadata()).await?;
+        let request = request.into_inner();
+        let id = parse_id("id", &request.id)?;
+        let project = self
+            .projects
+            .update_project(
+                &principal,
+                id,
+                ProjectUpdate {
+                    name: request.name,
+                    description: request.description,
+                },
+            )
+            .await?;
+        Ok(Response::new(project.into()))
+    }
+
+    async fn delete_project(
+        &self,
+        request: Request<proto::DeleteProjectRequest>,
+    ) -> Result<Response<proto::DeleteProjectResponse>, Status> {
+        let principal = self.auth.authenticate(request.metadata()).await?;
+        let id = parse_id("id", &request.get_ref().id)?;
+        self.projects.delete_project(&principal, id).await?;
+        Ok(Response::new(proto::DeleteProjectResponse {}))
+    }
+}
+
+/// The `tasks.v1.Tasks` service over the shared [`TaskService`], streaming changes from the
+/// same [`EventStream`] as the HTTP event stream.
+pub struct GrpcTasks {
+    tasks: Arc<TaskService>,
+    events: Arc<EventStream>,
+    auth: GrpcAuth,
+}
+
+impl GrpcTasks {
+    pub fn new(tasks: Arc<TaskService>, events: Arc<EventStream>, auth: GrpcAuth) -> Self {
+        Self {
+            tasks,
+            events,
+            auth,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl Tasks for GrpcTasks {
+    type WatchTasksStream = Pin<Box<dyn Stream<Item = Result<proto::TaskChange, Status>> + Send>>;
+
+    async fn list_tasks(
+        &self,
+        request: Request<proto::ListTasksRequest>,
+    ) -> Result<Response<proto::ListTasksResponse>, Status> {
+        let principal = self.auth.authenticate(request.metadata()).await?;
+        let tasks = match request.get_ref().project_id {
+            Some(ref project_id) => {
+                let project_id = parse_id("project_id", project_id)?;
+                self.tasks
+                    .list_tasks_by_project(&principal, project_id)
+                    .await?
+            }
+            None => self.tasks.list_tasks(&principal).await?,
+        };
+        Ok(Response::new(proto::ListTasksResponse {
+            tasks: tasks.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn get_task(
+        &self,
+        request: Request<proto::GetTaskRequest>,
+    ) -> Result<Response<proto::Task>, Status> {
+        let principal = self.auth.authenticate(request.metadata()).await?;
+        let id = parse_id("id", &request.get_ref().id)?;
+        let task = self.tasks.get_task(&principal, id).await?;
+        Ok(Response::new(task.into()))
+    }
+
+    async fn create_task(
+        &self,
+        request: Request<proto::CreateTaskRequest>,
+    ) -> Result<Response<proto::Task>, Status> {
+        let principal = self.auth.authenticate(request.metadata()).await?;
+        let request = request.into_inner();
+        let task = TaskCreate {
+            project_id: parse_id("project_id", &request.project_id)?,
+            title: request.title,
+            description: request.description,
+            column_id: request
+                .column_id
+                .map(|id| parse_id("column_id", &id))
+                .transpose()?,
+            due_at: request
+                .due_at
+                .map(|due_at| datetime("due_at", due_at))
+                .transpose()?,
+            occurrence: None,
+        };
+        let task = self.tasks.create_task(&principal, task).await?;
+        Ok(Response::new(task.into()))
+    }
+
+    async fn update_task(
+        &self,
+        request: Request<proto::UpdateTaskRequest>,
+    ) -> Result<Response<proto::Task>, Status> {
+        let principal = self.auth.authenticate(request.metadata()).await?;
+        let request = request.into_inner();
+        let id = parse_id("id", &request.id)?;
+        let update = TaskUpdate {
+            title: request.title,
+            description: request.description,
+            done: request.done,
+            column_id: request
+                .column_id
+                .map(|id| parse_id("column_id", &id))
+                .transpose()?,
+            due_at: request
+                .due_at
+                .map(|due_at| datetime("due_at", due_at))
+                .transpose()?,
+        };
+        let task = self.tasks.update_task(&principal, id, update).await?;
+        Ok(Response::new(task.into()))
+    }
+
+    async fn delete_task(
+        &self,
+        request: Request<proto::DeleteTaskRequest>,
+    ) -> Result<Response<proto::DeleteTaskResponse>, Status> {
+        let principal = self.auth.authenticate(request.metadata()).await?;
+        let id = parse_id("id", &request.get_ref().id)?;
+        self.tasks.delete_task(&principal, id).await?;
+        Ok(Response::new(proto::DeleteTaskResponse {}))
+    }
+
+    async fn watch_tasks(
+        &self,
+        request: Request<proto::WatchTasksRequest>,
+    ) -> Result<Response<Self::WatchTasksStream>, Status> {
+        let principal = self.auth.authenticate(request.metadata()).await?;
+        let request = request.into_inner();
+        let filter = EventFilter {
+            project_id: request
+                .project_id
+                .map(|id| parse_id("project_id", &id))
+                .transpose()?,
+            event_types: TASK_EVENTS.to_vec(),
+        };
+        let subscription = self
+            .events
+            .subscribe(&principal, filter, request.after_sequence)?;
+
+        let state = Some((subscription, VecDeque::new()));
+        let changes = futures::stream::unfold(state, |state| async move {
+            let (mut subscription, mut pending) = state?;
+            loop {
+                if let Some(change) = pending.pop_front() {
+                    return Some((Ok(change), Some((subscription, pending))));
+                }
+                match subscription.recv().await? {
+                    StreamItem::Event(event) => pending.extend(task_changes(&event)),
+                    // Ending the stream tells the client to reload rather than miss changes
+                    StreamItem::Reset => {
+                        return Some((
+                            Err(Status::aborted(
+                                "Task changes were missed; reload and watch again",
+                            )),
+                            None,
+                        ))
+                    }
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(changes)))
+    }
+}
+
+/// The task changes an event amounts to, all with the event's sequence: one for a task
+/// event, one per cascaded task for a project delete or restore.
+fn task_changes(event: &StreamEvent) -> Vec<proto::TaskChange> {
+    let envelope = &event.envelope;
+    let (kind, tasks) = match envelope.event {
+        DomainEvent::TaskCreated { ref task }
+        | DomainEvent::TaskDeleted { ref task }
+        | DomainEvent::TaskRestored { ref task }
+        | DomainEvent::TaskDueSoon { ref task }
+        | DomainEvent::TaskOverdue { ref task } => {
+            (envelope.event.kind(), std::slice::from_ref(task))
+        }
+        DomainEvent::TaskUpdated { ref after, .. } => {
+            (EventKind::TaskUpdated, std::slice::from_ref(after))
+        }
+        DomainEvent::ProjectDeleted {
+            ref cascaded_tasks, ..
+        } => (EventKind::TaskDeleted, cascaded_tasks.as_slice()),
+        DomainEvent::ProjectRestored {
+            ref restored_tasks, ..
+        } => (EventKind::TaskRestored, restored_tasks.as_slice()),
+        _ => return Vec::new(),
+    };
+    tasks
+        .iter()
+        .map(|task| proto::TaskChange {
+            sequence: event.id,
+            r#type: kind.as_str().to_string(),
+            task: Some(task.clone().into()),
+            actor: envelope.actor.clone(),
+            occurred_at: Some(timestamp(envelope.occurred_at)),
+        })
+        .collect()
+}
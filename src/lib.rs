@@ -0,0 +1,12 @@
+pub mod config;
+pub mod controllers;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod middleware;
+pub mod models;
+pub mod repositories;
+pub mod routes;
+pub mod services;
+pub mod views;
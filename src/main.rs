@@ -1,47 +1,328 @@
-mod config;
-mod models;
-mod repositories;
-mod services;
-mod controllers;
-mod views;
-mod middleware;
-mod routes;
+#![recursion_limit = "256"]
 
+use ntex::web::{middleware::Logger, App, HttpServer};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use ntex::web::{App, HttpServer, middleware::Logger};
-use tracing::info;
+use tracing::{debug, info, warn};
 
-use config::Config;
-use repositories::{ProjectRepository, TaskRepository};
-use services::{ProjectService, TaskService};
-use middleware::{cors_middleware, init_logging};
-use routes::configure_routes;
+use rust_mvc_api::config::{AuthMode, Config};
+#[cfg(feature = "graphql")]
+use rust_mvc_api::graphql::build_schema;
+#[cfg(feature = "grpc")]
+use rust_mvc_api::grpc::{GrpcAuth, GrpcServer};
+use rust_mvc_api::middleware::{
+    cors_middleware, init_logging, ApiKeyAuth, CalendarTokenAuth, ContentNegotiation, JwtAuth,
+    JwtVerifier, RequestIdentifier,
+};
+use rust_mvc_api::models::{Backup, BackupFormat, BackupReader, RestoreMode};
+use rust_mvc_api::repositories::{
+    ApiKeyRepository, AuditRepository, CalendarTokenRepository, ChangeLog, MembershipRepository,
+    ProjectRepository, RecurringTaskRepository, ReminderRepository, RevisionRepository,
+    TaskRepository, TimeEntryRepository, WebhookDeliveryRepository, WebhookRepository,
+};
+use rust_mvc_api::routes::configure_routes;
+use rust_mvc_api::services::{
+    ApiKeyService, AuditService, BackupService, CalendarService, ChangeService, EventBus,
+    EventStream, ProjectService, QuotaPolicy, RecurringTaskService, ReminderService, RetryPolicy,
+    TaskService, TimeService, TrashDependents, TrashService, WebhookService,
+};
+
+const USAGE: &str = "Usage: rust-mvc-api [serve | restore <archive> [--check]]";
+
+/// What the binary was asked to do.
+enum Command {
+    Serve,
+    /// Load a backup archive into the empty store, then serve; with `check_only`, verify the
+    /// archive and exit instead
+    Restore {
+        archive: PathBuf,
+        check_only: bool,
+    },
+}
+
+impl Command {
+    fn from_args(mut args: impl Iterator<Item = String>) -> io::Result<Self> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidInput, USAGE);
+        match args.next().as_deref() {
+            None | Some("serve") => Ok(Self::Serve),
+            Some("restore") => {
+                let mut archive = None;
+                let mut check_only = false;
+                for arg in args {
+                    match arg.as_str() {
+                        "--check" => check_only = true,
+                        _ if archive.is_none() => archive = Some(PathBuf::from(arg)),
+                        _ => return Err(invalid()),
+                    }
+                }
+                Ok(Self::Restore {
+                    archive: archive.ok_or_else(invalid)?,
+                    check_only,
+                })
+            }
+            Some(_) => Err(invalid()),
+        }
+    }
+}
+
+/// Reads an archive in chunks; files ending in `.json` hold a single document, anything else
+/// is NDJSON.
+fn read_backup_file(path: &Path) -> io::Result<Backup> {
+    let format = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("json") => BackupFormat::Json,
+        _ => BackupFormat::Ndjson,
+    };
+    let mut file = File::open(path)?;
+    let mut reader = BackupReader::new(format);
+    let mut chunk = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut chunk)?;
+        if read == 0 {
+            break;
+        }
+        reader
+            .feed(&chunk[..read])
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    }
+    reader
+        .finish()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
 
 #[ntex::main]
 async fn main() -> std::io::Result<()> {
     // Initialize logging
     init_logging();
-    
+
+    let command = Command::from_args(std::env::args().skip(1))?;
+
     // Load configuration
     let config = Config::from_env();
-    info!("Starting server on {}", config.address());
-    
+
+    // Load JWT verification keys
+    let jwt_verifier = JwtVerifier::from_config(&config)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?
+        .map(Arc::new);
+    match (config.auth_mode, &jwt_verifier) {
+        (AuthMode::Jwt, Some(_)) => {}
+        (AuthMode::Jwt, None) => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "No JWT keys configured; set JWT_SECRET, JWT_PUBLIC_KEY_PATH or JWT_JWKS_PATH, \
+                 or AUTH_MODE=anonymous for local development",
+            ));
+        }
+        (AuthMode::Anonymous, Some(_)) => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "AUTH_MODE=anonymous cannot be combined with JWT keys",
+            ));
+        }
+        (AuthMode::Anonymous, None) => {
+            warn!("AUTH_MODE=anonymous: requests without credentials run as an unprivileged anonymous user");
+        }
+    }
+
+    // Load tenant quotas
+    let quotas = QuotaPolicy::from_config(&config)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
     // Initialize repositories
-    let project_repository = Arc::new(ProjectRepository::new());
-    let task_repository = Arc::new(TaskRepository::new());
-    
+    let change_log = Arc::new(ChangeLog::new());
+    let project_repository = Arc::new(ProjectRepository::new(change_log.clone()));
+    let task_repository = Arc::new(TaskRepository::new(change_log.clone()));
+    let api_key_repository = Arc::new(ApiKeyRepository::new());
+    let membership_repository = Arc::new(MembershipRepository::new());
+    let audit_repository = Arc::new(AuditRepository::new());
+    let revision_repository = Arc::new(RevisionRepository::new());
+    let time_entry_repository = Arc::new(TimeEntryRepository::new());
+    let recurring_task_repository = Arc::new(RecurringTaskRepository::new());
+    let reminder_repository = Arc::new(ReminderRepository::new());
+    let calendar_token_repository = Arc::new(CalendarTokenRepository::new());
+    let webhook_repository = Arc::new(WebhookRepository::new());
+    let webhook_delivery_repository = Arc::new(WebhookDeliveryRepository::new());
+
+    // Seed the store from a backup before any background job runs
+    let backup_service = Arc::new(BackupService::new(
+        project_repository.clone(),
+        task_repository.clone(),
+        membership_repository.clone(),
+        recurring_task_repository.clone(),
+        time_entry_repository.clone(),
+        quotas.clone(),
+    ));
+    if let Command::Restore {
+        archive,
+        check_only,
+    } = command
+    {
+        let backup = read_backup_file(&archive)?;
+        let invalid = |e: rust_mvc_api::views::ApiError| {
+            io::Error::new(io::ErrorKind::InvalidData, e.to_string())
+        };
+        if check_only {
+            backup_service
+                .check(None, &backup, RestoreMode::Merge)
+                .map_err(invalid)?;
+            info!(
+                "{} passed its integrity checks: {} projects, {} tasks, {} members, {} recurring tasks, {} time entries",
+                archive.display(),
+                backup.projects.len(),
+                backup.tasks.len(),
+                backup.members.len(),
+                backup.recurring_tasks.len(),
+                backup.time_entries.len()
+            );
+            return Ok(());
+        }
+        let report = backup_service.restore_all(backup).map_err(invalid)?;
+        info!(
+            "Restored {} projects and {} tasks from {}",
+            report.projects,
+            report.tasks,
+            archive.display()
+        );
+    }
+
     // Initialize services
-    let project_service = Arc::new(ProjectService::new(project_repository.clone()));
-    let task_service = Arc::new(TaskService::new(task_repository, project_repository));
-    
+    let audit_service = Arc::new(AuditService::new(audit_repository, revision_repository));
+    let event_bus = Arc::new(EventBus::new());
+    event_bus.subscribe_with("log", |envelope| async move {
+        debug!(
+            "{:?} in project {} by {}",
+            envelope.event.kind(),
+            envelope.event.project_id(),
+            envelope.actor
+        );
+        Ok(())
+    });
+    let webhook_service = Arc::new(WebhookService::new(
+        webhook_repository,
+        webhook_delivery_repository,
+        project_repository.clone(),
+        RetryPolicy::from_config(&config),
+    ));
+    webhook_service.start(&event_bus);
+    let event_stream = Arc::new(EventStream::new(membership_repository.clone()));
+    event_stream.start(&event_bus);
+    let change_service = Arc::new(ChangeService::new(
+        change_log,
+        membership_repository.clone(),
+    ));
+    let project_service = Arc::new(ProjectService::new(
+        project_repository.clone(),
+        task_repository.clone(),
+        membership_repository.clone(),
+        quotas.clone(),
+        audit_service.as_ref().clone(),
+        event_bus.clone(),
+    ));
+    let trash_service = Arc::new(TrashService::new(
+        project_repository.clone(),
+        task_repository.clone(),
+        TrashDependents {
+            memberships: membership_repository.clone(),
+            time_entries: time_entry_repository.clone(),
+            recurring_tasks: recurring_task_repository.clone(),
+            reminders: reminder_repository.clone(),
+        },
+        quotas.clone(),
+        audit_service.as_ref().clone(),
+        event_bus.clone(),
+        TrashService::retention_from_config(&config),
+    ));
+    trash_service.start();
+    let time_service = Arc::new(TimeService::new(
+        time_entry_repository.clone(),
+        task_repository.clone(),
+        project_repository.clone(),
+        membership_repository.clone(),
+    ));
+    let reminder_service = Arc::new(ReminderService::new(
+        reminder_repository,
+        task_repository.clone(),
+        project_repository.clone(),
+        membership_repository.clone(),
+        event_bus.clone(),
+        ReminderService::lead_from_config(&config),
+    ));
+    reminder_service.start();
+    let calendar_service = Arc::new(CalendarService::new(
+        calendar_token_repository,
+        task_repository.clone(),
+        project_repository.clone(),
+        membership_repository.clone(),
+    ));
+    let task_service = Arc::new(TaskService::new(
+        task_repository,
+        project_repository.clone(),
+        membership_repository.clone(),
+        quotas,
+        audit_service.as_ref().clone(),
+        event_bus.clone(),
+    ));
+    let recurring_task_service = Arc::new(RecurringTaskService::new(
+        recurring_task_repository.clone(),
+        project_repository,
+        membership_repository,
+        task_service.clone(),
+    ));
+    recurring_task_service.start();
+    let api_key_service = Arc::new(ApiKeyService::new(api_key_repository));
+    #[cfg(feature = "graphql")]
+    let graphql_schema = build_schema(project_service.clone(), task_service.clone());
+    #[cfg(feature = "grpc")]
+    if let Some(grpc_address) = config.grpc_address() {
+        let grpc_address = GrpcServer::new(
+            project_service.clone(),
+            task_service.clone(),
+            event_stream.clone(),
+            GrpcAuth::new(
+                config.auth_mode,
+                jwt_verifier.clone(),
+                api_key_service.clone(),
+            ),
+        )
+        .spawn(&grpc_address)?;
+        info!("Starting gRPC server on {}", grpc_address);
+    }
+    #[cfg(not(feature = "grpc"))]
+    if config.grpc_port.is_some() {
+        warn!("GRPC_PORT is set, but this build has no gRPC server; enable the `grpc` feature");
+    }
+
     // Start HTTP server
+    info!("Starting server on {}", config.address());
     HttpServer::new(move || {
-        App::new()
+        let app = App::new()
+            .state(project_service.clone())
+            .state(task_service.clone())
+            .state(api_key_service.clone())
+            .state(audit_service.clone())
+            .state(webhook_service.clone())
+            .state(event_stream.clone())
+            .state(change_service.clone())
+            .state(trash_service.clone())
+            .state(time_service.clone())
+            .state(recurring_task_service.clone())
+            .state(reminder_service.clone())
+            .state(calendar_service.clone())
+            .state(backup_service.clone());
+        #[cfg(feature = "graphql")]
+        let app = app.state(graphql_schema.clone());
+
+        app.wrap(JwtAuth::new(jwt_verifier.clone()))
+            .wrap(ApiKeyAuth::new(api_key_service.clone()))
+            .wrap(CalendarTokenAuth::new(calendar_service.clone()))
+            .wrap(ContentNegotiation)
             .wrap(Logger::default())
+            .wrap(RequestIdentifier)
             .wrap(cors_middleware())
             .configure(configure_routes)
     })
-    .bind(&config.address())?
+    .bind(config.address())?
     .run()
     .await
-}
\ No newline at end of file
+}
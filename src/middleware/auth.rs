@@ -0,0 +1,454 @@
+use crate::config::Config;
+use crate::middleware::request_id::RequestId;
+use crate::models::{Principal, DEFAULT_TENANT};
+use crate::services::{ApiKeyService, CalendarService};
+use crate::views::ApiError;
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use ntex::http::{header, Method, Payload};
+use ntex::service::{Middleware, Service, ServiceCtx};
+use ntex::web::{DefaultError, FromRequest, HttpRequest, WebRequest, WebResponse};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Arc;
+
+/// Routes under this prefix require authentication; everything else stays public.
+const PROTECTED_PREFIX: &str = "/api/v1";
+
+/// The GraphQL endpoint, which requires authentication like the REST routes.
+const GRAPHQL_PATH: &str = "/graphql";
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+pub const TENANT_HEADER: &str = "x-tenant-id";
+
+/// Query parameter carrying a calendar token on calendar feed URLs.
+pub const CALENDAR_TOKEN_PARAM: &str = "token";
+
+/// Calendar feeds are the only routes a calendar token opens.
+const CALENDAR_FEED_SUFFIX: &str = ".ics";
+
+#[derive(Debug, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    pub name: Option<String>,
+    pub scope: Option<String>,
+    pub tenant: Option<String>,
+}
+
+impl Claims {
+    /// The tenant the token is bound to. Tokens without a `tenant` claim belong to the
+    /// default tenant, so only anonymous principals pick a tenant with `X-Tenant-Id`.
+    pub fn tenant_id(&self) -> &str {
+        self.tenant.as_deref().unwrap_or(DEFAULT_TENANT)
+    }
+
+    pub fn into_principal(self, tenant_id: String) -> Principal {
+        Principal {
+            subject: self.sub,
+            name: self.name,
+            scopes: self
+                .scope
+                .map(|scope| scope.split_whitespace().map(str::to_string).collect())
+                .unwrap_or_default(),
+            tenant_id,
+            request_id: None,
+        }
+    }
+}
+
+/// Validates HS256 and RS256 bearer tokens against the configured keys.
+pub struct JwtVerifier {
+    hmac_key: Option<DecodingKey>,
+    rsa_key: Option<DecodingKey>,
+    jwks: HashMap<String, DecodingKey>,
+    issuer: Option<String>,
+    audience: Option<String>,
+}
+
+impl JwtVerifier {
+    /// Builds a verifier from the configured keys, or `None` when no key is configured.
+    pub fn from_config(config: &Config) -> Result<Option<Self>, String> {
+        let hmac_key = config
+            .jwt_secret
+            .as_ref()
+            .map(|secret| DecodingKey::from_secret(secret.as_bytes()));
+
+        let rsa_key = match config.jwt_public_key_path {
+            Some(ref path) => {
+                let pem = fs::read(path)
+                    .map_err(|e| format!("Failed to read JWT public key {}: {}", path, e))?;
+                let key = DecodingKey::from_rsa_pem(&pem)
+                    .map_err(|e| format!("Invalid JWT public key {}: {}", path, e))?;
+                Some(key)
+            }
+            None => None,
+        };
+
+        let jwks = match config.jwt_jwks_path {
+            Some(ref path) => Self::load_jwks(path)?,
+            None => HashMap::new(),
+        };
+
+        if hmac_key.is_none() && rsa_key.is_none() && jwks.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            hmac_key,
+            rsa_key,
+            jwks,
+            issuer: config.jwt_issuer.clone(),
+            audience: config.jwt_audience.clone(),
+        }))
+    }
+
+    fn load_jwks(path: &str) -> Result<HashMap<String, DecodingKey>, String> {
+        let contents =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read JWKS {}: {}", path, e))?;
+        let set: JwkSet =
+            serde_json::from_str(&contents).map_err(|e| format!("Invalid JWKS {}: {}", path, e))?;
+
+        let mut keys = HashMap::new();
+        for jwk in &set.keys {
+            // Tokens pick their JWKS key by `kid`, so a key without one could never be used
+            let Some(ref kid) = jwk.common.key_id else {
+                return Err(format!("Key without a kid in JWKS {}", path));
+            };
+            let key = DecodingKey::from_jwk(jwk)
+                .map_err(|e| format!("Invalid key {} in JWKS {}: {}", kid, path, e))?;
+            keys.insert(kid.clone(), key);
+        }
+        Ok(keys)
+    }
+
+    pub fn verify(&self, token: &str) -> Result<Claims, ApiError> {
+        let header =
+            decode_header(token).map_err(|_| ApiError::unauthorized("Malformed bearer token"))?;
+
+        let key = match header.alg {
+            Algorithm::HS256 => self.hmac_key.as_ref(),
+            Algorithm::RS256 => match header.kid {
+                Some(ref kid) if !self.jwks.is_empty() => self.jwks.get(kid),
+                _ => self.rsa_key.as_ref(),
+            },
+            _ => None,
+        }
+        .ok_or_else(|| ApiError::unauthorized("Unsupported token signing key"))?;
+
+        let mut validation = Validation::new(header.alg);
+        match self.audience {
+            Some(ref audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+        if let Some(ref issuer) = self.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+
+        decode::<Claims>(token, key, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| match e.kind() {
+                ErrorKind::ExpiredSignature => ApiError::unauthorized("Token has expired"),
+                _ => ApiError::unauthorized("Invalid bearer token"),
+            })
+    }
+}
+
+/// Middleware that authenticates `/api/v1` requests with a JWT bearer token.
+///
+/// Requests already authenticated by another scheme are passed through. Without a verifier
+/// the API runs in anonymous mode, which the server only allows with `AUTH_MODE=anonymous`:
+/// unauthenticated requests run as the unprivileged [`Principal::anonymous`].
+#[derive(Clone)]
+pub struct JwtAuth {
+    verifier: Option<Arc<JwtVerifier>>,
+}
+
+impl JwtAuth {
+    pub fn new(verifier: Option<Arc<JwtVerifier>>) -> Self {
+        Self { verifier }
+    }
+}
+
+impl<S> Middleware<S> for JwtAuth {
+    type Service = JwtAuthMiddleware<S>;
+
+    fn create(&self, service: S) -> Self::Service {
+        JwtAuthMiddleware {
+            service,
+            verifier: self.verifier.clone(),
+        }
+    }
+}
+
+pub struct JwtAuthMiddleware<S> {
+    service: S,
+    verifier: Option<Arc<JwtVerifier>>,
+}
+
+impl<S> Service<WebRequest<DefaultError>> for JwtAuthMiddleware<S>
+where
+    S: Service<WebRequest<DefaultError>, Response = WebResponse>,
+{
+    type Response = WebResponse;
+    type Error = S::Error;
+
+    ntex::forward_poll!(service);
+    ntex::forward_ready!(service);
+    ntex::forward_shutdown!(service);
+
+    async fn call(
+        &self,
+        req: WebRequest<DefaultError>,
+        ctx: ServiceCtx<'_, Self>,
+    ) -> Result<Self::Response, Self::Error> {
+        if requires_auth(&req) && !req.extensions().contains::<Principal>() {
+            let result = match self.verifier {
+                Some(ref verifier) => bearer_token(&req)
+                    .ok_or_else(|| ApiError::unauthorized("Missing bearer token"))
+                    .and_then(|token| verifier.verify(token))
+                    .and_then(|claims| {
+                        let tenant_id = resolve_tenant(&req, Some(claims.tenant_id()))?;
+                        Ok(claims.into_principal(tenant_id))
+                    }),
+                None => resolve_tenant(&req, None).map(Principal::anonymous),
+            };
+
+            match result {
+                Ok(principal) => {
+                    req.extensions_mut().insert(principal);
+                }
+                Err(err) => return Ok(req.render_error(err)),
+            }
+        }
+
+        ctx.call(&self.service, req).await
+    }
+}
+
+/// Middleware that authenticates `/api/v1` requests carrying an `X-API-Key` header.
+///
+/// Requests without the header are left for the other authentication schemes.
+#[derive(Clone)]
+pub struct ApiKeyAuth {
+    service: Arc<ApiKeyService>,
+}
+
+impl ApiKeyAuth {
+    pub fn new(service: Arc<ApiKeyService>) -> Self {
+        Self { service }
+    }
+}
+
+impl<S> Middleware<S> for ApiKeyAuth {
+    type Service = ApiKeyAuthMiddleware<S>;
+
+    fn create(&self, service: S) -> Self::Service {
+        ApiKeyAuthMiddleware {
+            service,
+            api_keys: self.service.clone(),
+        }
+    }
+}
+
+pub struct ApiKeyAuthMiddleware<S> {
+    service: S,
+    api_keys: Arc<ApiKeyService>,
+}
+
+impl<S> Service<WebRequest<DefaultError>> for ApiKeyAuthMiddleware<S>
+where
+    S: Service<WebRequest<DefaultError>, Response = WebResponse>,
+{
+    type Response = WebResponse;
+    type Error = S::Error;
+
+    ntex::forward_poll!(service);
+    ntex::forward_ready!(service);
+    ntex::forward_shutdown!(service);
+
+    async fn call(
+        &self,
+        req: WebRequest<DefaultError>,
+        ctx: ServiceCtx<'_, Self>,
+    ) -> Result<Self::Response, Self::Error> {
+        if requires_auth(&req) {
+            if let Some(key) = req.headers().get(API_KEY_HEADER) {
+                let result = match key.to_str() {
+                    Ok(key) => self.api_keys.authenticate(key.trim()).await,
+                    Err(_) => Err(ApiError::unauthorized("Invalid API key")),
+                }
+                .and_then(|principal| {
+                    resolve_tenant(&req, Some(&principal.tenant_id))?;
+                    Ok(principal)
+                });
+
+                match result {
+                    Ok(principal) => {
+                        req.extensions_mut().insert(principal);
+                    }
+                    Err(err) => return Ok(req.render_error(err)),
+                }
+            }
+        }
+
+        ctx.call(&self.service, req).await
+    }
+}
+
+/// Middleware that authenticates calendar feed requests carrying a `?token=` calendar token,
+/// for calendar clients that cannot send headers.
+///
+/// The token only works on `.ics` routes; other requests are left for the other schemes.
+#[derive(Clone)]
+pub struct CalendarTokenAuth {
+    service: Arc<CalendarService>,
+}
+
+impl CalendarTokenAuth {
+    pub fn new(service: Arc<CalendarService>) -> Self {
+        Self { service }
+    }
+}
+
+impl<S> Middleware<S> for CalendarTokenAuth {
+    type Service = CalendarTokenAuthMiddleware<S>;
+
+    fn create(&self, service: S) -> Self::Service {
+        CalendarTokenAuthMiddleware {
+            service,
+            calendars: self.service.clone(),
+        }
+    }
+}
+
+pub struct CalendarTokenAuthMiddleware<S> {
+    service: S,
+    calendars: Arc<CalendarService>,
+}
+
+impl<S> Service<WebRequest<DefaultError>> for CalendarTokenAuthMiddleware<S>
+where
+    S: Service<WebRequest<DefaultError>, Response = WebResponse>,
+{
+    type Response = WebResponse;
+    type Error = S::Error;
+
+    ntex::forward_poll!(service);
+    ntex::forward_ready!(service);
+    ntex::forward_shutdown!(service);
+
+    async fn call(
+        &self,
+        req: WebRequest<DefaultError>,
+        ctx: ServiceCtx<'_, Self>,
+    ) -> Result<Self::Response, Self::Error> {
+        if requires_auth(&req)
+            && req.method() == Method::GET
+            && req.path().ends_with(CALENDAR_FEED_SUFFIX)
+            && !req.extensions().contains::<Principal>()
+        {
+            if let Some(token) = calendar_token(&req) {
+                let result = self
+                    .calendars
+                    .authenticate(token)
+                    .await
+                    .and_then(|principal| {
+                        resolve_tenant(&req, Some(&principal.tenant_id))?;
+                        Ok(principal)
+                    });
+
+                match result {
+                    Ok(principal) => {
+                        req.extensions_mut().insert(principal);
+                    }
+                    Err(err) => return Ok(req.render_error(err)),
+                }
+            }
+        }
+
+        ctx.call(&self.service, req).await
+    }
+}
+
+fn requires_auth(req: &WebRequest<DefaultError>) -> bool {
+    (req.path().starts_with(PROTECTED_PREFIX) || req.path() == GRAPHQL_PATH)
+        && req.method() != Method::OPTIONS
+}
+
+/// Picks the request tenant from the `X-Tenant-Id` header; see [`select_tenant`].
+fn resolve_tenant(req: &WebRequest<DefaultError>, bound: Option<&str>) -> Result<String, ApiError> {
+    let requested = match req.headers().get(TENANT_HEADER) {
+        Some(value) => Some(
+            value
+                .to_str()
+                .map_err(|_| ApiError::bad_request("Invalid X-Tenant-Id header"))?,
+        ),
+        None => None,
+    };
+    select_tenant(requested, bound)
+}
+
+/// Picks the tenant for a call: a tenant bound to the principal wins, otherwise the
+/// requested tenant, otherwise the default tenant. A bound principal requesting a
+/// different tenant is rejected; only anonymous principals are unbound.
+pub fn select_tenant(requested: Option<&str>, bound: Option<&str>) -> Result<String, ApiError> {
+    let requested = requested.map(str::trim);
+    if requested.is_some_and(|tenant_id| !is_valid_tenant_id(tenant_id)) {
+        return Err(ApiError::bad_request(
+            "X-Tenant-Id must be 1-64 alphanumeric, '-' or '_' characters",
+        ));
+    }
+
+    match (bound, requested) {
+        (Some(bound), Some(requested)) if bound != requested => Err(ApiError::forbidden(
+            "Principal does not belong to the requested tenant",
+        )),
+        (Some(bound), _) => Ok(bound.to_string()),
+        (None, Some(requested)) => Ok(requested.to_string()),
+        (None, None) => Ok(DEFAULT_TENANT.to_string()),
+    }
+}
+
+fn is_valid_tenant_id(tenant_id: &str) -> bool {
+    !tenant_id.is_empty()
+        && tenant_id.len() <= 64
+        && tenant_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn bearer_token(req: &WebRequest<DefaultError>) -> Option<&str> {
+    parse_bearer(req.headers().get(header::AUTHORIZATION)?.to_str().ok()?)
+}
+
+/// The token of a `Bearer <token>` authorization value.
+pub fn parse_bearer(authorization: &str) -> Option<&str> {
+    authorization
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+}
+
+fn calendar_token(req: &WebRequest<DefaultError>) -> Option<&str> {
+    req.query_string()
+        .split('&')
+        .find_map(|pair| pair.strip_prefix(CALENDAR_TOKEN_PARAM)?.strip_prefix('='))
+        .filter(|token| !token.is_empty())
+}
+
+impl FromRequest<DefaultError> for Principal {
+    type Error = ApiError;
+
+    async fn from_request(req: &HttpRequest, _: &mut Payload) -> Result<Self, Self::Error> {
+        let extensions = req.extensions();
+        let mut principal = extensions
+            .get::<Principal>()
+            .cloned()
+            .ok_or_else(|| ApiError::unauthorized("Authentication required"))?;
+        principal.request_id = extensions.get::<RequestId>().map(|id| id.0.clone());
+        Ok(principal)
+    }
+}
@@ -10,7 +10,7 @@ pub fn cors_middleware() -> DefaultHeaders {
         )
         .header(
             header::ACCESS_CONTROL_ALLOW_HEADERS,
-            "Content-Type, Authorization",
+            "Content-Type, Authorization, X-API-Key, X-Tenant-Id",
         )
         .header(header::ACCESS_CONTROL_MAX_AGE, "3600")
 }
@@ -1,5 +1,11 @@
+pub mod auth;
 pub mod cors;
 pub mod logging;
+pub mod negotiation;
+pub mod request_id;
 
+pub use auth::*;
 pub use cors::*;
 pub use logging::*;
+pub use negotiation::*;
+pub use request_id::*;
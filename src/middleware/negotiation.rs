@@ -0,0 +1,101 @@
+use crate::views::{ApiError, ContentFormat};
+use ntex::http::body::{Body, ResponseBody};
+use ntex::http::header::{self, HeaderValue};
+use ntex::http::Method;
+use ntex::service::{Middleware, Service, ServiceCtx};
+use ntex::web::{DefaultError, WebRequest, WebResponse};
+
+/// Middleware that serves JSON responses in the format the client asks for with `Accept`:
+/// JSON, MessagePack or CBOR, depending on the enabled features.
+///
+/// Only `application/json` responses are negotiated; CSV exports, calendar feeds, backups
+/// and event streams keep their own media types. When `Accept` rules out every format, a
+/// request that could change state is refused with 406 before it reaches its handler, while
+/// other requests get 406 only if they would have answered with JSON.
+#[derive(Clone, Default)]
+pub struct ContentNegotiation;
+
+impl<S> Middleware<S> for ContentNegotiation {
+    type Service = ContentNegotiationMiddleware<S>;
+
+    fn create(&self, service: S) -> Self::Service {
+        ContentNegotiationMiddleware { service }
+    }
+}
+
+pub struct ContentNegotiationMiddleware<S> {
+    service: S,
+}
+
+impl<S> Service<WebRequest<DefaultError>> for ContentNegotiationMiddleware<S>
+where
+    S: Service<WebRequest<DefaultError>, Response = WebResponse>,
+{
+    type Response = WebResponse;
+    type Error = S::Error;
+
+    ntex::forward_poll!(service);
+    ntex::forward_ready!(service);
+    ntex::forward_shutdown!(service);
+
+    async fn call(
+        &self,
+        req: WebRequest<DefaultError>,
+        ctx: ServiceCtx<'_, Self>,
+    ) -> Result<Self::Response, Self::Error> {
+        let format = match ContentFormat::negotiate(
+            req.headers()
+                .get(header::ACCEPT)
+                .and_then(|value| value.to_str().ok()),
+        ) {
+            Err(err) if !matches!(*req.method(), Method::GET | Method::HEAD) => {
+                return Ok(req.render_error(err));
+            }
+            format => format,
+        };
+
+        let mut res = ctx.call(&self.service, req).await?;
+        if !is_json(&res) {
+            return Ok(res);
+        }
+        res.headers_mut()
+            .append(header::VARY, HeaderValue::from_static("accept"));
+
+        let format = match format {
+            Ok(format) => format,
+            // Errors still go out as JSON rather than being masked by a 406
+            Err(_) if !res.status().is_success() => return Ok(res),
+            Err(err) => return Ok(res.error_response::<DefaultError, _>(err)),
+        };
+        if format == ContentFormat::Json {
+            return Ok(res);
+        }
+
+        let encoded = match res.take_body() {
+            ResponseBody::Body(Body::Bytes(json)) | ResponseBody::Other(Body::Bytes(json)) => {
+                serde_json::from_slice(&json)
+                    .map_err(|e| e.to_string())
+                    .and_then(|value| format.encode(&value))
+            }
+            body => return Ok(res.map_body(|_, _| body)),
+        };
+        match encoded {
+            Ok(bytes) => {
+                res.headers_mut().insert(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static(format.media_type()),
+                );
+                Ok(res.map_body(|_, _| ResponseBody::Other(Body::from(bytes))))
+            }
+            Err(_) => Ok(res.error_response::<DefaultError, _>(ApiError::InternalServerError)),
+        }
+    }
+}
+
+fn is_json(res: &WebResponse) -> bool {
+    res.headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|essence| essence.trim().eq_ignore_ascii_case("application/json"))
+}
@@ -0,0 +1,244 @@
+use crate::models::{Project, ProjectMember, RecurringTask, Task, TimeEntry};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "openapi")]
+use utoipa::{IntoParams, ToSchema};
+
+/// Archive format written by this build; restores reject other versions.
+pub const BACKUP_VERSION: u32 = 1;
+
+/// Projects and tasks, trashed ones included, with the records that hang off them. Ids and
+/// timestamps are kept as they are. Audit entries, revisions and credentials such as API
+/// keys, calendar tokens and webhooks are not part of a backup.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct Backup {
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub projects: Vec<Project>,
+    #[serde(default)]
+    pub tasks: Vec<Task>,
+    #[serde(default)]
+    pub members: Vec<ProjectMember>,
+    #[serde(default)]
+    pub recurring_tasks: Vec<BackupRecurringTask>,
+    #[serde(default)]
+    pub time_entries: Vec<TimeEntry>,
+}
+
+/// A recurring task together with the scopes its scheduler acts with, which the API never
+/// shows.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct BackupRecurringTask {
+    #[serde(flatten)]
+    pub recurring_task: RecurringTask,
+    #[serde(default)]
+    pub created_by_scopes: Vec<String>,
+}
+
+/// One line of an NDJSON backup; the first line is the header.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum BackupRecord {
+    Header {
+        version: u32,
+        created_at: DateTime<Utc>,
+    },
+    Project(Project),
+    Task(Task),
+    Member(ProjectMember),
+    RecurringTask(BackupRecurringTask),
+    TimeEntry(TimeEntry),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum BackupFormat {
+    /// A header line, then one record per line
+    #[default]
+    Ndjson,
+    /// A single [`Backup`] document
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum RestoreMode {
+    /// Add the archive's records, overwriting stored ones with the same id
+    #[default]
+    Merge,
+    /// Remove the tenant's records first, leaving exactly what the archive holds
+    Replace,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(IntoParams))]
+pub struct BackupQuery {
+    #[serde(default)]
+    pub format: BackupFormat,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(IntoParams))]
+pub struct RestoreQuery {
+    #[serde(default)]
+    pub format: BackupFormat,
+    #[serde(default)]
+    pub mode: RestoreMode,
+}
+
+/// Records written by a restore.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct RestoreReport {
+    pub mode: RestoreMode,
+    pub projects: usize,
+    pub tasks: usize,
+    pub members: usize,
+    pub recurring_tasks: usize,
+    pub time_entries: usize,
+}
+
+impl Backup {
+    pub fn new(created_at: DateTime<Utc>) -> Self {
+        Self {
+            version: BACKUP_VERSION,
+            created_at,
+            projects: Vec::new(),
+            tasks: Vec::new(),
+            members: Vec::new(),
+            recurring_tasks: Vec::new(),
+            time_entries: Vec::new(),
+        }
+    }
+
+    /// The header followed by every record, each as one line of JSON.
+    pub fn to_ndjson(&self) -> Result<String, serde_json::Error> {
+        let records = std::iter::once(BackupRecord::Header {
+            version: self.version,
+            created_at: self.created_at,
+        })
+        .chain(self.projects.iter().cloned().map(BackupRecord::Project))
+        .chain(self.tasks.iter().cloned().map(BackupRecord::Task))
+        .chain(self.members.iter().cloned().map(BackupRecord::Member))
+        .chain(
+            self.recurring_tasks
+                .iter()
+                .cloned()
+                .map(BackupRecord::RecurringTask),
+        )
+        .chain(
+            self.time_entries
+                .iter()
+                .cloned()
+                .map(BackupRecord::TimeEntry),
+        );
+
+        let mut ndjson = String::new();
+        for record in records {
+            ndjson.push_str(&serde_json::to_string(&record)?);
+            ndjson.push('\n');
+        }
+        Ok(ndjson)
+    }
+
+    fn push(&mut self, record: BackupRecord) -> Result<(), String> {
+        match record {
+            BackupRecord::Header { .. } => return Err("Header appears more than once".to_string()),
+            BackupRecord::Project(project) => self.projects.push(project),
+            BackupRecord::Task(task) => self.tasks.push(task),
+            BackupRecord::Member(member) => self.members.push(member),
+            BackupRecord::RecurringTask(recurring_task) => {
+                self.recurring_tasks.push(recurring_task)
+            }
+            BackupRecord::TimeEntry(entry) => self.time_entries.push(entry),
+        }
+        Ok(())
+    }
+}
+
+/// Reads a backup in either format from chunks of any size. NDJSON lines are parsed as soon
+/// as they are complete, so only the records are held in memory, not the text.
+#[derive(Debug)]
+pub struct BackupReader {
+    format: BackupFormat,
+    pending: Vec<u8>,
+    line: usize,
+    backup: Option<Backup>,
+}
+
+impl BackupReader {
+    pub fn new(format: BackupFormat) -> Self {
+        Self {
+            format,
+            pending: Vec::new(),
+            line: 0,
+            backup: None,
+        }
+    }
+
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<(), String> {
+        self.pending.extend_from_slice(chunk);
+        if self.format == BackupFormat::Json {
+            return Ok(());
+        }
+
+        let mut start = 0;
+        while let Some(end) = self.pending[start..].iter().position(|&byte| byte == b'\n') {
+            let line = self.pending[start..start + end].to_vec();
+            start += end + 1;
+            self.read_line(&line)?;
+        }
+        self.pending.drain(..start);
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<Backup, String> {
+        if self.format == BackupFormat::Json {
+            return serde_json::from_slice(&self.pending)
+                .map_err(|e| format!("Backup is not valid JSON: {}", e));
+        }
+
+        let rest = std::mem::take(&mut self.pending);
+        self.read_line(&rest)?;
+        self.backup
+            .ok_or_else(|| "Backup is empty; it needs a header line".to_string())
+    }
+
+    fn read_line(&mut self, line: &[u8]) -> Result<(), String> {
+        self.line += 1;
+        if line.trim_ascii().is_empty() {
+            return Ok(());
+        }
+        let record: BackupRecord =
+            serde_json::from_slice(line).map_err(|e| format!("Line {}: {}", self.line, e))?;
+
+        match (&mut self.backup, record) {
+            (
+                None,
+                BackupRecord::Header {
+                    version,
+                    created_at,
+                },
+            ) => {
+                self.backup = Some(Backup {
+                    version,
+                    ..Backup::new(created_at)
+                });
+                Ok(())
+            }
+            (None, _) => Err(format!(
+                "Line {}: the first record must be the header",
+                self.line
+            )),
+            (Some(backup), record) => backup
+                .push(record)
+                .map_err(|e| format!("Line {}: {}", self.line, e)),
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[cfg(feature = "openapi")]
+use utoipa::{IntoParams, ToSchema};
+
+/// Secret that lets a calendar client read its owner's feeds through `?token=` when it cannot
+/// send authentication headers. It stores no scopes: the owner's project access is looked up
+/// whenever the token is used.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct CalendarToken {
+    pub id: Uuid,
+    pub tenant_id: String,
+    /// Owner; feeds read with the token show what this subject can see
+    pub subject: String,
+    pub name: String,
+    #[serde(skip_serializing)]
+    pub salt: String,
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct CalendarTokenCreate {
+    pub name: String,
+    /// The token stops working at this instant; it never expires when absent
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Response for a freshly minted token; the plaintext `token` is never returned again.
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct CalendarTokenCreated {
+    pub token: String,
+    /// The caller's feed, ready to paste into a calendar client
+    pub feed_url: String,
+    pub calendar_token: CalendarToken,
+}
+
+/// Calendar component each task is emitted as.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum CalendarComponent {
+    /// `VTODO` with due date and completion status
+    #[default]
+    Todo,
+    /// Zero-length `VEVENT` at the due date, for clients that ignore to-dos
+    Event,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(IntoParams))]
+pub struct CalendarQuery {
+    #[serde(default)]
+    pub component: CalendarComponent,
+    /// Calendar token, for clients that cannot send authentication headers
+    pub token: Option<String>,
+}
+
+impl CalendarToken {
+    pub fn new(
+        tenant_id: String,
+        subject: String,
+        name: String,
+        salt: String,
+        token_hash: String,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            tenant_id,
+            subject,
+            name,
+            salt,
+            token_hash,
+            created_at: Utc::now(),
+            last_used_at: None,
+            expires_at,
+        }
+    }
+
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_none_or(|expires| expires > now)
+    }
+}
@@ -0,0 +1,43 @@
+use crate::models::EntityType;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+#[cfg(feature = "openapi")]
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeOperation {
+    Upsert,
+    Delete,
+}
+
+/// Latest mutation of a project or task; deletes are kept as tombstones.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct Change {
+    /// Position in the global change sequence, increasing with every mutation.
+    pub seq: u64,
+    #[serde(skip)]
+    pub tenant_id: String,
+    pub entity_type: EntityType,
+    pub entity_id: Uuid,
+    pub project_id: Uuid,
+    pub operation: ChangeOperation,
+    /// Entity as of this change; absent for deletes.
+    pub data: Option<Value>,
+    pub changed_at: DateTime<Utc>,
+}
+
+/// A page of changes in sequence order.
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct ChangeFeed {
+    pub changes: Vec<Change>,
+    /// Value to pass as `since` for the next page.
+    pub next_since: u64,
+    pub has_more: bool,
+}
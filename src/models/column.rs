@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[cfg(feature = "openapi")]
+use utoipa::ToSchema;
+
+#[cfg(feature = "graphql")]
+use async_graphql::SimpleObject;
+
+/// A column on a project's board.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[cfg_attr(feature = "graphql", derive(SimpleObject))]
+pub struct Column {
+    pub id: Uuid,
+    pub name: String,
+    /// Most tasks the column may hold; no limit when unset
+    pub wip_limit: Option<usize>,
+    /// Tasks in the done column have `done = true`, and tasks elsewhere `done = false`
+    #[serde(default)]
+    pub done: bool,
+}
+
+/// One column in a board definition; give the `id` of an existing column to keep it and its
+/// tasks.
+#[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct ColumnDefinition {
+    pub id: Option<Uuid>,
+    pub name: String,
+    pub wip_limit: Option<usize>,
+    #[serde(default)]
+    pub done: bool,
+}
@@ -0,0 +1,167 @@
+use crate::models::{Project, Task};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+
+#[cfg(feature = "openapi")]
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    ProjectCreated,
+    ProjectUpdated,
+    ProjectDeleted,
+    ProjectRestored,
+    TaskCreated,
+    TaskUpdated,
+    TaskDeleted,
+    TaskRestored,
+    TaskDueSoon,
+    TaskOverdue,
+}
+
+impl EventKind {
+    pub const ALL: [EventKind; 10] = [
+        EventKind::ProjectCreated,
+        EventKind::ProjectUpdated,
+        EventKind::ProjectDeleted,
+        EventKind::ProjectRestored,
+        EventKind::TaskCreated,
+        EventKind::TaskUpdated,
+        EventKind::TaskDeleted,
+        EventKind::TaskRestored,
+        EventKind::TaskDueSoon,
+        EventKind::TaskOverdue,
+    ];
+
+    /// Wire name, as used in serialized events.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::ProjectCreated => "project_created",
+            EventKind::ProjectUpdated => "project_updated",
+            EventKind::ProjectDeleted => "project_deleted",
+            EventKind::ProjectRestored => "project_restored",
+            EventKind::TaskCreated => "task_created",
+            EventKind::TaskUpdated => "task_updated",
+            EventKind::TaskDeleted => "task_deleted",
+            EventKind::TaskRestored => "task_restored",
+            EventKind::TaskDueSoon => "task_due_soon",
+            EventKind::TaskOverdue => "task_overdue",
+        }
+    }
+}
+
+impl FromStr for EventKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        EventKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s)
+            .ok_or_else(|| format!("Unknown event type '{}'", s))
+    }
+}
+
+/// Change to a project or task, published after the repository write succeeded, or a due-date
+/// reminder raised by the reminder job.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DomainEvent {
+    ProjectCreated {
+        project: Project,
+    },
+    ProjectUpdated {
+        before: Project,
+        after: Project,
+    },
+    ProjectDeleted {
+        project: Project,
+        cascaded_tasks: Vec<Task>,
+    },
+    ProjectRestored {
+        project: Project,
+        restored_tasks: Vec<Task>,
+    },
+    TaskCreated {
+        task: Task,
+    },
+    TaskUpdated {
+        before: Task,
+        after: Task,
+    },
+    TaskDeleted {
+        task: Task,
+    },
+    TaskRestored {
+        task: Task,
+    },
+    TaskDueSoon {
+        task: Task,
+    },
+    TaskOverdue {
+        task: Task,
+    },
+}
+
+impl DomainEvent {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            DomainEvent::ProjectCreated { .. } => EventKind::ProjectCreated,
+            DomainEvent::ProjectUpdated { .. } => EventKind::ProjectUpdated,
+            DomainEvent::ProjectDeleted { .. } => EventKind::ProjectDeleted,
+            DomainEvent::ProjectRestored { .. } => EventKind::ProjectRestored,
+            DomainEvent::TaskCreated { .. } => EventKind::TaskCreated,
+            DomainEvent::TaskUpdated { .. } => EventKind::TaskUpdated,
+            DomainEvent::TaskDeleted { .. } => EventKind::TaskDeleted,
+            DomainEvent::TaskRestored { .. } => EventKind::TaskRestored,
+            DomainEvent::TaskDueSoon { .. } => EventKind::TaskDueSoon,
+            DomainEvent::TaskOverdue { .. } => EventKind::TaskOverdue,
+        }
+    }
+
+    /// Project the changed entity belongs to.
+    pub fn project_id(&self) -> Uuid {
+        match self {
+            DomainEvent::ProjectCreated { project }
+            | DomainEvent::ProjectDeleted { project, .. }
+            | DomainEvent::ProjectRestored { project, .. } => project.id,
+            DomainEvent::ProjectUpdated { after, .. } => after.id,
+            DomainEvent::TaskCreated { task }
+            | DomainEvent::TaskDeleted { task }
+            | DomainEvent::TaskRestored { task }
+            | DomainEvent::TaskDueSoon { task }
+            | DomainEvent::TaskOverdue { task } => task.project_id,
+            DomainEvent::TaskUpdated { after, .. } => after.project_id,
+        }
+    }
+}
+
+/// A published event together with who caused it and when.
+#[derive(Debug, Clone, Serialize)]
+pub struct EventEnvelope {
+    pub id: Uuid,
+    pub tenant_id: String,
+    pub actor: String,
+    pub request_id: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+    pub event: DomainEvent,
+}
+
+/// Narrows a stream of events to one project and/or a set of event types.
+#[derive(Debug, Clone, Default)]
+pub struct EventFilter {
+    pub project_id: Option<Uuid>,
+    /// Empty means every type.
+    pub event_types: Vec<EventKind>,
+}
+
+impl EventFilter {
+    pub fn matches(&self, event: &DomainEvent) -> bool {
+        self.project_id
+            .is_none_or(|project_id| project_id == event.project_id())
+            && (self.event_types.is_empty() || self.event_types.contains(&event.kind()))
+    }
+}
@@ -1,5 +1,39 @@
+pub mod api_key;
+pub mod audit;
+pub mod backup;
+pub mod calendar;
+pub mod change;
+pub mod column;
+pub mod event;
+pub mod membership;
+pub mod principal;
 pub mod project;
+pub mod recurrence;
+pub mod reminder;
+pub mod revision;
+pub mod stats;
 pub mod task;
+pub mod task_import;
+pub mod time_entry;
+pub mod trash;
+pub mod webhook;
 
+pub use api_key::*;
+pub use audit::*;
+pub use backup::*;
+pub use calendar::*;
+pub use change::*;
+pub use column::*;
+pub use event::*;
+pub use membership::*;
+pub use principal::*;
 pub use project::*;
+pub use recurrence::*;
+pub use reminder::*;
+pub use revision::*;
+pub use stats::*;
 pub use task::*;
+pub use task_import::*;
+pub use time_entry::*;
+pub use trash::*;
+pub use webhook::*;
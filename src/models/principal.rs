@@ -0,0 +1,56 @@
+use serde::Serialize;
+
+#[cfg(feature = "openapi")]
+use utoipa::ToSchema;
+
+/// Scope granting full access to every project and to administrative endpoints.
+pub const ADMIN_SCOPE: &str = "admin";
+
+/// Tenant used when neither the principal nor the request names one.
+pub const DEFAULT_TENANT: &str = "default";
+
+/// Identity of the caller attached to a request by the authentication middleware.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct Principal {
+    pub subject: String,
+    pub name: Option<String>,
+    pub scopes: Vec<String>,
+    pub tenant_id: String,
+    /// Correlation id of the request this principal was extracted for.
+    #[serde(skip)]
+    pub request_id: Option<String>,
+}
+
+impl Principal {
+    /// Principal for requests without credentials in anonymous mode. It holds no scopes, so
+    /// it only reaches projects it created or was added to.
+    pub fn anonymous(tenant_id: String) -> Self {
+        Self {
+            subject: "anonymous".to_string(),
+            name: None,
+            scopes: Vec::new(),
+            tenant_id,
+            request_id: None,
+        }
+    }
+
+    /// Principal that background jobs act as; it holds no scopes.
+    pub fn system(tenant_id: String, job: &str) -> Self {
+        Self {
+            subject: format!("system:{}", job),
+            name: None,
+            scopes: Vec::new(),
+            tenant_id,
+            request_id: None,
+        }
+    }
+
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+
+    pub fn is_admin(&self) -> bool {
+        self.has_scope(ADMIN_SCOPE)
+    }
+}
@@ -1,3 +1,4 @@
+use crate::models::Column;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -5,18 +6,33 @@ use uuid::Uuid;
 #[cfg(feature = "openapi")]
 use utoipa::ToSchema;
 
+#[cfg(feature = "graphql")]
+use async_graphql::{InputObject, SimpleObject};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[cfg_attr(feature = "graphql", derive(SimpleObject), graphql(complex))]
 pub struct Project {
     pub id: Uuid,
+    pub tenant_id: String,
     pub name: String,
     pub description: Option<String>,
+    /// Archived projects are read-only and left out of default listings
+    #[serde(default)]
+    pub archived: bool,
+    /// Board columns, in display order
+    #[serde(default)]
+    pub columns: Vec<Column>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Set while the project is in the trash
+    #[serde(default)]
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Deserialize)]
 #[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[cfg_attr(feature = "graphql", derive(InputObject))]
 pub struct ProjectCreate {
     pub name: String,
     pub description: Option<String>,
@@ -24,20 +40,25 @@ pub struct ProjectCreate {
 
 #[derive(Debug, Deserialize)]
 #[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[cfg_attr(feature = "graphql", derive(InputObject))]
 pub struct ProjectUpdate {
     pub name: Option<String>,
     pub description: Option<String>,
 }
 
 impl Project {
-    pub fn new(name: String, description: Option<String>) -> Self {
+    pub fn new(tenant_id: String, name: String, description: Option<String>) -> Self {
         let now = Utc::now();
         Self {
             id: Uuid::new_v4(),
+            tenant_id,
             name,
             description,
+            archived: false,
+            columns: Vec::new(),
             created_at: now,
             updated_at: now,
+            deleted_at: None,
         }
     }
 
@@ -50,4 +71,30 @@ impl Project {
         }
         self.updated_at = Utc::now();
     }
+
+    pub fn set_archived(&mut self, archived: bool) {
+        self.archived = archived;
+        self.updated_at = Utc::now();
+    }
+
+    pub fn set_columns(&mut self, columns: Vec<Column>) {
+        self.columns = columns;
+        self.updated_at = Utc::now();
+    }
+
+    pub fn column(&self, id: &Uuid) -> Option<&Column> {
+        self.columns.iter().find(|column| column.id == *id)
+    }
+
+    /// The column whose tasks count as done, if the board has one.
+    pub fn done_column(&self) -> Option<&Column> {
+        self.columns.iter().find(|column| column.done)
+    }
+
+    /// Takes over the editable fields of an earlier version.
+    pub fn revert(&mut self, revision: &Project) {
+        self.name = revision.name.clone();
+        self.description = revision.description.clone();
+        self.updated_at = Utc::now();
+    }
 }
@@ -0,0 +1,410 @@
+use crate::models::Principal;
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+#[cfg(feature = "openapi")]
+use utoipa::ToSchema;
+
+#[cfg(feature = "graphql")]
+use async_graphql::SimpleObject;
+
+/// Periods scanned before giving up on finding another occurrence.
+const MAX_PERIODS: u32 = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+/// A `BYDAY` entry; the ordinal (`1MO`, `-1FR`) picks one weekday of the month.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByDay {
+    pub ordinal: Option<i8>,
+    pub weekday: Weekday,
+}
+
+/// A recurrence rule in RFC 5545 `RRULE` syntax, limited to daily, weekly and monthly
+/// frequencies with `INTERVAL`, `BYDAY`, `UNTIL` and `COUNT`. Occurrences keep the time of day
+/// of the first one, in UTC.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct RecurrenceRule {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub by_day: Vec<ByDay>,
+    /// Last instant an occurrence may fall on
+    pub until: Option<DateTime<Utc>>,
+    /// Number of occurrences, counting from the first
+    pub count: Option<u32>,
+}
+
+/// A template from which the scheduler creates a task at every occurrence of its rule.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct RecurringTask {
+    pub id: Uuid,
+    pub tenant_id: String,
+    pub project_id: Uuid,
+    pub title: String,
+    pub description: Option<String>,
+    #[cfg_attr(feature = "openapi", schema(value_type = String, example = "FREQ=WEEKLY;BYDAY=MO"))]
+    pub rule: RecurrenceRule,
+    /// First occurrence, which also fixes the time of day of later ones
+    pub starts_at: DateTime<Utc>,
+    /// When the next task is due to be created; `None` once the rule has run out
+    pub next_at: Option<DateTime<Utc>>,
+    /// Tasks are created on behalf of this subject
+    pub created_by: String,
+    /// Scopes the scheduler acts with, copied from the creator
+    #[serde(default, skip_serializing)]
+    pub created_by_scopes: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct RecurringTaskCreate {
+    pub project_id: Uuid,
+    pub title: String,
+    pub description: Option<String>,
+    /// e.g. `FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH;COUNT=10`
+    pub rrule: String,
+    /// Defaults to now
+    pub starts_at: Option<DateTime<Utc>>,
+}
+
+/// Marks a task as created for one occurrence of a recurring task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[cfg_attr(feature = "graphql", derive(SimpleObject))]
+pub struct TaskOccurrence {
+    pub recurring_task_id: Uuid,
+    pub occurs_at: DateTime<Utc>,
+}
+
+impl RecurringTask {
+    pub fn new(
+        creator: &Principal,
+        create: RecurringTaskCreate,
+        rule: RecurrenceRule,
+        starts_at: DateTime<Utc>,
+    ) -> Self {
+        let now = Utc::now();
+        let next_at = rule.occurrences(starts_at).next();
+        Self {
+            id: Uuid::new_v4(),
+            tenant_id: creator.tenant_id.clone(),
+            project_id: create.project_id,
+            title: create.title.trim().to_string(),
+            description: create.description,
+            next_at,
+            rule,
+            starts_at,
+            created_by: creator.subject.clone(),
+            created_by_scopes: creator.scopes.clone(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// The principal the scheduler creates tasks as.
+    pub fn creator(&self) -> Principal {
+        Principal {
+            subject: self.created_by.clone(),
+            name: None,
+            scopes: self.created_by_scopes.clone(),
+            tenant_id: self.tenant_id.clone(),
+            request_id: None,
+        }
+    }
+
+    /// Moves `next_at` past `now`, returning the latest occurrence that was due. Occurrences
+    /// missed while the scheduler was down are skipped rather than created in a burst.
+    pub fn advance(&mut self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut due = None;
+        self.next_at = None;
+        for occurrence in self.rule.occurrences(self.starts_at) {
+            if occurrence > now {
+                self.next_at = Some(occurrence);
+                break;
+            }
+            due = Some(occurrence);
+        }
+        self.updated_at = Utc::now();
+        due
+    }
+}
+
+impl RecurrenceRule {
+    /// Occurrences in order, starting from `start`.
+    pub fn occurrences(&self, start: DateTime<Utc>) -> impl Iterator<Item = DateTime<Utc>> + '_ {
+        let until = self.until;
+        (0..MAX_PERIODS)
+            .flat_map(move |index| self.period(start, index))
+            .filter(move |occurrence| *occurrence >= start)
+            .take_while(move |occurrence| until.is_none_or(|until| *occurrence <= until))
+            .take(self.count.map_or(usize::MAX, |count| count as usize))
+    }
+
+    /// Candidate occurrences in the `index`th period after the one containing `start`; none
+    /// once the period lies beyond the last representable date.
+    fn period(&self, start: DateTime<Utc>, index: u32) -> Vec<DateTime<Utc>> {
+        let step = index.saturating_mul(self.interval);
+        let first = start.date_naive();
+        let mut dates: Vec<NaiveDate> = match self.frequency {
+            Frequency::Daily => {
+                let Some(day) = first.checked_add_days(Days::new(step.into())) else {
+                    return Vec::new();
+                };
+                if self.by_day.is_empty() || self.by_day.iter().any(|d| d.weekday == day.weekday())
+                {
+                    vec![day]
+                } else {
+                    Vec::new()
+                }
+            }
+            Frequency::Weekly => {
+                let Some(monday) = first
+                    .checked_sub_days(Days::new(first.weekday().num_days_from_monday().into()))
+                    .and_then(|monday| monday.checked_add_days(Days::new(u64::from(step) * 7)))
+                else {
+                    return Vec::new();
+                };
+                let weekdays = if self.by_day.is_empty() {
+                    vec![first.weekday()]
+                } else {
+                    self.by_day.iter().map(|d| d.weekday).collect()
+                };
+                weekdays
+                    .into_iter()
+                    .filter_map(|weekday| {
+                        monday.checked_add_days(Days::new(weekday.num_days_from_monday().into()))
+                    })
+                    .collect()
+            }
+            Frequency::Monthly => {
+                let Some(month) = first
+                    .with_day(1)
+                    .and_then(|month| month.checked_add_months(Months::new(step)))
+                else {
+                    return Vec::new();
+                };
+                if self.by_day.is_empty() {
+                    month.with_day(first.day()).into_iter().collect()
+                } else {
+                    self.by_day
+                        .iter()
+                        .flat_map(|by_day| days_in_month(month, *by_day))
+                        .collect()
+                }
+            }
+        };
+        dates.sort();
+        dates.dedup();
+        dates
+            .into_iter()
+            .map(|date| Utc.from_utc_datetime(&date.and_time(start.time())))
+            .collect()
+    }
+}
+
+/// Days of `month` matching `by_day`: every such weekday, or only the one its ordinal picks.
+fn days_in_month(month: NaiveDate, by_day: ByDay) -> Vec<NaiveDate> {
+    let days: Vec<NaiveDate> = month
+        .iter_days()
+        .take_while(|day| day.month() == month.month())
+        .filter(|day| day.weekday() == by_day.weekday)
+        .collect();
+    match by_day.ordinal {
+        None => days,
+        Some(ordinal) if ordinal > 0 => days
+            .get(ordinal as usize - 1)
+            .copied()
+            .into_iter()
+            .collect(),
+        Some(ordinal) => days
+            .len()
+            .checked_sub(ordinal.unsigned_abs() as usize)
+            .and_then(|i| days.get(i).copied())
+            .into_iter()
+            .collect(),
+    }
+}
+
+impl FromStr for RecurrenceRule {
+    type Err = String;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let rule = rule.trim();
+        let rule = rule.strip_prefix("RRULE:").unwrap_or(rule);
+
+        let mut frequency = None;
+        let mut interval = 1;
+        let mut by_day = Vec::new();
+        let mut until = None;
+        let mut count = None;
+        for part in rule.split(';').filter(|part| !part.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("Malformed RRULE part '{}'", part))?;
+            match key.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        _ => return Err(format!("Unsupported FREQ '{}'", value)),
+                    })
+                }
+                "INTERVAL" => {
+                    interval = value
+                        .parse()
+                        .ok()
+                        .filter(|interval| *interval >= 1)
+                        .ok_or("INTERVAL must be a positive number")?
+                }
+                "BYDAY" => {
+                    by_day = value
+                        .split(',')
+                        .map(parse_by_day)
+                        .collect::<Result<_, _>>()?
+                }
+                "UNTIL" => until = Some(parse_until(value)?),
+                "COUNT" => {
+                    count = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|count| *count >= 1)
+                            .ok_or("COUNT must be a positive number")?,
+                    )
+                }
+                _ => return Err(format!("Unsupported RRULE part '{}'", key)),
+            }
+        }
+
+        let frequency = frequency.ok_or("RRULE needs a FREQ")?;
+        if until.is_some() && count.is_some() {
+            return Err("RRULE cannot have both UNTIL and COUNT".to_string());
+        }
+        if frequency != Frequency::Monthly && by_day.iter().any(|d: &ByDay| d.ordinal.is_some()) {
+            return Err("Numbered BYDAY values need FREQ=MONTHLY".to_string());
+        }
+
+        Ok(Self {
+            frequency,
+            interval,
+            by_day,
+            until,
+            count,
+        })
+    }
+}
+
+fn parse_by_day(value: &str) -> Result<ByDay, String> {
+    let invalid = || format!("Invalid BYDAY value '{}'", value);
+    let split = value.len().checked_sub(2).ok_or_else(invalid)?;
+    let (ordinal, code) = value.split_at_checked(split).ok_or_else(invalid)?;
+    let weekday = match code.to_ascii_uppercase().as_str() {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return Err(invalid()),
+    };
+    let ordinal = match ordinal {
+        "" => None,
+        ordinal => Some(
+            ordinal
+                .trim_start_matches('+')
+                .parse::<i8>()
+                .ok()
+                .filter(|n| *n != 0 && (-5..=5).contains(n))
+                .ok_or_else(invalid)?,
+        ),
+    };
+    Ok(ByDay { ordinal, weekday })
+}
+
+/// `UNTIL` as a UTC date-time (`20250101T090000Z`) or a date, which includes the whole day.
+fn parse_until(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(until) = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%SZ") {
+        return Ok(Utc.from_utc_datetime(&until));
+    }
+    NaiveDate::parse_from_str(value, "%Y%m%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(23, 59, 59))
+        .map(|until| Utc.from_utc_datetime(&until))
+        .ok_or_else(|| {
+            format!(
+                "Invalid UNTIL '{}'; use YYYYMMDD or YYYYMMDDTHHMMSSZ",
+                value
+            )
+        })
+}
+
+impl fmt::Display for RecurrenceRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let frequency = match self.frequency {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+        };
+        write!(f, "FREQ={}", frequency)?;
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if !self.by_day.is_empty() {
+            let days: Vec<String> = self
+                .by_day
+                .iter()
+                .map(|by_day| {
+                    let code = match by_day.weekday {
+                        Weekday::Mon => "MO",
+                        Weekday::Tue => "TU",
+                        Weekday::Wed => "WE",
+                        Weekday::Thu => "TH",
+                        Weekday::Fri => "FR",
+                        Weekday::Sat => "SA",
+                        Weekday::Sun => "SU",
+                    };
+                    match by_day.ordinal {
+                        Some(ordinal) => format!("{}{}", ordinal, code),
+                        None => code.to_string(),
+                    }
+                })
+                .collect();
+            write!(f, ";BYDAY={}", days.join(","))?;
+        }
+        if let Some(until) = self.until {
+            write!(f, ";UNTIL={}", until.format("%Y%m%dT%H%M%SZ"))?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={}", count)?;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<String> for RecurrenceRule {
+    type Error = String;
+
+    fn try_from(rule: String) -> Result<Self, Self::Error> {
+        rule.parse()
+    }
+}
+
+impl From<RecurrenceRule> for String {
+    fn from(rule: RecurrenceRule) -> Self {
+        rule.to_string()
+    }
+}
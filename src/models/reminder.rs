@@ -0,0 +1,49 @@
+use crate::models::Task;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[cfg(feature = "openapi")]
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ReminderKind {
+    /// The due date is within the lead time
+    DueSoon,
+    /// The due date has passed
+    Overdue,
+}
+
+/// Record of a reminder that fired for a task's due date.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reminder {
+    pub tenant_id: String,
+    pub task_id: Uuid,
+    pub kind: ReminderKind,
+    /// Due date the reminder was for; moving the due date re-arms the reminder
+    pub due_at: DateTime<Utc>,
+    pub sent_at: DateTime<Utc>,
+}
+
+impl Reminder {
+    pub fn new(task: &Task, kind: ReminderKind, due_at: DateTime<Utc>, now: DateTime<Utc>) -> Self {
+        Self {
+            tenant_id: task.tenant_id.clone(),
+            task_id: task.id,
+            kind,
+            due_at,
+            sent_at: now,
+        }
+    }
+}
+
+/// Number of open tasks past their due date in one project.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct OverdueCount {
+    pub project_id: Uuid,
+    pub project_name: String,
+    pub overdue: usize,
+}
@@ -0,0 +1,44 @@
+use crate::models::{EntityType, FieldChange};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+#[cfg(feature = "openapi")]
+use utoipa::{IntoParams, ToSchema};
+
+/// One version of a project or task, as written by a create, update or revert.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct Revision {
+    /// Position in the entity's history, starting at 1 for the created version
+    pub number: u32,
+    #[serde(skip)]
+    pub tenant_id: String,
+    pub entity_type: EntityType,
+    pub entity_id: Uuid,
+    /// Subject that wrote this version
+    pub actor: String,
+    pub updated_at: DateTime<Utc>,
+    /// The entity as it was stored
+    pub data: Value,
+}
+
+/// Field-level differences going from revision `from` to revision `to`.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct RevisionDiff {
+    pub from: u32,
+    pub to: u32,
+    pub changes: BTreeMap<String, FieldChange>,
+}
+
+#[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(IntoParams))]
+pub struct RevisionDiffQuery {
+    /// Older revision number
+    pub from: u32,
+    /// Newer revision number; the latest when absent
+    pub to: Option<u32>,
+}
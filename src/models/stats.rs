@@ -0,0 +1,101 @@
+use crate::models::{Revision, Task};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[cfg(feature = "openapi")]
+use utoipa::{IntoParams, ToSchema};
+
+/// Progress of a project's tasks.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct ProjectStats {
+    pub project_id: Uuid,
+    pub total: usize,
+    pub open: usize,
+    pub done: usize,
+    /// Share of tasks that are done, 0 to 100
+    pub completion_percent: f64,
+    /// Mean time from creation to the last completion of the tasks that are done
+    pub average_hours_to_done: Option<f64>,
+    /// Task counts at the end of each day, oldest first; today counts up to now
+    pub burndown: Vec<BurndownPoint>,
+}
+
+/// Task counts at the end of one day: `open` burns down while `done` burns up towards
+/// `total`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct BurndownPoint {
+    pub date: NaiveDate,
+    pub total: usize,
+    pub open: usize,
+    pub done: usize,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(IntoParams))]
+pub struct ProjectStatsQuery {
+    /// First day of the burndown; defaults to 30 days before `to`, but not before the project
+    /// was created
+    pub from: Option<NaiveDate>,
+    /// Last day of the burndown; defaults to today
+    pub to: Option<NaiveDate>,
+}
+
+/// When a task existed and when it was done, rebuilt from its revisions.
+#[derive(Debug, Clone)]
+pub struct TaskHistory {
+    created_at: DateTime<Utc>,
+    deleted_at: Option<DateTime<Utc>>,
+    /// Value of `done` from each instant on, oldest first
+    done: Vec<(DateTime<Utc>, bool)>,
+}
+
+impl TaskHistory {
+    /// Falls back to the task's current state when it has no revisions.
+    pub fn new(task: &Task, revisions: &[Revision]) -> Self {
+        let mut done: Vec<(DateTime<Utc>, bool)> = Vec::new();
+        for revision in revisions {
+            let Some(value) = revision.data.get("done").and_then(|done| done.as_bool()) else {
+                continue;
+            };
+            if done.last().is_none_or(|&(_, last)| last != value) {
+                done.push((revision.updated_at, value));
+            }
+        }
+        if done.is_empty() {
+            done.push((task.created_at, task.done));
+        }
+
+        Self {
+            created_at: task.created_at,
+            deleted_at: task.deleted_at,
+            done,
+        }
+    }
+
+    pub fn exists_at(&self, at: DateTime<Utc>) -> bool {
+        self.created_at <= at && self.deleted_at.is_none_or(|deleted_at| deleted_at > at)
+    }
+
+    pub fn is_done_at(&self, at: DateTime<Utc>) -> bool {
+        self.done
+            .iter()
+            .take_while(|&&(since, _)| since <= at)
+            .last()
+            .is_some_and(|&(_, done)| done)
+    }
+
+    /// Start of the current done stretch, if the task is done.
+    pub fn completed_at(&self) -> Option<DateTime<Utc>> {
+        self.done
+            .last()
+            .filter(|&&(_, done)| done)
+            .map(|&(since, _)| since)
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+}
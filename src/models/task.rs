@@ -1,3 +1,4 @@
+use crate::models::TaskOccurrence;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -5,45 +6,100 @@ use uuid::Uuid;
 #[cfg(feature = "openapi")]
 use utoipa::ToSchema;
 
+#[cfg(feature = "graphql")]
+use async_graphql::{InputObject, SimpleObject};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[cfg_attr(feature = "graphql", derive(SimpleObject))]
 pub struct Task {
     pub id: Uuid,
+    pub tenant_id: String,
     pub project_id: Uuid,
     pub title: String,
     pub description: Option<String>,
     pub done: bool,
+    /// Position within the project; tasks sort by this key
+    #[serde(default)]
+    pub rank: String,
+    /// Board column of the task's project, if assigned
+    #[serde(default)]
+    pub column_id: Option<Uuid>,
+    /// When the task should be done; reminders fire before and after it
+    #[serde(default)]
+    pub due_at: Option<DateTime<Utc>>,
+    /// Set on tasks created by a recurring task
+    #[serde(default)]
+    pub occurrence: Option<TaskOccurrence>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Set while the task is in the trash
+    #[serde(default)]
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Deserialize)]
 #[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[cfg_attr(feature = "graphql", derive(InputObject))]
 pub struct TaskCreate {
     pub project_id: Uuid,
     pub title: String,
     pub description: Option<String>,
+    /// Board column to start in; defaults to the project's first column
+    pub column_id: Option<Uuid>,
+    pub due_at: Option<DateTime<Utc>>,
+    /// Set by the recurring task scheduler; creating the same occurrence again returns the
+    /// existing task
+    #[serde(skip)]
+    #[cfg_attr(feature = "graphql", graphql(skip))]
+    pub occurrence: Option<TaskOccurrence>,
 }
 
 #[derive(Debug, Deserialize)]
 #[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[cfg_attr(feature = "graphql", derive(InputObject))]
 pub struct TaskUpdate {
     pub title: Option<String>,
     pub description: Option<String>,
     pub done: Option<bool>,
+    /// Moves the task to another column; takes precedence over `done`
+    pub column_id: Option<Uuid>,
+    pub due_at: Option<DateTime<Utc>>,
+}
+
+/// Where to put a task within its project; give either neighbour, or both when they are
+/// adjacent.
+#[derive(Debug, Default, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct TaskMove {
+    /// Place the task directly in front of this one
+    pub before: Option<Uuid>,
+    /// Place the task directly behind this one
+    pub after: Option<Uuid>,
 }
 
 impl Task {
-    pub fn new(project_id: Uuid, title: String, description: Option<String>) -> Self {
+    pub fn new(
+        tenant_id: String,
+        project_id: Uuid,
+        title: String,
+        description: Option<String>,
+    ) -> Self {
         let now = Utc::now();
         Self {
             id: Uuid::new_v4(),
+            tenant_id,
             project_id,
             title,
             description,
             done: false,
+            rank: String::new(),
+            column_id: None,
+            due_at: None,
+            occurrence: None,
             created_at: now,
             updated_at: now,
+            deleted_at: None,
         }
     }
 
@@ -57,6 +113,36 @@ impl Task {
         if let Some(done) = update.done {
             self.done = done;
         }
+        if update.column_id.is_some() {
+            self.column_id = update.column_id;
+        }
+        if update.due_at.is_some() {
+            self.due_at = update.due_at;
+        }
+        self.updated_at = Utc::now();
+    }
+
+    pub fn set_done(&mut self, done: bool) {
+        self.done = done;
+        self.updated_at = Utc::now();
+    }
+
+    pub fn move_to(&mut self, rank: String) {
+        self.rank = rank;
+        self.updated_at = Utc::now();
+    }
+
+    /// Open and past its due date at `now`.
+    pub fn is_overdue(&self, now: DateTime<Utc>) -> bool {
+        !self.done && self.due_at.is_some_and(|due_at| due_at <= now)
+    }
+
+    /// Takes over the editable fields of an earlier version.
+    pub fn revert(&mut self, revision: &Task) {
+        self.title = revision.title.clone();
+        self.description = revision.description.clone();
+        self.done = revision.done;
+        self.due_at = revision.due_at;
         self.updated_at = Utc::now();
     }
 }
@@ -0,0 +1,199 @@
+use crate::models::{Project, Task, TaskCreate};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[cfg(feature = "openapi")]
+use utoipa::{IntoParams, ToSchema};
+
+/// Columns of an exported task CSV, in order.
+pub const TASK_CSV_COLUMNS: [&str; 8] = [
+    "id",
+    "title",
+    "description",
+    "done",
+    "column",
+    "due_at",
+    "created_at",
+    "updated_at",
+];
+
+#[derive(Debug, Default, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(IntoParams))]
+pub struct TaskImportQuery {
+    /// Validate every row without creating tasks
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Outcome of a CSV import.
+#[derive(Debug, Clone, Default, Serialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct TaskImportReport {
+    pub dry_run: bool,
+    /// Data rows read, not counting the header
+    pub rows: usize,
+    /// Tasks created, or on a dry run the rows that passed validation
+    pub imported: usize,
+    pub failed: usize,
+    /// Header columns that do not map to a task field
+    pub ignored_columns: Vec<String>,
+    /// The first failures, in file order; `failed` counts them all
+    pub errors: Vec<TaskImportError>,
+}
+
+/// Why one row of a CSV import failed.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct TaskImportError {
+    /// Line of the file the row starts on, counting the header as line 1
+    pub line: usize,
+    pub code: String,
+    pub message: String,
+}
+
+/// Which CSV columns hold which `TaskCreate` fields, read from the header row. Names match
+/// case-insensitively; `column` takes a column name or id and `due_at` an RFC 3339
+/// timestamp or a `YYYY-MM-DD` date. The quote [`task_csv_row`] puts before formula-like
+/// text is dropped again.
+#[derive(Debug, Clone)]
+pub struct TaskCsvHeader {
+    title: usize,
+    description: Option<usize>,
+    column: Option<usize>,
+    due_at: Option<usize>,
+    width: usize,
+    pub ignored: Vec<String>,
+}
+
+impl TaskCsvHeader {
+    pub fn parse(fields: &[String]) -> Result<Self, String> {
+        let mut title = None;
+        let mut description = None;
+        let mut column = None;
+        let mut due_at = None;
+        let mut ignored = Vec::new();
+
+        for (index, name) in fields.iter().enumerate() {
+            // Spreadsheets often start UTF-8 files with a byte order mark
+            let name = name.trim_start_matches('\u{feff}').trim();
+            let slot = match name.to_ascii_lowercase().as_str() {
+                "title" => &mut title,
+                "description" => &mut description,
+                "column" | "column_id" => &mut column,
+                "due_at" | "due" => &mut due_at,
+                _ => {
+                    ignored.push(name.to_string());
+                    continue;
+                }
+            };
+            if slot.replace(index).is_some() {
+                return Err(format!("Column '{}' appears more than once", name));
+            }
+        }
+
+        Ok(Self {
+            title: title.ok_or("The header has no 'title' column")?,
+            description,
+            column,
+            due_at,
+            width: fields.len(),
+            ignored,
+        })
+    }
+
+    /// Maps a data row to a task of `project`; empty cells leave fields unset.
+    pub fn task_create(&self, project: &Project, fields: &[String]) -> Result<TaskCreate, String> {
+        if fields.len() > self.width {
+            return Err(format!(
+                "Row has {} fields but the header has {}",
+                fields.len(),
+                self.width
+            ));
+        }
+        let cell = |index: Option<usize>| {
+            index
+                .and_then(|index| fields.get(index))
+                .map(|value| {
+                    let value = value.trim();
+                    value
+                        .strip_prefix('\'')
+                        .filter(|rest| rest.trim_start().starts_with(FORMULA_PREFIXES))
+                        .unwrap_or(value)
+                })
+                .filter(|value| !value.is_empty())
+        };
+
+        let column_id = cell(self.column)
+            .map(|value| {
+                Uuid::parse_str(value)
+                    .ok()
+                    .and_then(|id| project.column(&id))
+                    .or_else(|| {
+                        project
+                            .columns
+                            .iter()
+                            .find(|column| column.name.eq_ignore_ascii_case(value))
+                    })
+                    .map(|column| column.id)
+                    .ok_or_else(|| format!("Column '{}' is not on this project's board", value))
+            })
+            .transpose()?;
+        let due_at = cell(self.due_at).map(parse_due_at).transpose()?;
+
+        Ok(TaskCreate {
+            project_id: project.id,
+            title: cell(Some(self.title)).unwrap_or_default().to_string(),
+            description: cell(self.description).map(str::to_string),
+            column_id,
+            due_at,
+            occurrence: None,
+        })
+    }
+}
+
+/// A task as a row of [`TASK_CSV_COLUMNS`]. Text that a spreadsheet would run as a formula
+/// is quoted with a leading `'`.
+pub fn task_csv_row(project: &Project, task: &Task) -> [String; 8] {
+    let column = task
+        .column_id
+        .and_then(|id| project.column(&id))
+        .map(|column| column.name.as_str())
+        .unwrap_or_default();
+    [
+        task.id.to_string(),
+        as_text(&task.title),
+        as_text(task.description.as_deref().unwrap_or_default()),
+        task.done.to_string(),
+        as_text(column),
+        task.due_at.map(|at| at.to_rfc3339()).unwrap_or_default(),
+        task.created_at.to_rfc3339(),
+        task.updated_at.to_rfc3339(),
+    ]
+}
+
+/// Leading characters that make a spreadsheet cell a formula.
+const FORMULA_PREFIXES: [char; 4] = ['=', '+', '-', '@'];
+
+fn as_text(value: &str) -> String {
+    if value.trim_start().starts_with(FORMULA_PREFIXES) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    }
+}
+
+fn parse_due_at(value: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|at| at.with_timezone(&Utc))
+        .or_else(|_| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .map(|date| date.and_time(NaiveTime::MIN).and_utc())
+        })
+        .map_err(|_| {
+            format!(
+                "due_at '{}' is not an RFC 3339 timestamp or a YYYY-MM-DD date",
+                value
+            )
+        })
+}
@@ -0,0 +1,158 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[cfg(feature = "openapi")]
+use utoipa::{IntoParams, ToSchema};
+
+/// Time a user spent on a task; entries without an end are running timers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct TimeEntry {
+    pub id: Uuid,
+    pub tenant_id: String,
+    pub project_id: Uuid,
+    pub task_id: Uuid,
+    pub user: String,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A finished entry: give `started_at` with either `ended_at` or `duration_minutes`, or only
+/// `duration_minutes` for time that ends now.
+#[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct TimeEntryCreate {
+    pub task_id: Uuid,
+    pub started_at: Option<DateTime<Utc>>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub duration_minutes: Option<u32>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct TimerStart {
+    pub task_id: Uuid,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(IntoParams))]
+pub struct TimeEntryFilter {
+    pub project_id: Option<Uuid>,
+    pub task_id: Option<Uuid>,
+    pub user: Option<String>,
+    /// Only entries still running at or after this instant
+    pub from: Option<DateTime<Utc>>,
+    /// Only entries started before this instant
+    pub to: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum TimeReportGroup {
+    #[default]
+    Project,
+    Task,
+    User,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(IntoParams))]
+pub struct TimeReportQuery {
+    /// What to total hours by (default `project`)
+    #[serde(default)]
+    pub group_by: TimeReportGroup,
+    pub project_id: Option<Uuid>,
+    /// Start of the reporting period; time before it is not counted
+    pub from: Option<DateTime<Utc>>,
+    /// End of the reporting period; time from it on is not counted
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// Hours booked on one project, task or user within the reporting period.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct TimeReportRow {
+    /// Project or task ID, or the user's subject
+    pub key: String,
+    /// Project name, task title or user subject
+    pub name: String,
+    pub hours: f64,
+    pub entries: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct TimeReport {
+    pub group_by: TimeReportGroup,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub total_hours: f64,
+    pub rows: Vec<TimeReportRow>,
+}
+
+impl TimeEntry {
+    pub fn new(
+        tenant_id: String,
+        project_id: Uuid,
+        task_id: Uuid,
+        user: String,
+        started_at: DateTime<Utc>,
+        ended_at: Option<DateTime<Utc>>,
+        note: Option<String>,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            tenant_id,
+            project_id,
+            task_id,
+            user,
+            started_at,
+            ended_at,
+            note,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.ended_at.is_none()
+    }
+
+    pub fn stop(&mut self, at: DateTime<Utc>) {
+        self.ended_at = Some(at);
+        self.updated_at = Utc::now();
+    }
+
+    /// Time spent within `[from, to)`, counting a running timer up to `now`.
+    pub fn duration_within(
+        &self,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> Duration {
+        let start = from.map_or(self.started_at, |from| from.max(self.started_at));
+        let end = self.ended_at.unwrap_or(now);
+        let end = to.map_or(end, |to| to.min(end));
+        (end - start).max(Duration::zero())
+    }
+}
+
+impl TimeEntryFilter {
+    pub fn matches(&self, entry: &TimeEntry) -> bool {
+        self.project_id.is_none_or(|id| entry.project_id == id)
+            && self.task_id.is_none_or(|id| entry.task_id == id)
+            && self.user.as_ref().is_none_or(|user| entry.user == *user)
+            && self
+                .from
+                .is_none_or(|from| entry.ended_at.is_none_or(|ended_at| ended_at > from))
+            && self.to.is_none_or(|to| entry.started_at < to)
+    }
+}
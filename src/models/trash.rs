@@ -0,0 +1,13 @@
+use crate::models::{Project, Task};
+use serde::Serialize;
+
+#[cfg(feature = "openapi")]
+use utoipa::ToSchema;
+
+/// Deleted projects and tasks that can still be restored, most recently deleted first.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct Trash {
+    pub projects: Vec<Project>,
+    pub tasks: Vec<Task>,
+}
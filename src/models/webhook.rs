@@ -0,0 +1,157 @@
+use crate::models::{EventEnvelope, EventKind};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+#[cfg(feature = "openapi")]
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct Webhook {
+    pub id: Uuid,
+    pub tenant_id: String,
+    pub url: String,
+    /// Event types delivered to this webhook; empty means every type.
+    pub event_types: Vec<EventKind>,
+    /// Only deliver events from this project when set.
+    pub project_id: Option<Uuid>,
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct WebhookCreate {
+    pub url: String,
+    #[serde(default)]
+    pub event_types: Vec<EventKind>,
+    pub project_id: Option<Uuid>,
+    /// Signing secret; one is generated when omitted.
+    pub secret: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct WebhookUpdate {
+    pub url: Option<String>,
+    pub event_types: Option<Vec<EventKind>>,
+    pub project_id: Option<Uuid>,
+    pub secret: Option<String>,
+    pub active: Option<bool>,
+}
+
+/// Response for a new webhook; the signing `secret` is never returned again.
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct WebhookCreated {
+    pub secret: String,
+    pub webhook: Webhook,
+}
+
+impl Webhook {
+    pub fn new(
+        tenant_id: String,
+        url: String,
+        event_types: Vec<EventKind>,
+        project_id: Option<Uuid>,
+        secret: String,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            tenant_id,
+            url,
+            event_types,
+            project_id,
+            secret,
+            active: true,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    pub fn update(&mut self, update_data: WebhookUpdate) {
+        if let Some(url) = update_data.url {
+            self.url = url;
+        }
+        if let Some(event_types) = update_data.event_types {
+            self.event_types = event_types;
+        }
+        if let Some(project_id) = update_data.project_id {
+            self.project_id = Some(project_id);
+        }
+        if let Some(secret) = update_data.secret {
+            self.secret = secret;
+        }
+        if let Some(active) = update_data.active {
+            self.active = active;
+        }
+        self.updated_at = Utc::now();
+    }
+
+    pub fn matches(&self, envelope: &EventEnvelope) -> bool {
+        self.active
+            && self.tenant_id == envelope.tenant_id
+            && (self.event_types.is_empty() || self.event_types.contains(&envelope.event.kind()))
+            && self
+                .project_id
+                .is_none_or(|project_id| project_id == envelope.event.project_id())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    /// Waiting for its first attempt or a retry.
+    Pending,
+    Delivered,
+    /// Gave up after the configured number of attempts.
+    DeadLettered,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub tenant_id: String,
+    pub webhook_id: Uuid,
+    pub event_id: Uuid,
+    pub event_type: EventKind,
+    pub payload: Value,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub last_attempt_at: Option<DateTime<Utc>>,
+    pub last_status_code: Option<u16>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+impl WebhookDelivery {
+    pub fn new(webhook: &Webhook, envelope: &EventEnvelope, payload: Value) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            tenant_id: webhook.tenant_id.clone(),
+            webhook_id: webhook.id,
+            event_id: envelope.id,
+            event_type: envelope.event.kind(),
+            payload,
+            status: DeliveryStatus::Pending,
+            attempts: 0,
+            next_attempt_at: Some(now),
+            last_attempt_at: None,
+            last_status_code: None,
+            last_error: None,
+            created_at: now,
+            delivered_at: None,
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use crate::models::ApiKey;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct ApiKeyRepository {
+    keys: RwLock<HashMap<Uuid, ApiKey>>,
+}
+
+impl ApiKeyRepository {
+    pub fn new() -> Self {
+        Self {
+            keys: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn create(&self, key: ApiKey) -> Result<ApiKey, String> {
+        let mut keys = self
+            .keys
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        keys.insert(key.id, key.clone());
+        Ok(key)
+    }
+
+    /// Unscoped lookup, used to authenticate a key before its tenant is known.
+    pub fn find_by_id(&self, id: &Uuid) -> Result<Option<ApiKey>, String> {
+        let keys = self
+            .keys
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(keys.get(id).cloned())
+    }
+
+    pub fn find_all(&self, tenant_id: &str) -> Result<Vec<ApiKey>, String> {
+        let keys = self
+            .keys
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(keys
+            .values()
+            .filter(|key| key.tenant_id == tenant_id)
+            .cloned()
+            .collect())
+    }
+
+    pub fn touch(&self, id: &Uuid, used_at: DateTime<Utc>) -> Result<(), String> {
+        let mut keys = self
+            .keys
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        if let Some(key) = keys.get_mut(id) {
+            key.last_used_at = Some(used_at);
+        }
+        Ok(())
+    }
+
+    pub fn revoke(
+        &self,
+        tenant_id: &str,
+        id: &Uuid,
+        revoked_at: DateTime<Utc>,
+    ) -> Result<Option<ApiKey>, String> {
+        let mut keys = self
+            .keys
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        Ok(keys
+            .get_mut(id)
+            .filter(|key| key.tenant_id == tenant_id)
+            .map(|key| {
+                key.revoked_at.get_or_insert(revoked_at);
+                key.clone()
+            }))
+    }
+}
+
+impl Default for ApiKeyRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,45 @@
+use crate::models::{AuditEntry, AuditFilter};
+use std::sync::RwLock;
+
+/// Append-only audit log; entries can be added and read but never changed or removed.
+#[derive(Debug)]
+pub struct AuditRepository {
+    entries: RwLock<Vec<AuditEntry>>,
+}
+
+impl AuditRepository {
+    pub fn new() -> Self {
+        Self {
+            entries: RwLock::new(Vec::new()),
+        }
+    }
+
+    pub fn append(&self, entry: AuditEntry) -> Result<AuditEntry, String> {
+        let mut entries = self
+            .entries
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        entries.push(entry.clone());
+        Ok(entry)
+    }
+
+    /// Returns matching entries of a tenant in the order they were recorded.
+    pub fn find(&self, tenant_id: &str, filter: &AuditFilter) -> Result<Vec<AuditEntry>, String> {
+        let entries = self
+            .entries
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(entries
+            .iter()
+            .filter(|entry| entry.tenant_id == tenant_id && filter.matches(entry))
+            .take(filter.limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect())
+    }
+}
+
+impl Default for AuditRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,76 @@
+use crate::models::CalendarToken;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use uuid::Uuid;
+
+#[derive(Debug, Default)]
+pub struct CalendarTokenRepository {
+    tokens: RwLock<HashMap<Uuid, CalendarToken>>,
+}
+
+impl CalendarTokenRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn create(&self, token: CalendarToken) -> Result<CalendarToken, String> {
+        let mut tokens = self
+            .tokens
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        tokens.insert(token.id, token.clone());
+        Ok(token)
+    }
+
+    /// Looks a token up across tenants, for authentication.
+    pub fn find_by_id(&self, id: &Uuid) -> Result<Option<CalendarToken>, String> {
+        let tokens = self
+            .tokens
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(tokens.get(id).cloned())
+    }
+
+    pub fn find_by_subject(
+        &self,
+        tenant_id: &str,
+        subject: &str,
+    ) -> Result<Vec<CalendarToken>, String> {
+        let tokens = self
+            .tokens
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(tokens
+            .values()
+            .filter(|token| token.tenant_id == tenant_id && token.subject == subject)
+            .cloned()
+            .collect())
+    }
+
+    pub fn touch(&self, id: &Uuid, used_at: DateTime<Utc>) -> Result<(), String> {
+        let mut tokens = self
+            .tokens
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        if let Some(token) = tokens.get_mut(id) {
+            token.last_used_at = Some(used_at);
+        }
+        Ok(())
+    }
+
+    /// Deletes one of `subject`'s tokens.
+    pub fn delete(&self, tenant_id: &str, subject: &str, id: &Uuid) -> Result<bool, String> {
+        let mut tokens = self
+            .tokens
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        match tokens.get(id) {
+            Some(token) if token.tenant_id == tenant_id && token.subject == subject => {
+                tokens.remove(id);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
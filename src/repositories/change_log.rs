@@ -0,0 +1,116 @@
+use crate::models::{Change, ChangeFeed, ChangeOperation, EntityType};
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::RwLock;
+use uuid::Uuid;
+
+#[derive(Debug, Default)]
+struct ChangeLogState {
+    last_seq: u64,
+    changes: BTreeMap<u64, Change>,
+    /// Sequence number of the current change of each entity.
+    latest: HashMap<(EntityType, Uuid), u64>,
+}
+
+/// Global sequence of project and task mutations, shared by their repositories.
+///
+/// Only the latest change per entity is kept, so a client syncing from any point receives
+/// each entity at most once, in its current state or as a tombstone.
+#[derive(Debug, Default)]
+pub struct ChangeLog {
+    state: RwLock<ChangeLogState>,
+}
+
+impl ChangeLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Assigns the next sequence number to a mutation; `data` is `None` for deletes.
+    ///
+    /// Repositories call this while holding their own write lock, so sequence order always
+    /// matches the order in which changes became visible.
+    pub fn record<T: Serialize>(
+        &self,
+        tenant_id: &str,
+        entity_type: EntityType,
+        entity_id: Uuid,
+        project_id: Uuid,
+        data: Option<&T>,
+    ) -> Result<u64, String> {
+        let data = data
+            .map(serde_json::to_value)
+            .transpose()
+            .map_err(|e| e.to_string())?;
+        let mut state = self
+            .state
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+
+        state.last_seq += 1;
+        let seq = state.last_seq;
+        if let Some(previous) = state.latest.insert((entity_type, entity_id), seq) {
+            state.changes.remove(&previous);
+        }
+        state.changes.insert(
+            seq,
+            Change {
+                seq,
+                tenant_id: tenant_id.to_string(),
+                entity_type,
+                entity_id,
+                project_id,
+                operation: if data.is_some() {
+                    ChangeOperation::Upsert
+                } else {
+                    ChangeOperation::Delete
+                },
+                data,
+                changed_at: Utc::now(),
+            },
+        );
+        Ok(seq)
+    }
+
+    pub fn last_seq(&self) -> Result<u64, String> {
+        let state = self
+            .state
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(state.last_seq)
+    }
+
+    /// Up to `limit` of the tenant's changes after `since` accepted by `filter`.
+    pub fn find_since(
+        &self,
+        tenant_id: &str,
+        since: u64,
+        limit: usize,
+        filter: impl Fn(&Change) -> bool,
+    ) -> Result<ChangeFeed, String> {
+        let state = self
+            .state
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+
+        let mut matching = state
+            .changes
+            .range(since.saturating_add(1)..)
+            .map(|(_, change)| change)
+            .filter(|change| change.tenant_id == tenant_id && filter(change));
+        let changes: Vec<Change> = matching.by_ref().take(limit).cloned().collect();
+        let has_more = matching.next().is_some();
+
+        // Without more matches everything up to the head was scanned and can be skipped.
+        let next_since = match changes.last() {
+            Some(last) if has_more => last.seq,
+            _ => state.last_seq.max(since),
+        };
+        Ok(ChangeFeed {
+            changes,
+            next_since,
+            has_more,
+        })
+    }
+}
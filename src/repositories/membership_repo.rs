@@ -0,0 +1,99 @@
+use crate::models::ProjectMember;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct MembershipRepository {
+    members: RwLock<HashMap<(Uuid, String), ProjectMember>>,
+}
+
+impl MembershipRepository {
+    pub fn new() -> Self {
+        Self {
+            members: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn upsert(&self, member: ProjectMember) -> Result<ProjectMember, String> {
+        let mut members = self
+            .members
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        let key = (member.project_id, member.subject.clone());
+        members.insert(key, member.clone());
+        Ok(member)
+    }
+
+    pub fn find(&self, project_id: &Uuid, subject: &str) -> Result<Option<ProjectMember>, String> {
+        let members = self
+            .members
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(members.get(&(*project_id, subject.to_string())).cloned())
+    }
+
+    pub fn find_by_project_id(&self, project_id: &Uuid) -> Result<Vec<ProjectMember>, String> {
+        let members = self
+            .members
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(members
+            .values()
+            .filter(|member| member.project_id == *project_id)
+            .cloned()
+            .collect())
+    }
+
+    pub fn find_by_subject(&self, subject: &str) -> Result<Vec<ProjectMember>, String> {
+        let members = self
+            .members
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(members
+            .values()
+            .filter(|member| member.subject == subject)
+            .cloned()
+            .collect())
+    }
+
+    pub fn delete(&self, project_id: &Uuid, subject: &str) -> Result<bool, String> {
+        let mut members = self
+            .members
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        Ok(members
+            .remove(&(*project_id, subject.to_string()))
+            .is_some())
+    }
+
+    pub fn delete_by_project_id(&self, project_id: &Uuid) -> Result<usize, String> {
+        let mut members = self
+            .members
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        let before = members.len();
+        members.retain(|(id, _), _| id != project_id);
+        Ok(before - members.len())
+    }
+
+    /// Stores members from a backup as they are. Members of `replace` projects that are not
+    /// in `imported` are removed first.
+    pub fn import(&self, imported: Vec<ProjectMember>, replace: &[Uuid]) -> Result<(), String> {
+        let mut members = self
+            .members
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        members.retain(|(project_id, _), _| !replace.contains(project_id));
+        for member in imported {
+            members.insert((member.project_id, member.subject.clone()), member);
+        }
+        Ok(())
+    }
+}
+
+impl Default for MembershipRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
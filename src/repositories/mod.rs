@@ -1,5 +1,27 @@
+pub mod api_key_repo;
+pub mod audit_repo;
+pub mod calendar_token_repo;
+pub mod change_log;
+pub mod membership_repo;
 pub mod project_repo;
+pub mod recurring_task_repo;
+pub mod reminder_repo;
+pub mod revision_repo;
 pub mod task_repo;
+pub mod time_entry_repo;
+pub mod webhook_delivery_repo;
+pub mod webhook_repo;
 
+pub use api_key_repo::*;
+pub use audit_repo::*;
+pub use calendar_token_repo::*;
+pub use change_log::*;
+pub use membership_repo::*;
 pub use project_repo::*;
+pub use recurring_task_repo::*;
+pub use reminder_repo::*;
+pub use revision_repo::*;
 pub use task_repo::*;
+pub use time_entry_repo::*;
+pub use webhook_delivery_repo::*;
+pub use webhook_repo::*;
@@ -1,17 +1,26 @@
-use crate::models::Project;
-use std::collections::HashMap;
-use std::sync::RwLock;
+use crate::models::{EntityType, Project};
+use crate::repositories::ChangeLog;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
 use uuid::Uuid;
 
+/// Project storage; every lookup is scoped to a tenant so ids never resolve across tenants.
+///
+/// Deleted projects stay in the trash, hidden from everything but the `*_deleted` lookups,
+/// until they are restored or purged.
 #[derive(Debug)]
 pub struct ProjectRepository {
     projects: RwLock<HashMap<Uuid, Project>>,
+    changes: Arc<ChangeLog>,
 }
 
 impl ProjectRepository {
-    pub fn new() -> Self {
+    /// Records every mutation in `changes`.
+    pub fn new(changes: Arc<ChangeLog>) -> Self {
         Self {
             projects: RwLock::new(HashMap::new()),
+            changes,
         }
     }
 
@@ -20,51 +29,312 @@ impl ProjectRepository {
             .projects
             .write()
             .map_err(|_| "Failed to acquire write lock")?;
+        self.changes.record(
+            &project.tenant_id,
+            EntityType::Project,
+            project.id,
+            project.id,
+            Some(&project),
+        )?;
         let id = project.id;
         projects.insert(id, project.clone());
         Ok(project)
     }
 
-    pub fn find_by_id(&self, id: &Uuid) -> Result<Option<Project>, String> {
+    /// Creates the project unless its tenant already has `limit` live projects, checked under the
+    /// same lock as the insert so concurrent creates cannot overshoot. Returns `None` at the limit.
+    pub fn create_if_below(
+        &self,
+        project: Project,
+        limit: usize,
+    ) -> Result<Option<Project>, String> {
+        let mut projects = self
+            .projects
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        let count = projects
+            .values()
+            .filter(|existing| {
+                existing.tenant_id == project.tenant_id && existing.deleted_at.is_none()
+            })
+            .count();
+        if count >= limit {
+            return Ok(None);
+        }
+        self.changes.record(
+            &project.tenant_id,
+            EntityType::Project,
+            project.id,
+            project.id,
+            Some(&project),
+        )?;
+        projects.insert(project.id, project.clone());
+        Ok(Some(project))
+    }
+
+    pub fn find_by_id(&self, tenant_id: &str, id: &Uuid) -> Result<Option<Project>, String> {
+        let projects = self
+            .projects
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(projects
+            .get(id)
+            .filter(|project| project.tenant_id == tenant_id && project.deleted_at.is_none())
+            .cloned())
+    }
+
+    pub fn find_all(&self, tenant_id: &str) -> Result<Vec<Project>, String> {
         let projects = self
             .projects
             .read()
             .map_err(|_| "Failed to acquire read lock")?;
-        Ok(projects.get(id).cloned())
+        Ok(projects
+            .values()
+            .filter(|project| project.tenant_id == tenant_id && project.deleted_at.is_none())
+            .cloned()
+            .collect())
     }
 
-    pub fn find_all(&self) -> Result<Vec<Project>, String> {
+    pub fn count(&self, tenant_id: &str) -> Result<usize, String> {
         let projects = self
             .projects
             .read()
             .map_err(|_| "Failed to acquire read lock")?;
-        Ok(projects.values().cloned().collect())
+        Ok(projects
+            .values()
+            .filter(|project| project.tenant_id == tenant_id && project.deleted_at.is_none())
+            .count())
     }
 
-    pub fn update(&self, id: &Uuid, updated_project: Project) -> Result<Option<Project>, String> {
+    pub fn update(
+        &self,
+        tenant_id: &str,
+        id: &Uuid,
+        updated_project: Project,
+    ) -> Result<Option<Project>, String> {
         let mut projects = self
             .projects
             .write()
             .map_err(|_| "Failed to acquire write lock")?;
-        if projects.contains_key(id) {
-            projects.insert(*id, updated_project.clone());
-            Ok(Some(updated_project))
-        } else {
-            Ok(None)
+        match projects.get(id) {
+            Some(project) if project.tenant_id == tenant_id && project.deleted_at.is_none() => {
+                self.changes.record(
+                    tenant_id,
+                    EntityType::Project,
+                    *id,
+                    *id,
+                    Some(&updated_project),
+                )?;
+                projects.insert(*id, updated_project.clone());
+                Ok(Some(updated_project))
+            }
+            _ => Ok(None),
         }
     }
 
-    pub fn delete(&self, id: &Uuid) -> Result<bool, String> {
+    /// Records the live project again at the head of the change log, so clients syncing
+    /// from an earlier cursor receive it. Returns whether the project exists.
+    pub fn record_again(&self, tenant_id: &str, id: &Uuid) -> Result<bool, String> {
+        let projects = self
+            .projects
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        match projects.get(id) {
+            Some(project) if project.tenant_id == tenant_id && project.deleted_at.is_none() => {
+                self.changes
+                    .record(tenant_id, EntityType::Project, *id, *id, Some(project))?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Moves the project to the trash.
+    pub fn delete(
+        &self,
+        tenant_id: &str,
+        id: &Uuid,
+        deleted_at: DateTime<Utc>,
+    ) -> Result<bool, String> {
         let mut projects = self
             .projects
             .write()
             .map_err(|_| "Failed to acquire write lock")?;
-        Ok(projects.remove(id).is_some())
+        match projects.get_mut(id) {
+            Some(project) if project.tenant_id == tenant_id && project.deleted_at.is_none() => {
+                self.changes
+                    .record::<Project>(tenant_id, EntityType::Project, *id, *id, None)?;
+                project.deleted_at = Some(deleted_at);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    pub fn find_deleted(&self, tenant_id: &str) -> Result<Vec<Project>, String> {
+        let projects = self
+            .projects
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(projects
+            .values()
+            .filter(|project| project.tenant_id == tenant_id && project.deleted_at.is_some())
+            .cloned()
+            .collect())
+    }
+
+    pub fn find_deleted_by_id(
+        &self,
+        tenant_id: &str,
+        id: &Uuid,
+    ) -> Result<Option<Project>, String> {
+        let projects = self
+            .projects
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(projects
+            .get(id)
+            .filter(|project| project.tenant_id == tenant_id && project.deleted_at.is_some())
+            .cloned())
+    }
+
+    /// Projects of every tenant that were deleted before `cutoff`.
+    pub fn find_deleted_before(&self, cutoff: DateTime<Utc>) -> Result<Vec<Project>, String> {
+        let projects = self
+            .projects
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(projects
+            .values()
+            .filter(|project| project.deleted_at.is_some_and(|at| at < cutoff))
+            .cloned()
+            .collect())
+    }
+
+    /// Takes the project out of the trash unless its tenant already has `limit` live projects,
+    /// checked under the same lock. Returns `None` if it is not in the trash or the tenant is at
+    /// the limit.
+    pub fn restore(
+        &self,
+        tenant_id: &str,
+        id: &Uuid,
+        limit: Option<usize>,
+    ) -> Result<Option<Project>, String> {
+        let mut projects = self
+            .projects
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        if let Some(limit) = limit {
+            let count = projects
+                .values()
+                .filter(|project| project.tenant_id == tenant_id && project.deleted_at.is_none())
+                .count();
+            if count >= limit {
+                return Ok(None);
+            }
+        }
+        match projects.get_mut(id) {
+            Some(project) if project.tenant_id == tenant_id && project.deleted_at.is_some() => {
+                let mut restored = project.clone();
+                restored.deleted_at = None;
+                self.changes
+                    .record(tenant_id, EntityType::Project, *id, *id, Some(&restored))?;
+                *project = restored.clone();
+                Ok(Some(restored))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Removes a project from the trash for good.
+    pub fn purge(&self, tenant_id: &str, id: &Uuid) -> Result<bool, String> {
+        let mut projects = self
+            .projects
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        match projects.get(id) {
+            Some(project) if project.tenant_id == tenant_id && project.deleted_at.is_some() => {
+                projects.remove(id);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Tenant of a project, live or trashed, looking across all tenants.
+    pub fn tenant_of(&self, id: &Uuid) -> Result<Option<String>, String> {
+        let projects = self
+            .projects
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(projects.get(id).map(|project| project.tenant_id.clone()))
+    }
+
+    /// Stores projects from a backup as they are, ids and timestamps included. `replace`
+    /// first removes every project of that tenant. With a `limit`, nothing is stored and `false`
+    /// returned if that tenant would end up with more live projects.
+    pub fn import(
+        &self,
+        imported: Vec<Project>,
+        replace: Option<&str>,
+        limit: Option<(&str, usize)>,
+    ) -> Result<bool, String> {
+        let mut projects = self
+            .projects
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        if let Some((tenant_id, limit)) = limit {
+            let incoming: HashSet<Uuid> = imported.iter().map(|project| project.id).collect();
+            let kept = projects
+                .values()
+                .filter(|project| {
+                    replace != Some(tenant_id)
+                        && project.tenant_id == tenant_id
+                        && project.deleted_at.is_none()
+                        && !incoming.contains(&project.id)
+                })
+                .count();
+            let added = imported
+                .iter()
+                .filter(|project| project.tenant_id == tenant_id && project.deleted_at.is_none())
+                .count();
+            if kept + added > limit {
+                return Ok(false);
+            }
+        }
+        if let Some(tenant_id) = replace {
+            let removed: Vec<Uuid> = projects
+                .values()
+                .filter(|project| project.tenant_id == tenant_id)
+                .map(|project| project.id)
+                .collect();
+            for id in removed {
+                if projects
+                    .remove(&id)
+                    .is_some_and(|project| project.deleted_at.is_none())
+                {
+                    self.changes
+                        .record::<Project>(tenant_id, EntityType::Project, id, id, None)?;
+                }
+            }
+        }
+        for project in imported {
+            let live = project.deleted_at.is_none().then_some(&project);
+            self.changes.record(
+                &project.tenant_id,
+                EntityType::Project,
+                project.id,
+                project.id,
+                live,
+            )?;
+            projects.insert(project.id, project);
+        }
+        Ok(true)
     }
 }
 
 impl Default for ProjectRepository {
     fn default() -> Self {
-        Self::new()
+        Self::new(Arc::default())
     }
 }
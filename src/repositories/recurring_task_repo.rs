@@ -0,0 +1,142 @@
+use crate::models::RecurringTask;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use uuid::Uuid;
+
+#[derive(Debug, Default)]
+pub struct RecurringTaskRepository {
+    recurring_tasks: RwLock<HashMap<Uuid, RecurringTask>>,
+}
+
+impl RecurringTaskRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn create(&self, recurring_task: RecurringTask) -> Result<RecurringTask, String> {
+        let mut recurring_tasks = self
+            .recurring_tasks
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        recurring_tasks.insert(recurring_task.id, recurring_task.clone());
+        Ok(recurring_task)
+    }
+
+    pub fn find_by_id(&self, tenant_id: &str, id: &Uuid) -> Result<Option<RecurringTask>, String> {
+        let recurring_tasks = self
+            .recurring_tasks
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(recurring_tasks
+            .get(id)
+            .filter(|recurring_task| recurring_task.tenant_id == tenant_id)
+            .cloned())
+    }
+
+    pub fn find_all(&self, tenant_id: &str) -> Result<Vec<RecurringTask>, String> {
+        let recurring_tasks = self
+            .recurring_tasks
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(recurring_tasks
+            .values()
+            .filter(|recurring_task| recurring_task.tenant_id == tenant_id)
+            .cloned()
+            .collect())
+    }
+
+    /// Recurring tasks in every tenant whose next occurrence is at or before `now`.
+    pub fn find_due(&self, now: DateTime<Utc>) -> Result<Vec<RecurringTask>, String> {
+        let recurring_tasks = self
+            .recurring_tasks
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(recurring_tasks
+            .values()
+            .filter(|recurring_task| recurring_task.next_at.is_some_and(|next_at| next_at <= now))
+            .cloned()
+            .collect())
+    }
+
+    pub fn update(
+        &self,
+        tenant_id: &str,
+        id: &Uuid,
+        updated_recurring_task: RecurringTask,
+    ) -> Result<Option<RecurringTask>, String> {
+        let mut recurring_tasks = self
+            .recurring_tasks
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        match recurring_tasks.get(id) {
+            Some(recurring_task) if recurring_task.tenant_id == tenant_id => {
+                recurring_tasks.insert(*id, updated_recurring_task.clone());
+                Ok(Some(updated_recurring_task))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    pub fn delete(&self, tenant_id: &str, id: &Uuid) -> Result<bool, String> {
+        let mut recurring_tasks = self
+            .recurring_tasks
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        match recurring_tasks.get(id) {
+            Some(recurring_task) if recurring_task.tenant_id == tenant_id => {
+                recurring_tasks.remove(id);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Removes a project's recurring tasks; returns how many.
+    pub fn delete_by_project_id(
+        &self,
+        tenant_id: &str,
+        project_id: &Uuid,
+    ) -> Result<usize, String> {
+        let mut recurring_tasks = self
+            .recurring_tasks
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        let before = recurring_tasks.len();
+        recurring_tasks.retain(|_, recurring_task| {
+            recurring_task.tenant_id != tenant_id || recurring_task.project_id != *project_id
+        });
+        Ok(before - recurring_tasks.len())
+    }
+
+    /// Tenant of a recurring task, looking across all tenants.
+    pub fn tenant_of(&self, id: &Uuid) -> Result<Option<String>, String> {
+        let recurring_tasks = self
+            .recurring_tasks
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(recurring_tasks
+            .get(id)
+            .map(|recurring_task| recurring_task.tenant_id.clone()))
+    }
+
+    /// Stores recurring tasks from a backup as they are. `replace` first removes every
+    /// recurring task of that tenant.
+    pub fn import(
+        &self,
+        imported: Vec<RecurringTask>,
+        replace: Option<&str>,
+    ) -> Result<(), String> {
+        let mut recurring_tasks = self
+            .recurring_tasks
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        if let Some(tenant_id) = replace {
+            recurring_tasks.retain(|_, recurring_task| recurring_task.tenant_id != tenant_id);
+        }
+        for recurring_task in imported {
+            recurring_tasks.insert(recurring_task.id, recurring_task);
+        }
+        Ok(())
+    }
+}
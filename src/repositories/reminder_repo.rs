@@ -0,0 +1,44 @@
+use crate::models::{Reminder, ReminderKind};
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+use uuid::Uuid;
+
+/// Remembers which reminders fired, one per task and kind.
+#[derive(Debug, Default)]
+pub struct ReminderRepository {
+    reminders: RwLock<HashMap<(Uuid, ReminderKind), Reminder>>,
+}
+
+impl ReminderRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records `reminder` unless the same reminder already fired for the same due date;
+    /// returns whether it was recorded, i.e. whether it should be sent.
+    pub fn record(&self, reminder: Reminder) -> Result<bool, String> {
+        let mut reminders = self
+            .reminders
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        let key = (reminder.task_id, reminder.kind);
+        if reminders
+            .get(&key)
+            .is_some_and(|sent| sent.due_at == reminder.due_at)
+        {
+            return Ok(false);
+        }
+        reminders.insert(key, reminder);
+        Ok(true)
+    }
+
+    /// Forgets the reminders sent for `task_ids`.
+    pub fn delete_by_task_ids(&self, task_ids: &HashSet<Uuid>) -> Result<(), String> {
+        let mut reminders = self
+            .reminders
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        reminders.retain(|(task_id, _), _| !task_ids.contains(task_id));
+        Ok(())
+    }
+}
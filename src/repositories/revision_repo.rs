@@ -0,0 +1,90 @@
+use crate::models::{EntityType, Revision};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use uuid::Uuid;
+
+/// Version history of projects and tasks, kept until the entity is purged.
+#[derive(Debug)]
+pub struct RevisionRepository {
+    revisions: RwLock<HashMap<(EntityType, Uuid), Vec<Revision>>>,
+}
+
+impl RevisionRepository {
+    pub fn new() -> Self {
+        Self {
+            revisions: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Adds the next version of an entity; its `number` is assigned here.
+    pub fn append(&self, mut revision: Revision) -> Result<Revision, String> {
+        let mut revisions = self
+            .revisions
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        let history = revisions
+            .entry((revision.entity_type, revision.entity_id))
+            .or_default();
+        revision.number = history.len() as u32 + 1;
+        history.push(revision.clone());
+        Ok(revision)
+    }
+
+    /// Returns an entity's revisions, oldest first.
+    pub fn find_by_entity(
+        &self,
+        tenant_id: &str,
+        entity_type: EntityType,
+        entity_id: &Uuid,
+    ) -> Result<Vec<Revision>, String> {
+        let revisions = self
+            .revisions
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(revisions
+            .get(&(entity_type, *entity_id))
+            .into_iter()
+            .flatten()
+            .filter(|revision| revision.tenant_id == tenant_id)
+            .cloned()
+            .collect())
+    }
+
+    pub fn find(
+        &self,
+        tenant_id: &str,
+        entity_type: EntityType,
+        entity_id: &Uuid,
+        number: u32,
+    ) -> Result<Option<Revision>, String> {
+        let revisions = self
+            .revisions
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(revisions
+            .get(&(entity_type, *entity_id))
+            .and_then(|history| history.get((number as usize).checked_sub(1)?))
+            .filter(|revision| revision.tenant_id == tenant_id)
+            .cloned())
+    }
+
+    pub fn delete_by_entity(
+        &self,
+        entity_type: EntityType,
+        entity_id: &Uuid,
+    ) -> Result<usize, String> {
+        let mut revisions = self
+            .revisions
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        Ok(revisions
+            .remove(&(entity_type, *entity_id))
+            .map_or(0, |history| history.len()))
+    }
+}
+
+impl Default for RevisionRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,17 +1,26 @@
-use crate::models::Task;
-use std::collections::HashMap;
-use std::sync::RwLock;
+use crate::models::{EntityType, Task, TaskOccurrence};
+use crate::repositories::ChangeLog;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
 use uuid::Uuid;
 
+/// Task storage; every lookup is scoped to a tenant so ids never resolve across tenants.
+///
+/// Deleted tasks stay in the trash, hidden from everything but the `*_deleted` lookups,
+/// until they are restored or purged.
 #[derive(Debug)]
 pub struct TaskRepository {
     tasks: RwLock<HashMap<Uuid, Task>>,
+    changes: Arc<ChangeLog>,
 }
 
 impl TaskRepository {
-    pub fn new() -> Self {
+    /// Records every mutation in `changes`.
+    pub fn new(changes: Arc<ChangeLog>) -> Self {
         Self {
             tasks: RwLock::new(HashMap::new()),
+            changes,
         }
     }
 
@@ -20,81 +29,621 @@ impl TaskRepository {
             .tasks
             .write()
             .map_err(|_| "Failed to acquire write lock")?;
+        self.changes.record(
+            &task.tenant_id,
+            EntityType::Task,
+            task.id,
+            task.project_id,
+            Some(&task),
+        )?;
         let id = task.id;
         tasks.insert(id, task.clone());
         Ok(task)
     }
 
-    pub fn find_by_id(&self, id: &Uuid) -> Result<Option<Task>, String> {
+    /// Creates the task unless its tenant already has `limit` live tasks, checked under the
+    /// same lock as the insert so concurrent creates cannot overshoot. Returns `None` at the limit.
+    ///
+    /// `check_column` is given the number of tasks already in the task's column, counted under
+    /// the same lock, and can turn the insert down.
+    pub fn create_if_below<E: From<String>>(
+        &self,
+        task: Task,
+        limit: Option<usize>,
+        check_column: impl FnOnce(usize) -> Result<(), E>,
+    ) -> Result<Option<Task>, E> {
+        let mut tasks = self
+            .tasks
+            .write()
+            .map_err(|_| String::from("Failed to acquire write lock"))?;
+        if limit.is_some_and(|limit| live_count(&tasks, &task.tenant_id) >= limit) {
+            return Ok(None);
+        }
+        check_column(column_count(&tasks, &task))?;
+        self.changes.record(
+            &task.tenant_id,
+            EntityType::Task,
+            task.id,
+            task.project_id,
+            Some(&task),
+        )?;
+        tasks.insert(task.id, task.clone());
+        Ok(Some(task))
+    }
+
+    /// Creates one tenant's `batch` in order under a single lock, stopping before the tenant
+    /// would exceed `limit` live tasks. Returns the tasks that were created.
+    pub fn create_all(&self, batch: Vec<Task>, limit: Option<usize>) -> Result<Vec<Task>, String> {
+        let mut tasks = self
+            .tasks
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        let Some(tenant_id) = batch.first().map(|task| task.tenant_id.clone()) else {
+            return Ok(Vec::new());
+        };
+        let count = live_count(&tasks, &tenant_id);
+        let room = limit.map_or(batch.len(), |limit| limit.saturating_sub(count));
+
+        let mut created = Vec::new();
+        for task in batch.into_iter().take(room) {
+            self.changes.record(
+                &task.tenant_id,
+                EntityType::Task,
+                task.id,
+                task.project_id,
+                Some(&task),
+            )?;
+            tasks.insert(task.id, task.clone());
+            created.push(task);
+        }
+        Ok(created)
+    }
+
+    pub fn find_by_id(&self, tenant_id: &str, id: &Uuid) -> Result<Option<Task>, String> {
+        let tasks = self
+            .tasks
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(tasks
+            .get(id)
+            .filter(|task| task.tenant_id == tenant_id && task.deleted_at.is_none())
+            .cloned())
+    }
+
+    /// The task created for a recurring task's occurrence, even if it is in the trash.
+    pub fn find_by_occurrence(
+        &self,
+        tenant_id: &str,
+        occurrence: &TaskOccurrence,
+    ) -> Result<Option<Task>, String> {
         let tasks = self
             .tasks
             .read()
             .map_err(|_| "Failed to acquire read lock")?;
-        Ok(tasks.get(id).cloned())
+        Ok(tasks
+            .values()
+            .find(|task| {
+                task.tenant_id == tenant_id && task.occurrence.as_ref() == Some(occurrence)
+            })
+            .cloned())
     }
 
-    pub fn find_all(&self) -> Result<Vec<Task>, String> {
+    pub fn find_all(&self, tenant_id: &str) -> Result<Vec<Task>, String> {
         let tasks = self
             .tasks
             .read()
             .map_err(|_| "Failed to acquire read lock")?;
-        Ok(tasks.values().cloned().collect())
+        Ok(tasks
+            .values()
+            .filter(|task| task.tenant_id == tenant_id && task.deleted_at.is_none())
+            .cloned()
+            .collect())
     }
 
-    pub fn find_by_project_id(&self, project_id: &Uuid) -> Result<Vec<Task>, String> {
+    pub fn find_by_project_id(
+        &self,
+        tenant_id: &str,
+        project_id: &Uuid,
+    ) -> Result<Vec<Task>, String> {
         let tasks = self
             .tasks
             .read()
             .map_err(|_| "Failed to acquire read lock")?;
         Ok(tasks
             .values()
-            .filter(|task| task.project_id == *project_id)
+            .filter(|task| {
+                task.tenant_id == tenant_id
+                    && task.project_id == *project_id
+                    && task.deleted_at.is_none()
+            })
             .cloned()
             .collect())
     }
 
-    pub fn update(&self, id: &Uuid, updated_task: Task) -> Result<Option<Task>, String> {
+    /// Live tasks of any of `project_ids`, found in one pass over the store.
+    pub fn find_by_project_ids(
+        &self,
+        tenant_id: &str,
+        project_ids: &HashSet<Uuid>,
+    ) -> Result<Vec<Task>, String> {
+        let tasks = self
+            .tasks
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(tasks
+            .values()
+            .filter(|task| {
+                task.tenant_id == tenant_id
+                    && project_ids.contains(&task.project_id)
+                    && task.deleted_at.is_none()
+            })
+            .cloned()
+            .collect())
+    }
+
+    pub fn count(&self, tenant_id: &str) -> Result<usize, String> {
+        let tasks = self
+            .tasks
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(tasks
+            .values()
+            .filter(|task| task.tenant_id == tenant_id && task.deleted_at.is_none())
+            .count())
+    }
+
+    pub fn update(
+        &self,
+        tenant_id: &str,
+        id: &Uuid,
+        updated_task: Task,
+    ) -> Result<Option<Task>, String> {
         let mut tasks = self
             .tasks
             .write()
             .map_err(|_| "Failed to acquire write lock")?;
-        if tasks.contains_key(id) {
-            tasks.insert(*id, updated_task.clone());
-            Ok(Some(updated_task))
-        } else {
-            Ok(None)
+        match tasks.get(id) {
+            Some(task) if task.tenant_id == tenant_id && task.deleted_at.is_none() => {
+                self.changes.record(
+                    tenant_id,
+                    EntityType::Task,
+                    *id,
+                    updated_task.project_id,
+                    Some(&updated_task),
+                )?;
+                tasks.insert(*id, updated_task.clone());
+                Ok(Some(updated_task))
+            }
+            _ => Ok(None),
         }
     }
 
-    pub fn delete(&self, id: &Uuid) -> Result<bool, String> {
+    /// Like [`update`](Self::update), but `check_column` is first given the number of other
+    /// tasks in the task's column, counted under the same lock, and can turn the write down.
+    pub fn update_checked<E: From<String>>(
+        &self,
+        tenant_id: &str,
+        id: &Uuid,
+        updated_task: Task,
+        check_column: impl FnOnce(usize) -> Result<(), E>,
+    ) -> Result<Option<Task>, E> {
         let mut tasks = self
+            .tasks
+            .write()
+            .map_err(|_| String::from("Failed to acquire write lock"))?;
+        match tasks.get(id) {
+            Some(task) if task.tenant_id == tenant_id && task.deleted_at.is_none() => {
+                check_column(column_count(&tasks, &updated_task))?;
+                self.changes.record(
+                    tenant_id,
+                    EntityType::Task,
+                    *id,
+                    updated_task.project_id,
+                    Some(&updated_task),
+                )?;
+                tasks.insert(*id, updated_task.clone());
+                Ok(Some(updated_task))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Sets `done` on the project's live tasks in the columns of `done_by_column` to that
+    /// column's value, under one lock. Returns each changed task before and after.
+    pub fn set_done_by_column(
+        &self,
+        tenant_id: &str,
+        project_id: &Uuid,
+        done_by_column: &HashMap<Uuid, bool>,
+    ) -> Result<Vec<(Task, Task)>, String> {
+        let mut tasks = self
+            .tasks
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        let mut changed = Vec::new();
+        for task in tasks.values_mut().filter(|task| {
+            task.tenant_id == tenant_id
+                && task.project_id == *project_id
+                && task.deleted_at.is_none()
+        }) {
+            let Some(&done) = task.column_id.and_then(|id| done_by_column.get(&id)) else {
+                continue;
+            };
+            if task.done != done {
+                let mut updated = task.clone();
+                updated.set_done(done);
+                self.changes.record(
+                    tenant_id,
+                    EntityType::Task,
+                    task.id,
+                    *project_id,
+                    Some(&updated),
+                )?;
+                changed.push((std::mem::replace(task, updated.clone()), updated));
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Records the project's live tasks again at the head of the change log; see
+    /// [`ProjectRepository::record_again`](super::ProjectRepository::record_again).
+    pub fn record_again_by_project_id(
+        &self,
+        tenant_id: &str,
+        project_id: &Uuid,
+    ) -> Result<usize, String> {
+        let tasks = self
             .tasks
             .write()
             .map_err(|_| "Failed to acquire write lock")?;
-        Ok(tasks.remove(id).is_some())
+        let mut count = 0;
+        for task in tasks.values().filter(|task| {
+            task.tenant_id == tenant_id
+                && task.project_id == *project_id
+                && task.deleted_at.is_none()
+        }) {
+            self.changes.record(
+                tenant_id,
+                EntityType::Task,
+                task.id,
+                *project_id,
+                Some(task),
+            )?;
+            count += 1;
+        }
+        Ok(count)
     }
 
-    pub fn delete_by_project_id(&self, project_id: &Uuid) -> Result<usize, String> {
+    /// Moves the task to the trash.
+    pub fn delete(
+        &self,
+        tenant_id: &str,
+        id: &Uuid,
+        deleted_at: DateTime<Utc>,
+    ) -> Result<bool, String> {
         let mut tasks = self
             .tasks
             .write()
             .map_err(|_| "Failed to acquire write lock")?;
-        let ids_to_remove: Vec<Uuid> = tasks
-            .values()
-            .filter(|task| task.project_id == *project_id)
-            .map(|task| task.id)
-            .collect();
+        match tasks.get_mut(id) {
+            Some(task) if task.tenant_id == tenant_id && task.deleted_at.is_none() => {
+                self.changes.record::<Task>(
+                    tenant_id,
+                    EntityType::Task,
+                    *id,
+                    task.project_id,
+                    None,
+                )?;
+                task.deleted_at = Some(deleted_at);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
 
-        let count = ids_to_remove.len();
-        for id in ids_to_remove {
-            tasks.remove(&id);
+    /// Moves every live task of the project to the trash, stamped with `deleted_at` so
+    /// they can be restored together.
+    pub fn delete_by_project_id(
+        &self,
+        tenant_id: &str,
+        project_id: &Uuid,
+        deleted_at: DateTime<Utc>,
+    ) -> Result<usize, String> {
+        let mut tasks = self
+            .tasks
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        let mut count = 0;
+        for task in tasks.values_mut().filter(|task| {
+            task.tenant_id == tenant_id
+                && task.project_id == *project_id
+                && task.deleted_at.is_none()
+        }) {
+            self.changes
+                .record::<Task>(tenant_id, EntityType::Task, task.id, *project_id, None)?;
+            task.deleted_at = Some(deleted_at);
+            count += 1;
         }
         Ok(count)
     }
+
+    pub fn find_deleted(&self, tenant_id: &str) -> Result<Vec<Task>, String> {
+        let tasks = self
+            .tasks
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(tasks
+            .values()
+            .filter(|task| task.tenant_id == tenant_id && task.deleted_at.is_some())
+            .cloned()
+            .collect())
+    }
+
+    pub fn find_deleted_by_id(&self, tenant_id: &str, id: &Uuid) -> Result<Option<Task>, String> {
+        let tasks = self
+            .tasks
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(tasks
+            .get(id)
+            .filter(|task| task.tenant_id == tenant_id && task.deleted_at.is_some())
+            .cloned())
+    }
+
+    /// Tasks of every tenant that were deleted before `cutoff`.
+    pub fn find_deleted_before(&self, cutoff: DateTime<Utc>) -> Result<Vec<Task>, String> {
+        let tasks = self
+            .tasks
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(tasks
+            .values()
+            .filter(|task| task.deleted_at.is_some_and(|at| at < cutoff))
+            .cloned()
+            .collect())
+    }
+
+    /// Open live tasks of every tenant that are due at or before `cutoff`.
+    pub fn find_due_before(&self, cutoff: DateTime<Utc>) -> Result<Vec<Task>, String> {
+        let tasks = self
+            .tasks
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(tasks
+            .values()
+            .filter(|task| {
+                task.deleted_at.is_none()
+                    && !task.done
+                    && task.due_at.is_some_and(|due_at| due_at <= cutoff)
+            })
+            .cloned()
+            .collect())
+    }
+
+    /// Takes the task out of the trash unless its tenant already has `limit` live tasks,
+    /// checked under the same lock. Returns `None` if it is not in the trash or the tenant is at
+    /// the limit.
+    pub fn restore(
+        &self,
+        tenant_id: &str,
+        id: &Uuid,
+        limit: Option<usize>,
+    ) -> Result<Option<Task>, String> {
+        let mut tasks = self
+            .tasks
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        if limit.is_some_and(|limit| live_count(&tasks, tenant_id) >= limit) {
+            return Ok(None);
+        }
+        match tasks.get_mut(id) {
+            Some(task) if task.tenant_id == tenant_id && task.deleted_at.is_some() => {
+                let mut restored = task.clone();
+                restored.deleted_at = None;
+                self.changes.record(
+                    tenant_id,
+                    EntityType::Task,
+                    *id,
+                    restored.project_id,
+                    Some(&restored),
+                )?;
+                *task = restored.clone();
+                Ok(Some(restored))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Restores the project's tasks that were deleted at exactly `deleted_at`, i.e. together
+    /// with the project, unless the tenant would end up with more than `limit` live tasks; then
+    /// it returns `None`.
+    ///
+    /// `restore_project` runs under the task lock once the tasks fit, and they are only restored
+    /// if it succeeds, so the project and its tasks come back together or not at all.
+    pub fn restore_by_project_id<T, E: From<String>>(
+        &self,
+        tenant_id: &str,
+        project_id: &Uuid,
+        deleted_at: DateTime<Utc>,
+        limit: Option<usize>,
+        restore_project: impl FnOnce() -> Result<T, E>,
+    ) -> Result<Option<(T, Vec<Task>)>, E> {
+        let mut tasks = self
+            .tasks
+            .write()
+            .map_err(|_| String::from("Failed to acquire write lock"))?;
+        if let Some(limit) = limit {
+            let restoring = tasks
+                .values()
+                .filter(|task| {
+                    task.tenant_id == tenant_id
+                        && task.project_id == *project_id
+                        && task.deleted_at == Some(deleted_at)
+                })
+                .count();
+            if live_count(&tasks, tenant_id) + restoring > limit {
+                return Ok(None);
+            }
+        }
+        let project = restore_project()?;
+
+        let mut restored = Vec::new();
+        for task in tasks.values_mut().filter(|task| {
+            task.tenant_id == tenant_id
+                && task.project_id == *project_id
+                && task.deleted_at == Some(deleted_at)
+        }) {
+            let mut restored_task = task.clone();
+            restored_task.deleted_at = None;
+            self.changes.record(
+                tenant_id,
+                EntityType::Task,
+                task.id,
+                *project_id,
+                Some(&restored_task),
+            )?;
+            *task = restored_task.clone();
+            restored.push(restored_task);
+        }
+        Ok(Some((project, restored)))
+    }
+
+    /// Removes a task from the trash for good.
+    pub fn purge(&self, tenant_id: &str, id: &Uuid) -> Result<bool, String> {
+        let mut tasks = self
+            .tasks
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        match tasks.get(id) {
+            Some(task) if task.tenant_id == tenant_id && task.deleted_at.is_some() => {
+                tasks.remove(id);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Removes every trashed task of the project for good.
+    pub fn purge_by_project_id(&self, tenant_id: &str, project_id: &Uuid) -> Result<usize, String> {
+        let mut tasks = self
+            .tasks
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        let before = tasks.len();
+        tasks.retain(|_, task| {
+            !(task.tenant_id == tenant_id
+                && task.project_id == *project_id
+                && task.deleted_at.is_some())
+        });
+        Ok(before - tasks.len())
+    }
+
+    /// Tenant of a task, live or trashed, looking across all tenants.
+    pub fn tenant_of(&self, id: &Uuid) -> Result<Option<String>, String> {
+        let tasks = self
+            .tasks
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(tasks.get(id).map(|task| task.tenant_id.clone()))
+    }
+
+    /// Stores tasks from a backup as they are, ids and timestamps included. `replace` first
+    /// removes every task of that tenant. With a `limit`, nothing is stored and `None` returned
+    /// if that tenant would end up with more live tasks.
+    ///
+    /// `store_projects` runs under the task lock once the tasks fit, and they are only stored if
+    /// it succeeds, so a backup's projects and tasks are written together or not at all.
+    pub fn import<T, E: From<String>>(
+        &self,
+        imported: Vec<Task>,
+        replace: Option<&str>,
+        limit: Option<(&str, usize)>,
+        store_projects: impl FnOnce() -> Result<T, E>,
+    ) -> Result<Option<T>, E> {
+        let mut tasks = self
+            .tasks
+            .write()
+            .map_err(|_| String::from("Failed to acquire write lock"))?;
+        if let Some((tenant_id, limit)) = limit {
+            let incoming: HashSet<Uuid> = imported.iter().map(|task| task.id).collect();
+            let kept = tasks
+                .values()
+                .filter(|task| {
+                    replace != Some(tenant_id)
+                        && task.tenant_id == tenant_id
+                        && task.deleted_at.is_none()
+                        && !incoming.contains(&task.id)
+                })
+                .count();
+            let added = imported
+                .iter()
+                .filter(|task| task.tenant_id == tenant_id && task.deleted_at.is_none())
+                .count();
+            if kept + added > limit {
+                return Ok(None);
+            }
+        }
+        let projects = store_projects()?;
+
+        if let Some(tenant_id) = replace {
+            let removed: Vec<Uuid> = tasks
+                .values()
+                .filter(|task| task.tenant_id == tenant_id)
+                .map(|task| task.id)
+                .collect();
+            for id in removed {
+                if let Some(task) = tasks.remove(&id).filter(|task| task.deleted_at.is_none()) {
+                    self.changes.record::<Task>(
+                        tenant_id,
+                        EntityType::Task,
+                        id,
+                        task.project_id,
+                        None,
+                    )?;
+                }
+            }
+        }
+        for task in imported {
+            let live = task.deleted_at.is_none().then_some(&task);
+            self.changes.record(
+                &task.tenant_id,
+                EntityType::Task,
+                task.id,
+                task.project_id,
+                live,
+            )?;
+            tasks.insert(task.id, task);
+        }
+        Ok(Some(projects))
+    }
+}
+
+/// Live tasks of the tenant.
+fn live_count(tasks: &HashMap<Uuid, Task>, tenant_id: &str) -> usize {
+    tasks
+        .values()
+        .filter(|task| task.tenant_id == tenant_id && task.deleted_at.is_none())
+        .count()
+}
+
+/// Live tasks other than `task` in the column `task` is in, within its project.
+fn column_count(tasks: &HashMap<Uuid, Task>, task: &Task) -> usize {
+    let Some(column_id) = task.column_id else {
+        return 0;
+    };
+    tasks
+        .values()
+        .filter(|other| {
+            other.project_id == task.project_id
+                && other.column_id == Some(column_id)
+                && other.id != task.id
+                && other.deleted_at.is_none()
+        })
+        .count()
 }
 
 impl Default for TaskRepository {
     fn default() -> Self {
-        Self::new()
+        Self::new(Arc::default())
     }
 }
@@ -0,0 +1,165 @@
+use crate::models::{TimeEntry, TimeEntryFilter};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use uuid::Uuid;
+
+#[derive(Debug, Default)]
+pub struct TimeEntryRepository {
+    entries: RwLock<HashMap<Uuid, TimeEntry>>,
+}
+
+impl TimeEntryRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn create(&self, entry: TimeEntry) -> Result<TimeEntry, String> {
+        let mut entries = self
+            .entries
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        entries.insert(entry.id, entry.clone());
+        Ok(entry)
+    }
+
+    /// Stores a running timer unless its user already has one; returns `None` in that case.
+    pub fn start(&self, timer: TimeEntry) -> Result<Option<TimeEntry>, String> {
+        let mut entries = self
+            .entries
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        let running = entries.values().any(|entry| {
+            entry.tenant_id == timer.tenant_id && entry.user == timer.user && entry.is_running()
+        });
+        if running {
+            return Ok(None);
+        }
+        entries.insert(timer.id, timer.clone());
+        Ok(Some(timer))
+    }
+
+    pub fn find_by_id(&self, tenant_id: &str, id: &Uuid) -> Result<Option<TimeEntry>, String> {
+        let entries = self
+            .entries
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(entries
+            .get(id)
+            .filter(|entry| entry.tenant_id == tenant_id)
+            .cloned())
+    }
+
+    pub fn find_running(&self, tenant_id: &str, user: &str) -> Result<Option<TimeEntry>, String> {
+        let entries = self
+            .entries
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(entries
+            .values()
+            .find(|entry| entry.tenant_id == tenant_id && entry.user == user && entry.is_running())
+            .cloned())
+    }
+
+    /// Entries matching `filter`, oldest first.
+    pub fn find(
+        &self,
+        tenant_id: &str,
+        filter: &TimeEntryFilter,
+    ) -> Result<Vec<TimeEntry>, String> {
+        let entries = self
+            .entries
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        let mut matching: Vec<TimeEntry> = entries
+            .values()
+            .filter(|entry| entry.tenant_id == tenant_id && filter.matches(entry))
+            .cloned()
+            .collect();
+        matching.sort_by_key(|entry| (entry.started_at, entry.id));
+        Ok(matching)
+    }
+
+    pub fn update(
+        &self,
+        tenant_id: &str,
+        id: &Uuid,
+        updated_entry: TimeEntry,
+    ) -> Result<Option<TimeEntry>, String> {
+        let mut entries = self
+            .entries
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        match entries.get(id) {
+            Some(entry) if entry.tenant_id == tenant_id => {
+                entries.insert(*id, updated_entry.clone());
+                Ok(Some(updated_entry))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    pub fn delete(&self, tenant_id: &str, id: &Uuid) -> Result<bool, String> {
+        let mut entries = self
+            .entries
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        match entries.get(id) {
+            Some(entry) if entry.tenant_id == tenant_id => {
+                entries.remove(id);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Removes a project's entries; returns how many.
+    pub fn delete_by_project_id(
+        &self,
+        tenant_id: &str,
+        project_id: &Uuid,
+    ) -> Result<usize, String> {
+        let mut entries = self
+            .entries
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        let before = entries.len();
+        entries.retain(|_, entry| entry.tenant_id != tenant_id || entry.project_id != *project_id);
+        Ok(before - entries.len())
+    }
+
+    /// Removes a task's entries; returns how many.
+    pub fn delete_by_task_id(&self, tenant_id: &str, task_id: &Uuid) -> Result<usize, String> {
+        let mut entries = self
+            .entries
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        let before = entries.len();
+        entries.retain(|_, entry| entry.tenant_id != tenant_id || entry.task_id != *task_id);
+        Ok(before - entries.len())
+    }
+
+    /// Tenant of an entry, looking across all tenants.
+    pub fn tenant_of(&self, id: &Uuid) -> Result<Option<String>, String> {
+        let entries = self
+            .entries
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(entries.get(id).map(|entry| entry.tenant_id.clone()))
+    }
+
+    /// Stores entries from a backup as they are. `replace` first removes every entry of that
+    /// tenant.
+    pub fn import(&self, imported: Vec<TimeEntry>, replace: Option<&str>) -> Result<(), String> {
+        let mut entries = self
+            .entries
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        if let Some(tenant_id) = replace {
+            entries.retain(|_, entry| entry.tenant_id != tenant_id);
+        }
+        for entry in imported {
+            entries.insert(entry.id, entry);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,166 @@
+use crate::models::{DeliveryStatus, WebhookDelivery};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::RwLock;
+use uuid::Uuid;
+
+/// Delivery log and retry queue: pending deliveries stay here until they succeed or are
+/// dead-lettered, so nothing is lost between attempts. Pending deliveries are indexed by their
+/// next attempt, so finding the due ones does not walk the whole log.
+#[derive(Debug)]
+pub struct WebhookDeliveryRepository {
+    deliveries: RwLock<Deliveries>,
+}
+
+#[derive(Debug, Default)]
+struct Deliveries {
+    by_id: HashMap<Uuid, WebhookDelivery>,
+    /// Next attempt and id of every pending delivery
+    queue: BTreeSet<(DateTime<Utc>, Uuid)>,
+}
+
+impl Deliveries {
+    fn insert(&mut self, delivery: WebhookDelivery) {
+        self.remove(&delivery.id);
+        if let Some(at) = queued_at(&delivery) {
+            self.queue.insert((at, delivery.id));
+        }
+        self.by_id.insert(delivery.id, delivery);
+    }
+
+    fn remove(&mut self, id: &Uuid) -> Option<WebhookDelivery> {
+        let delivery = self.by_id.remove(id)?;
+        if let Some(at) = queued_at(&delivery) {
+            self.queue.remove(&(at, *id));
+        }
+        Some(delivery)
+    }
+}
+
+impl WebhookDeliveryRepository {
+    pub fn new() -> Self {
+        Self {
+            deliveries: RwLock::new(Deliveries::default()),
+        }
+    }
+
+    pub fn create(&self, delivery: WebhookDelivery) -> Result<WebhookDelivery, String> {
+        let mut deliveries = self
+            .deliveries
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        deliveries.insert(delivery.clone());
+        Ok(delivery)
+    }
+
+    pub fn find_by_id(
+        &self,
+        tenant_id: &str,
+        id: &Uuid,
+    ) -> Result<Option<WebhookDelivery>, String> {
+        let deliveries = self
+            .deliveries
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(deliveries
+            .by_id
+            .get(id)
+            .filter(|delivery| delivery.tenant_id == tenant_id)
+            .cloned())
+    }
+
+    pub fn find_by_webhook_id(
+        &self,
+        tenant_id: &str,
+        webhook_id: &Uuid,
+    ) -> Result<Vec<WebhookDelivery>, String> {
+        let deliveries = self
+            .deliveries
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(deliveries
+            .by_id
+            .values()
+            .filter(|delivery| {
+                delivery.tenant_id == tenant_id && delivery.webhook_id == *webhook_id
+            })
+            .cloned()
+            .collect())
+    }
+
+    /// Pending deliveries across all tenants whose next attempt is due, oldest first.
+    pub fn find_due(&self, now: DateTime<Utc>) -> Result<Vec<WebhookDelivery>, String> {
+        let deliveries = self
+            .deliveries
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(deliveries
+            .queue
+            .iter()
+            .take_while(|(at, _)| *at <= now)
+            .filter_map(|(_, id)| deliveries.by_id.get(id))
+            .cloned()
+            .collect())
+    }
+
+    /// Stores the new state of a delivery that is still in the log. Returns `None` once it is
+    /// gone, e.g. removed with its webhook while an attempt was under way.
+    pub fn update(&self, delivery: WebhookDelivery) -> Result<Option<WebhookDelivery>, String> {
+        let mut deliveries = self
+            .deliveries
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        if !deliveries.by_id.contains_key(&delivery.id) {
+            return Ok(None);
+        }
+        deliveries.insert(delivery.clone());
+        Ok(Some(delivery))
+    }
+
+    pub fn delete_by_webhook_id(&self, tenant_id: &str, webhook_id: &Uuid) -> Result<(), String> {
+        let mut deliveries = self
+            .deliveries
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        let removed: Vec<Uuid> = deliveries
+            .by_id
+            .values()
+            .filter(|delivery| {
+                delivery.tenant_id == tenant_id && delivery.webhook_id == *webhook_id
+            })
+            .map(|delivery| delivery.id)
+            .collect();
+        for id in removed {
+            deliveries.remove(&id);
+        }
+        Ok(())
+    }
+
+    /// Removes delivered and dead-lettered deliveries, across all tenants, whose last attempt
+    /// was before `cutoff`. Returns how many were removed.
+    pub fn delete_finished_before(&self, cutoff: DateTime<Utc>) -> Result<usize, String> {
+        let mut deliveries = self
+            .deliveries
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        let before = deliveries.by_id.len();
+        deliveries.by_id.retain(|_, delivery| {
+            delivery.status == DeliveryStatus::Pending
+                || delivery.last_attempt_at.unwrap_or(delivery.created_at) >= cutoff
+        });
+        Ok(before - deliveries.by_id.len())
+    }
+}
+
+/// When a delivery is next due, if it is still pending.
+fn queued_at(delivery: &WebhookDelivery) -> Option<DateTime<Utc>> {
+    delivery
+        .next_attempt_at
+        .filter(|_| delivery.status == DeliveryStatus::Pending)
+}
+
+impl Default for WebhookDeliveryRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
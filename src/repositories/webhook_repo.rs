@@ -0,0 +1,88 @@
+use crate::models::Webhook;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct WebhookRepository {
+    webhooks: RwLock<HashMap<Uuid, Webhook>>,
+}
+
+impl WebhookRepository {
+    pub fn new() -> Self {
+        Self {
+            webhooks: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn create(&self, webhook: Webhook) -> Result<Webhook, String> {
+        let mut webhooks = self
+            .webhooks
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        webhooks.insert(webhook.id, webhook.clone());
+        Ok(webhook)
+    }
+
+    pub fn find_by_id(&self, tenant_id: &str, id: &Uuid) -> Result<Option<Webhook>, String> {
+        let webhooks = self
+            .webhooks
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(webhooks
+            .get(id)
+            .filter(|webhook| webhook.tenant_id == tenant_id)
+            .cloned())
+    }
+
+    pub fn find_all(&self, tenant_id: &str) -> Result<Vec<Webhook>, String> {
+        let webhooks = self
+            .webhooks
+            .read()
+            .map_err(|_| "Failed to acquire read lock")?;
+        Ok(webhooks
+            .values()
+            .filter(|webhook| webhook.tenant_id == tenant_id)
+            .cloned()
+            .collect())
+    }
+
+    pub fn update(
+        &self,
+        tenant_id: &str,
+        id: &Uuid,
+        updated_webhook: Webhook,
+    ) -> Result<Option<Webhook>, String> {
+        let mut webhooks = self
+            .webhooks
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        match webhooks.get(id) {
+            Some(webhook) if webhook.tenant_id == tenant_id => {
+                webhooks.insert(*id, updated_webhook.clone());
+                Ok(Some(updated_webhook))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    pub fn delete(&self, tenant_id: &str, id: &Uuid) -> Result<bool, String> {
+        let mut webhooks = self
+            .webhooks
+            .write()
+            .map_err(|_| "Failed to acquire write lock")?;
+        match webhooks.get(id) {
+            Some(webhook) if webhook.tenant_id == tenant_id => {
+                webhooks.remove(id);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+impl Default for WebhookRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
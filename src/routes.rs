@@ -1,11 +1,29 @@
 use crate::controllers::{
-    create_project, create_task, delete_project, delete_task, get_project, get_task, health_check,
-    list_projects, list_tasks, update_project, update_task,
+    archive_project, connect_websocket, create_api_key, create_calendar_token, create_project,
+    create_recurring_task, create_task, create_time_entry, create_webhook, current_principal,
+    delete_calendar_token, delete_project, delete_recurring_task, delete_task, delete_time_entry,
+    delete_webhook, diff_project_revisions, diff_task_revisions, export_audit_entries,
+    export_backup, export_project_tasks, export_time_report, get_project, get_project_revision,
+    get_recurring_task, get_task, get_task_revision, get_timer, get_webhook, health_check,
+    import_project_tasks, list_api_keys, list_audit_entries, list_calendar_tokens, list_changes,
+    list_overdue_tasks, list_project_columns, list_project_members, list_project_revisions,
+    list_projects, list_recurring_tasks, list_task_revisions, list_tasks, list_time_entries,
+    list_trash, list_webhook_deliveries, list_webhooks, move_task, overdue_counts,
+    project_calendar, project_stats, purge_project, purge_task, remove_project_member,
+    restore_backup, restore_project, restore_task, retry_webhook_delivery, revert_project,
+    revert_task, revoke_api_key, set_project_columns, set_project_member, start_timer, stop_timer,
+    stream_events, time_report, unarchive_project, update_project, update_task, update_webhook,
+    user_calendar,
 };
-use ntex::web::{self, HttpResponse, ServiceConfig};
+use ntex::web::{self, ServiceConfig};
 
+#[cfg(any(feature = "openapi", feature = "graphql"))]
+use ntex::web::HttpResponse;
+
+#[cfg(feature = "openapi")]
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
 #[cfg(feature = "openapi")]
-use utoipa::OpenApi;
+use utoipa::{Modify, OpenApi};
 
 #[cfg(feature = "openapi")]
 #[derive(OpenApi)]
@@ -17,31 +35,184 @@ use utoipa::OpenApi;
         crate::controllers::list_projects,
         crate::controllers::update_project,
         crate::controllers::delete_project,
+        crate::controllers::archive_project,
+        crate::controllers::unarchive_project,
+        crate::controllers::project_stats,
+        crate::controllers::list_project_columns,
+        crate::controllers::set_project_columns,
+        crate::controllers::list_project_members,
+        crate::controllers::set_project_member,
+        crate::controllers::remove_project_member,
+        crate::controllers::list_project_revisions,
+        crate::controllers::get_project_revision,
+        crate::controllers::diff_project_revisions,
+        crate::controllers::revert_project,
         crate::controllers::create_task,
         crate::controllers::get_task,
         crate::controllers::list_tasks,
+        crate::controllers::list_overdue_tasks,
+        crate::controllers::overdue_counts,
         crate::controllers::update_task,
         crate::controllers::delete_task,
+        crate::controllers::move_task,
+        crate::controllers::list_task_revisions,
+        crate::controllers::get_task_revision,
+        crate::controllers::diff_task_revisions,
+        crate::controllers::revert_task,
+        crate::controllers::export_project_tasks,
+        crate::controllers::import_project_tasks,
+        crate::controllers::create_calendar_token,
+        crate::controllers::list_calendar_tokens,
+        crate::controllers::delete_calendar_token,
+        crate::controllers::user_calendar,
+        crate::controllers::project_calendar,
+        crate::controllers::create_recurring_task,
+        crate::controllers::list_recurring_tasks,
+        crate::controllers::get_recurring_task,
+        crate::controllers::delete_recurring_task,
+        crate::controllers::create_time_entry,
+        crate::controllers::list_time_entries,
+        crate::controllers::delete_time_entry,
+        crate::controllers::get_timer,
+        crate::controllers::start_timer,
+        crate::controllers::stop_timer,
+        crate::controllers::time_report,
+        crate::controllers::export_time_report,
+        crate::controllers::list_trash,
+        crate::controllers::restore_project,
+        crate::controllers::purge_project,
+        crate::controllers::restore_task,
+        crate::controllers::purge_task,
+        crate::controllers::current_principal,
+        crate::controllers::create_api_key,
+        crate::controllers::list_api_keys,
+        crate::controllers::revoke_api_key,
+        crate::controllers::list_audit_entries,
+        crate::controllers::export_audit_entries,
+        crate::controllers::export_backup,
+        crate::controllers::restore_backup,
+        crate::controllers::list_changes,
+        crate::controllers::stream_events,
+        crate::controllers::connect_websocket,
+        crate::controllers::create_webhook,
+        crate::controllers::list_webhooks,
+        crate::controllers::get_webhook,
+        crate::controllers::update_webhook,
+        crate::controllers::delete_webhook,
+        crate::controllers::list_webhook_deliveries,
+        crate::controllers::retry_webhook_delivery,
     ),
     components(
         schemas(crate::models::project::Project),
         schemas(crate::models::project::ProjectCreate),
         schemas(crate::models::project::ProjectUpdate),
+        schemas(crate::models::column::Column),
+        schemas(crate::models::column::ColumnDefinition),
+        schemas(crate::models::stats::ProjectStats),
+        schemas(crate::models::stats::BurndownPoint),
+        schemas(crate::views::api_response::ApiResponse<crate::models::stats::ProjectStats>),
+        schemas(crate::views::api_response::ApiResponse<Vec<crate::models::column::Column>>),
+        schemas(crate::models::membership::ProjectRole),
+        schemas(crate::models::membership::ProjectMember),
+        schemas(crate::models::membership::ProjectMemberUpdate),
         schemas(crate::models::task::Task),
         schemas(crate::models::task::TaskCreate),
         schemas(crate::models::task::TaskUpdate),
+        schemas(crate::models::task::TaskMove),
+        schemas(crate::models::task_import::TaskImportReport),
+        schemas(crate::models::task_import::TaskImportError),
+        schemas(crate::views::api_response::ApiResponse<crate::models::task_import::TaskImportReport>),
+        schemas(crate::models::reminder::OverdueCount),
+        schemas(crate::views::api_response::ApiResponse<Vec<crate::models::reminder::OverdueCount>>),
         schemas(crate::views::api_response::ApiResponse<crate::models::project::Project>),
         schemas(crate::views::api_response::ApiResponse<Vec<crate::models::project::Project>>),
+        schemas(crate::views::api_response::ApiResponse<crate::models::membership::ProjectMember>),
+        schemas(crate::views::api_response::ApiResponse<Vec<crate::models::membership::ProjectMember>>),
         schemas(crate::views::api_response::ApiResponse<crate::models::task::Task>),
         schemas(crate::views::api_response::ApiResponse<Vec<crate::models::task::Task>>),
+        schemas(crate::models::revision::Revision),
+        schemas(crate::models::revision::RevisionDiff),
+        schemas(crate::views::api_response::ApiResponse<crate::models::revision::Revision>),
+        schemas(crate::views::api_response::ApiResponse<Vec<crate::models::revision::Revision>>),
+        schemas(crate::views::api_response::ApiResponse<crate::models::revision::RevisionDiff>),
+        schemas(crate::models::calendar::CalendarToken),
+        schemas(crate::models::calendar::CalendarTokenCreate),
+        schemas(crate::models::calendar::CalendarTokenCreated),
+        schemas(crate::models::calendar::CalendarComponent),
+        schemas(crate::views::api_response::ApiResponse<crate::models::calendar::CalendarTokenCreated>),
+        schemas(crate::views::api_response::ApiResponse<Vec<crate::models::calendar::CalendarToken>>),
+        schemas(crate::models::recurrence::RecurringTask),
+        schemas(crate::models::recurrence::RecurringTaskCreate),
+        schemas(crate::models::recurrence::TaskOccurrence),
+        schemas(crate::views::api_response::ApiResponse<crate::models::recurrence::RecurringTask>),
+        schemas(crate::views::api_response::ApiResponse<Vec<crate::models::recurrence::RecurringTask>>),
+        schemas(crate::models::time_entry::TimeEntry),
+        schemas(crate::models::time_entry::TimeEntryCreate),
+        schemas(crate::models::time_entry::TimerStart),
+        schemas(crate::models::time_entry::TimeReportGroup),
+        schemas(crate::models::time_entry::TimeReportRow),
+        schemas(crate::models::time_entry::TimeReport),
+        schemas(crate::views::api_response::ApiResponse<crate::models::time_entry::TimeEntry>),
+        schemas(crate::views::api_response::ApiResponse<Vec<crate::models::time_entry::TimeEntry>>),
+        schemas(crate::views::api_response::ApiResponse<crate::models::time_entry::TimeReport>),
+        schemas(crate::models::trash::Trash),
+        schemas(crate::views::api_response::ApiResponse<crate::models::trash::Trash>),
+        schemas(crate::views::api_response::ApiResponse<crate::models::principal::Principal>),
         schemas(crate::views::api_response::ErrorResponse),
+        schemas(crate::models::principal::Principal),
+        schemas(crate::models::api_key::ApiKey),
+        schemas(crate::models::api_key::ApiKeyCreate),
+        schemas(crate::models::api_key::ApiKeyCreated),
+        schemas(crate::views::api_response::ApiResponse<crate::models::api_key::ApiKey>),
+        schemas(crate::views::api_response::ApiResponse<Vec<crate::models::api_key::ApiKey>>),
+        schemas(crate::views::api_response::ApiResponse<crate::models::api_key::ApiKeyCreated>),
+        schemas(crate::models::audit::AuditAction),
+        schemas(crate::models::audit::EntityType),
+        schemas(crate::models::audit::FieldChange),
+        schemas(crate::models::audit::AuditEntry),
+        schemas(crate::views::api_response::ApiResponse<Vec<crate::models::audit::AuditEntry>>),
+        schemas(crate::models::backup::Backup),
+        schemas(crate::models::backup::BackupRecurringTask),
+        schemas(crate::models::backup::BackupFormat),
+        schemas(crate::models::backup::RestoreMode),
+        schemas(crate::models::backup::RestoreReport),
+        schemas(crate::views::api_response::ApiResponse<crate::models::backup::RestoreReport>),
+        schemas(crate::models::change::ChangeOperation),
+        schemas(crate::models::change::Change),
+        schemas(crate::models::change::ChangeFeed),
+        schemas(crate::views::api_response::ApiResponse<crate::models::change::ChangeFeed>),
+        schemas(crate::models::event::EventKind),
+        schemas(crate::models::webhook::Webhook),
+        schemas(crate::models::webhook::WebhookCreate),
+        schemas(crate::models::webhook::WebhookUpdate),
+        schemas(crate::models::webhook::WebhookCreated),
+        schemas(crate::models::webhook::DeliveryStatus),
+        schemas(crate::models::webhook::WebhookDelivery),
+        schemas(crate::views::api_response::ApiResponse<crate::models::webhook::Webhook>),
+        schemas(crate::views::api_response::ApiResponse<Vec<crate::models::webhook::Webhook>>),
+        schemas(crate::views::api_response::ApiResponse<crate::models::webhook::WebhookCreated>),
+        schemas(crate::views::api_response::ApiResponse<crate::models::webhook::WebhookDelivery>),
+        schemas(crate::views::api_response::ApiResponse<Vec<crate::models::webhook::WebhookDelivery>>),
         schemas(crate::controllers::health_controller::HealthResponse)
     ),
     tags(
         (name = "projects", description = "Project management endpoints"),
         (name = "tasks", description = "Task management endpoints"),
+        (name = "recurring-tasks", description = "Task templates created on a schedule"),
+        (name = "calendar", description = "iCalendar feeds of task due dates"),
+        (name = "time", description = "Time tracking and billing reports"),
+        (name = "trash", description = "Restore and purge deleted projects and tasks"),
+        (name = "auth", description = "Authentication endpoints"),
+        (name = "api-keys", description = "API key management endpoints"),
+        (name = "audit", description = "Audit log endpoints"),
+        (name = "backup", description = "Backup and restore of a tenant's data"),
+        (name = "changes", description = "Incremental sync endpoints"),
+        (name = "events", description = "Live change stream endpoints"),
+        (name = "webhooks", description = "Webhook subscription and delivery endpoints"),
         (name = "health", description = "Health check endpoints")
     ),
+    modifiers(&SecurityAddon),
+    security(("bearer_auth" = []), ("api_key" = [])),
     info(
         title = "Rust MVC API",
         description = "A production-leaning Rust REST API using MVC architecture with ntex framework",
@@ -62,6 +233,29 @@ use utoipa::OpenApi;
 )]
 pub struct ApiDoc;
 
+#[cfg(feature = "openapi")]
+struct SecurityAddon;
+
+#[cfg(feature = "openapi")]
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-API-Key"))),
+        );
+    }
+}
+
 #[cfg(feature = "openapi")]
 async fn openapi_spec() -> HttpResponse {
     HttpResponse::Ok()
@@ -75,6 +269,12 @@ async fn swagger_ui() -> HttpResponse {
     HttpResponse::Ok().content_type("text/html").body(html)
 }
 
+#[cfg(feature = "graphql")]
+async fn graphiql() -> HttpResponse {
+    let html = include_str!("../static/graphiql.html");
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
+
 pub fn configure_routes(config: &mut ServiceConfig) {
     config
         .service(
@@ -85,15 +285,115 @@ pub fn configure_routes(config: &mut ServiceConfig) {
                         .route("", web::get().to(list_projects))
                         .route("/{id}", web::get().to(get_project))
                         .route("/{id}", web::put().to(update_project))
-                        .route("/{id}", web::delete().to(delete_project)),
+                        .route("/{id}", web::delete().to(delete_project))
+                        .route("/{id}/archive", web::post().to(archive_project))
+                        .route("/{id}/unarchive", web::post().to(unarchive_project))
+                        .route("/{id}/calendar.ics", web::get().to(project_calendar))
+                        .route("/{id}/stats", web::get().to(project_stats))
+                        .route("/{id}/tasks.csv", web::get().to(export_project_tasks))
+                        .route("/{id}/tasks.csv", web::post().to(import_project_tasks))
+                        .route("/{id}/columns", web::get().to(list_project_columns))
+                        .route("/{id}/columns", web::put().to(set_project_columns))
+                        .route("/{id}/members", web::get().to(list_project_members))
+                        .route("/{id}/members/{subject}", web::put().to(set_project_member))
+                        .route(
+                            "/{id}/members/{subject}",
+                            web::delete().to(remove_project_member),
+                        )
+                        .route("/{id}/revisions", web::get().to(list_project_revisions))
+                        .route(
+                            "/{id}/revisions/diff",
+                            web::get().to(diff_project_revisions),
+                        )
+                        .route(
+                            "/{id}/revisions/{number}",
+                            web::get().to(get_project_revision),
+                        )
+                        .route(
+                            "/{id}/revisions/{number}/revert",
+                            web::post().to(revert_project),
+                        ),
                 )
                 .service(
                     web::scope("/tasks")
                         .route("", web::post().to(create_task))
                         .route("", web::get().to(list_tasks))
+                        .route("/overdue", web::get().to(list_overdue_tasks))
+                        .route("/overdue/counts", web::get().to(overdue_counts))
                         .route("/{id}", web::get().to(get_task))
                         .route("/{id}", web::put().to(update_task))
-                        .route("/{id}", web::delete().to(delete_task)),
+                        .route("/{id}", web::delete().to(delete_task))
+                        .route("/{id}/move", web::post().to(move_task))
+                        .route("/{id}/revisions", web::get().to(list_task_revisions))
+                        .route("/{id}/revisions/diff", web::get().to(diff_task_revisions))
+                        .route("/{id}/revisions/{number}", web::get().to(get_task_revision))
+                        .route(
+                            "/{id}/revisions/{number}/revert",
+                            web::post().to(revert_task),
+                        ),
+                )
+                .service(
+                    web::scope("/calendar")
+                        .route("/tokens", web::post().to(create_calendar_token))
+                        .route("/tokens", web::get().to(list_calendar_tokens))
+                        .route("/tokens/{id}", web::delete().to(delete_calendar_token)),
+                )
+                .route("/calendar.ics", web::get().to(user_calendar))
+                .service(
+                    web::scope("/recurring-tasks")
+                        .route("", web::post().to(create_recurring_task))
+                        .route("", web::get().to(list_recurring_tasks))
+                        .route("/{id}", web::get().to(get_recurring_task))
+                        .route("/{id}", web::delete().to(delete_recurring_task)),
+                )
+                .service(
+                    web::scope("/time")
+                        .route("/entries", web::post().to(create_time_entry))
+                        .route("/entries", web::get().to(list_time_entries))
+                        .route("/entries/{id}", web::delete().to(delete_time_entry))
+                        .route("/timer", web::get().to(get_timer))
+                        .route("/timer/start", web::post().to(start_timer))
+                        .route("/timer/stop", web::post().to(stop_timer))
+                        .route("/report", web::get().to(time_report))
+                        .route("/report.csv", web::get().to(export_time_report)),
+                )
+                .service(
+                    web::scope("/trash")
+                        .route("", web::get().to(list_trash))
+                        .route("/projects/{id}/restore", web::post().to(restore_project))
+                        .route("/projects/{id}", web::delete().to(purge_project))
+                        .route("/tasks/{id}/restore", web::post().to(restore_task))
+                        .route("/tasks/{id}", web::delete().to(purge_task)),
+                )
+                .service(web::scope("/auth").route("/me", web::get().to(current_principal)))
+                .service(
+                    web::scope("/admin/api-keys")
+                        .route("", web::post().to(create_api_key))
+                        .route("", web::get().to(list_api_keys))
+                        .route("/{id}", web::delete().to(revoke_api_key)),
+                )
+                .route("/admin/backup", web::get().to(export_backup))
+                .route("/admin/restore", web::post().to(restore_backup))
+                .service(
+                    web::scope("/audit")
+                        .route("", web::get().to(list_audit_entries))
+                        .route("/export", web::get().to(export_audit_entries)),
+                )
+                .route("/changes", web::get().to(list_changes))
+                .route("/events", web::get().to(stream_events))
+                .route("/ws", web::get().to(connect_websocket))
+                .service(
+                    web::scope("/webhooks")
+                        .route("", web::post().to(create_webhook))
+                        .route("", web::get().to(list_webhooks))
+                        .route("/{id}", web::get().to(get_webhook))
+                        .route("/{id}", web::put().to(update_webhook))
+                        .route("/{id}", web::delete().to(delete_webhook))
+                        .route("/{id}/deliveries", web::get().to(list_webhook_deliveries))
+                        .route(
+                            "/{id}/deliveries/{delivery_id}/retry",
+                            web::post().to(retry_webhook_delivery),
+                        ),
                 ),
         )
         .route("/health", web::get().to(health_check));
@@ -105,4 +405,12 @@ pub fn configure_routes(config: &mut ServiceConfig) {
             .route("/openapi.json", web::get().to(openapi_spec))
             .route("/docs", web::get().to(swagger_ui));
     }
+
+    // Add GraphQL endpoint and GraphiQL
+    #[cfg(feature = "graphql")]
+    {
+        config
+            .route("/graphql", web::post().to(crate::controllers::graphql))
+            .route("/graphiql", web::get().to(graphiql));
+    }
 }
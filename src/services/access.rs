@@ -0,0 +1,109 @@
+use crate::models::{Principal, Project, ProjectRole};
+use crate::repositories::MembershipRepository;
+use crate::views::ApiError;
+use std::collections::HashSet;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Resolves a caller's role on a project from its membership records.
+#[derive(Debug, Clone)]
+pub struct AccessPolicy {
+    memberships: Arc<MembershipRepository>,
+}
+
+/// Projects a caller may see: either every project or an explicit set.
+pub enum Visibility {
+    All,
+    Projects(HashSet<Uuid>),
+}
+
+impl Visibility {
+    pub fn allows(&self, project_id: &Uuid) -> bool {
+        match self {
+            Visibility::All => true,
+            Visibility::Projects(ids) => ids.contains(project_id),
+        }
+    }
+}
+
+impl AccessPolicy {
+    pub fn new(memberships: Arc<MembershipRepository>) -> Self {
+        Self { memberships }
+    }
+
+    pub fn role(
+        &self,
+        actor: &Principal,
+        project_id: &Uuid,
+    ) -> Result<Option<ProjectRole>, ApiError> {
+        if actor.is_admin() {
+            return Ok(Some(ProjectRole::Owner));
+        }
+
+        Ok(self
+            .memberships
+            .find(project_id, &actor.subject)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .map(|member| member.role))
+    }
+
+    /// Fails with `Forbidden` unless the caller holds at least `required` on the project.
+    pub fn require(
+        &self,
+        actor: &Principal,
+        project_id: &Uuid,
+        required: ProjectRole,
+    ) -> Result<ProjectRole, ApiError> {
+        match self.role(actor, project_id)? {
+            Some(role) if role >= required => Ok(role),
+            Some(_) => Err(ApiError::forbidden(&format!(
+                "Requires {} role on this project",
+                role_name(required)
+            ))),
+            None => Err(ApiError::forbidden("Not a member of this project")),
+        }
+    }
+
+    pub fn visible_projects(&self, actor: &Principal) -> Result<Visibility, ApiError> {
+        if actor.is_admin() {
+            return Ok(Visibility::All);
+        }
+
+        let ids = self
+            .memberships
+            .find_by_subject(&actor.subject)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .into_iter()
+            .map(|member| member.project_id)
+            .collect();
+        Ok(Visibility::Projects(ids))
+    }
+}
+
+/// Fails with `Forbidden` unless the caller holds the admin scope, which tenant-wide
+/// operations such as webhooks, backups and API keys require.
+pub fn require_admin(actor: &Principal) -> Result<(), ApiError> {
+    if !actor.is_admin() {
+        return Err(ApiError::forbidden("Admin scope required"));
+    }
+    Ok(())
+}
+
+/// Fails with `Conflict` while the project is archived; archived projects and their tasks
+/// are read-only until unarchived.
+pub fn ensure_active(project: &Project) -> Result<(), ApiError> {
+    if project.archived {
+        return Err(ApiError::conflict(
+            "Project is archived; unarchive it to make changes",
+        ));
+    }
+    Ok(())
+}
+
+fn role_name(role: ProjectRole) -> &'static str {
+    match role {
+        ProjectRole::Viewer => "viewer",
+        ProjectRole::Editor => "editor",
+        ProjectRole::Owner => "owner",
+    }
+}
@@ -0,0 +1,153 @@
+use crate::models::{ApiKey, ApiKeyCreate, ApiKeyCreated, Principal};
+use crate::repositories::ApiKeyRepository;
+use crate::services::access::require_admin;
+use crate::views::ApiError;
+use chrono::Utc;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Plaintext keys look like `<key id>.<secret>`, so a key can be looked up before hashing.
+const KEY_SEPARATOR: char = '.';
+
+#[derive(Debug, Clone)]
+pub struct ApiKeyService {
+    repository: Arc<ApiKeyRepository>,
+}
+
+impl ApiKeyService {
+    pub fn new(repository: Arc<ApiKeyRepository>) -> Self {
+        Self { repository }
+    }
+
+    /// Mints a key in the caller's tenant.
+    pub async fn create_key(
+        &self,
+        actor: &Principal,
+        create_data: ApiKeyCreate,
+    ) -> Result<ApiKeyCreated, ApiError> {
+        require_admin(actor)?;
+
+        // Validation
+        if create_data.name.trim().is_empty() {
+            return Err(ApiError::validation_error("API key name cannot be empty"));
+        }
+
+        if create_data.name.len() > 200 {
+            return Err(ApiError::validation_error(
+                "API key name cannot exceed 200 characters",
+            ));
+        }
+
+        if create_data
+            .scopes
+            .iter()
+            .any(|scope| scope.trim().is_empty() || scope.contains(char::is_whitespace))
+        {
+            return Err(ApiError::validation_error(
+                "API key scopes cannot be empty or contain whitespace",
+            ));
+        }
+
+        if let Some(expires_at) = create_data.expires_at {
+            if expires_at <= Utc::now() {
+                return Err(ApiError::validation_error(
+                    "API key expiry must be in the future",
+                ));
+            }
+        }
+
+        let secret = random_hex(32);
+        let salt = random_hex(16);
+        let api_key = ApiKey::new(
+            actor.tenant_id.clone(),
+            create_data.name.trim().to_string(),
+            create_data.scopes,
+            salt.clone(),
+            hash_secret(&salt, &secret),
+            create_data.expires_at,
+        );
+        let key = format!("{}{}{}", api_key.id.simple(), KEY_SEPARATOR, secret);
+
+        let api_key = self
+            .repository
+            .create(api_key)
+            .map_err(|e| ApiError::repository_error(&e))?;
+
+        Ok(ApiKeyCreated { key, api_key })
+    }
+
+    pub async fn list_keys(&self, actor: &Principal) -> Result<Vec<ApiKey>, ApiError> {
+        require_admin(actor)?;
+        let mut keys = self
+            .repository
+            .find_all(&actor.tenant_id)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        keys.sort_by_key(|key| key.created_at);
+        Ok(keys)
+    }
+
+    pub async fn revoke_key(&self, actor: &Principal, id: Uuid) -> Result<ApiKey, ApiError> {
+        require_admin(actor)?;
+        self.repository
+            .revoke(&actor.tenant_id, &id, Utc::now())
+            .map_err(|e| ApiError::repository_error(&e))?
+            .ok_or_else(|| ApiError::not_found("API key"))
+    }
+
+    /// Resolves a plaintext key to the principal it identifies, recording its use.
+    pub async fn authenticate(&self, key: &str) -> Result<Principal, ApiError> {
+        let invalid = || ApiError::unauthorized("Invalid API key");
+
+        let (id, secret) = key.split_once(KEY_SEPARATOR).ok_or_else(invalid)?;
+        let id = Uuid::parse_str(id).map_err(|_| invalid())?;
+
+        let api_key = self
+            .repository
+            .find_by_id(&id)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .ok_or_else(invalid)?;
+
+        if !constant_time_eq(
+            hash_secret(&api_key.salt, secret).as_bytes(),
+            api_key.key_hash.as_bytes(),
+        ) {
+            return Err(invalid());
+        }
+
+        let now = Utc::now();
+        if !api_key.is_active(now) {
+            return Err(ApiError::unauthorized("API key is revoked or expired"));
+        }
+
+        self.repository
+            .touch(&id, now)
+            .map_err(|e| ApiError::repository_error(&e))?;
+
+        Ok(Principal {
+            subject: format!("api-key:{}", api_key.id),
+            name: Some(api_key.name),
+            scopes: api_key.scopes,
+            tenant_id: api_key.tenant_id,
+            request_id: None,
+        })
+    }
+}
+
+pub(crate) fn random_hex(len: usize) -> String {
+    let mut bytes = vec![0u8; len];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+pub(crate) fn hash_secret(salt: &str, secret: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(salt.as_bytes());
+    hasher.update(secret.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
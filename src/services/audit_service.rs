@@ -0,0 +1,225 @@
+use crate::models::{
+    AuditAction, AuditEntry, AuditFilter, EntityType, FieldChange, Principal, Revision,
+    RevisionDiff,
+};
+use crate::repositories::{AuditRepository, RevisionRepository};
+use crate::services::access::require_admin;
+use crate::views::ApiError;
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tracing::error;
+use uuid::Uuid;
+
+/// Keeps the audit log and, alongside it, the revision history of projects and tasks.
+#[derive(Debug, Clone)]
+pub struct AuditService {
+    repository: Arc<AuditRepository>,
+    revisions: Arc<RevisionRepository>,
+}
+
+impl AuditService {
+    pub fn new(repository: Arc<AuditRepository>, revisions: Arc<RevisionRepository>) -> Self {
+        Self {
+            repository,
+            revisions,
+        }
+    }
+
+    /// Appends an entry with the field-level diff between `before` and `after`. Creates and
+    /// updates of projects and tasks also store `after` as the entity's next revision.
+    ///
+    /// Callers record after their write has been committed, so a failure here is logged
+    /// instead of failing a mutation that already happened.
+    pub fn record<T: Serialize>(
+        &self,
+        actor: &Principal,
+        action: AuditAction,
+        entity_type: EntityType,
+        entity_id: Uuid,
+        before: Option<&T>,
+        after: Option<&T>,
+    ) {
+        if let Err(e) = self.try_record(actor, action, entity_type, entity_id, before, after) {
+            error!(
+                "Failed to audit {:?} of {:?} {}: {}",
+                action, entity_type, entity_id, e
+            );
+        }
+    }
+
+    fn try_record<T: Serialize>(
+        &self,
+        actor: &Principal,
+        action: AuditAction,
+        entity_type: EntityType,
+        entity_id: Uuid,
+        before: Option<&T>,
+        after: Option<&T>,
+    ) -> Result<(), ApiError> {
+        let after = to_object(after)?;
+        let revision = keeps_revisions(action, entity_type).then(|| Revision {
+            number: 0,
+            tenant_id: actor.tenant_id.clone(),
+            entity_type,
+            entity_id,
+            actor: actor.subject.clone(),
+            updated_at: after
+                .get("updated_at")
+                .and_then(|value| serde_json::from_value(value.clone()).ok())
+                .unwrap_or_else(Utc::now),
+            data: Value::Object(after.clone()),
+        });
+        let entry = AuditEntry {
+            id: Uuid::new_v4(),
+            tenant_id: actor.tenant_id.clone(),
+            actor: actor.subject.clone(),
+            action,
+            entity_type,
+            entity_id,
+            changes: diff(to_object(before)?, after),
+            request_id: actor.request_id.clone(),
+            timestamp: Utc::now(),
+        };
+
+        self.repository
+            .append(entry)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        if let Some(revision) = revision {
+            self.revisions
+                .append(revision)
+                .map_err(|e| ApiError::repository_error(&e))?;
+        }
+        Ok(())
+    }
+
+    /// Revisions of a project or task, oldest first. Callers check access to the entity.
+    pub fn revisions(
+        &self,
+        tenant_id: &str,
+        entity_type: EntityType,
+        entity_id: &Uuid,
+    ) -> Result<Vec<Revision>, ApiError> {
+        self.revisions
+            .find_by_entity(tenant_id, entity_type, entity_id)
+            .map_err(|e| ApiError::repository_error(&e))
+    }
+
+    pub fn revision(
+        &self,
+        tenant_id: &str,
+        entity_type: EntityType,
+        entity_id: &Uuid,
+        number: u32,
+    ) -> Result<Revision, ApiError> {
+        self.revisions
+            .find(tenant_id, entity_type, entity_id, number)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .ok_or_else(|| ApiError::not_found("Revision"))
+    }
+
+    /// Diff from revision `from` to revision `to`, or to the latest one when `to` is absent.
+    pub fn diff_revisions(
+        &self,
+        tenant_id: &str,
+        entity_type: EntityType,
+        entity_id: &Uuid,
+        from: u32,
+        to: Option<u32>,
+    ) -> Result<RevisionDiff, ApiError> {
+        let older = self.revision(tenant_id, entity_type, entity_id, from)?;
+        let newer = match to {
+            Some(to) => self.revision(tenant_id, entity_type, entity_id, to)?,
+            None => self
+                .revisions(tenant_id, entity_type, entity_id)?
+                .pop()
+                .ok_or_else(|| ApiError::not_found("Revision"))?,
+        };
+
+        Ok(RevisionDiff {
+            from: older.number,
+            to: newer.number,
+            changes: diff(to_object(Some(&older.data))?, to_object(Some(&newer.data))?),
+        })
+    }
+
+    /// Drops the history of a purged entity; its audit entries stay.
+    pub fn discard_revisions(
+        &self,
+        entity_type: EntityType,
+        entity_id: &Uuid,
+    ) -> Result<(), ApiError> {
+        self.revisions
+            .delete_by_entity(entity_type, entity_id)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        Ok(())
+    }
+
+    pub async fn list_entries(
+        &self,
+        actor: &Principal,
+        filter: &AuditFilter,
+    ) -> Result<Vec<AuditEntry>, ApiError> {
+        require_admin(actor)?;
+
+        if let (Some(from), Some(to)) = (filter.from, filter.to) {
+            if from > to {
+                return Err(ApiError::validation_error(
+                    "Audit range start must not be after its end",
+                ));
+            }
+        }
+
+        self.repository
+            .find(&actor.tenant_id, filter)
+            .map_err(|e| ApiError::repository_error(&e))
+    }
+}
+
+fn keeps_revisions(action: AuditAction, entity_type: EntityType) -> bool {
+    matches!(action, AuditAction::Create | AuditAction::Update)
+        && matches!(entity_type, EntityType::Project | EntityType::Task)
+}
+
+fn to_object<T: Serialize>(value: Option<&T>) -> Result<Map<String, Value>, ApiError> {
+    match value.map(serde_json::to_value).transpose() {
+        Ok(Some(Value::Object(map))) => Ok(map),
+        Ok(_) => Ok(Map::new()),
+        Err(e) => Err(ApiError::repository_error(&e.to_string())),
+    }
+}
+
+fn diff(
+    before: Map<String, Value>,
+    mut after: Map<String, Value>,
+) -> BTreeMap<String, FieldChange> {
+    let mut changes = BTreeMap::new();
+
+    for (field, old) in before {
+        let new = after.remove(&field).unwrap_or(Value::Null);
+        if old != new {
+            changes.insert(
+                field,
+                FieldChange {
+                    before: old,
+                    after: new,
+                },
+            );
+        }
+    }
+    for (field, new) in after {
+        if !new.is_null() {
+            changes.insert(
+                field,
+                FieldChange {
+                    before: Value::Null,
+                    after: new,
+                },
+            );
+        }
+    }
+
+    changes
+}
@@ -0,0 +1,486 @@
+use crate::models::{
+    Backup, BackupFormat, BackupReader, BackupRecurringTask, Principal, Project, RestoreMode,
+    RestoreReport, Task, TimeEntryFilter, BACKUP_VERSION,
+};
+use crate::repositories::{
+    MembershipRepository, ProjectRepository, RecurringTaskRepository, TaskRepository,
+    TimeEntryRepository,
+};
+use crate::services::access::require_admin;
+use crate::services::quota::QuotaPolicy;
+use crate::views::ApiError;
+use chrono::Utc;
+use futures::{Stream, StreamExt};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Integrity problems listed in a rejected restore; further ones are only counted.
+const MAX_REPORTED_PROBLEMS: usize = 10;
+
+/// Exports a tenant's data as a portable archive and restores archives, checking them in full
+/// before anything is written.
+#[derive(Debug, Clone)]
+pub struct BackupService {
+    project_repository: Arc<ProjectRepository>,
+    task_repository: Arc<TaskRepository>,
+    membership_repository: Arc<MembershipRepository>,
+    recurring_task_repository: Arc<RecurringTaskRepository>,
+    time_entry_repository: Arc<TimeEntryRepository>,
+    quotas: QuotaPolicy,
+}
+
+impl BackupService {
+    pub fn new(
+        project_repository: Arc<ProjectRepository>,
+        task_repository: Arc<TaskRepository>,
+        membership_repository: Arc<MembershipRepository>,
+        recurring_task_repository: Arc<RecurringTaskRepository>,
+        time_entry_repository: Arc<TimeEntryRepository>,
+        quotas: QuotaPolicy,
+    ) -> Self {
+        Self {
+            project_repository,
+            task_repository,
+            membership_repository,
+            recurring_task_repository,
+            time_entry_repository,
+            quotas,
+        }
+    }
+
+    /// Everything in the caller's tenant, trashed projects and tasks included.
+    pub async fn export(&self, actor: &Principal) -> Result<Backup, ApiError> {
+        require_admin(actor)?;
+        let tenant_id = &actor.tenant_id;
+        let mut backup = Backup::new(Utc::now());
+
+        backup.projects = self
+            .project_repository
+            .find_all(tenant_id)
+            .and_then(|mut projects| {
+                projects.extend(self.project_repository.find_deleted(tenant_id)?);
+                Ok(projects)
+            })
+            .map_err(|e| ApiError::repository_error(&e))?;
+        backup
+            .projects
+            .sort_by_key(|project| (project.created_at, project.id));
+
+        backup.tasks = self
+            .task_repository
+            .find_all(tenant_id)
+            .and_then(|mut tasks| {
+                tasks.extend(self.task_repository.find_deleted(tenant_id)?);
+                Ok(tasks)
+            })
+            .map_err(|e| ApiError::repository_error(&e))?;
+        backup.tasks.sort_by_key(|task| (task.created_at, task.id));
+
+        for project in &backup.projects {
+            let mut members = self
+                .membership_repository
+                .find_by_project_id(&project.id)
+                .map_err(|e| ApiError::repository_error(&e))?;
+            members.sort_by(|a, b| a.subject.cmp(&b.subject));
+            backup.members.extend(members);
+        }
+
+        let mut recurring_tasks = self
+            .recurring_task_repository
+            .find_all(tenant_id)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        recurring_tasks
+            .sort_by_key(|recurring_task| (recurring_task.created_at, recurring_task.id));
+        backup.recurring_tasks = recurring_tasks
+            .into_iter()
+            .map(|recurring_task| BackupRecurringTask {
+                created_by_scopes: recurring_task.created_by_scopes.clone(),
+                recurring_task,
+            })
+            .collect();
+
+        backup.time_entries = self
+            .time_entry_repository
+            .find(tenant_id, &TimeEntryFilter::default())
+            .map_err(|e| ApiError::repository_error(&e))?;
+
+        Ok(backup)
+    }
+
+    /// Parses an uploaded archive, a chunk at a time.
+    pub async fn read_backup<S, B, E>(mut body: S, format: BackupFormat) -> Result<Backup, ApiError>
+    where
+        S: Stream<Item = Result<B, E>> + Unpin,
+        B: AsRef<[u8]>,
+        E: std::fmt::Display,
+    {
+        let mut reader = BackupReader::new(format);
+        while let Some(chunk) = body.next().await {
+            let chunk = chunk
+                .map_err(|e| ApiError::bad_request(&format!("Failed to read the upload: {}", e)))?;
+            reader
+                .feed(chunk.as_ref())
+                .map_err(|e| ApiError::bad_request(&e))?;
+        }
+        reader.finish().map_err(|e| ApiError::bad_request(&e))
+    }
+
+    /// Restores an archive of the caller's tenant.
+    pub async fn restore(
+        &self,
+        actor: &Principal,
+        backup: Backup,
+        mode: RestoreMode,
+    ) -> Result<RestoreReport, ApiError> {
+        require_admin(actor)?;
+        self.check(Some(&actor.tenant_id), &backup, mode)?;
+        self.write(Some(&actor.tenant_id), backup, mode)
+    }
+
+    /// Restores an archive that may hold several tenants, merging it into the stored data;
+    /// used to seed a server at startup.
+    pub fn restore_all(&self, backup: Backup) -> Result<RestoreReport, ApiError> {
+        self.check(None, &backup, RestoreMode::Merge)?;
+        self.write(None, backup, RestoreMode::Merge)
+    }
+
+    /// Verifies that an archive can be restored: its version, that every record belongs to
+    /// `tenant_id` (any tenant when `None`) and has a unique id no other tenant uses, and that
+    /// every reference resolves within the archive or, when merging, the stored data.
+    pub fn check(
+        &self,
+        tenant_id: Option<&str>,
+        backup: &Backup,
+        mode: RestoreMode,
+    ) -> Result<(), ApiError> {
+        if backup.version != BACKUP_VERSION {
+            return Err(ApiError::validation_error(&format!(
+                "Backup version {} is not supported; expected {}",
+                backup.version, BACKUP_VERSION
+            )));
+        }
+
+        let mut check = IntegrityCheck {
+            tenant_id,
+            problems: Vec::new(),
+        };
+        let merge = mode == RestoreMode::Merge;
+        let repository_error = |e: String| ApiError::repository_error(&e);
+
+        // Ownership and uniqueness
+        let mut seen = HashSet::new();
+        for project in &backup.projects {
+            let stored = self
+                .project_repository
+                .tenant_of(&project.id)
+                .map_err(repository_error)?;
+            check.record(
+                "Project",
+                &project.id,
+                &project.tenant_id,
+                stored,
+                &mut seen,
+            );
+        }
+        let mut seen = HashSet::new();
+        for task in &backup.tasks {
+            let stored = self
+                .task_repository
+                .tenant_of(&task.id)
+                .map_err(repository_error)?;
+            check.record("Task", &task.id, &task.tenant_id, stored, &mut seen);
+        }
+        let mut seen = HashSet::new();
+        for backup_task in &backup.recurring_tasks {
+            let recurring_task = &backup_task.recurring_task;
+            let stored = self
+                .recurring_task_repository
+                .tenant_of(&recurring_task.id)
+                .map_err(repository_error)?;
+            check.record(
+                "Recurring task",
+                &recurring_task.id,
+                &recurring_task.tenant_id,
+                stored,
+                &mut seen,
+            );
+        }
+        let mut seen = HashSet::new();
+        for entry in &backup.time_entries {
+            let stored = self
+                .time_entry_repository
+                .tenant_of(&entry.id)
+                .map_err(repository_error)?;
+            check.record("Time entry", &entry.id, &entry.tenant_id, stored, &mut seen);
+        }
+        let mut seen = HashSet::new();
+        for member in &backup.members {
+            if !seen.insert((member.project_id, member.subject.as_str())) {
+                check.problem(format!(
+                    "Member '{}' of project {} appears more than once",
+                    member.subject, member.project_id
+                ));
+            }
+        }
+
+        // References
+        let projects: HashMap<Uuid, &Project> = backup
+            .projects
+            .iter()
+            .map(|project| (project.id, project))
+            .collect();
+        let tasks: HashMap<Uuid, &Task> = backup.tasks.iter().map(|task| (task.id, task)).collect();
+        let find_project = |tenant_id: &str, id: &Uuid| -> Result<Option<Project>, ApiError> {
+            if let Some(project) = projects.get(id) {
+                return Ok(Some(project)
+                    .filter(|project| project.tenant_id == tenant_id)
+                    .map(|project| (*project).clone()));
+            }
+            if !merge {
+                return Ok(None);
+            }
+            let live = self
+                .project_repository
+                .find_by_id(tenant_id, id)
+                .map_err(repository_error)?;
+            match live {
+                Some(project) => Ok(Some(project)),
+                None => self
+                    .project_repository
+                    .find_deleted_by_id(tenant_id, id)
+                    .map_err(repository_error),
+            }
+        };
+        let find_task = |tenant_id: &str, id: &Uuid| -> Result<Option<Task>, ApiError> {
+            if let Some(task) = tasks.get(id) {
+                return Ok(Some(task)
+                    .filter(|task| task.tenant_id == tenant_id)
+                    .map(|task| (*task).clone()));
+            }
+            if !merge {
+                return Ok(None);
+            }
+            let live = self
+                .task_repository
+                .find_by_id(tenant_id, id)
+                .map_err(repository_error)?;
+            match live {
+                Some(task) => Ok(Some(task)),
+                None => self
+                    .task_repository
+                    .find_deleted_by_id(tenant_id, id)
+                    .map_err(repository_error),
+            }
+        };
+
+        for task in &backup.tasks {
+            match find_project(&task.tenant_id, &task.project_id)? {
+                None => check.problem(format!(
+                    "Task {} refers to missing project {}",
+                    task.id, task.project_id
+                )),
+                Some(project) => {
+                    if let Some(column_id) =
+                        task.column_id.filter(|id| project.column(id).is_none())
+                    {
+                        check.problem(format!(
+                            "Task {} is in column {}, which is not on its project's board",
+                            task.id, column_id
+                        ));
+                    }
+                }
+            }
+        }
+        for member in &backup.members {
+            let found = match (projects.get(&member.project_id), tenant_id) {
+                (Some(_), _) => true,
+                (None, Some(tenant_id)) => find_project(tenant_id, &member.project_id)?.is_some(),
+                (None, None) => false,
+            };
+            if !found {
+                check.problem(format!(
+                    "Member '{}' refers to missing project {}",
+                    member.subject, member.project_id
+                ));
+            }
+        }
+        for backup_task in &backup.recurring_tasks {
+            let recurring_task = &backup_task.recurring_task;
+            if find_project(&recurring_task.tenant_id, &recurring_task.project_id)?.is_none() {
+                check.problem(format!(
+                    "Recurring task {} refers to missing project {}",
+                    recurring_task.id, recurring_task.project_id
+                ));
+            }
+        }
+        let mut running: HashMap<(&str, &str), usize> = HashMap::new();
+        for entry in &backup.time_entries {
+            match find_task(&entry.tenant_id, &entry.task_id)? {
+                None => check.problem(format!(
+                    "Time entry {} refers to missing task {}",
+                    entry.id, entry.task_id
+                )),
+                Some(task) if task.project_id != entry.project_id => check.problem(format!(
+                    "Time entry {} is for project {} but its task belongs to project {}",
+                    entry.id, entry.project_id, task.project_id
+                )),
+                Some(_) => {}
+            }
+            if entry.is_running() {
+                *running
+                    .entry((entry.tenant_id.as_str(), entry.user.as_str()))
+                    .or_default() += 1;
+            }
+        }
+        let entry_ids: HashSet<Uuid> = backup.time_entries.iter().map(|entry| entry.id).collect();
+        for ((tenant_id, user), mut count) in running {
+            if merge {
+                let stored = self
+                    .time_entry_repository
+                    .find_running(tenant_id, user)
+                    .map_err(repository_error)?;
+                if stored.is_some_and(|entry| !entry_ids.contains(&entry.id)) {
+                    count += 1;
+                }
+            }
+            if count > 1 {
+                check.problem(format!(
+                    "User '{}' would have more than one running timer",
+                    user
+                ));
+            }
+        }
+
+        check.finish()
+    }
+
+    /// Writes a checked archive; replacing and quotas apply to `tenant_id` only.
+    fn write(
+        &self,
+        tenant_id: Option<&str>,
+        backup: Backup,
+        mode: RestoreMode,
+    ) -> Result<RestoreReport, ApiError> {
+        let replace = tenant_id.filter(|_| mode == RestoreMode::Replace);
+        let report = RestoreReport {
+            mode,
+            projects: backup.projects.len(),
+            tasks: backup.tasks.len(),
+            members: backup.members.len(),
+            recurring_tasks: backup.recurring_tasks.len(),
+            time_entries: backup.time_entries.len(),
+        };
+
+        // Members of projects that are replaced go with them
+        let mut replaced_projects = Vec::new();
+        if let Some(tenant_id) = replace {
+            for projects in [
+                self.project_repository.find_all(tenant_id),
+                self.project_repository.find_deleted(tenant_id),
+            ] {
+                let projects = projects.map_err(|e| ApiError::repository_error(&e))?;
+                replaced_projects.extend(projects.into_iter().map(|project| project.id));
+            }
+        }
+
+        // The tenant's quotas are checked under the repository locks, with the projects stored
+        // while the task lock is held so that neither is written if either would overshoot
+        let quota = tenant_id
+            .map(|tenant_id| self.quotas.quota_for(tenant_id))
+            .unwrap_or_default();
+        self.task_repository
+            .import(
+                backup.tasks,
+                replace,
+                tenant_id.zip(quota.max_tasks),
+                || {
+                    let stored = self
+                        .project_repository
+                        .import(backup.projects, replace, tenant_id.zip(quota.max_projects))
+                        .map_err(|e| ApiError::repository_error(&e))?;
+                    if !stored {
+                        return Err(ApiError::conflict(&format!(
+                            "Restoring would exceed the tenant project quota of {}",
+                            quota.max_projects.unwrap_or_default()
+                        )));
+                    }
+                    Ok(())
+                },
+            )?
+            .ok_or_else(|| {
+                ApiError::conflict(&format!(
+                    "Restoring would exceed the tenant task quota of {}",
+                    quota.max_tasks.unwrap_or_default()
+                ))
+            })?;
+        self.membership_repository
+            .import(backup.members, &replaced_projects)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        let recurring_tasks = backup
+            .recurring_tasks
+            .into_iter()
+            .map(|backup_task| {
+                let mut recurring_task = backup_task.recurring_task;
+                recurring_task.created_by_scopes = backup_task.created_by_scopes;
+                recurring_task
+            })
+            .collect();
+        self.recurring_task_repository
+            .import(recurring_tasks, replace)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        self.time_entry_repository
+            .import(backup.time_entries, replace)
+            .map_err(|e| ApiError::repository_error(&e))?;
+
+        Ok(report)
+    }
+}
+
+/// Problems found while checking an archive.
+struct IntegrityCheck<'a> {
+    tenant_id: Option<&'a str>,
+    problems: Vec<String>,
+}
+
+impl IntegrityCheck<'_> {
+    /// Checks that a record belongs to the tenant being restored, is the only one with its id
+    /// in the archive, and does not take over an id of another tenant.
+    fn record(
+        &mut self,
+        kind: &str,
+        id: &Uuid,
+        owner: &str,
+        stored: Option<String>,
+        seen: &mut HashSet<Uuid>,
+    ) {
+        if self.tenant_id.is_some_and(|tenant_id| tenant_id != owner) {
+            self.problem(format!("{} {} belongs to tenant '{}'", kind, id, owner));
+        }
+        if !seen.insert(*id) {
+            self.problem(format!("{} {} appears more than once", kind, id));
+        }
+        if stored.is_some_and(|stored| stored != owner) {
+            self.problem(format!("{} {} already exists in another tenant", kind, id));
+        }
+    }
+
+    fn problem(&mut self, problem: String) {
+        self.problems.push(problem);
+    }
+
+    fn finish(self) -> Result<(), ApiError> {
+        if self.problems.is_empty() {
+            return Ok(());
+        }
+        let mut message = format!(
+            "Backup failed {} integrity check(s): {}",
+            self.problems.len(),
+            self.problems[..self.problems.len().min(MAX_REPORTED_PROBLEMS)].join("; ")
+        );
+        if self.problems.len() > MAX_REPORTED_PROBLEMS {
+            message.push_str("; ...");
+        }
+        Err(ApiError::validation_error(&message))
+    }
+}
@@ -0,0 +1,208 @@
+use crate::models::{
+    CalendarToken, CalendarTokenCreate, CalendarTokenCreated, Principal, Project, ProjectRole, Task,
+};
+use crate::repositories::{
+    CalendarTokenRepository, MembershipRepository, ProjectRepository, TaskRepository,
+};
+use crate::services::access::AccessPolicy;
+use crate::services::api_key_service::{constant_time_eq, hash_secret, random_hex};
+use crate::views::ApiError;
+use chrono::Utc;
+use std::collections::HashSet;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Plaintext tokens look like `<token id>.<secret>`, like API keys.
+const TOKEN_SEPARATOR: char = '.';
+
+/// Serves task due dates as calendar feeds and manages the tokens that authenticate them.
+#[derive(Debug, Clone)]
+pub struct CalendarService {
+    repository: Arc<CalendarTokenRepository>,
+    task_repository: Arc<TaskRepository>,
+    project_repository: Arc<ProjectRepository>,
+    access: AccessPolicy,
+}
+
+impl CalendarService {
+    pub fn new(
+        repository: Arc<CalendarTokenRepository>,
+        task_repository: Arc<TaskRepository>,
+        project_repository: Arc<ProjectRepository>,
+        membership_repository: Arc<MembershipRepository>,
+    ) -> Self {
+        Self {
+            repository,
+            task_repository,
+            project_repository,
+            access: AccessPolicy::new(membership_repository),
+        }
+    }
+
+    /// Mints a token that reads the caller's feeds with whatever project access the caller
+    /// holds when it is used.
+    pub async fn create_token(
+        &self,
+        actor: &Principal,
+        create_data: CalendarTokenCreate,
+    ) -> Result<CalendarTokenCreated, ApiError> {
+        // Validation
+        if create_data.name.trim().is_empty() {
+            return Err(ApiError::validation_error(
+                "Calendar token name cannot be empty",
+            ));
+        }
+
+        if create_data.name.len() > 200 {
+            return Err(ApiError::validation_error(
+                "Calendar token name cannot exceed 200 characters",
+            ));
+        }
+
+        if let Some(expires_at) = create_data.expires_at {
+            if expires_at <= Utc::now() {
+                return Err(ApiError::validation_error(
+                    "Calendar token expiry must be in the future",
+                ));
+            }
+        }
+
+        let secret = random_hex(32);
+        let salt = random_hex(16);
+        let calendar_token = CalendarToken::new(
+            actor.tenant_id.clone(),
+            actor.subject.clone(),
+            create_data.name.trim().to_string(),
+            salt.clone(),
+            hash_secret(&salt, &secret),
+            create_data.expires_at,
+        );
+        let token = format!(
+            "{}{}{}",
+            calendar_token.id.simple(),
+            TOKEN_SEPARATOR,
+            secret
+        );
+
+        let calendar_token = self
+            .repository
+            .create(calendar_token)
+            .map_err(|e| ApiError::repository_error(&e))?;
+
+        Ok(CalendarTokenCreated {
+            feed_url: format!("/api/v1/calendar.ics?token={}", token),
+            token,
+            calendar_token,
+        })
+    }
+
+    pub async fn list_tokens(&self, actor: &Principal) -> Result<Vec<CalendarToken>, ApiError> {
+        let mut tokens = self
+            .repository
+            .find_by_subject(&actor.tenant_id, &actor.subject)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        tokens.sort_by_key(|token| token.created_at);
+        Ok(tokens)
+    }
+
+    pub async fn delete_token(&self, actor: &Principal, id: Uuid) -> Result<(), ApiError> {
+        let deleted = self
+            .repository
+            .delete(&actor.tenant_id, &actor.subject, &id)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        if !deleted {
+            return Err(ApiError::not_found("Calendar token"));
+        }
+        Ok(())
+    }
+
+    /// Resolves a plaintext token to its owner, recording its use. The principal carries no
+    /// scopes, so feeds show only the projects the owner is a member of at that moment.
+    pub async fn authenticate(&self, token: &str) -> Result<Principal, ApiError> {
+        let invalid = || ApiError::unauthorized("Invalid calendar token");
+
+        let (id, secret) = token.split_once(TOKEN_SEPARATOR).ok_or_else(invalid)?;
+        let id = Uuid::parse_str(id).map_err(|_| invalid())?;
+
+        let calendar_token = self
+            .repository
+            .find_by_id(&id)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .ok_or_else(invalid)?;
+
+        if !constant_time_eq(
+            hash_secret(&calendar_token.salt, secret).as_bytes(),
+            calendar_token.token_hash.as_bytes(),
+        ) {
+            return Err(invalid());
+        }
+        if !calendar_token.is_active(Utc::now()) {
+            return Err(ApiError::unauthorized("Calendar token has expired"));
+        }
+
+        self.repository
+            .touch(&id, Utc::now())
+            .map_err(|e| ApiError::repository_error(&e))?;
+
+        Ok(Principal {
+            subject: calendar_token.subject,
+            name: None,
+            scopes: Vec::new(),
+            tenant_id: calendar_token.tenant_id,
+            request_id: None,
+        })
+    }
+
+    /// A project together with its tasks that have a due date, in due date order.
+    pub async fn project_calendar(
+        &self,
+        actor: &Principal,
+        project_id: Uuid,
+    ) -> Result<(Project, Vec<Task>), ApiError> {
+        let project = self
+            .project_repository
+            .find_by_id(&actor.tenant_id, &project_id)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .ok_or_else(|| ApiError::not_found("Project"))?;
+        self.access
+            .require(actor, &project_id, ProjectRole::Viewer)?;
+
+        let tasks = self
+            .task_repository
+            .find_by_project_id(&actor.tenant_id, &project_id)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        Ok((project, with_due_dates(tasks)))
+    }
+
+    /// Tasks with a due date in every active project the caller can see, in due date order.
+    pub async fn user_calendar(&self, actor: &Principal) -> Result<Vec<Task>, ApiError> {
+        let visibility = self.access.visible_projects(actor)?;
+
+        let projects: HashSet<Uuid> = self
+            .project_repository
+            .find_all(&actor.tenant_id)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .into_iter()
+            .filter(|project| !project.archived && visibility.allows(&project.id))
+            .map(|project| project.id)
+            .collect();
+
+        let tasks = self
+            .task_repository
+            .find_all(&actor.tenant_id)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .into_iter()
+            .filter(|task| projects.contains(&task.project_id))
+            .collect();
+        Ok(with_due_dates(tasks))
+    }
+}
+
+fn with_due_dates(tasks: Vec<Task>) -> Vec<Task> {
+    let mut tasks: Vec<Task> = tasks
+        .into_iter()
+        .filter(|task| task.due_at.is_some())
+        .collect();
+    tasks.sort_by_key(|task| (task.due_at, task.id));
+    tasks
+}
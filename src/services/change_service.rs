@@ -0,0 +1,49 @@
+use crate::models::{ChangeFeed, Principal};
+use crate::repositories::{ChangeLog, MembershipRepository};
+use crate::services::access::AccessPolicy;
+use crate::views::ApiError;
+use std::sync::Arc;
+
+pub const DEFAULT_CHANGE_LIMIT: usize = 100;
+pub const MAX_CHANGE_LIMIT: usize = 1000;
+
+#[derive(Debug, Clone)]
+pub struct ChangeService {
+    changes: Arc<ChangeLog>,
+    access: AccessPolicy,
+}
+
+impl ChangeService {
+    pub fn new(changes: Arc<ChangeLog>, membership_repository: Arc<MembershipRepository>) -> Self {
+        Self {
+            changes,
+            access: AccessPolicy::new(membership_repository),
+        }
+    }
+
+    /// Changes after `since` the caller can read, in sequence order.
+    ///
+    /// Tombstones follow the same visibility as upserts; memberships outlive a deleted
+    /// project until it is purged from the trash.
+    pub async fn list_changes(
+        &self,
+        actor: &Principal,
+        since: u64,
+        limit: Option<usize>,
+    ) -> Result<ChangeFeed, ApiError> {
+        let limit = limit.unwrap_or(DEFAULT_CHANGE_LIMIT);
+        if limit == 0 || limit > MAX_CHANGE_LIMIT {
+            return Err(ApiError::validation_error(&format!(
+                "Change limit must be between 1 and {}",
+                MAX_CHANGE_LIMIT
+            )));
+        }
+
+        let visibility = self.access.visible_projects(actor)?;
+        self.changes
+            .find_since(&actor.tenant_id, since, limit, |change| {
+                visibility.allows(&change.project_id)
+            })
+            .map_err(|e| ApiError::repository_error(&e))
+    }
+}
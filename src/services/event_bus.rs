@@ -0,0 +1,93 @@
+use crate::models::{DomainEvent, EventEnvelope, Principal};
+use chrono::Utc;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tracing::warn;
+use uuid::Uuid;
+
+/// Events a subscriber may fall behind by before further events are dropped for it.
+const SUBSCRIBER_CAPACITY: usize = 1024;
+
+struct Subscriber {
+    name: &'static str,
+    sender: mpsc::Sender<Arc<EventEnvelope>>,
+}
+
+/// In-process fan-out of domain events to asynchronous subscribers.
+///
+/// Publishing never waits on subscribers: each one reads from its own bounded queue, and
+/// events for a subscriber whose queue is full are dropped with a warning.
+#[derive(Default)]
+pub struct EventBus {
+    subscribers: RwLock<Vec<Subscriber>>,
+}
+
+impl std::fmt::Debug for EventBus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let count = self.subscribers.read().map(|s| s.len()).unwrap_or_default();
+        f.debug_struct("EventBus")
+            .field("subscribers", &count)
+            .finish()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a queue receiving every event published from now on.
+    pub fn subscribe(&self, name: &'static str) -> mpsc::Receiver<Arc<EventEnvelope>> {
+        let (sender, receiver) = mpsc::channel(SUBSCRIBER_CAPACITY);
+        if let Ok(mut subscribers) = self.subscribers.write() {
+            subscribers.push(Subscriber { name, sender });
+        }
+        receiver
+    }
+
+    /// Runs `handler` for every event on the current runtime; handler errors are logged.
+    pub fn subscribe_with<F, Fut>(&self, name: &'static str, handler: F)
+    where
+        F: Fn(Arc<EventEnvelope>) -> Fut + 'static,
+        Fut: Future<Output = Result<(), String>> + 'static,
+    {
+        let mut receiver = self.subscribe(name);
+        ntex::rt::spawn(async move {
+            while let Some(event) = receiver.recv().await {
+                if let Err(e) = handler(event).await {
+                    warn!("Event subscriber {} failed: {}", name, e);
+                }
+            }
+        });
+    }
+
+    pub fn publish(&self, actor: &Principal, event: DomainEvent) {
+        let envelope = Arc::new(EventEnvelope {
+            id: Uuid::new_v4(),
+            tenant_id: actor.tenant_id.clone(),
+            actor: actor.subject.clone(),
+            request_id: actor.request_id.clone(),
+            occurred_at: Utc::now(),
+            event,
+        });
+
+        let Ok(mut subscribers) = self.subscribers.write() else {
+            return;
+        };
+        subscribers.retain(
+            |subscriber| match subscriber.sender.try_send(envelope.clone()) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_)) => {
+                    warn!(
+                        "Event subscriber {} is lagging, dropped {:?}",
+                        subscriber.name,
+                        envelope.event.kind()
+                    );
+                    true
+                }
+                Err(TrySendError::Closed(_)) => false,
+            },
+        );
+    }
+}
@@ -0,0 +1,205 @@
+use crate::models::{DomainEvent, EventEnvelope, EventFilter, Principal, ProjectRole};
+use crate::repositories::MembershipRepository;
+use crate::services::access::{AccessPolicy, Visibility};
+use crate::services::EventBus;
+use crate::views::ApiError;
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::{self, error::RecvError};
+use uuid::Uuid;
+
+/// Events kept for clients resuming with `Last-Event-ID`.
+const REPLAY_CAPACITY: usize = 1024;
+/// Live events a connection may fall behind by before it is told to resynchronise.
+const LIVE_CAPACITY: usize = 256;
+
+/// A published event numbered for streaming; ids increase by one per event.
+#[derive(Debug)]
+pub struct StreamEvent {
+    pub id: u64,
+    pub envelope: Arc<EventEnvelope>,
+}
+
+pub enum StreamItem {
+    Event(Arc<StreamEvent>),
+    /// Events were missed, either because they left the replay buffer or because the
+    /// connection lagged; the client should reload its state.
+    Reset,
+}
+
+struct ReplayBuffer {
+    next_id: u64,
+    events: VecDeque<Arc<StreamEvent>>,
+}
+
+/// Numbers bus events, keeps the most recent ones for replay and fans them out to streaming
+/// connections on any thread.
+pub struct EventStream {
+    buffer: Mutex<ReplayBuffer>,
+    sender: broadcast::Sender<Arc<StreamEvent>>,
+    access: AccessPolicy,
+}
+
+impl std::fmt::Debug for EventStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventStream")
+            .field("connections", &self.sender.receiver_count())
+            .finish()
+    }
+}
+
+impl EventStream {
+    pub fn new(membership_repository: Arc<MembershipRepository>) -> Self {
+        let (sender, _) = broadcast::channel(LIVE_CAPACITY);
+        Self {
+            buffer: Mutex::new(ReplayBuffer {
+                next_id: 1,
+                events: VecDeque::with_capacity(REPLAY_CAPACITY),
+            }),
+            sender,
+            access: AccessPolicy::new(membership_repository),
+        }
+    }
+
+    /// Feeds every event published on `events` into the stream.
+    pub fn start(self: &Arc<Self>, events: &EventBus) {
+        let stream = self.clone();
+        events.subscribe_with("event-stream", move |envelope| {
+            stream.push(envelope);
+            async { Ok(()) }
+        });
+    }
+
+    pub fn push(&self, envelope: Arc<EventEnvelope>) -> u64 {
+        let Ok(mut buffer) = self.buffer.lock() else {
+            return 0;
+        };
+        let event = Arc::new(StreamEvent {
+            id: buffer.next_id,
+            envelope,
+        });
+        buffer.next_id += 1;
+        if buffer.events.len() == REPLAY_CAPACITY {
+            buffer.events.pop_front();
+        }
+        buffer.events.push_back(event.clone());
+        // Sent under the lock so a subscriber's replay and live events never overlap.
+        let _ = self.sender.send(event.clone());
+        event.id
+    }
+
+    /// Opens a subscription for the caller, first replaying buffered events after
+    /// `last_event_id` when resuming.
+    pub fn subscribe(
+        &self,
+        actor: &Principal,
+        filter: EventFilter,
+        last_event_id: Option<u64>,
+    ) -> Result<EventSubscription, ApiError> {
+        if let Some(project_id) = filter.project_id {
+            self.access
+                .require(actor, &project_id, ProjectRole::Viewer)?;
+        }
+        let known_projects = match self.access.visible_projects(actor)? {
+            Visibility::All => HashSet::new(),
+            Visibility::Projects(ids) => ids,
+        };
+
+        let buffer = self
+            .buffer
+            .lock()
+            .map_err(|_| ApiError::repository_error("Failed to acquire event stream lock"))?;
+        let receiver = self.sender.subscribe();
+
+        let mut replay = VecDeque::new();
+        let mut reset = false;
+        if let Some(last_id) = last_event_id {
+            let oldest = buffer
+                .events
+                .front()
+                .map_or(buffer.next_id, |event| event.id);
+            // Either events after `last_id` were evicted or the id predates a restart.
+            reset = last_id >= buffer.next_id
+                || last_id.checked_add(1).is_none_or(|next| next < oldest);
+            if !reset {
+                replay.extend(
+                    buffer
+                        .events
+                        .iter()
+                        .filter(|event| event.id > last_id)
+                        .cloned(),
+                );
+            }
+        }
+
+        Ok(EventSubscription {
+            actor: actor.clone(),
+            filter,
+            access: self.access.clone(),
+            known_projects,
+            reset,
+            replay,
+            receiver,
+        })
+    }
+}
+
+/// One client's view of the stream, limited to its tenant and the projects it can read.
+pub struct EventSubscription {
+    actor: Principal,
+    filter: EventFilter,
+    access: AccessPolicy,
+    /// Projects the caller could read at some point during the subscription, so that it
+    /// still hears about their deletion if its membership is gone by the time it arrives.
+    known_projects: HashSet<Uuid>,
+    reset: bool,
+    replay: VecDeque<Arc<StreamEvent>>,
+    receiver: broadcast::Receiver<Arc<StreamEvent>>,
+}
+
+impl EventSubscription {
+    /// Next item for this subscriber; `None` once the stream has shut down.
+    ///
+    /// Cancel safe, so it can race against a heartbeat timer.
+    pub async fn recv(&mut self) -> Option<StreamItem> {
+        if std::mem::take(&mut self.reset) {
+            return Some(StreamItem::Reset);
+        }
+        while let Some(event) = self.replay.pop_front() {
+            if self.allows(&event) {
+                return Some(StreamItem::Event(event));
+            }
+        }
+
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) if self.allows(&event) => return Some(StreamItem::Event(event)),
+                Ok(_) => continue,
+                Err(RecvError::Lagged(_)) => return Some(StreamItem::Reset),
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    fn allows(&mut self, event: &StreamEvent) -> bool {
+        let envelope = &event.envelope;
+        if envelope.tenant_id != self.actor.tenant_id || !self.filter.matches(&envelope.event) {
+            return false;
+        }
+        if self.actor.is_admin() {
+            return true;
+        }
+
+        let project_id = envelope.event.project_id();
+        match self.access.role(&self.actor, &project_id) {
+            Ok(Some(_)) => {
+                self.known_projects.insert(project_id);
+                true
+            }
+            _ => {
+                matches!(envelope.event, DomainEvent::ProjectDeleted { .. })
+                    && self.known_projects.contains(&project_id)
+            }
+        }
+    }
+}
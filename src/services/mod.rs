@@ -1,5 +1,34 @@
+pub mod access;
+pub mod api_key_service;
+pub mod audit_service;
+pub mod backup_service;
+pub mod calendar_service;
+pub mod change_service;
+pub mod event_bus;
+pub mod event_stream;
 pub mod project_service;
+pub mod quota;
+pub mod rank;
+pub mod recurring_task_service;
+pub mod reminder_service;
 pub mod task_service;
+pub mod time_service;
+pub mod trash_service;
+pub mod webhook_service;
 
+pub use access::AccessPolicy;
+pub use api_key_service::ApiKeyService;
+pub use audit_service::AuditService;
+pub use backup_service::BackupService;
+pub use calendar_service::CalendarService;
+pub use change_service::ChangeService;
+pub use event_bus::EventBus;
+pub use event_stream::{EventStream, EventSubscription, StreamEvent, StreamItem};
 pub use project_service::ProjectService;
+pub use quota::{QuotaPolicy, TenantQuota};
+pub use recurring_task_service::RecurringTaskService;
+pub use reminder_service::ReminderService;
 pub use task_service::TaskService;
+pub use time_service::TimeService;
+pub use trash_service::{TrashDependents, TrashService};
+pub use webhook_service::{RetryPolicy, WebhookService};
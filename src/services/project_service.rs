@@ -1,20 +1,64 @@
-use crate::models::{Project, ProjectCreate, ProjectUpdate};
-use crate::repositories::ProjectRepository;
+use crate::models::{
+    AuditAction, BurndownPoint, Column, ColumnDefinition, DomainEvent, EntityType, Principal,
+    Project, ProjectCreate, ProjectMember, ProjectMemberUpdate, ProjectRole, ProjectStats,
+    ProjectStatsQuery, ProjectUpdate, Revision, RevisionDiff, TaskHistory,
+};
+use crate::repositories::{MembershipRepository, ProjectRepository, TaskRepository};
+use crate::services::access::{ensure_active, AccessPolicy};
+use crate::services::audit_service::AuditService;
+use crate::services::event_bus::EventBus;
+use crate::services::quota::QuotaPolicy;
 use crate::views::ApiError;
+use chrono::{Days, NaiveTime, Utc};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use uuid::Uuid;
 
+/// Most columns a project board may have.
+pub const MAX_COLUMNS: usize = 20;
+
+/// Days a burndown covers unless `from` is given.
+pub const DEFAULT_BURNDOWN_DAYS: u64 = 30;
+
+/// Most days a single burndown may cover.
+pub const MAX_BURNDOWN_DAYS: u64 = 366;
+
 #[derive(Debug, Clone)]
 pub struct ProjectService {
     repository: Arc<ProjectRepository>,
+    task_repository: Arc<TaskRepository>,
+    membership_repository: Arc<MembershipRepository>,
+    access: AccessPolicy,
+    quotas: QuotaPolicy,
+    audit: AuditService,
+    events: Arc<EventBus>,
 }
 
 impl ProjectService {
-    pub fn new(repository: Arc<ProjectRepository>) -> Self {
-        Self { repository }
+    pub fn new(
+        repository: Arc<ProjectRepository>,
+        task_repository: Arc<TaskRepository>,
+        membership_repository: Arc<MembershipRepository>,
+        quotas: QuotaPolicy,
+        audit: AuditService,
+        events: Arc<EventBus>,
+    ) -> Self {
+        Self {
+            repository,
+            task_repository,
+            access: AccessPolicy::new(membership_repository.clone()),
+            membership_repository,
+            quotas,
+            audit,
+            events,
+        }
     }
 
-    pub async fn create_project(&self, create_data: ProjectCreate) -> Result<Project, ApiError> {
+    pub async fn create_project(
+        &self,
+        actor: &Principal,
+        create_data: ProjectCreate,
+    ) -> Result<Project, ApiError> {
         // Validation
         if create_data.name.trim().is_empty() {
             return Err(ApiError::validation_error("Project name cannot be empty"));
@@ -34,28 +78,88 @@ impl ProjectService {
             }
         }
 
-        let project = Project::new(create_data.name.trim().to_string(), create_data.description);
+        let project = Project::new(
+            actor.tenant_id.clone(),
+            create_data.name.trim().to_string(),
+            create_data.description,
+        );
 
-        self.repository
-            .create(project)
-            .map_err(|e| ApiError::repository_error(&e))
+        let project = match self.quotas.quota_for(&actor.tenant_id).max_projects {
+            Some(max_projects) => self
+                .repository
+                .create_if_below(project, max_projects)
+                .map_err(|e| ApiError::repository_error(&e))?
+                .ok_or_else(|| {
+                    ApiError::conflict(&format!("Tenant project quota of {} reached", max_projects))
+                })?,
+            None => self
+                .repository
+                .create(project)
+                .map_err(|e| ApiError::repository_error(&e))?,
+        };
+
+        // The creator owns the project
+        let owner = self
+            .membership_repository
+            .upsert(ProjectMember::new(
+                project.id,
+                actor.subject.clone(),
+                ProjectRole::Owner,
+            ))
+            .map_err(|e| ApiError::repository_error(&e))?;
+
+        self.audit.record(
+            actor,
+            AuditAction::Create,
+            EntityType::Project,
+            project.id,
+            None,
+            Some(&project),
+        );
+        self.audit.record(
+            actor,
+            AuditAction::Create,
+            EntityType::ProjectMember,
+            project.id,
+            None,
+            Some(&owner),
+        );
+        self.events.publish(
+            actor,
+            DomainEvent::ProjectCreated {
+                project: project.clone(),
+            },
+        );
+
+        Ok(project)
     }
 
-    pub async fn get_project(&self, id: Uuid) -> Result<Project, ApiError> {
-        self.repository
-            .find_by_id(&id)
-            .map_err(|e| ApiError::repository_error(&e))?
-            .ok_or_else(|| ApiError::not_found("Project"))
+    pub async fn get_project(&self, actor: &Principal, id: Uuid) -> Result<Project, ApiError> {
+        let project = self.find_project(actor, &id)?;
+        self.access.require(actor, &id, ProjectRole::Viewer)?;
+        Ok(project)
     }
 
-    pub async fn list_projects(&self) -> Result<Vec<Project>, ApiError> {
-        self.repository
-            .find_all()
-            .map_err(|e| ApiError::repository_error(&e))
+    pub async fn list_projects(
+        &self,
+        actor: &Principal,
+        include_archived: bool,
+    ) -> Result<Vec<Project>, ApiError> {
+        let visibility = self.access.visible_projects(actor)?;
+
+        Ok(self
+            .repository
+            .find_all(&actor.tenant_id)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .into_iter()
+            .filter(|project| visibility.allows(&project.id))
+            .filter(|project| include_archived || !project.archived)
+            .collect())
     }
 
     pub async fn update_project(
         &self,
+        actor: &Principal,
         id: Uuid,
         update_data: ProjectUpdate,
     ) -> Result<Project, ApiError> {
@@ -80,32 +184,572 @@ impl ProjectService {
         }
 
         // Get existing project
-        let mut project = self
-            .repository
-            .find_by_id(&id)
-            .map_err(|e| ApiError::repository_error(&e))?
-            .ok_or_else(|| ApiError::not_found("Project"))?;
+        let before = self.find_project(actor, &id)?;
+        self.access.require(actor, &id, ProjectRole::Editor)?;
+        ensure_active(&before)?;
 
         // Apply updates
+        let mut project = before.clone();
         project.update(update_data);
 
-        // Save updated project
-        self.repository
-            .update(&id, project)
+        self.save(actor, before, project)
+    }
+
+    pub async fn list_project_revisions(
+        &self,
+        actor: &Principal,
+        id: Uuid,
+    ) -> Result<Vec<Revision>, ApiError> {
+        self.get_project(actor, id).await?;
+        self.audit
+            .revisions(&actor.tenant_id, EntityType::Project, &id)
+    }
+
+    pub async fn get_project_revision(
+        &self,
+        actor: &Principal,
+        id: Uuid,
+        number: u32,
+    ) -> Result<Revision, ApiError> {
+        self.get_project(actor, id).await?;
+        self.audit
+            .revision(&actor.tenant_id, EntityType::Project, &id, number)
+    }
+
+    pub async fn diff_project_revisions(
+        &self,
+        actor: &Principal,
+        id: Uuid,
+        from: u32,
+        to: Option<u32>,
+    ) -> Result<RevisionDiff, ApiError> {
+        self.get_project(actor, id).await?;
+        self.audit
+            .diff_revisions(&actor.tenant_id, EntityType::Project, &id, from, to)
+    }
+
+    /// Writes a new revision with the name and description of revision `number`.
+    pub async fn revert_project(
+        &self,
+        actor: &Principal,
+        id: Uuid,
+        number: u32,
+    ) -> Result<Project, ApiError> {
+        let before = self.find_project(actor, &id)?;
+        self.access.require(actor, &id, ProjectRole::Editor)?;
+        ensure_active(&before)?;
+
+        let revision = self
+            .audit
+            .revision(&actor.tenant_id, EntityType::Project, &id, number)?;
+        let revision: Project = serde_json::from_value(revision.data)
+            .map_err(|e| ApiError::repository_error(&e.to_string()))?;
+
+        let mut project = before.clone();
+        project.revert(&revision);
+
+        self.save(actor, before, project)
+    }
+
+    /// Makes the project and its tasks read-only and hides it from default listings.
+    pub async fn archive_project(&self, actor: &Principal, id: Uuid) -> Result<Project, ApiError> {
+        self.set_archived(actor, id, true)
+    }
+
+    pub async fn unarchive_project(
+        &self,
+        actor: &Principal,
+        id: Uuid,
+    ) -> Result<Project, ApiError> {
+        self.set_archived(actor, id, false)
+    }
+
+    pub async fn list_columns(&self, actor: &Principal, id: Uuid) -> Result<Vec<Column>, ApiError> {
+        Ok(self.get_project(actor, id).await?.columns)
+    }
+
+    /// Replaces the project's board. Columns given with an existing `id` keep their tasks; a
+    /// column can only be dropped once it is empty. When the board has a done column, tasks
+    /// whose column changed its `done` flag are updated to match.
+    pub async fn set_columns(
+        &self,
+        actor: &Principal,
+        id: Uuid,
+        definitions: Vec<ColumnDefinition>,
+    ) -> Result<Vec<Column>, ApiError> {
+        let before = self.find_project(actor, &id)?;
+        self.access.require(actor, &id, ProjectRole::Editor)?;
+        ensure_active(&before)?;
+
+        let columns = board_columns(&before, definitions)?;
+        let tasks = self
+            .task_repository
+            .find_by_project_id(&actor.tenant_id, &id)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        for column in &before.columns {
+            let kept = columns.iter().any(|kept| kept.id == column.id);
+            if !kept && tasks.iter().any(|task| task.column_id == Some(column.id)) {
+                return Err(ApiError::conflict(&format!(
+                    "Column '{}' still has tasks; move them before removing it",
+                    column.name
+                )));
+            }
+        }
+
+        let mut project = before.clone();
+        project.set_columns(columns);
+        let project = self.save(actor, before, project)?;
+
+        if project.done_column().is_some() {
+            let done_by_column: HashMap<Uuid, bool> = project
+                .columns
+                .iter()
+                .map(|column| (column.id, column.done))
+                .collect();
+            let changed = self
+                .task_repository
+                .set_done_by_column(&actor.tenant_id, &id, &done_by_column)
+                .map_err(|e| ApiError::repository_error(&e))?;
+            for (before, after) in changed {
+                self.audit.record(
+                    actor,
+                    AuditAction::Update,
+                    EntityType::Task,
+                    after.id,
+                    Some(&before),
+                    Some(&after),
+                );
+                self.events
+                    .publish(actor, DomainEvent::TaskUpdated { before, after });
+            }
+        }
+
+        Ok(project.columns)
+    }
+
+    /// Current task counts plus a daily burndown replayed from the task revisions, so past
+    /// days show what was open and done back then. Deleted tasks count until their deletion.
+    pub async fn project_stats(
+        &self,
+        actor: &Principal,
+        id: Uuid,
+        query: &ProjectStatsQuery,
+    ) -> Result<ProjectStats, ApiError> {
+        let project = self.get_project(actor, id).await?;
+
+        let now = Utc::now();
+        let today = now.date_naive();
+        let to = query.to.unwrap_or(today).min(today);
+        let from = match query.from {
+            Some(from) => from,
+            None => to
+                .checked_sub_days(Days::new(DEFAULT_BURNDOWN_DAYS - 1))
+                .unwrap_or(to)
+                .max(project.created_at.date_naive())
+                .min(to),
+        };
+        if from > to {
+            return Err(ApiError::validation_error(
+                "from must not be after to or today",
+            ));
+        }
+        if (to - from).num_days() >= MAX_BURNDOWN_DAYS as i64 {
+            return Err(ApiError::validation_error(&format!(
+                "A burndown covers at most {} days",
+                MAX_BURNDOWN_DAYS
+            )));
+        }
+
+        let live = self
+            .task_repository
+            .find_by_project_id(&actor.tenant_id, &id)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        let trashed: Vec<_> = self
+            .task_repository
+            .find_deleted(&actor.tenant_id)
             .map_err(|e| ApiError::repository_error(&e))?
-            .ok_or_else(|| ApiError::not_found("Project"))
+            .into_iter()
+            .filter(|task| task.project_id == id)
+            .collect();
+        let histories = live
+            .iter()
+            .chain(&trashed)
+            .map(|task| {
+                let revisions =
+                    self.audit
+                        .revisions(&actor.tenant_id, EntityType::Task, &task.id)?;
+                Ok(TaskHistory::new(task, &revisions))
+            })
+            .collect::<Result<Vec<_>, ApiError>>()?;
+
+        let total = live.len();
+        let done = live.iter().filter(|task| task.done).count();
+        let completion_percent = if total == 0 {
+            0.0
+        } else {
+            round2(done as f64 * 100.0 / total as f64)
+        };
+        // Live tasks come first in `histories`
+        let hours_to_done: Vec<f64> = histories[..total]
+            .iter()
+            .filter_map(|history| {
+                let completed_at = history.completed_at()?;
+                Some((completed_at - history.created_at()).num_seconds() as f64 / 3600.0)
+            })
+            .collect();
+        let average_hours_to_done = (!hours_to_done.is_empty())
+            .then(|| round2(hours_to_done.iter().sum::<f64>() / hours_to_done.len() as f64));
+
+        let burndown = from
+            .iter_days()
+            .take_while(|date| *date <= to)
+            .map(|date| {
+                let end_of_day = date
+                    .succ_opt()
+                    .map(|next| next.and_time(NaiveTime::MIN).and_utc())
+                    .map_or(now, |end| end.min(now));
+                let existing = histories
+                    .iter()
+                    .filter(|history| history.exists_at(end_of_day));
+                let (total, done) = existing.fold((0, 0), |(total, done), history| {
+                    (
+                        total + 1,
+                        done + usize::from(history.is_done_at(end_of_day)),
+                    )
+                });
+                BurndownPoint {
+                    date,
+                    total,
+                    open: total - done,
+                    done,
+                }
+            })
+            .collect();
+
+        Ok(ProjectStats {
+            project_id: id,
+            total,
+            open: total - done,
+            done,
+            completion_percent,
+            average_hours_to_done,
+            burndown,
+        })
     }
 
-    pub async fn delete_project(&self, id: Uuid) -> Result<(), ApiError> {
+    /// Moves a project and its tasks to the trash. Memberships are kept so that owners can
+    /// still find and restore it; they go when the project is purged.
+    pub async fn delete_project(&self, actor: &Principal, id: Uuid) -> Result<(), ApiError> {
+        let project = self.find_project(actor, &id)?;
+        self.access.require(actor, &id, ProjectRole::Owner)?;
+
+        let tasks = self
+            .task_repository
+            .find_by_project_id(&actor.tenant_id, &id)
+            .map_err(|e| ApiError::repository_error(&e))?;
+
+        // Project and cascaded tasks share the timestamp that restoring matches on.
+        let deleted_at = Utc::now();
         let deleted = self
             .repository
-            .delete(&id)
+            .delete(&actor.tenant_id, &id, deleted_at)
             .map_err(|e| ApiError::repository_error(&e))?;
 
-        if deleted {
+        if !deleted {
+            return Err(ApiError::not_found("Project"));
+        }
+
+        self.task_repository
+            .delete_by_project_id(&actor.tenant_id, &id, deleted_at)
+            .map_err(|e| ApiError::repository_error(&e))?;
+
+        self.audit.record(
+            actor,
+            AuditAction::Delete,
+            EntityType::Project,
+            id,
+            Some(&project),
+            None,
+        );
+        for task in &tasks {
+            self.audit.record(
+                actor,
+                AuditAction::Delete,
+                EntityType::Task,
+                task.id,
+                Some(task),
+                None,
+            );
+        }
+        self.events.publish(
+            actor,
+            DomainEvent::ProjectDeleted {
+                project,
+                cascaded_tasks: tasks,
+            },
+        );
+
+        Ok(())
+    }
+
+    pub async fn list_members(
+        &self,
+        actor: &Principal,
+        id: Uuid,
+    ) -> Result<Vec<ProjectMember>, ApiError> {
+        self.find_project(actor, &id)?;
+        self.access.require(actor, &id, ProjectRole::Viewer)?;
+
+        let mut members = self
+            .membership_repository
+            .find_by_project_id(&id)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        members.sort_by_key(|member| member.created_at);
+        Ok(members)
+    }
+
+    /// Adds a member or changes their role. A new member's change feed cursor predates
+    /// their access, so the project and its tasks are recorded again for them to sync.
+    pub async fn set_member(
+        &self,
+        actor: &Principal,
+        id: Uuid,
+        subject: String,
+        update_data: ProjectMemberUpdate,
+    ) -> Result<ProjectMember, ApiError> {
+        if subject.trim().is_empty() {
+            return Err(ApiError::validation_error("Member subject cannot be empty"));
+        }
+
+        self.find_project(actor, &id)?;
+        self.access.require(actor, &id, ProjectRole::Owner)?;
+
+        let existing = self
+            .membership_repository
+            .find(&id, &subject)
+            .map_err(|e| ApiError::repository_error(&e))?;
+
+        let member = match existing {
+            Some(ref member) => {
+                if member.role == ProjectRole::Owner && update_data.role != ProjectRole::Owner {
+                    self.ensure_other_owner(&id, &subject)?;
+                }
+                let mut member = member.clone();
+                member.role = update_data.role;
+                member.updated_at = chrono::Utc::now();
+                member
+            }
+            None => ProjectMember::new(id, subject, update_data.role),
+        };
+
+        let member = self
+            .membership_repository
+            .upsert(member)
+            .map_err(|e| ApiError::repository_error(&e))?;
+
+        if existing.is_none() {
+            self.task_repository
+                .record_again_by_project_id(&actor.tenant_id, &id)
+                .and_then(|_| self.repository.record_again(&actor.tenant_id, &id))
+                .map_err(|e| ApiError::repository_error(&e))?;
+        }
+
+        let action = if existing.is_some() {
+            AuditAction::Update
+        } else {
+            AuditAction::Create
+        };
+        self.audit.record(
+            actor,
+            action,
+            EntityType::ProjectMember,
+            id,
+            existing.as_ref(),
+            Some(&member),
+        );
+
+        Ok(member)
+    }
+
+    pub async fn remove_member(
+        &self,
+        actor: &Principal,
+        id: Uuid,
+        subject: &str,
+    ) -> Result<(), ApiError> {
+        self.find_project(actor, &id)?;
+        self.access.require(actor, &id, ProjectRole::Owner)?;
+
+        let member = self
+            .membership_repository
+            .find(&id, subject)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .ok_or_else(|| ApiError::not_found("Project member"))?;
+
+        if member.role == ProjectRole::Owner {
+            self.ensure_other_owner(&id, subject)?;
+        }
+
+        self.membership_repository
+            .delete(&id, subject)
+            .map_err(|e| ApiError::repository_error(&e))?;
+
+        self.audit.record(
+            actor,
+            AuditAction::Delete,
+            EntityType::ProjectMember,
+            id,
+            Some(&member),
+            None,
+        );
+        Ok(())
+    }
+
+    fn set_archived(
+        &self,
+        actor: &Principal,
+        id: Uuid,
+        archived: bool,
+    ) -> Result<Project, ApiError> {
+        let before = self.find_project(actor, &id)?;
+        self.access.require(actor, &id, ProjectRole::Owner)?;
+        if before.archived == archived {
+            return Ok(before);
+        }
+
+        let mut project = before.clone();
+        project.set_archived(archived);
+
+        self.save(actor, before, project)
+    }
+
+    /// Stores an edited project, then audits and announces the change.
+    fn save(
+        &self,
+        actor: &Principal,
+        before: Project,
+        project: Project,
+    ) -> Result<Project, ApiError> {
+        let id = project.id;
+        let project = self
+            .repository
+            .update(&actor.tenant_id, &id, project)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .ok_or_else(|| ApiError::not_found("Project"))?;
+
+        self.audit.record(
+            actor,
+            AuditAction::Update,
+            EntityType::Project,
+            id,
+            Some(&before),
+            Some(&project),
+        );
+        self.events.publish(
+            actor,
+            DomainEvent::ProjectUpdated {
+                before,
+                after: project.clone(),
+            },
+        );
+
+        Ok(project)
+    }
+
+    fn find_project(&self, actor: &Principal, id: &Uuid) -> Result<Project, ApiError> {
+        self.repository
+            .find_by_id(&actor.tenant_id, id)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .ok_or_else(|| ApiError::not_found("Project"))
+    }
+
+    /// A project must always keep at least one owner.
+    fn ensure_other_owner(&self, id: &Uuid, subject: &str) -> Result<(), ApiError> {
+        let has_other_owner = self
+            .membership_repository
+            .find_by_project_id(id)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .iter()
+            .any(|member| member.role == ProjectRole::Owner && member.subject != subject);
+
+        if has_other_owner {
             Ok(())
         } else {
-            Err(ApiError::not_found("Project"))
+            Err(ApiError::conflict("A project must keep at least one owner"))
         }
     }
 }
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+/// Validates a board definition against the project's current columns.
+fn board_columns(
+    project: &Project,
+    definitions: Vec<ColumnDefinition>,
+) -> Result<Vec<Column>, ApiError> {
+    if definitions.len() > MAX_COLUMNS {
+        return Err(ApiError::validation_error(&format!(
+            "A board cannot have more than {} columns",
+            MAX_COLUMNS
+        )));
+    }
+    if definitions.iter().filter(|column| column.done).count() > 1 {
+        return Err(ApiError::validation_error(
+            "Only one column can be the done column",
+        ));
+    }
+
+    let mut names = HashSet::new();
+    let mut ids = HashSet::new();
+    definitions
+        .into_iter()
+        .map(|definition| {
+            let name = definition.name.trim().to_string();
+            if name.is_empty() {
+                return Err(ApiError::validation_error("Column name cannot be empty"));
+            }
+            if name.len() > 100 {
+                return Err(ApiError::validation_error(
+                    "Column name cannot exceed 100 characters",
+                ));
+            }
+            if !names.insert(name.to_lowercase()) {
+                return Err(ApiError::validation_error(&format!(
+                    "Column '{}' appears more than once",
+                    name
+                )));
+            }
+            if definition.wip_limit == Some(0) {
+                return Err(ApiError::validation_error(
+                    "Column WIP limit must be at least 1",
+                ));
+            }
+
+            let id = match definition.id {
+                Some(id) if project.column(&id).is_none() => {
+                    return Err(ApiError::validation_error(&format!(
+                        "Column {} is not on this project's board",
+                        id
+                    )));
+                }
+                Some(id) => id,
+                None => Uuid::new_v4(),
+            };
+            if !ids.insert(id) {
+                return Err(ApiError::validation_error(&format!(
+                    "Column {} appears more than once",
+                    id
+                )));
+            }
+
+            Ok(Column {
+                id,
+                name,
+                wip_limit: definition.wip_limit,
+                done: definition.done,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,74 @@
+use crate::config::Config;
+use std::collections::HashMap;
+
+/// Resource limits for a single tenant; `None` means unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TenantQuota {
+    pub max_projects: Option<usize>,
+    pub max_tasks: Option<usize>,
+}
+
+/// Default tenant quota plus per-tenant overrides.
+#[derive(Debug, Clone, Default)]
+pub struct QuotaPolicy {
+    default: TenantQuota,
+    overrides: HashMap<String, TenantQuota>,
+}
+
+impl QuotaPolicy {
+    pub fn new(default: TenantQuota) -> Self {
+        Self {
+            default,
+            overrides: HashMap::new(),
+        }
+    }
+
+    pub fn with_override(mut self, tenant_id: &str, quota: TenantQuota) -> Self {
+        self.overrides.insert(tenant_id.to_string(), quota);
+        self
+    }
+
+    /// Reads `TENANT_MAX_PROJECTS`/`TENANT_MAX_TASKS` as defaults and `TENANT_QUOTAS`
+    /// overrides of the form `tenant=projects/tasks,...`, where `*` means unlimited.
+    pub fn from_config(config: &Config) -> Result<Self, String> {
+        let mut policy = Self::new(TenantQuota {
+            max_projects: config.tenant_max_projects,
+            max_tasks: config.tenant_max_tasks,
+        });
+
+        let Some(ref overrides) = config.tenant_quotas else {
+            return Ok(policy);
+        };
+
+        for entry in overrides
+            .split(',')
+            .map(str::trim)
+            .filter(|e| !e.is_empty())
+        {
+            let invalid = || format!("Invalid TENANT_QUOTAS entry: {}", entry);
+            let (tenant_id, limits) = entry.split_once('=').ok_or_else(invalid)?;
+            let (projects, tasks) = limits.split_once('/').ok_or_else(invalid)?;
+            let quota = TenantQuota {
+                max_projects: parse_limit(projects).ok_or_else(invalid)?,
+                max_tasks: parse_limit(tasks).ok_or_else(invalid)?,
+            };
+            policy = policy.with_override(tenant_id.trim(), quota);
+        }
+
+        Ok(policy)
+    }
+
+    pub fn quota_for(&self, tenant_id: &str) -> TenantQuota {
+        self.overrides
+            .get(tenant_id)
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+fn parse_limit(value: &str) -> Option<Option<usize>> {
+    match value.trim() {
+        "*" => Some(None),
+        limit => limit.parse().ok().map(Some),
+    }
+}
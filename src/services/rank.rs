@@ -0,0 +1,107 @@
+//! Fractional rank keys: strings over an ordered alphabet that sort in display order, where a
+//! new key can always be made between two neighbours without touching any other key.
+
+/// Digits in ascending byte order, so keys compare correctly as plain strings.
+const DIGITS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+const BASE: usize = DIGITS.len();
+
+/// Keys longer than this trigger a rebalance of the whole list.
+pub const MAX_RANK_LEN: usize = 32;
+
+/// Digits [`after`] steps at, leaving room for millions of appends after a short key.
+const STEP_WIDTH: usize = 4;
+
+/// A key sorting strictly between `lower` and `upper`; `None` stands for the start or end of
+/// the list. Keys never end in the lowest digit, so there is always room below them.
+///
+/// Panics unless `lower < upper`.
+pub fn between(lower: Option<&str>, upper: Option<&str>) -> String {
+    let lower = lower.unwrap_or_default().as_bytes();
+    let upper = upper.map(str::as_bytes);
+    if let Some(upper) = upper {
+        assert!(lower < upper, "rank bounds out of order");
+    }
+    String::from_utf8(midpoint(lower, upper)).expect("rank digits are ASCII")
+}
+
+/// A key sorting just after `lower`, for appending many keys in a row: unlike
+/// [`between`], it steps `lower` at a fixed width of at least [`STEP_WIDTH`] digits, so
+/// the keys do not grow. `None` once every digit at that width is used up.
+pub fn after(lower: &str) -> Option<String> {
+    let mut key = lower.as_bytes().to_vec();
+    key.resize(key.len().max(STEP_WIDTH), DIGITS[0]);
+    for slot in key.iter_mut().rev() {
+        let next = index(*slot) + 1;
+        if next < BASE {
+            *slot = DIGITS[next];
+            while key.last() == Some(&DIGITS[0]) {
+                key.pop();
+            }
+            return Some(String::from_utf8(key).expect("rank digits are ASCII"));
+        }
+        *slot = DIGITS[0];
+    }
+    None
+}
+
+/// `count` evenly spaced keys, used to rebalance a list whose keys grew too long.
+pub fn spread(count: usize) -> Vec<String> {
+    let mut width = 1;
+    while BASE.pow(width) <= count * 2 {
+        width += 1;
+    }
+    let space = BASE.pow(width);
+    (1..=count)
+        .map(|i| {
+            let mut value = i * space / (count + 1);
+            let mut key = vec![DIGITS[0]; width as usize];
+            for slot in key.iter_mut().rev() {
+                *slot = DIGITS[value % BASE];
+                value /= BASE;
+            }
+            while key.last() == Some(&DIGITS[0]) {
+                key.pop();
+            }
+            String::from_utf8(key).expect("rank digits are ASCII")
+        })
+        .collect()
+}
+
+fn midpoint(lower: &[u8], upper: Option<&[u8]>) -> Vec<u8> {
+    if let Some(upper) = upper {
+        // Keep the common prefix, reading missing digits of `lower` as zeros.
+        let shared = upper
+            .iter()
+            .enumerate()
+            .take_while(|(i, digit)| lower.get(*i).unwrap_or(&DIGITS[0]) == *digit)
+            .count();
+        if shared > 0 {
+            let mut key = upper[..shared].to_vec();
+            key.extend(midpoint(
+                lower.get(shared..).unwrap_or_default(),
+                Some(&upper[shared..]),
+            ));
+            return key;
+        }
+    }
+
+    let low = lower.first().map_or(0, |digit| index(*digit));
+    let high = upper.map_or(BASE, |upper| index(upper[0]));
+    if high - low > 1 {
+        vec![DIGITS[(low + high) / 2]]
+    } else if let Some(upper) = upper.filter(|upper| upper.len() > 1) {
+        // The first digit of `upper` alone already sorts below it.
+        vec![upper[0]]
+    } else {
+        let mut key = vec![DIGITS[low]];
+        key.extend(midpoint(lower.get(1..).unwrap_or_default(), None));
+        key
+    }
+}
+
+fn index(digit: u8) -> usize {
+    DIGITS
+        .iter()
+        .position(|candidate| *candidate == digit)
+        .unwrap_or(0)
+}
@@ -0,0 +1,209 @@
+use crate::models::{
+    Principal, ProjectRole, RecurrenceRule, RecurringTask, RecurringTaskCreate, TaskCreate,
+    TaskOccurrence,
+};
+use crate::repositories::{MembershipRepository, ProjectRepository, RecurringTaskRepository};
+use crate::services::access::ensure_active;
+use crate::services::access::AccessPolicy;
+use crate::services::task_service::TaskService;
+use crate::views::ApiError;
+use chrono::{DateTime, Utc};
+use ntex::time::{sleep, Millis};
+use std::sync::Arc;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// How often the scheduler looks for recurring tasks that are due.
+const SCHEDULER_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Manages recurring task templates and creates their tasks when they fall due.
+#[derive(Debug, Clone)]
+pub struct RecurringTaskService {
+    repository: Arc<RecurringTaskRepository>,
+    project_repository: Arc<ProjectRepository>,
+    access: AccessPolicy,
+    tasks: Arc<TaskService>,
+}
+
+impl RecurringTaskService {
+    pub fn new(
+        repository: Arc<RecurringTaskRepository>,
+        project_repository: Arc<ProjectRepository>,
+        membership_repository: Arc<MembershipRepository>,
+        tasks: Arc<TaskService>,
+    ) -> Self {
+        Self {
+            repository,
+            project_repository,
+            access: AccessPolicy::new(membership_repository),
+            tasks,
+        }
+    }
+
+    /// Spawns the scheduler that creates tasks for due occurrences.
+    pub fn start(self: &Arc<Self>) {
+        let service = self.clone();
+        ntex::rt::spawn(async move {
+            loop {
+                match service.run_due(Utc::now()).await {
+                    Ok(0) => {}
+                    Ok(count) => info!("Created {} recurring tasks", count),
+                    Err(e) => warn!("Recurring task scheduler failed: {}", e),
+                }
+                sleep(Millis::from(SCHEDULER_INTERVAL)).await;
+            }
+        });
+    }
+
+    pub async fn create_recurring_task(
+        &self,
+        actor: &Principal,
+        create_data: RecurringTaskCreate,
+    ) -> Result<RecurringTask, ApiError> {
+        // Validation
+        if create_data.title.trim().is_empty() {
+            return Err(ApiError::validation_error("Task title cannot be empty"));
+        }
+
+        if create_data.title.len() > 200 {
+            return Err(ApiError::validation_error(
+                "Task title cannot exceed 200 characters",
+            ));
+        }
+
+        if let Some(ref description) = create_data.description {
+            if description.len() > 1000 {
+                return Err(ApiError::validation_error(
+                    "Task description cannot exceed 1000 characters",
+                ));
+            }
+        }
+
+        let rule: RecurrenceRule = create_data
+            .rrule
+            .parse()
+            .map_err(|e: String| ApiError::validation_error(&e))?;
+
+        let project = self
+            .project_repository
+            .find_by_id(&actor.tenant_id, &create_data.project_id)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .ok_or_else(|| ApiError::not_found("Project"))?;
+        self.access
+            .require(actor, &project.id, ProjectRole::Editor)?;
+        ensure_active(&project)?;
+
+        let starts_at = create_data.starts_at.unwrap_or_else(Utc::now);
+        let recurring_task = RecurringTask::new(actor, create_data, rule, starts_at);
+        if recurring_task.next_at.is_none() {
+            return Err(ApiError::validation_error(
+                "The rule has no occurrences from starts_at on",
+            ));
+        }
+
+        self.repository
+            .create(recurring_task)
+            .map_err(|e| ApiError::repository_error(&e))
+    }
+
+    pub async fn get_recurring_task(
+        &self,
+        actor: &Principal,
+        id: Uuid,
+    ) -> Result<RecurringTask, ApiError> {
+        let recurring_task = self.find_recurring_task(actor, &id)?;
+        self.access
+            .require(actor, &recurring_task.project_id, ProjectRole::Viewer)?;
+        Ok(recurring_task)
+    }
+
+    pub async fn list_recurring_tasks(
+        &self,
+        actor: &Principal,
+    ) -> Result<Vec<RecurringTask>, ApiError> {
+        let visibility = self.access.visible_projects(actor)?;
+
+        let mut recurring_tasks: Vec<RecurringTask> = self
+            .repository
+            .find_all(&actor.tenant_id)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .into_iter()
+            .filter(|recurring_task| visibility.allows(&recurring_task.project_id))
+            .collect();
+        recurring_tasks.sort_by_key(|recurring_task| recurring_task.created_at);
+        Ok(recurring_tasks)
+    }
+
+    /// Stops future occurrences; tasks already created are kept.
+    pub async fn delete_recurring_task(&self, actor: &Principal, id: Uuid) -> Result<(), ApiError> {
+        let recurring_task = self.find_recurring_task(actor, &id)?;
+        self.access
+            .require(actor, &recurring_task.project_id, ProjectRole::Editor)?;
+
+        let deleted = self
+            .repository
+            .delete(&actor.tenant_id, &id)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        if !deleted {
+            return Err(ApiError::not_found("Recurring task"));
+        }
+        Ok(())
+    }
+
+    /// Creates a task for every recurring task, in every tenant, that is due at `now`, and
+    /// returns how many occurrences were handled. The task is created before the schedule
+    /// moves on, and creating an occurrence again returns the existing task, so a run that is
+    /// interrupted can simply be repeated.
+    pub async fn run_due(&self, now: DateTime<Utc>) -> Result<usize, ApiError> {
+        let due = self
+            .repository
+            .find_due(now)
+            .map_err(|e| ApiError::repository_error(&e))?;
+
+        let mut created = 0;
+        for mut recurring_task in due {
+            let Some(occurs_at) = recurring_task.advance(now) else {
+                continue;
+            };
+            let result = self
+                .tasks
+                .create_task(
+                    &recurring_task.creator(),
+                    TaskCreate {
+                        project_id: recurring_task.project_id,
+                        title: recurring_task.title.clone(),
+                        description: recurring_task.description.clone(),
+                        column_id: None,
+                        due_at: None,
+                        occurrence: Some(TaskOccurrence {
+                            recurring_task_id: recurring_task.id,
+                            occurs_at,
+                        }),
+                    },
+                )
+                .await;
+            match result {
+                Ok(_) => created += 1,
+                // Skip this occurrence, e.g. while the project is archived
+                Err(e) => warn!(
+                    "Skipped occurrence {} of recurring task {}: {}",
+                    occurs_at, recurring_task.id, e
+                ),
+            }
+
+            let tenant_id = recurring_task.tenant_id.clone();
+            let id = recurring_task.id;
+            self.repository
+                .update(&tenant_id, &id, recurring_task)
+                .map_err(|e| ApiError::repository_error(&e))?;
+        }
+        Ok(created)
+    }
+
+    fn find_recurring_task(&self, actor: &Principal, id: &Uuid) -> Result<RecurringTask, ApiError> {
+        self.repository
+            .find_by_id(&actor.tenant_id, id)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .ok_or_else(|| ApiError::not_found("Recurring task"))
+    }
+}
@@ -0,0 +1,203 @@
+use crate::config::Config;
+use crate::models::{
+    DomainEvent, OverdueCount, Principal, Project, ProjectRole, Reminder, ReminderKind, Task,
+};
+use crate::repositories::{
+    MembershipRepository, ProjectRepository, ReminderRepository, TaskRepository,
+};
+use crate::services::access::AccessPolicy;
+use crate::services::event_bus::EventBus;
+use crate::views::ApiError;
+use chrono::{DateTime, Duration, Utc};
+use ntex::time::{sleep, Millis};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Hours before the due date that the due-soon reminder fires unless `REMINDER_LEAD_HOURS` is
+/// set.
+pub const DEFAULT_LEAD_HOURS: u32 = 24;
+
+/// How often the reminder job looks for tasks that are due.
+const SCAN_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Sends due-date reminders and finds overdue tasks.
+#[derive(Debug, Clone)]
+pub struct ReminderService {
+    repository: Arc<ReminderRepository>,
+    task_repository: Arc<TaskRepository>,
+    project_repository: Arc<ProjectRepository>,
+    access: AccessPolicy,
+    events: Arc<EventBus>,
+    lead: Duration,
+}
+
+impl ReminderService {
+    pub fn new(
+        repository: Arc<ReminderRepository>,
+        task_repository: Arc<TaskRepository>,
+        project_repository: Arc<ProjectRepository>,
+        membership_repository: Arc<MembershipRepository>,
+        events: Arc<EventBus>,
+        lead: Duration,
+    ) -> Self {
+        Self {
+            repository,
+            task_repository,
+            project_repository,
+            access: AccessPolicy::new(membership_repository),
+            events,
+            lead,
+        }
+    }
+
+    pub fn lead_from_config(config: &Config) -> Duration {
+        Duration::hours(
+            config
+                .reminder_lead_hours
+                .unwrap_or(DEFAULT_LEAD_HOURS)
+                .into(),
+        )
+    }
+
+    /// Spawns the job that sends due-date reminders.
+    pub fn start(self: &Arc<Self>) {
+        let service = self.clone();
+        ntex::rt::spawn(async move {
+            loop {
+                match service.send_due(Utc::now()) {
+                    Ok(0) => {}
+                    Ok(count) => info!("Sent {} due-date reminders", count),
+                    Err(e) => warn!("Reminder job failed: {}", e),
+                }
+                sleep(Millis::from(SCAN_INTERVAL)).await;
+            }
+        });
+    }
+
+    /// Publishes a `task_due_soon` event for every open task, in every tenant, due within the
+    /// lead time of `now` and a `task_overdue` event for every one past its due date, and
+    /// returns how many were sent. Each reminder is sent once per due date; tasks of archived
+    /// projects are skipped.
+    pub fn send_due(&self, now: DateTime<Utc>) -> Result<usize, ApiError> {
+        let due = self
+            .task_repository
+            .find_due_before(now + self.lead)
+            .map_err(|e| ApiError::repository_error(&e))?;
+
+        let mut sent = 0;
+        for task in due {
+            let Some(due_at) = task.due_at else {
+                continue;
+            };
+            let project = self
+                .project_repository
+                .find_by_id(&task.tenant_id, &task.project_id)
+                .map_err(|e| ApiError::repository_error(&e))?;
+            if project.is_none_or(|project| project.archived) {
+                continue;
+            }
+
+            let kind = if due_at <= now {
+                ReminderKind::Overdue
+            } else {
+                ReminderKind::DueSoon
+            };
+            let recorded = self
+                .repository
+                .record(Reminder::new(&task, kind, due_at, now))
+                .map_err(|e| ApiError::repository_error(&e))?;
+            if !recorded {
+                continue;
+            }
+
+            let actor = Principal::system(task.tenant_id.clone(), "reminders");
+            let event = match kind {
+                ReminderKind::DueSoon => DomainEvent::TaskDueSoon { task },
+                ReminderKind::Overdue => DomainEvent::TaskOverdue { task },
+            };
+            self.events.publish(&actor, event);
+            sent += 1;
+        }
+        Ok(sent)
+    }
+
+    /// Open tasks past their due date, most overdue first, optionally of one project.
+    pub async fn list_overdue_tasks(
+        &self,
+        actor: &Principal,
+        project_id: Option<Uuid>,
+    ) -> Result<Vec<Task>, ApiError> {
+        if let Some(project_id) = project_id {
+            // Verify project exists
+            self.project_repository
+                .find_by_id(&actor.tenant_id, &project_id)
+                .map_err(|e| ApiError::repository_error(&e))?
+                .ok_or_else(|| ApiError::not_found("Project"))?;
+            self.access
+                .require(actor, &project_id, ProjectRole::Viewer)?;
+        }
+
+        let (tasks, _) = self.find_overdue(actor, Utc::now())?;
+        Ok(tasks
+            .into_iter()
+            .filter(|task| project_id.is_none_or(|id| id == task.project_id))
+            .collect())
+    }
+
+    /// Number of overdue tasks per project, for projects that have any.
+    pub async fn overdue_counts(&self, actor: &Principal) -> Result<Vec<OverdueCount>, ApiError> {
+        let (tasks, projects) = self.find_overdue(actor, Utc::now())?;
+
+        let mut counts: HashMap<Uuid, usize> = HashMap::new();
+        for task in &tasks {
+            *counts.entry(task.project_id).or_default() += 1;
+        }
+        let mut counts: Vec<OverdueCount> = counts
+            .into_iter()
+            .filter_map(|(project_id, overdue)| {
+                projects.get(&project_id).map(|project| OverdueCount {
+                    project_id,
+                    project_name: project.name.clone(),
+                    overdue,
+                })
+            })
+            .collect();
+        counts.sort_by(|a, b| {
+            b.overdue
+                .cmp(&a.overdue)
+                .then_with(|| a.project_name.cmp(&b.project_name))
+        });
+        Ok(counts)
+    }
+
+    /// Overdue tasks the actor can see, sorted by due date, together with their projects.
+    /// Archived projects are left out.
+    fn find_overdue(
+        &self,
+        actor: &Principal,
+        now: DateTime<Utc>,
+    ) -> Result<(Vec<Task>, HashMap<Uuid, Project>), ApiError> {
+        let visibility = self.access.visible_projects(actor)?;
+
+        let projects: HashMap<Uuid, Project> = self
+            .project_repository
+            .find_all(&actor.tenant_id)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .into_iter()
+            .filter(|project| !project.archived && visibility.allows(&project.id))
+            .map(|project| (project.id, project))
+            .collect();
+
+        let mut tasks: Vec<Task> = self
+            .task_repository
+            .find_all(&actor.tenant_id)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .into_iter()
+            .filter(|task| task.is_overdue(now) && projects.contains_key(&task.project_id))
+            .collect();
+        tasks.sort_by_key(|task| (task.due_at, task.id));
+        Ok((tasks, projects))
+    }
+}
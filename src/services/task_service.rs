@@ -1,89 +1,471 @@
-use crate::models::{Task, TaskCreate, TaskUpdate};
-use crate::repositories::{ProjectRepository, TaskRepository};
-use crate::views::ApiError;
+use crate::models::{
+    AuditAction, Column, DomainEvent, EntityType, Principal, Project, ProjectRole, Revision,
+    RevisionDiff, Task, TaskCreate, TaskCsvHeader, TaskImportError, TaskImportReport, TaskMove,
+    TaskUpdate,
+};
+use crate::repositories::{MembershipRepository, ProjectRepository, TaskRepository};
+use crate::services::access::{ensure_active, AccessPolicy};
+use crate::services::audit_service::AuditService;
+use crate::services::event_bus::EventBus;
+use crate::services::quota::QuotaPolicy;
+use crate::services::rank;
+use crate::views::{ApiError, CsvReader, CsvRow};
+use chrono::Utc;
+use futures::{Stream, StreamExt};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use uuid::Uuid;
 
+/// Row errors listed in an import report; further failures are only counted.
+const MAX_IMPORT_ERRORS: usize = 100;
+
+/// Running state of a CSV import. The project is read once up front: new tasks are ranked
+/// after the last one and checked against WIP limits from counts kept here, and rows are
+/// staged so each chunk of the upload is stored under one quota check.
+struct ImportBatch {
+    header: Option<TaskCsvHeader>,
+    /// Rank of the last task in the project, staged ones included
+    last_rank: Option<String>,
+    /// Tasks in each column, staged ones included
+    column_counts: HashMap<Uuid, usize>,
+    /// Rows read since the last flush, in file order
+    pending: Vec<(usize, Result<Task, ApiError>)>,
+}
+
 #[derive(Debug, Clone)]
 pub struct TaskService {
     task_repository: Arc<TaskRepository>,
     project_repository: Arc<ProjectRepository>,
+    access: AccessPolicy,
+    quotas: QuotaPolicy,
+    audit: AuditService,
+    events: Arc<EventBus>,
 }
 
 impl TaskService {
     pub fn new(
         task_repository: Arc<TaskRepository>,
         project_repository: Arc<ProjectRepository>,
+        membership_repository: Arc<MembershipRepository>,
+        quotas: QuotaPolicy,
+        audit: AuditService,
+        events: Arc<EventBus>,
     ) -> Self {
         Self {
             task_repository,
             project_repository,
+            access: AccessPolicy::new(membership_repository),
+            quotas,
+            audit,
+            events,
         }
     }
 
-    pub async fn create_task(&self, create_data: TaskCreate) -> Result<Task, ApiError> {
-        // Validation
-        if create_data.title.trim().is_empty() {
-            return Err(ApiError::validation_error("Task title cannot be empty"));
-        }
-
-        if create_data.title.len() > 200 {
-            return Err(ApiError::validation_error(
-                "Task title cannot exceed 200 characters",
-            ));
-        }
-
-        if let Some(ref description) = create_data.description {
-            if description.len() > 1000 {
-                return Err(ApiError::validation_error(
-                    "Task description cannot exceed 1000 characters",
-                ));
-            }
-        }
+    pub async fn create_task(
+        &self,
+        actor: &Principal,
+        create_data: TaskCreate,
+    ) -> Result<Task, ApiError> {
+        validate_create(&create_data)?;
 
         // Verify project exists
-        self.project_repository
-            .find_by_id(&create_data.project_id)
+        let project = self
+            .project_repository
+            .find_by_id(&actor.tenant_id, &create_data.project_id)
             .map_err(|e| ApiError::repository_error(&e))?
             .ok_or_else(|| ApiError::not_found("Project"))?;
+        self.access
+            .require(actor, &create_data.project_id, ProjectRole::Editor)?;
+        ensure_active(&project)?;
+
+        if let Some(ref occurrence) = create_data.occurrence {
+            let existing = self
+                .task_repository
+                .find_by_occurrence(&actor.tenant_id, occurrence)
+                .map_err(|e| ApiError::repository_error(&e))?;
+            if let Some(task) = existing {
+                return Ok(task);
+            }
+        }
 
-        let task = Task::new(
+        let mut task = Task::new(
+            actor.tenant_id.clone(),
             create_data.project_id,
             create_data.title.trim().to_string(),
             create_data.description,
         );
+        task.due_at = create_data.due_at;
+        task.occurrence = create_data.occurrence;
+        task.column_id = create_data
+            .column_id
+            .or_else(|| project.columns.first().map(|column| column.id));
+        let entering = board_column(&project, &mut task, None, create_data.column_id, false)?;
+        // New tasks go to the end of the project. A key that came out too long is only fixed
+        // by a rebalance once the task is stored, so a create turned down changes nothing.
+        let last = self
+            .ordered_tasks(&actor.tenant_id, &create_data.project_id)?
+            .pop()
+            .map(|task| task.rank)
+            .filter(|rank| !rank.is_empty());
+        task.rank = rank::between(last.as_deref(), None);
 
-        self.task_repository
-            .create(task)
-            .map_err(|e| ApiError::repository_error(&e))
+        let max_tasks = self.quotas.quota_for(&actor.tenant_id).max_tasks;
+        let task = self
+            .task_repository
+            .create_if_below(task, max_tasks, |count| {
+                entering.map_or(Ok(()), |column| check_wip_limit(column, count))
+            })?
+            .ok_or_else(|| task_quota_reached(max_tasks.unwrap_or_default()))?;
+
+        self.audit.record(
+            actor,
+            AuditAction::Create,
+            EntityType::Task,
+            task.id,
+            None,
+            Some(&task),
+        );
+        self.events
+            .publish(actor, DomainEvent::TaskCreated { task: task.clone() });
+
+        if task.rank.len() > rank::MAX_RANK_LEN {
+            let rank = self.rank_for(
+                actor,
+                &task.project_id,
+                Some(&task.id),
+                &TaskMove::default(),
+            )?;
+            let mut moved = task.clone();
+            moved.move_to(rank);
+            return self.save(actor, task, moved);
+        }
+        Ok(task)
     }
 
-    pub async fn get_task(&self, id: Uuid) -> Result<Task, ApiError> {
-        self.task_repository
-            .find_by_id(&id)
-            .map_err(|e| ApiError::repository_error(&e))?
-            .ok_or_else(|| ApiError::not_found("Task"))
+    pub async fn get_task(&self, actor: &Principal, id: Uuid) -> Result<Task, ApiError> {
+        let task = self.find_task(actor, &id)?;
+        self.access
+            .require(actor, &task.project_id, ProjectRole::Viewer)?;
+        Ok(task)
     }
 
-    pub async fn list_tasks(&self) -> Result<Vec<Task>, ApiError> {
-        self.task_repository
-            .find_all()
-            .map_err(|e| ApiError::repository_error(&e))
+    pub async fn list_tasks(&self, actor: &Principal) -> Result<Vec<Task>, ApiError> {
+        let visibility = self.access.visible_projects(actor)?;
+
+        Ok(self
+            .task_repository
+            .find_all(&actor.tenant_id)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .into_iter()
+            .filter(|task| visibility.allows(&task.project_id))
+            .collect())
     }
 
-    pub async fn list_tasks_by_project(&self, project_id: Uuid) -> Result<Vec<Task>, ApiError> {
+    pub async fn list_tasks_by_project(
+        &self,
+        actor: &Principal,
+        project_id: Uuid,
+    ) -> Result<Vec<Task>, ApiError> {
         // Verify project exists
         self.project_repository
-            .find_by_id(&project_id)
+            .find_by_id(&actor.tenant_id, &project_id)
             .map_err(|e| ApiError::repository_error(&e))?
             .ok_or_else(|| ApiError::not_found("Project"))?;
+        self.access
+            .require(actor, &project_id, ProjectRole::Viewer)?;
 
-        self.task_repository
-            .find_by_project_id(&project_id)
-            .map_err(|e| ApiError::repository_error(&e))
+        self.ordered_tasks(&actor.tenant_id, &project_id)
+    }
+
+    /// Live tasks of several projects at once, each list in rank order, read in a single
+    /// repository scan. Projects the actor cannot see, or that have no tasks, are left out.
+    pub async fn list_tasks_by_projects(
+        &self,
+        actor: &Principal,
+        project_ids: &[Uuid],
+    ) -> Result<HashMap<Uuid, Vec<Task>>, ApiError> {
+        let visibility = self.access.visible_projects(actor)?;
+        let visible: HashSet<Uuid> = project_ids
+            .iter()
+            .filter(|id| visibility.allows(id))
+            .copied()
+            .collect();
+
+        let mut grouped: HashMap<Uuid, Vec<Task>> = HashMap::new();
+        for task in self
+            .task_repository
+            .find_by_project_ids(&actor.tenant_id, &visible)
+            .map_err(|e| ApiError::repository_error(&e))?
+        {
+            grouped.entry(task.project_id).or_default().push(task);
+        }
+        for tasks in grouped.values_mut() {
+            sort_by_rank(tasks);
+        }
+        Ok(grouped)
+    }
+
+    /// A project with its live tasks in rank order, for export.
+    pub async fn export_tasks(
+        &self,
+        actor: &Principal,
+        project_id: Uuid,
+    ) -> Result<(Project, Vec<Task>), ApiError> {
+        let project = self
+            .project_repository
+            .find_by_id(&actor.tenant_id, &project_id)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .ok_or_else(|| ApiError::not_found("Project"))?;
+        self.access
+            .require(actor, &project_id, ProjectRole::Viewer)?;
+
+        let tasks = self.ordered_tasks(&actor.tenant_id, &project_id)?;
+        Ok((project, tasks))
+    }
+
+    /// Creates a task from each row of a CSV upload, reading `body` a chunk at a time so rows
+    /// are handled as they arrive. The first row is the header (see [`TaskCsvHeader`]). A
+    /// failing row is reported with its line and does not stop the rest; a dry run only
+    /// validates the rows, so quotas and WIP limits are not checked. Imported tasks go to
+    /// the end of the project in file order.
+    pub async fn import_tasks<S, B, E>(
+        &self,
+        actor: &Principal,
+        project_id: Uuid,
+        mut body: S,
+        dry_run: bool,
+    ) -> Result<TaskImportReport, ApiError>
+    where
+        S: Stream<Item = Result<B, E>> + Unpin,
+        B: AsRef<[u8]>,
+        E: std::fmt::Display,
+    {
+        let project = self
+            .project_repository
+            .find_by_id(&actor.tenant_id, &project_id)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .ok_or_else(|| ApiError::not_found("Project"))?;
+        self.access
+            .require(actor, &project_id, ProjectRole::Editor)?;
+        ensure_active(&project)?;
+
+        let tasks = self.ordered_tasks(&actor.tenant_id, &project_id)?;
+        let mut column_counts = HashMap::new();
+        for column_id in tasks.iter().filter_map(|task| task.column_id) {
+            *column_counts.entry(column_id).or_default() += 1;
+        }
+        let mut batch = ImportBatch {
+            header: None,
+            last_rank: tasks
+                .last()
+                .map(|task| task.rank.clone())
+                .filter(|rank| !rank.is_empty()),
+            column_counts,
+            pending: Vec::new(),
+        };
+
+        let mut reader = CsvReader::new();
+        let mut report = TaskImportReport {
+            dry_run,
+            ..TaskImportReport::default()
+        };
+        while let Some(chunk) = body.next().await {
+            let chunk = chunk
+                .map_err(|e| ApiError::bad_request(&format!("Failed to read the upload: {}", e)))?;
+            for row in reader.feed(chunk.as_ref()) {
+                self.import_row(actor, &project, &mut batch, row, &mut report)?;
+            }
+            self.flush_import(actor, &mut batch, &mut report)?;
+        }
+        if let Some(row) = reader.finish() {
+            self.import_row(actor, &project, &mut batch, row, &mut report)?;
+        }
+        self.flush_import(actor, &mut batch, &mut report)?;
+
+        if batch.header.is_none() {
+            return Err(ApiError::validation_error("The CSV file has no header row"));
+        }
+        Ok(report)
+    }
+
+    /// Reads the header from the first row, then stages (or on a dry run validates) a task
+    /// for each later one.
+    fn import_row(
+        &self,
+        actor: &Principal,
+        project: &Project,
+        batch: &mut ImportBatch,
+        row: CsvRow,
+        report: &mut TaskImportReport,
+    ) -> Result<(), ApiError> {
+        let Some(mapping) = batch.header.as_ref() else {
+            let mapping = row
+                .fields
+                .and_then(|fields| TaskCsvHeader::parse(&fields))
+                .map_err(|e| ApiError::validation_error(&format!("Line {}: {}", row.line, e)))?;
+            report.ignored_columns = mapping.ignored.clone();
+            batch.header = Some(mapping);
+            return Ok(());
+        };
+
+        report.rows += 1;
+        let create_data = row
+            .fields
+            .and_then(|fields| mapping.task_create(project, &fields))
+            .map_err(|e| ApiError::validation_error(&e));
+        if report.dry_run {
+            let result = create_data.and_then(|create_data| validate_create(&create_data));
+            report_row(report, row.line, result);
+            return Ok(());
+        }
+
+        let staged = match create_data {
+            Ok(create_data) => self.stage_import(actor, project, batch, create_data, report)?,
+            Err(e) => Err(e),
+        };
+        batch.pending.push((row.line, staged));
+        Ok(())
     }
 
-    pub async fn update_task(&self, id: Uuid, update_data: TaskUpdate) -> Result<Task, ApiError> {
+    /// Builds the task for an imported row as `create_task` would, at the end of the
+    /// project. The outer error fails the import; the inner one only the row.
+    fn stage_import(
+        &self,
+        actor: &Principal,
+        project: &Project,
+        batch: &mut ImportBatch,
+        create_data: TaskCreate,
+        report: &mut TaskImportReport,
+    ) -> Result<Result<Task, ApiError>, ApiError> {
+        if let Err(e) = validate_create(&create_data) {
+            return Ok(Err(e));
+        }
+
+        let mut task = Task::new(
+            actor.tenant_id.clone(),
+            project.id,
+            create_data.title.trim().to_string(),
+            create_data.description,
+        );
+        task.due_at = create_data.due_at;
+        task.column_id = create_data
+            .column_id
+            .or_else(|| project.columns.first().map(|column| column.id));
+        let column = match board_column(project, &mut task, None, create_data.column_id, false) {
+            Ok(column) => column,
+            Err(e) => return Ok(Err(e)),
+        };
+        if let Some(column) = column {
+            let count = batch.column_counts.entry(column.id).or_default();
+            if let Err(e) = check_wip_limit(column, *count) {
+                return Ok(Err(e));
+            }
+            *count += 1;
+        }
+
+        let rank = match batch.last_rank.as_deref() {
+            Some(last) => rank::after(last),
+            None => Some(rank::between(None, None)),
+        };
+        task.rank = match rank {
+            Some(rank) => rank,
+            None => {
+                // Out of keys: store what is staged, then rebalance the project
+                self.flush_import(actor, batch, report)?;
+                self.rank_for(actor, &project.id, None, &TaskMove::default())?
+            }
+        };
+        batch.last_rank = Some(task.rank.clone());
+        Ok(Ok(task))
+    }
+
+    /// Stores the staged tasks up to the tenant's quota and reports the pending rows in
+    /// file order.
+    fn flush_import(
+        &self,
+        actor: &Principal,
+        batch: &mut ImportBatch,
+        report: &mut TaskImportReport,
+    ) -> Result<(), ApiError> {
+        let pending = std::mem::take(&mut batch.pending);
+        let staged: Vec<Task> = pending
+            .iter()
+            .filter_map(|(_, staged)| staged.as_ref().ok().cloned())
+            .collect();
+        let max_tasks = self.quotas.quota_for(&actor.tenant_id).max_tasks;
+        // The created tasks are the first of those staged, in order
+        let mut created = self
+            .task_repository
+            .create_all(staged, max_tasks)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .into_iter();
+
+        for (line, staged) in pending {
+            let result = staged.and_then(|staged| {
+                let Some(task) = created.next() else {
+                    if let Some(count) = staged
+                        .column_id
+                        .and_then(|id| batch.column_counts.get_mut(&id))
+                    {
+                        *count -= 1;
+                    }
+                    // Staged tasks are only held back by a quota
+                    return Err(task_quota_reached(max_tasks.unwrap_or_default()));
+                };
+
+                self.audit.record(
+                    actor,
+                    AuditAction::Create,
+                    EntityType::Task,
+                    task.id,
+                    None,
+                    Some(&task),
+                );
+                self.events
+                    .publish(actor, DomainEvent::TaskCreated { task });
+                Ok(())
+            });
+            report_row(report, line, result);
+        }
+        Ok(())
+    }
+
+    /// Moves a task next to one or two neighbours in its project.
+    pub async fn move_task(
+        &self,
+        actor: &Principal,
+        id: Uuid,
+        placement: TaskMove,
+    ) -> Result<Task, ApiError> {
+        if placement.before.is_none() && placement.after.is_none() {
+            return Err(ApiError::validation_error(
+                "Give the task to move before or after",
+            ));
+        }
+        if placement.before == Some(id) || placement.after == Some(id) {
+            return Err(ApiError::validation_error(
+                "A task cannot be moved next to itself",
+            ));
+        }
+
+        let before = self.find_task(actor, &id)?;
+        self.access
+            .require(actor, &before.project_id, ProjectRole::Editor)?;
+        self.ensure_project_active(actor, &before.project_id)?;
+
+        let rank = self.rank_for(actor, &before.project_id, Some(&id), &placement)?;
+        let mut task = before.clone();
+        task.move_to(rank);
+
+        self.save(actor, before, task)
+    }
+
+    pub async fn update_task(
+        &self,
+        actor: &Principal,
+        id: Uuid,
+        update_data: TaskUpdate,
+    ) -> Result<Task, ApiError> {
         // Validation
         if let Some(ref title) = update_data.title {
             if title.trim().is_empty() {
@@ -105,38 +487,405 @@ impl TaskService {
         }
 
         // Get existing task
-        let mut task = self
-            .task_repository
-            .find_by_id(&id)
-            .map_err(|e| ApiError::repository_error(&e))?
-            .ok_or_else(|| ApiError::not_found("Task"))?;
+        let before = self.find_task(actor, &id)?;
+        self.access
+            .require(actor, &before.project_id, ProjectRole::Editor)?;
+        let project = self.find_active_project(actor, &before.project_id)?;
 
         // Apply updates
+        let requested = update_data.column_id;
+        let done_changed = update_data.done.is_some();
+        let mut task = before.clone();
         task.update(update_data);
+        let entering = board_column(
+            &project,
+            &mut task,
+            before.column_id,
+            requested,
+            done_changed,
+        )?;
 
-        // Save updated task
-        self.task_repository
-            .update(&id, task)
-            .map_err(|e| ApiError::repository_error(&e))?
-            .ok_or_else(|| ApiError::not_found("Task"))
+        self.save_into(actor, before, task, entering)
+    }
+
+    pub async fn list_task_revisions(
+        &self,
+        actor: &Principal,
+        id: Uuid,
+    ) -> Result<Vec<Revision>, ApiError> {
+        self.get_task(actor, id).await?;
+        self.audit
+            .revisions(&actor.tenant_id, EntityType::Task, &id)
+    }
+
+    pub async fn get_task_revision(
+        &self,
+        actor: &Principal,
+        id: Uuid,
+        number: u32,
+    ) -> Result<Revision, ApiError> {
+        self.get_task(actor, id).await?;
+        self.audit
+            .revision(&actor.tenant_id, EntityType::Task, &id, number)
+    }
+
+    pub async fn diff_task_revisions(
+        &self,
+        actor: &Principal,
+        id: Uuid,
+        from: u32,
+        to: Option<u32>,
+    ) -> Result<RevisionDiff, ApiError> {
+        self.get_task(actor, id).await?;
+        self.audit
+            .diff_revisions(&actor.tenant_id, EntityType::Task, &id, from, to)
+    }
+
+    /// Writes a new revision with the title, description and status of revision `number`; on a
+    /// board the status decides whether the task goes to or leaves the done column.
+    pub async fn revert_task(
+        &self,
+        actor: &Principal,
+        id: Uuid,
+        number: u32,
+    ) -> Result<Task, ApiError> {
+        let before = self.find_task(actor, &id)?;
+        self.access
+            .require(actor, &before.project_id, ProjectRole::Editor)?;
+        let project = self.find_active_project(actor, &before.project_id)?;
+
+        let revision = self
+            .audit
+            .revision(&actor.tenant_id, EntityType::Task, &id, number)?;
+        let revision: Task = serde_json::from_value(revision.data)
+            .map_err(|e| ApiError::repository_error(&e.to_string()))?;
+
+        let mut task = before.clone();
+        task.revert(&revision);
+        let entering = board_column(&project, &mut task, before.column_id, None, true)?;
+
+        self.save_into(actor, before, task, entering)
     }
 
-    pub async fn delete_task(&self, id: Uuid) -> Result<(), ApiError> {
+    pub async fn delete_task(&self, actor: &Principal, id: Uuid) -> Result<(), ApiError> {
+        let task = self.find_task(actor, &id)?;
+        self.access
+            .require(actor, &task.project_id, ProjectRole::Editor)?;
+        self.ensure_project_active(actor, &task.project_id)?;
+
         let deleted = self
             .task_repository
-            .delete(&id)
+            .delete(&actor.tenant_id, &id, Utc::now())
             .map_err(|e| ApiError::repository_error(&e))?;
 
-        if deleted {
-            Ok(())
-        } else {
-            Err(ApiError::not_found("Task"))
+        if !deleted {
+            return Err(ApiError::not_found("Task"));
         }
+
+        self.audit.record(
+            actor,
+            AuditAction::Delete,
+            EntityType::Task,
+            id,
+            Some(&task),
+            None,
+        );
+        self.events
+            .publish(actor, DomainEvent::TaskDeleted { task });
+
+        Ok(())
     }
 
-    pub async fn delete_tasks_by_project(&self, project_id: Uuid) -> Result<usize, ApiError> {
+    pub async fn delete_tasks_by_project(
+        &self,
+        actor: &Principal,
+        project_id: Uuid,
+    ) -> Result<usize, ApiError> {
+        self.access
+            .require(actor, &project_id, ProjectRole::Owner)?;
+        self.ensure_project_active(actor, &project_id)?;
+
+        let tasks = self
+            .task_repository
+            .find_by_project_id(&actor.tenant_id, &project_id)
+            .map_err(|e| ApiError::repository_error(&e))?;
+
+        let count = self
+            .task_repository
+            .delete_by_project_id(&actor.tenant_id, &project_id, Utc::now())
+            .map_err(|e| ApiError::repository_error(&e))?;
+
+        for task in tasks {
+            self.audit.record(
+                actor,
+                AuditAction::Delete,
+                EntityType::Task,
+                task.id,
+                Some(&task),
+                None,
+            );
+            self.events
+                .publish(actor, DomainEvent::TaskDeleted { task });
+        }
+
+        Ok(count)
+    }
+
+    /// Stores an edited task, then audits and announces the change.
+    fn save(&self, actor: &Principal, before: Task, task: Task) -> Result<Task, ApiError> {
+        self.save_into(actor, before, task, None)
+    }
+
+    /// Like [`save`](Self::save) for a task that may be `entering` a column, whose WIP limit
+    /// is then checked under the same lock as the write.
+    fn save_into(
+        &self,
+        actor: &Principal,
+        before: Task,
+        task: Task,
+        entering: Option<&Column>,
+    ) -> Result<Task, ApiError> {
+        let id = task.id;
+        let stored = match entering.filter(|column| column.wip_limit.is_some()) {
+            Some(column) => {
+                self.task_repository
+                    .update_checked(&actor.tenant_id, &id, task, |count| {
+                        check_wip_limit(column, count)
+                    })?
+            }
+            None => self
+                .task_repository
+                .update(&actor.tenant_id, &id, task)
+                .map_err(|e| ApiError::repository_error(&e))?,
+        };
+        let task = stored.ok_or_else(|| ApiError::not_found("Task"))?;
+
+        self.audit.record(
+            actor,
+            AuditAction::Update,
+            EntityType::Task,
+            id,
+            Some(&before),
+            Some(&task),
+        );
+        self.events.publish(
+            actor,
+            DomainEvent::TaskUpdated {
+                before,
+                after: task.clone(),
+            },
+        );
+
+        Ok(task)
+    }
+
+    /// A project's live tasks in rank order.
+    fn ordered_tasks(&self, tenant_id: &str, project_id: &Uuid) -> Result<Vec<Task>, ApiError> {
+        let mut tasks = self
+            .task_repository
+            .find_by_project_id(tenant_id, project_id)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        sort_by_rank(&mut tasks);
+        Ok(tasks)
+    }
+
+    /// Rank key for `moving` (or a new task) at `placement` among the project's other tasks;
+    /// no neighbours means the end of the list. When no usable key fits between the
+    /// neighbours, the other tasks are first given evenly spaced keys, saved like any other
+    /// move so they are audited and published.
+    fn rank_for(
+        &self,
+        actor: &Principal,
+        project_id: &Uuid,
+        moving: Option<&Uuid>,
+        placement: &TaskMove,
+    ) -> Result<String, ApiError> {
+        let siblings: Vec<Task> = self
+            .ordered_tasks(&actor.tenant_id, project_id)?
+            .into_iter()
+            .filter(|task| Some(&task.id) != moving)
+            .collect();
+        let position = |id: &Uuid| {
+            siblings
+                .iter()
+                .position(|task| task.id == *id)
+                .ok_or_else(|| {
+                    ApiError::validation_error(&format!("Task {} is not in this project", id))
+                })
+        };
+
+        let at = match (placement.after, placement.before) {
+            (Some(after), Some(before)) => {
+                let at = position(&after)? + 1;
+                if at != position(&before)? {
+                    return Err(ApiError::validation_error(
+                        "The tasks to move between must be adjacent",
+                    ));
+                }
+                at
+            }
+            (Some(after), None) => position(&after)? + 1,
+            (None, Some(before)) => position(&before)?,
+            (None, None) => siblings.len(),
+        };
+
+        let lower = at
+            .checked_sub(1)
+            .map(|i| siblings[i].rank.as_str())
+            .filter(|rank| !rank.is_empty());
+        let upper = siblings.get(at).map(|task| task.rank.as_str());
+        if upper.is_none_or(|upper| lower.unwrap_or_default() < upper) {
+            let rank = rank::between(lower, upper);
+            if rank.len() <= rank::MAX_RANK_LEN {
+                return Ok(rank);
+            }
+        }
+
+        // Rebalance, leaving slot `at` free for the placed task
+        let mut ranks = rank::spread(siblings.len() + 1);
+        let rank = ranks.remove(at);
+        for (before, new_rank) in siblings.into_iter().zip(ranks) {
+            if before.rank != new_rank {
+                let mut task = before.clone();
+                task.move_to(new_rank);
+                self.save(actor, before, task)?;
+            }
+        }
+        Ok(rank)
+    }
+
+    fn find_active_project(
+        &self,
+        actor: &Principal,
+        project_id: &Uuid,
+    ) -> Result<Project, ApiError> {
+        let project = self
+            .project_repository
+            .find_by_id(&actor.tenant_id, project_id)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .ok_or_else(|| ApiError::not_found("Project"))?;
+        ensure_active(&project)?;
+        Ok(project)
+    }
+
+    fn ensure_project_active(&self, actor: &Principal, project_id: &Uuid) -> Result<(), ApiError> {
+        let project = self
+            .project_repository
+            .find_by_id(&actor.tenant_id, project_id)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        project.as_ref().map_or(Ok(()), ensure_active)
+    }
+
+    fn find_task(&self, actor: &Principal, id: &Uuid) -> Result<Task, ApiError> {
         self.task_repository
-            .delete_by_project_id(&project_id)
-            .map_err(|e| ApiError::repository_error(&e))
+            .find_by_id(&actor.tenant_id, id)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .ok_or_else(|| ApiError::not_found("Task"))
+    }
+}
+
+fn validate_create(create_data: &TaskCreate) -> Result<(), ApiError> {
+    if create_data.title.trim().is_empty() {
+        return Err(ApiError::validation_error("Task title cannot be empty"));
+    }
+
+    if create_data.title.len() > 200 {
+        return Err(ApiError::validation_error(
+            "Task title cannot exceed 200 characters",
+        ));
+    }
+
+    if let Some(ref description) = create_data.description {
+        if description.len() > 1000 {
+            return Err(ApiError::validation_error(
+                "Task description cannot exceed 1000 characters",
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Keeps a task's column and `done` flag in step with its project's board, returning the
+/// column it enters, if any. An explicitly `requested` column wins; otherwise a change of
+/// `done` moves the task into or out of the done column.
+fn board_column<'a>(
+    project: &'a Project,
+    task: &mut Task,
+    previous: Option<Uuid>,
+    requested: Option<Uuid>,
+    done_changed: bool,
+) -> Result<Option<&'a Column>, ApiError> {
+    if let Some(id) = requested {
+        if project.column(&id).is_none() {
+            return Err(ApiError::validation_error(&format!(
+                "Column {} is not on this project's board",
+                id
+            )));
+        }
+    } else {
+        // Forget columns that have since been removed from the board
+        if task
+            .column_id
+            .is_some_and(|id| project.column(&id).is_none())
+        {
+            task.column_id = None;
+        }
+        if let Some(done_column) = project.done_column().filter(|_| done_changed) {
+            let in_done_column = task.column_id == Some(done_column.id);
+            if task.done && !in_done_column {
+                task.column_id = Some(done_column.id);
+            } else if !task.done && in_done_column {
+                task.column_id = project
+                    .columns
+                    .iter()
+                    .find(|column| !column.done)
+                    .map(|column| column.id);
+            }
+        }
+    }
+
+    let column = task.column_id.and_then(|id| project.column(&id));
+    if let (Some(column), Some(_)) = (column, project.done_column()) {
+        task.done = column.done;
     }
+
+    Ok(column.filter(|_| task.column_id != previous))
+}
+
+/// Fails when a column already holding `count` other tasks is at its WIP limit.
+fn check_wip_limit(column: &Column, count: usize) -> Result<(), ApiError> {
+    match column.wip_limit {
+        Some(wip_limit) if count >= wip_limit => Err(ApiError::conflict(&format!(
+            "Column '{}' is at its WIP limit of {}",
+            column.name, wip_limit
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn task_quota_reached(max_tasks: usize) -> ApiError {
+    ApiError::conflict(&format!("Tenant task quota of {} reached", max_tasks))
+}
+
+/// Counts an import row, listing its error while the report has room.
+fn report_row(report: &mut TaskImportReport, line: usize, result: Result<(), ApiError>) {
+    match result {
+        Ok(()) => report.imported += 1,
+        Err(e) => {
+            report.failed += 1;
+            if report.errors.len() < MAX_IMPORT_ERRORS {
+                let response = e.to_error_response();
+                report.errors.push(TaskImportError {
+                    line,
+                    code: response.code,
+                    message: response.message,
+                });
+            }
+        }
+    }
+}
+
+/// Project order: by rank, with creation time and id settling ties.
+fn sort_by_rank(tasks: &mut [Task]) {
+    tasks.sort_by(|a, b| (&a.rank, a.created_at, a.id).cmp(&(&b.rank, b.created_at, b.id)));
 }
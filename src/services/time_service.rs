@@ -0,0 +1,333 @@
+use crate::models::{
+    Principal, ProjectRole, Task, TimeEntry, TimeEntryCreate, TimeEntryFilter, TimeReport,
+    TimeReportGroup, TimeReportQuery, TimeReportRow, TimerStart,
+};
+use crate::repositories::{
+    MembershipRepository, ProjectRepository, TaskRepository, TimeEntryRepository,
+};
+use crate::services::access::ensure_active;
+use crate::services::access::AccessPolicy;
+use crate::views::ApiError;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Longest single time entry that can be booked by hand.
+const MAX_ENTRY_HOURS: i64 = 24;
+
+/// Books time on tasks, runs per-user timers and totals hours for billing.
+#[derive(Debug, Clone)]
+pub struct TimeService {
+    repository: Arc<TimeEntryRepository>,
+    task_repository: Arc<TaskRepository>,
+    project_repository: Arc<ProjectRepository>,
+    access: AccessPolicy,
+}
+
+impl TimeService {
+    pub fn new(
+        repository: Arc<TimeEntryRepository>,
+        task_repository: Arc<TaskRepository>,
+        project_repository: Arc<ProjectRepository>,
+        membership_repository: Arc<MembershipRepository>,
+    ) -> Self {
+        Self {
+            repository,
+            task_repository,
+            project_repository,
+            access: AccessPolicy::new(membership_repository),
+        }
+    }
+
+    pub async fn create_entry(
+        &self,
+        actor: &Principal,
+        create_data: TimeEntryCreate,
+    ) -> Result<TimeEntry, ApiError> {
+        validate_note(create_data.note.as_deref())?;
+
+        let now = Utc::now();
+        let (started_at, ended_at) = match (
+            create_data.started_at,
+            create_data.ended_at,
+            create_data.duration_minutes,
+        ) {
+            (Some(started_at), Some(ended_at), None) => (started_at, ended_at),
+            (Some(started_at), None, Some(minutes)) => {
+                (started_at, started_at + Duration::minutes(minutes.into()))
+            }
+            (None, None, Some(minutes)) => (now - Duration::minutes(minutes.into()), now),
+            _ => {
+                return Err(ApiError::validation_error(
+                    "Give started_at with either ended_at or duration_minutes, or only duration_minutes",
+                ));
+            }
+        };
+        if ended_at <= started_at {
+            return Err(ApiError::validation_error(
+                "A time entry must end after it starts",
+            ));
+        }
+        if ended_at > now {
+            return Err(ApiError::validation_error(
+                "A time entry cannot end in the future",
+            ));
+        }
+        if ended_at - started_at > Duration::hours(MAX_ENTRY_HOURS) {
+            return Err(ApiError::validation_error(&format!(
+                "A time entry cannot be longer than {} hours",
+                MAX_ENTRY_HOURS
+            )));
+        }
+
+        let task = self.find_bookable_task(actor, &create_data.task_id)?;
+
+        let entry = TimeEntry::new(
+            actor.tenant_id.clone(),
+            task.project_id,
+            task.id,
+            actor.subject.clone(),
+            started_at,
+            Some(ended_at),
+            create_data.note,
+        );
+        self.repository
+            .create(entry)
+            .map_err(|e| ApiError::repository_error(&e))
+    }
+
+    /// Starts a timer on a task; each user can have only one timer running at a time.
+    pub async fn start_timer(
+        &self,
+        actor: &Principal,
+        start_data: TimerStart,
+    ) -> Result<TimeEntry, ApiError> {
+        validate_note(start_data.note.as_deref())?;
+        let task = self.find_bookable_task(actor, &start_data.task_id)?;
+
+        let timer = TimeEntry::new(
+            actor.tenant_id.clone(),
+            task.project_id,
+            task.id,
+            actor.subject.clone(),
+            Utc::now(),
+            None,
+            start_data.note,
+        );
+        self.repository
+            .start(timer)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .ok_or_else(|| ApiError::conflict("A timer is already running; stop it first"))
+    }
+
+    /// Stops the caller's running timer.
+    pub async fn stop_timer(&self, actor: &Principal) -> Result<TimeEntry, ApiError> {
+        let mut timer = self
+            .current_timer(actor)
+            .await?
+            .ok_or_else(|| ApiError::not_found("Running timer"))?;
+        timer.stop(Utc::now());
+
+        let id = timer.id;
+        self.repository
+            .update(&actor.tenant_id, &id, timer)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .ok_or_else(|| ApiError::not_found("Running timer"))
+    }
+
+    pub async fn current_timer(&self, actor: &Principal) -> Result<Option<TimeEntry>, ApiError> {
+        self.repository
+            .find_running(&actor.tenant_id, &actor.subject)
+            .map_err(|e| ApiError::repository_error(&e))
+    }
+
+    pub async fn list_entries(
+        &self,
+        actor: &Principal,
+        filter: &TimeEntryFilter,
+    ) -> Result<Vec<TimeEntry>, ApiError> {
+        validate_range(filter.from, filter.to)?;
+        self.visible_entries(actor, filter)
+    }
+
+    /// Deletes an entry; users can delete their own, project owners anyone's.
+    pub async fn delete_entry(&self, actor: &Principal, id: Uuid) -> Result<(), ApiError> {
+        let entry = self
+            .repository
+            .find_by_id(&actor.tenant_id, &id)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .ok_or_else(|| ApiError::not_found("Time entry"))?;
+        if entry.user == actor.subject {
+            self.access
+                .require(actor, &entry.project_id, ProjectRole::Viewer)?;
+        } else {
+            self.access
+                .require(actor, &entry.project_id, ProjectRole::Owner)?;
+        }
+
+        let deleted = self
+            .repository
+            .delete(&actor.tenant_id, &id)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        if !deleted {
+            return Err(ApiError::not_found("Time entry"));
+        }
+        Ok(())
+    }
+
+    /// Hours per project, task or user in the projects the caller can see. Only the part of
+    /// each entry inside the period counts, and running timers count up to now.
+    pub async fn report(
+        &self,
+        actor: &Principal,
+        query: &TimeReportQuery,
+    ) -> Result<TimeReport, ApiError> {
+        validate_range(query.from, query.to)?;
+        let entries = self.visible_entries(
+            actor,
+            &TimeEntryFilter {
+                project_id: query.project_id,
+                from: query.from,
+                to: query.to,
+                ..TimeEntryFilter::default()
+            },
+        )?;
+
+        let now = Utc::now();
+        let mut totals: HashMap<String, (Duration, usize)> = HashMap::new();
+        let mut total = Duration::zero();
+        for entry in &entries {
+            let spent = entry.duration_within(query.from, query.to, now);
+            let key = match query.group_by {
+                TimeReportGroup::Project => entry.project_id.to_string(),
+                TimeReportGroup::Task => entry.task_id.to_string(),
+                TimeReportGroup::User => entry.user.clone(),
+            };
+            let slot = totals.entry(key).or_insert((Duration::zero(), 0));
+            slot.0 += spent;
+            slot.1 += 1;
+            total += spent;
+        }
+
+        let mut rows = totals
+            .into_iter()
+            .map(|(key, (spent, entries))| {
+                Ok(TimeReportRow {
+                    name: self.row_name(actor, query.group_by, &key)?,
+                    key,
+                    hours: hours(spent),
+                    entries,
+                })
+            })
+            .collect::<Result<Vec<_>, ApiError>>()?;
+        rows.sort_by(|a, b| {
+            b.hours
+                .total_cmp(&a.hours)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        Ok(TimeReport {
+            group_by: query.group_by,
+            from: query.from,
+            to: query.to,
+            total_hours: hours(total),
+            rows,
+        })
+    }
+
+    fn visible_entries(
+        &self,
+        actor: &Principal,
+        filter: &TimeEntryFilter,
+    ) -> Result<Vec<TimeEntry>, ApiError> {
+        let visibility = self.access.visible_projects(actor)?;
+
+        Ok(self
+            .repository
+            .find(&actor.tenant_id, filter)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .into_iter()
+            .filter(|entry| visibility.allows(&entry.project_id))
+            .collect())
+    }
+
+    /// A live task in an active project the caller can edit.
+    fn find_bookable_task(&self, actor: &Principal, task_id: &Uuid) -> Result<Task, ApiError> {
+        let task = self
+            .task_repository
+            .find_by_id(&actor.tenant_id, task_id)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .ok_or_else(|| ApiError::not_found("Task"))?;
+        self.access
+            .require(actor, &task.project_id, ProjectRole::Editor)?;
+        let project = self
+            .project_repository
+            .find_by_id(&actor.tenant_id, &task.project_id)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        if let Some(project) = &project {
+            ensure_active(project)?;
+        }
+        Ok(task)
+    }
+
+    /// Display name for a report row, looking into the trash for deleted projects and tasks.
+    fn row_name(
+        &self,
+        actor: &Principal,
+        group_by: TimeReportGroup,
+        key: &str,
+    ) -> Result<String, ApiError> {
+        let Ok(id) = key.parse::<Uuid>() else {
+            return Ok(key.to_string());
+        };
+        let tenant_id = &actor.tenant_id;
+        let name = match group_by {
+            TimeReportGroup::Project => self
+                .project_repository
+                .find_by_id(tenant_id, &id)
+                .and_then(|project| match project {
+                    Some(project) => Ok(Some(project)),
+                    None => self.project_repository.find_deleted_by_id(tenant_id, &id),
+                })
+                .map_err(|e| ApiError::repository_error(&e))?
+                .map(|project| project.name),
+            TimeReportGroup::Task => self
+                .task_repository
+                .find_by_id(tenant_id, &id)
+                .and_then(|task| match task {
+                    Some(task) => Ok(Some(task)),
+                    None => self.task_repository.find_deleted_by_id(tenant_id, &id),
+                })
+                .map_err(|e| ApiError::repository_error(&e))?
+                .map(|task| task.title),
+            TimeReportGroup::User => None,
+        };
+        Ok(name.unwrap_or_else(|| key.to_string()))
+    }
+}
+
+fn validate_note(note: Option<&str>) -> Result<(), ApiError> {
+    if note.is_some_and(|note| note.len() > 1000) {
+        return Err(ApiError::validation_error(
+            "Time entry note cannot exceed 1000 characters",
+        ));
+    }
+    Ok(())
+}
+
+fn validate_range(from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Result<(), ApiError> {
+    if let (Some(from), Some(to)) = (from, to) {
+        if from > to {
+            return Err(ApiError::validation_error(
+                "Time range start must not be after its end",
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Hours rounded to two decimals.
+fn hours(spent: Duration) -> f64 {
+    (spent.num_seconds() as f64 / 36.0).round() / 100.0
+}
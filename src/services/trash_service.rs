@@ -0,0 +1,409 @@
+use crate::config::Config;
+use crate::models::{
+    AuditAction, DomainEvent, EntityType, Principal, Project, ProjectRole, Task, Trash,
+};
+use crate::repositories::{
+    MembershipRepository, ProjectRepository, RecurringTaskRepository, ReminderRepository,
+    TaskRepository, TimeEntryRepository,
+};
+use crate::services::access::ensure_active;
+use crate::services::access::AccessPolicy;
+use crate::services::audit_service::AuditService;
+use crate::services::event_bus::EventBus;
+use crate::services::quota::QuotaPolicy;
+use crate::views::ApiError;
+use chrono::{DateTime, Duration, Utc};
+use ntex::time::{sleep, Millis};
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Days a deleted project or task stays restorable unless `TRASH_RETENTION_DAYS` is set.
+pub const DEFAULT_RETENTION_DAYS: u32 = 30;
+
+/// How often the purge job looks for expired tombstones.
+const PURGE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Records that belong to a project or task and are purged along with it.
+#[derive(Debug, Clone)]
+pub struct TrashDependents {
+    pub memberships: Arc<MembershipRepository>,
+    pub time_entries: Arc<TimeEntryRepository>,
+    pub recurring_tasks: Arc<RecurringTaskRepository>,
+    pub reminders: Arc<ReminderRepository>,
+}
+
+/// Lists, restores and purges soft-deleted projects and tasks.
+#[derive(Debug, Clone)]
+pub struct TrashService {
+    project_repository: Arc<ProjectRepository>,
+    task_repository: Arc<TaskRepository>,
+    dependents: TrashDependents,
+    access: AccessPolicy,
+    quotas: QuotaPolicy,
+    audit: AuditService,
+    events: Arc<EventBus>,
+    retention: Duration,
+}
+
+impl TrashService {
+    pub fn new(
+        project_repository: Arc<ProjectRepository>,
+        task_repository: Arc<TaskRepository>,
+        dependents: TrashDependents,
+        quotas: QuotaPolicy,
+        audit: AuditService,
+        events: Arc<EventBus>,
+        retention: Duration,
+    ) -> Self {
+        Self {
+            project_repository,
+            task_repository,
+            access: AccessPolicy::new(dependents.memberships.clone()),
+            dependents,
+            quotas,
+            audit,
+            events,
+            retention,
+        }
+    }
+
+    pub fn retention_from_config(config: &Config) -> Duration {
+        Duration::days(
+            config
+                .trash_retention_days
+                .unwrap_or(DEFAULT_RETENTION_DAYS)
+                .into(),
+        )
+    }
+
+    /// Spawns the job that purges tombstones older than the retention period.
+    pub fn start(self: &Arc<Self>) {
+        let service = self.clone();
+        ntex::rt::spawn(async move {
+            loop {
+                sleep(Millis::from(PURGE_INTERVAL)).await;
+                match service.purge_expired(Utc::now()) {
+                    Ok(0) => {}
+                    Ok(count) => info!("Purged {} expired items from the trash", count),
+                    Err(e) => warn!("Trash purge failed: {}", e),
+                }
+            }
+        });
+    }
+
+    pub async fn list_trash(&self, actor: &Principal) -> Result<Trash, ApiError> {
+        let visibility = self.access.visible_projects(actor)?;
+
+        let mut projects: Vec<Project> = self
+            .project_repository
+            .find_deleted(&actor.tenant_id)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .into_iter()
+            .filter(|project| visibility.allows(&project.id))
+            .collect();
+        projects.sort_by_key(|project| Reverse(project.deleted_at));
+
+        let mut tasks: Vec<Task> = self
+            .task_repository
+            .find_deleted(&actor.tenant_id)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .into_iter()
+            .filter(|task| visibility.allows(&task.project_id))
+            .collect();
+        tasks.sort_by_key(|task| Reverse(task.deleted_at));
+
+        Ok(Trash { projects, tasks })
+    }
+
+    /// Restores a project together with the tasks that were deleted along with it.
+    pub async fn restore_project(&self, actor: &Principal, id: Uuid) -> Result<Project, ApiError> {
+        let trashed = self.find_deleted_project(actor, &id)?;
+        self.access.require(actor, &id, ProjectRole::Owner)?;
+
+        let Some(deleted_at) = trashed.deleted_at else {
+            return Err(ApiError::not_found("Project"));
+        };
+        let quota = self.quotas.quota_for(&actor.tenant_id);
+        let (project, tasks) = self
+            .task_repository
+            .restore_by_project_id(&actor.tenant_id, &id, deleted_at, quota.max_tasks, || {
+                self.project_repository
+                    .restore(&actor.tenant_id, &id, quota.max_projects)
+                    .map_err(|e| ApiError::repository_error(&e))?
+                    .ok_or_else(|| {
+                        let in_trash = self.find_deleted_project(actor, &id).is_ok();
+                        restore_refused("Project", quota.max_projects, in_trash)
+                    })
+            })?
+            .ok_or_else(|| restore_refused("Task", quota.max_tasks, true))?;
+
+        self.audit.record(
+            actor,
+            AuditAction::Restore,
+            EntityType::Project,
+            id,
+            Some(&trashed),
+            Some(&project),
+        );
+        for task in &tasks {
+            let before = Task {
+                deleted_at: trashed.deleted_at,
+                ..task.clone()
+            };
+            self.audit.record(
+                actor,
+                AuditAction::Restore,
+                EntityType::Task,
+                task.id,
+                Some(&before),
+                Some(task),
+            );
+        }
+        self.events.publish(
+            actor,
+            DomainEvent::ProjectRestored {
+                project: project.clone(),
+                restored_tasks: tasks,
+            },
+        );
+
+        Ok(project)
+    }
+
+    /// Restores a single task; its project must not be in the trash.
+    pub async fn restore_task(&self, actor: &Principal, id: Uuid) -> Result<Task, ApiError> {
+        let trashed = self.find_deleted_task(actor, &id)?;
+        self.access
+            .require(actor, &trashed.project_id, ProjectRole::Editor)?;
+
+        let project = self
+            .project_repository
+            .find_by_id(&actor.tenant_id, &trashed.project_id)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .ok_or_else(|| {
+                ApiError::conflict("The task's project is in the trash; restore the project first")
+            })?;
+        ensure_active(&project)?;
+
+        let max_tasks = self.quotas.quota_for(&actor.tenant_id).max_tasks;
+        let task = self
+            .task_repository
+            .restore(&actor.tenant_id, &id, max_tasks)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .ok_or_else(|| {
+                restore_refused(
+                    "Task",
+                    max_tasks,
+                    self.find_deleted_task(actor, &id).is_ok(),
+                )
+            })?;
+
+        self.audit.record(
+            actor,
+            AuditAction::Restore,
+            EntityType::Task,
+            id,
+            Some(&trashed),
+            Some(&task),
+        );
+        self.events
+            .publish(actor, DomainEvent::TaskRestored { task: task.clone() });
+
+        Ok(task)
+    }
+
+    /// Permanently removes a trashed project with its tasks and everything that belongs to
+    /// them.
+    pub async fn purge_project(&self, actor: &Principal, id: Uuid) -> Result<(), ApiError> {
+        let project = self.find_deleted_project(actor, &id)?;
+        self.access.require(actor, &id, ProjectRole::Owner)?;
+
+        let tasks: Vec<Task> = self
+            .task_repository
+            .find_deleted(&actor.tenant_id)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .into_iter()
+            .filter(|task| task.project_id == id)
+            .collect();
+        let members = self
+            .dependents
+            .memberships
+            .find_by_project_id(&id)
+            .map_err(|e| ApiError::repository_error(&e))?;
+
+        self.purge_project_data(&project)?;
+
+        self.audit.record(
+            actor,
+            AuditAction::Purge,
+            EntityType::Project,
+            id,
+            Some(&project),
+            None,
+        );
+        for task in &tasks {
+            self.audit.record(
+                actor,
+                AuditAction::Purge,
+                EntityType::Task,
+                task.id,
+                Some(task),
+                None,
+            );
+        }
+        for member in &members {
+            self.audit.record(
+                actor,
+                AuditAction::Delete,
+                EntityType::ProjectMember,
+                id,
+                Some(member),
+                None,
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Permanently removes a trashed task with its time entries and reminders.
+    pub async fn purge_task(&self, actor: &Principal, id: Uuid) -> Result<(), ApiError> {
+        let task = self.find_deleted_task(actor, &id)?;
+        self.access
+            .require(actor, &task.project_id, ProjectRole::Editor)?;
+
+        if !self.purge_task_data(&task)? {
+            return Err(ApiError::not_found("Task"));
+        }
+
+        self.audit.record(
+            actor,
+            AuditAction::Purge,
+            EntityType::Task,
+            id,
+            Some(&task),
+            None,
+        );
+
+        Ok(())
+    }
+
+    /// Purges everything, in every tenant, that was deleted more than the retention period
+    /// before `now`. Returns the number of projects and tasks removed.
+    pub fn purge_expired(&self, now: DateTime<Utc>) -> Result<usize, ApiError> {
+        let cutoff = now - self.retention;
+        let mut purged = 0;
+
+        let projects = self
+            .project_repository
+            .find_deleted_before(cutoff)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        for project in &projects {
+            purged += 1 + self.purge_project_data(project)?;
+        }
+
+        let tasks = self
+            .task_repository
+            .find_deleted_before(cutoff)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        for task in &tasks {
+            if self.purge_task_data(task)? {
+                purged += 1;
+            }
+        }
+
+        Ok(purged)
+    }
+
+    /// Removes a trashed project with its tasks, memberships, recurring tasks, time entries,
+    /// reminders and revisions; returns the task count.
+    fn purge_project_data(&self, project: &Project) -> Result<usize, ApiError> {
+        let task_ids: HashSet<Uuid> = self
+            .task_repository
+            .find_deleted(&project.tenant_id)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .into_iter()
+            .filter(|task| task.project_id == project.id)
+            .map(|task| task.id)
+            .collect();
+        for id in &task_ids {
+            self.audit.discard_revisions(EntityType::Task, id)?;
+        }
+        self.audit
+            .discard_revisions(EntityType::Project, &project.id)?;
+        self.dependents
+            .time_entries
+            .delete_by_project_id(&project.tenant_id, &project.id)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        self.dependents
+            .recurring_tasks
+            .delete_by_project_id(&project.tenant_id, &project.id)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        self.dependents
+            .reminders
+            .delete_by_task_ids(&task_ids)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        let tasks = self
+            .task_repository
+            .purge_by_project_id(&project.tenant_id, &project.id)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        self.dependents
+            .memberships
+            .delete_by_project_id(&project.id)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        self.project_repository
+            .purge(&project.tenant_id, &project.id)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        Ok(tasks)
+    }
+
+    /// Removes a trashed task with its time entries, reminders and revisions; returns
+    /// whether it was still there.
+    fn purge_task_data(&self, task: &Task) -> Result<bool, ApiError> {
+        let purged = self
+            .task_repository
+            .purge(&task.tenant_id, &task.id)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        if purged {
+            self.dependents
+                .time_entries
+                .delete_by_task_id(&task.tenant_id, &task.id)
+                .map_err(|e| ApiError::repository_error(&e))?;
+            self.dependents
+                .reminders
+                .delete_by_task_ids(&HashSet::from([task.id]))
+                .map_err(|e| ApiError::repository_error(&e))?;
+            self.audit.discard_revisions(EntityType::Task, &task.id)?;
+        }
+        Ok(purged)
+    }
+
+    fn find_deleted_project(&self, actor: &Principal, id: &Uuid) -> Result<Project, ApiError> {
+        self.project_repository
+            .find_deleted_by_id(&actor.tenant_id, id)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .ok_or_else(|| ApiError::not_found("Project"))
+    }
+
+    fn find_deleted_task(&self, actor: &Principal, id: &Uuid) -> Result<Task, ApiError> {
+        self.task_repository
+            .find_deleted_by_id(&actor.tenant_id, id)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .ok_or_else(|| ApiError::not_found("Task"))
+    }
+}
+
+/// The error for a restore the repository turned down: the tenant is at its `limit`, unless the
+/// project or task has left the trash in the meantime.
+fn restore_refused(entity: &str, limit: Option<usize>, in_trash: bool) -> ApiError {
+    match limit {
+        Some(limit) if in_trash => ApiError::conflict(&format!(
+            "Tenant {} quota of {} reached",
+            entity.to_lowercase(),
+            limit
+        )),
+        _ => ApiError::not_found(entity),
+    }
+}
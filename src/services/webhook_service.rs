@@ -0,0 +1,521 @@
+use crate::config::Config;
+use crate::models::{
+    DeliveryStatus, EventEnvelope, Principal, Webhook, WebhookCreate, WebhookCreated,
+    WebhookDelivery, WebhookUpdate,
+};
+use crate::repositories::{ProjectRepository, WebhookDeliveryRepository, WebhookRepository};
+use crate::services::access::require_admin;
+use crate::services::EventBus;
+use crate::views::ApiError;
+use chrono::{DateTime, Duration, Utc};
+use futures::stream::{self, StreamExt};
+use hmac::{Hmac, Mac};
+use ntex::http::client::Client;
+use ntex::http::Uri;
+use ntex::time::{sleep, timeout, Millis};
+use rand::RngCore;
+use sha2::Sha256;
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+pub const WEBHOOK_ID_HEADER: &str = "x-webhook-id";
+pub const DELIVERY_ID_HEADER: &str = "x-webhook-delivery";
+pub const EVENT_HEADER: &str = "x-webhook-event";
+pub const TIMESTAMP_HEADER: &str = "x-webhook-timestamp";
+pub const SIGNATURE_HEADER: &str = "x-webhook-signature";
+
+/// How often the worker looks for due deliveries.
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// How often delivered and dead-lettered deliveries past their retention are removed.
+const PRUNE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Deliveries sent at the same time by one run of the worker.
+const MAX_CONCURRENT_DELIVERIES: usize = 16;
+
+/// Upper bound for configured delays and timeouts, which keeps them within the range of
+/// chrono durations and ntex timers.
+const MAX_CONFIGURED_SECS: u64 = 30 * 24 * 60 * 60;
+
+/// Retry schedule for failed deliveries, and how long finished ones are kept.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Attempts before a delivery is dead-lettered.
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled for every further attempt.
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Time a receiver has to answer a single attempt.
+    pub timeout: std::time::Duration,
+    /// Time delivered and dead-lettered deliveries stay in the log after their last attempt.
+    pub retention: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 8,
+            base_delay: Duration::seconds(10),
+            max_delay: Duration::hours(1),
+            timeout: std::time::Duration::from_secs(10),
+            retention: Duration::days(7),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn from_config(config: &Config) -> Self {
+        let default = Self::default();
+        Self {
+            max_attempts: config
+                .webhook_max_attempts
+                .unwrap_or(default.max_attempts)
+                .max(1),
+            base_delay: config
+                .webhook_retry_base_secs
+                .map_or(default.base_delay, |secs| {
+                    Duration::seconds(secs.min(MAX_CONFIGURED_SECS) as i64)
+                }),
+            max_delay: config
+                .webhook_retry_max_secs
+                .map_or(default.max_delay, |secs| {
+                    Duration::seconds(secs.min(MAX_CONFIGURED_SECS) as i64)
+                }),
+            timeout: config.webhook_timeout_secs.map_or(default.timeout, |secs| {
+                std::time::Duration::from_secs(secs.min(MAX_CONFIGURED_SECS))
+            }),
+            retention: config
+                .webhook_retention_days
+                .map_or(default.retention, |days| Duration::days(days.into())),
+        }
+    }
+
+    /// Delay after the given number of failed attempts.
+    pub fn backoff(&self, attempts: u32) -> Duration {
+        let factor = 1i32 << attempts.saturating_sub(1).min(20);
+        self.base_delay
+            .checked_mul(factor)
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay))
+    }
+}
+
+/// Signature sent in [`SIGNATURE_HEADER`]: `sha256=` followed by the hex HMAC-SHA256 of
+/// `<timestamp>.<body>` keyed with the webhook secret.
+pub fn sign(secret: &str, timestamp: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+#[derive(Debug, Clone)]
+pub struct WebhookService {
+    repository: Arc<WebhookRepository>,
+    delivery_repository: Arc<WebhookDeliveryRepository>,
+    project_repository: Arc<ProjectRepository>,
+    policy: RetryPolicy,
+}
+
+impl WebhookService {
+    pub fn new(
+        repository: Arc<WebhookRepository>,
+        delivery_repository: Arc<WebhookDeliveryRepository>,
+        project_repository: Arc<ProjectRepository>,
+        policy: RetryPolicy,
+    ) -> Self {
+        Self {
+            repository,
+            delivery_repository,
+            project_repository,
+            policy,
+        }
+    }
+
+    /// Queues deliveries for published events and sends them from the current runtime.
+    pub fn start(self: &Arc<Self>, events: &EventBus) {
+        let service = self.clone();
+        events.subscribe_with("webhooks", move |envelope| {
+            let service = service.clone();
+            async move {
+                service
+                    .enqueue(&envelope)
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            }
+        });
+
+        let service = self.clone();
+        ntex::rt::spawn(async move {
+            loop {
+                sleep(Millis::from(POLL_INTERVAL)).await;
+                if let Err(e) = service.deliver_due(Utc::now()).await {
+                    warn!("Webhook delivery failed: {}", e);
+                }
+            }
+        });
+
+        let service = self.clone();
+        ntex::rt::spawn(async move {
+            loop {
+                sleep(Millis::from(PRUNE_INTERVAL)).await;
+                match service.prune_deliveries(Utc::now()) {
+                    Ok(0) => {}
+                    Ok(count) => info!("Removed {} finished webhook deliveries", count),
+                    Err(e) => warn!("Webhook delivery cleanup failed: {}", e),
+                }
+            }
+        });
+    }
+
+    pub async fn create_webhook(
+        &self,
+        actor: &Principal,
+        create_data: WebhookCreate,
+    ) -> Result<WebhookCreated, ApiError> {
+        require_admin(actor)?;
+        validate_url(&create_data.url)?;
+        if let Some(secret) = &create_data.secret {
+            validate_secret(secret)?;
+        }
+        if let Some(project_id) = create_data.project_id {
+            self.ensure_project(actor, &project_id)?;
+        }
+
+        let secret = create_data.secret.unwrap_or_else(generate_secret);
+        let webhook = Webhook::new(
+            actor.tenant_id.clone(),
+            create_data.url.trim().to_string(),
+            create_data.event_types,
+            create_data.project_id,
+            secret.clone(),
+        );
+
+        let webhook = self
+            .repository
+            .create(webhook)
+            .map_err(|e| ApiError::repository_error(&e))?;
+
+        Ok(WebhookCreated { secret, webhook })
+    }
+
+    pub async fn get_webhook(&self, actor: &Principal, id: Uuid) -> Result<Webhook, ApiError> {
+        require_admin(actor)?;
+        self.find_webhook(actor, &id)
+    }
+
+    pub async fn list_webhooks(&self, actor: &Principal) -> Result<Vec<Webhook>, ApiError> {
+        require_admin(actor)?;
+        let mut webhooks = self
+            .repository
+            .find_all(&actor.tenant_id)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        webhooks.sort_by_key(|webhook| webhook.created_at);
+        Ok(webhooks)
+    }
+
+    pub async fn update_webhook(
+        &self,
+        actor: &Principal,
+        id: Uuid,
+        update_data: WebhookUpdate,
+    ) -> Result<Webhook, ApiError> {
+        require_admin(actor)?;
+        if let Some(url) = &update_data.url {
+            validate_url(url)?;
+        }
+        if let Some(secret) = &update_data.secret {
+            validate_secret(secret)?;
+        }
+        if let Some(project_id) = update_data.project_id {
+            self.ensure_project(actor, &project_id)?;
+        }
+
+        let mut webhook = self.find_webhook(actor, &id)?;
+        webhook.update(WebhookUpdate {
+            url: update_data.url.map(|url| url.trim().to_string()),
+            ..update_data
+        });
+
+        self.repository
+            .update(&actor.tenant_id, &id, webhook)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .ok_or_else(|| ApiError::not_found("Webhook"))
+    }
+
+    /// Removes the webhook together with its delivery log and pending retries.
+    pub async fn delete_webhook(&self, actor: &Principal, id: Uuid) -> Result<(), ApiError> {
+        require_admin(actor)?;
+        let deleted = self
+            .repository
+            .delete(&actor.tenant_id, &id)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        if !deleted {
+            return Err(ApiError::not_found("Webhook"));
+        }
+
+        self.delivery_repository
+            .delete_by_webhook_id(&actor.tenant_id, &id)
+            .map_err(|e| ApiError::repository_error(&e))
+    }
+
+    /// Delivery log of a webhook, newest first.
+    pub async fn list_deliveries(
+        &self,
+        actor: &Principal,
+        webhook_id: Uuid,
+        status: Option<DeliveryStatus>,
+    ) -> Result<Vec<WebhookDelivery>, ApiError> {
+        require_admin(actor)?;
+        self.find_webhook(actor, &webhook_id)?;
+
+        let mut deliveries = self
+            .delivery_repository
+            .find_by_webhook_id(&actor.tenant_id, &webhook_id)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        deliveries.retain(|delivery| status.is_none_or(|status| delivery.status == status));
+        deliveries.sort_by_key(|delivery| std::cmp::Reverse(delivery.created_at));
+        Ok(deliveries)
+    }
+
+    /// Puts a dead-lettered delivery back on the queue with a fresh set of attempts.
+    pub async fn retry_delivery(
+        &self,
+        actor: &Principal,
+        webhook_id: Uuid,
+        delivery_id: Uuid,
+    ) -> Result<WebhookDelivery, ApiError> {
+        require_admin(actor)?;
+        let mut delivery = self
+            .delivery_repository
+            .find_by_id(&actor.tenant_id, &delivery_id)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .filter(|delivery| delivery.webhook_id == webhook_id)
+            .ok_or_else(|| ApiError::not_found("Webhook delivery"))?;
+
+        if delivery.status != DeliveryStatus::DeadLettered {
+            return Err(ApiError::conflict(
+                "Only dead-lettered deliveries can be retried",
+            ));
+        }
+
+        delivery.status = DeliveryStatus::Pending;
+        delivery.attempts = 0;
+        delivery.next_attempt_at = Some(Utc::now());
+        self.delivery_repository
+            .update(delivery)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .ok_or_else(|| ApiError::not_found("Webhook delivery"))
+    }
+
+    /// Queues one delivery per active webhook of the event's tenant that wants it.
+    pub fn enqueue(&self, envelope: &EventEnvelope) -> Result<usize, ApiError> {
+        let webhooks = self
+            .repository
+            .find_all(&envelope.tenant_id)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        let matching: Vec<&Webhook> = webhooks
+            .iter()
+            .filter(|webhook| webhook.matches(envelope))
+            .collect();
+        if matching.is_empty() {
+            return Ok(0);
+        }
+
+        let payload = serde_json::to_value(envelope)
+            .map_err(|e| ApiError::repository_error(&e.to_string()))?;
+        for webhook in &matching {
+            self.delivery_repository
+                .create(WebhookDelivery::new(webhook, envelope, payload.clone()))
+                .map_err(|e| ApiError::repository_error(&e))?;
+        }
+        Ok(matching.len())
+    }
+
+    /// Attempts every delivery due at `now`, rescheduling failures with exponential backoff
+    /// and dead-lettering them once the retry policy is exhausted. Up to
+    /// [`MAX_CONCURRENT_DELIVERIES`] are sent at once, each bounded by the policy timeout.
+    /// Returns the attempt count.
+    pub async fn deliver_due(&self, now: DateTime<Utc>) -> Result<usize, ApiError> {
+        let due = self
+            .delivery_repository
+            .find_due(now)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        if due.is_empty() {
+            return Ok(0);
+        }
+
+        let client = Client::build()
+            .timeout(Millis::from(self.policy.timeout))
+            .finish();
+        let count = due.len();
+        let results: Vec<Result<(), ApiError>> = stream::iter(due)
+            .map(|delivery| self.attempt(&client, delivery, now))
+            .buffer_unordered(MAX_CONCURRENT_DELIVERIES)
+            .collect()
+            .await;
+        results.into_iter().collect::<Result<(), _>>()?;
+        Ok(count)
+    }
+
+    /// Sends a single delivery and stores the outcome.
+    async fn attempt(
+        &self,
+        client: &Client,
+        mut delivery: WebhookDelivery,
+        now: DateTime<Utc>,
+    ) -> Result<(), ApiError> {
+        let webhook = self
+            .repository
+            .find_by_id(&delivery.tenant_id, &delivery.webhook_id)
+            .map_err(|e| ApiError::repository_error(&e))?;
+
+        let outcome = match webhook {
+            Some(webhook) if webhook.active => timeout(
+                Millis::from(self.policy.timeout),
+                self.send(client, &webhook, &delivery),
+            )
+            .await
+            .unwrap_or_else(|_| {
+                Err((
+                    None,
+                    format!(
+                        "Receiver did not answer within {}s",
+                        self.policy.timeout.as_secs()
+                    ),
+                ))
+            }),
+            Some(_) => Err((None, "Webhook is disabled".to_string())),
+            None => Err((None, "Webhook was deleted".to_string())),
+        };
+
+        delivery.attempts += 1;
+        delivery.last_attempt_at = Some(now);
+        match outcome {
+            Ok(status_code) => {
+                delivery.status = DeliveryStatus::Delivered;
+                delivery.last_status_code = Some(status_code);
+                delivery.last_error = None;
+                delivery.next_attempt_at = None;
+                delivery.delivered_at = Some(now);
+            }
+            Err((status_code, error)) => {
+                debug!(
+                    "Webhook delivery {} attempt {} failed: {}",
+                    delivery.id, delivery.attempts, error
+                );
+                delivery.last_status_code = status_code;
+                delivery.last_error = Some(error);
+                if delivery.attempts >= self.policy.max_attempts {
+                    warn!(
+                        "Webhook delivery {} dead-lettered after {} attempts",
+                        delivery.id, delivery.attempts
+                    );
+                    delivery.status = DeliveryStatus::DeadLettered;
+                    delivery.next_attempt_at = None;
+                } else {
+                    delivery.next_attempt_at = Some(now + self.policy.backoff(delivery.attempts));
+                }
+            }
+        }
+
+        // A delivery removed with its webhook during the attempt stays removed
+        let id = delivery.id;
+        let stored = self
+            .delivery_repository
+            .update(delivery)
+            .map_err(|e| ApiError::repository_error(&e))?;
+        if stored.is_none() {
+            debug!("Webhook delivery {} was removed during its attempt", id);
+        }
+        Ok(())
+    }
+
+    /// Removes delivered and dead-lettered deliveries whose last attempt is older than the
+    /// retention period. Returns how many were removed.
+    pub fn prune_deliveries(&self, now: DateTime<Utc>) -> Result<usize, ApiError> {
+        let Some(cutoff) = now.checked_sub_signed(self.policy.retention) else {
+            return Ok(0);
+        };
+        self.delivery_repository
+            .delete_finished_before(cutoff)
+            .map_err(|e| ApiError::repository_error(&e))
+    }
+
+    async fn send(
+        &self,
+        client: &Client,
+        webhook: &Webhook,
+        delivery: &WebhookDelivery,
+    ) -> Result<u16, (Option<u16>, String)> {
+        let body = serde_json::to_vec(&delivery.payload).map_err(|e| (None, e.to_string()))?;
+        let timestamp = Utc::now().timestamp().to_string();
+
+        let response = client
+            .post(&webhook.url)
+            .content_type("application/json")
+            .header(WEBHOOK_ID_HEADER, webhook.id.to_string())
+            .header(DELIVERY_ID_HEADER, delivery.id.to_string())
+            .header(EVENT_HEADER, delivery.event_type.as_str())
+            .header(TIMESTAMP_HEADER, timestamp.as_str())
+            .header(SIGNATURE_HEADER, sign(&webhook.secret, &timestamp, &body))
+            .send_body(body)
+            .await
+            .map_err(|e| (None, e.to_string()))?;
+
+        let status = response.status();
+        if status.is_success() {
+            Ok(status.as_u16())
+        } else {
+            Err((
+                Some(status.as_u16()),
+                format!("Receiver responded with {}", status),
+            ))
+        }
+    }
+
+    fn find_webhook(&self, actor: &Principal, id: &Uuid) -> Result<Webhook, ApiError> {
+        self.repository
+            .find_by_id(&actor.tenant_id, id)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .ok_or_else(|| ApiError::not_found("Webhook"))
+    }
+
+    fn ensure_project(&self, actor: &Principal, project_id: &Uuid) -> Result<(), ApiError> {
+        self.project_repository
+            .find_by_id(&actor.tenant_id, project_id)
+            .map_err(|e| ApiError::repository_error(&e))?
+            .map(|_| ())
+            .ok_or_else(|| ApiError::not_found("Project"))
+    }
+}
+
+fn validate_url(url: &str) -> Result<(), ApiError> {
+    let uri: Uri = url
+        .trim()
+        .parse()
+        .map_err(|_| ApiError::validation_error("Webhook URL is not a valid URL"))?;
+    match (uri.scheme_str(), uri.host()) {
+        (Some("http" | "https"), Some(host)) if !host.is_empty() => Ok(()),
+        _ => Err(ApiError::validation_error(
+            "Webhook URL must be an absolute http or https URL",
+        )),
+    }
+}
+
+fn validate_secret(secret: &str) -> Result<(), ApiError> {
+    if secret.len() < 16 || secret.len() > 256 {
+        return Err(ApiError::validation_error(
+            "Webhook secret must be between 16 and 256 characters",
+        ));
+    }
+    Ok(())
+}
+
+fn generate_secret() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
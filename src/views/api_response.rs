@@ -1,4 +1,4 @@
-use ntex::http::StatusCode;
+use ntex::http::{header, StatusCode};
 use ntex::web::{HttpResponse, WebResponseError};
 use serde::Serialize;
 use thiserror::Error;
@@ -59,6 +59,18 @@ pub enum ApiError {
 
     #[error("Conflict: {message}")]
     Conflict { message: String },
+
+    #[error("Unauthorized: {message}")]
+    Unauthorized { message: String },
+
+    #[error("Forbidden: {message}")]
+    Forbidden { message: String },
+
+    #[error("Not acceptable: {message}")]
+    NotAcceptable { message: String },
+
+    #[error("Unsupported media type: {message}")]
+    UnsupportedMediaType { message: String },
 }
 
 impl ApiError {
@@ -92,7 +104,32 @@ impl ApiError {
         }
     }
 
-    fn to_error_response(&self) -> ErrorResponse {
+    pub fn unauthorized(message: &str) -> Self {
+        Self::Unauthorized {
+            message: message.to_string(),
+        }
+    }
+
+    pub fn forbidden(message: &str) -> Self {
+        Self::Forbidden {
+            message: message.to_string(),
+        }
+    }
+
+    pub fn not_acceptable(message: &str) -> Self {
+        Self::NotAcceptable {
+            message: message.to_string(),
+        }
+    }
+
+    pub fn unsupported_media_type(message: &str) -> Self {
+        Self::UnsupportedMediaType {
+            message: message.to_string(),
+        }
+    }
+
+    /// Error body as sent to clients, also used for errors outside HTTP responses.
+    pub fn to_error_response(&self) -> ErrorResponse {
         match self {
             ApiError::NotFound { resource } => ErrorResponse {
                 code: "NOT_FOUND".to_string(),
@@ -124,6 +161,26 @@ impl ApiError {
                 message: message.clone(),
                 details: None,
             },
+            ApiError::Unauthorized { message } => ErrorResponse {
+                code: "UNAUTHORIZED".to_string(),
+                message: message.clone(),
+                details: None,
+            },
+            ApiError::Forbidden { message } => ErrorResponse {
+                code: "FORBIDDEN".to_string(),
+                message: message.clone(),
+                details: None,
+            },
+            ApiError::NotAcceptable { message } => ErrorResponse {
+                code: "NOT_ACCEPTABLE".to_string(),
+                message: message.clone(),
+                details: None,
+            },
+            ApiError::UnsupportedMediaType { message } => ErrorResponse {
+                code: "UNSUPPORTED_MEDIA_TYPE".to_string(),
+                message: message.clone(),
+                details: None,
+            },
         }
     }
 
@@ -135,6 +192,10 @@ impl ApiError {
             ApiError::InternalServerError => StatusCode::INTERNAL_SERVER_ERROR,
             ApiError::RepositoryError { .. } => StatusCode::INTERNAL_SERVER_ERROR,
             ApiError::Conflict { .. } => StatusCode::CONFLICT,
+            ApiError::Unauthorized { .. } => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden { .. } => StatusCode::FORBIDDEN,
+            ApiError::NotAcceptable { .. } => StatusCode::NOT_ACCEPTABLE,
+            ApiError::UnsupportedMediaType { .. } => StatusCode::UNSUPPORTED_MEDIA_TYPE,
         }
     }
 }
@@ -142,7 +203,11 @@ impl ApiError {
 impl WebResponseError for ApiError {
     fn error_response(&self, _: &ntex::web::HttpRequest) -> HttpResponse {
         let error_response = self.to_error_response();
-        HttpResponse::build(self.status_code()).json(&error_response)
+        let mut response = HttpResponse::build(self.status_code());
+        if let ApiError::Unauthorized { .. } = self {
+            response.header(header::WWW_AUTHENTICATE, "Bearer");
+        }
+        response.json(&ApiResponse::<()>::error(error_response))
     }
 }
 
@@ -0,0 +1,188 @@
+/// Longest field [`CsvReader`] buffers before rejecting its record.
+pub const MAX_FIELD_LEN: usize = 64 * 1024;
+/// Longest record [`CsvReader`] buffers before rejecting it.
+pub const MAX_RECORD_LEN: usize = 1024 * 1024;
+
+/// One CSV record, quoting fields that contain separators, quotes or line breaks.
+pub fn csv_record<S: AsRef<str>>(fields: &[S]) -> String {
+    let mut record = fields
+        .iter()
+        .map(|field| {
+            let field = field.as_ref();
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    record.push_str("\r\n");
+    record
+}
+
+/// A record read by [`CsvReader`], with the line it starts on. Fields that are not valid
+/// UTF-8, fields or records over [`MAX_FIELD_LEN`] or [`MAX_RECORD_LEN`] bytes, or a quote left
+/// open at the end of the input, make the record an error.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvRow {
+    pub line: usize,
+    pub fields: Result<Vec<String>, String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CsvState {
+    FieldStart,
+    Unquoted,
+    Quoted,
+    /// A quote inside a quoted field: either the closing quote or the first of a pair
+    QuoteInQuoted,
+}
+
+/// Incremental CSV parser: records come out as soon as their last byte is fed, so input can
+/// be read in chunks of any size. Accepts CRLF and LF line ends and skips blank lines. An
+/// oversized record stops being buffered but is still read to its end, so the rows after it
+/// keep their line numbers.
+#[derive(Debug)]
+pub struct CsvReader {
+    state: CsvState,
+    field: Vec<u8>,
+    fields: Vec<Vec<u8>>,
+    line: usize,
+    record_line: usize,
+    record_len: usize,
+    /// Why the current record is rejected, once it has outgrown a limit
+    error: Option<String>,
+}
+
+impl Default for CsvReader {
+    fn default() -> Self {
+        Self {
+            state: CsvState::FieldStart,
+            field: Vec::new(),
+            fields: Vec::new(),
+            line: 1,
+            record_line: 1,
+            record_len: 0,
+            error: None,
+        }
+    }
+}
+
+impl CsvReader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds the next chunk, returning the records it completed.
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<CsvRow> {
+        let mut rows = Vec::new();
+        for &byte in chunk {
+            if self.state == CsvState::QuoteInQuoted {
+                if byte == b'"' {
+                    self.push(b'"');
+                    self.state = CsvState::Quoted;
+                    continue;
+                }
+                self.state = CsvState::Unquoted;
+            }
+
+            match (self.state, byte) {
+                (CsvState::Quoted, b'"') => self.state = CsvState::QuoteInQuoted,
+                (CsvState::Quoted, byte) => {
+                    if byte == b'\n' {
+                        self.line += 1;
+                    }
+                    self.push(byte);
+                }
+                (CsvState::FieldStart, b'"') => self.state = CsvState::Quoted,
+                (_, b',') => self.end_field(),
+                (_, b'\n') => {
+                    self.line += 1;
+                    if let Some(row) = self.end_record() {
+                        rows.push(row);
+                    }
+                }
+                // Dropped; a CR is only meaningful as part of CRLF
+                (_, b'\r') => {}
+                (_, byte) => {
+                    self.push(byte);
+                    self.state = CsvState::Unquoted;
+                }
+            }
+        }
+        rows
+    }
+
+    /// Ends the input, returning the last record if it had no line break.
+    pub fn finish(&mut self) -> Option<CsvRow> {
+        if matches!(self.state, CsvState::Quoted) {
+            let line = self.record_line;
+            *self = Self::default();
+            return Some(CsvRow {
+                line,
+                fields: Err("Quoted field is not closed".to_string()),
+            });
+        }
+        self.end_record()
+    }
+
+    /// Buffers a byte of the current field unless the record has outgrown a limit.
+    fn push(&mut self, byte: u8) {
+        if self.field.len() >= MAX_FIELD_LEN {
+            self.reject(format!("Field is longer than {} bytes", MAX_FIELD_LEN));
+        }
+        if self.grow() {
+            self.field.push(byte);
+        }
+    }
+
+    fn end_field(&mut self) {
+        // Separators count towards the record length too
+        if self.grow() {
+            self.fields.push(std::mem::take(&mut self.field));
+        }
+        self.state = CsvState::FieldStart;
+    }
+
+    /// Counts one more byte of the current record, rejecting it past [`MAX_RECORD_LEN`].
+    /// Returns whether the record is still being buffered.
+    fn grow(&mut self) -> bool {
+        self.record_len += 1;
+        if self.record_len > MAX_RECORD_LEN && self.error.is_none() {
+            self.reject(format!("Row is longer than {} bytes", MAX_RECORD_LEN));
+        }
+        self.error.is_none()
+    }
+
+    /// Drops what was buffered of the current record.
+    fn reject(&mut self, e: String) {
+        self.error = Some(e);
+        self.field = Vec::new();
+        self.fields = Vec::new();
+    }
+
+    fn end_record(&mut self) -> Option<CsvRow> {
+        let blank = self.fields.is_empty()
+            && self.field.is_empty()
+            && self.state == CsvState::FieldStart
+            && self.error.is_none();
+        let line = std::mem::replace(&mut self.record_line, self.line);
+        if blank {
+            return None;
+        }
+
+        self.end_field();
+        self.record_len = 0;
+        let fields = match self.error.take() {
+            Some(e) => Err(e),
+            None => std::mem::take(&mut self.fields)
+                .into_iter()
+                .map(|field| {
+                    String::from_utf8(field).map_err(|_| "Row is not valid UTF-8".to_string())
+                })
+                .collect(),
+        };
+        Some(CsvRow { line, fields })
+    }
+}
@@ -0,0 +1,106 @@
+use crate::models::{CalendarComponent, Task};
+use chrono::{DateTime, Utc};
+
+/// Longest content line in octets before it is folded (RFC 5545, section 3.1).
+const MAX_LINE_OCTETS: usize = 75;
+
+/// A task calendar with one component per task that has a due date. UIDs are the task ids,
+/// so clients update entries in place when a task changes.
+pub fn task_calendar(
+    name: &str,
+    tasks: &[Task],
+    component: CalendarComponent,
+    now: DateTime<Utc>,
+) -> String {
+    let mut calendar = String::new();
+    calendar.push_str(&ical_line("BEGIN", "VCALENDAR"));
+    calendar.push_str(&ical_line("VERSION", "2.0"));
+    calendar.push_str(&ical_line(
+        "PRODID",
+        concat!("-//", env!("CARGO_PKG_NAME"), "//Tasks//EN"),
+    ));
+    calendar.push_str(&ical_line("CALSCALE", "GREGORIAN"));
+    calendar.push_str(&ical_line("X-WR-CALNAME", &ical_text(name)));
+
+    for task in tasks {
+        let Some(due_at) = task.due_at else {
+            continue;
+        };
+        let kind = match component {
+            CalendarComponent::Todo => "VTODO",
+            CalendarComponent::Event => "VEVENT",
+        };
+        calendar.push_str(&ical_line("BEGIN", kind));
+        calendar.push_str(&ical_line("UID", &task.id.to_string()));
+        calendar.push_str(&ical_line("DTSTAMP", &ical_timestamp(now)));
+        calendar.push_str(&ical_line("CREATED", &ical_timestamp(task.created_at)));
+        calendar.push_str(&ical_line(
+            "LAST-MODIFIED",
+            &ical_timestamp(task.updated_at),
+        ));
+        calendar.push_str(&ical_line("SUMMARY", &ical_text(&task.title)));
+        if let Some(ref description) = task.description {
+            calendar.push_str(&ical_line("DESCRIPTION", &ical_text(description)));
+        }
+        match component {
+            CalendarComponent::Todo => {
+                calendar.push_str(&ical_line("DUE", &ical_timestamp(due_at)));
+                if task.done {
+                    calendar.push_str(&ical_line("STATUS", "COMPLETED"));
+                    calendar.push_str(&ical_line("PERCENT-COMPLETE", "100"));
+                    calendar.push_str(&ical_line("COMPLETED", &ical_timestamp(task.updated_at)));
+                } else {
+                    calendar.push_str(&ical_line("STATUS", "NEEDS-ACTION"));
+                }
+            }
+            CalendarComponent::Event => {
+                calendar.push_str(&ical_line("DTSTART", &ical_timestamp(due_at)));
+                calendar.push_str(&ical_line("DTEND", &ical_timestamp(due_at)));
+                calendar.push_str(&ical_line("TRANSP", "TRANSPARENT"));
+            }
+        }
+        calendar.push_str(&ical_line("END", kind));
+    }
+
+    calendar.push_str(&ical_line("END", "VCALENDAR"));
+    calendar
+}
+
+/// One content line, folded after 75 octets and ending with CRLF.
+pub fn ical_line(name: &str, value: &str) -> String {
+    let line = format!("{}:{}", name, value);
+    let mut folded = String::with_capacity(line.len() + line.len() / MAX_LINE_OCTETS * 3 + 2);
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            // The leading space counts towards the continuation line
+            octets = 1;
+        }
+        folded.push(c);
+        octets += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+/// Escapes a TEXT value (RFC 5545, section 3.3.11).
+pub fn ical_text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// UTC DATE-TIME value, e.g. `20240301T090000Z`.
+pub fn ical_timestamp(at: DateTime<Utc>) -> String {
+    at.format("%Y%m%dT%H%M%SZ").to_string()
+}
@@ -1,3 +1,9 @@
 pub mod api_response;
+pub mod csv;
+pub mod ical;
+pub mod negotiation;
 
 pub use api_response::*;
+pub use csv::*;
+pub use ical::*;
+pub use negotiation::*;
@@ -0,0 +1,241 @@
+use crate::views::ApiError;
+use ntex::http::{header, Payload};
+use ntex::web::{DefaultError, FromRequest, HttpRequest};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::ops::Deref;
+
+/// Largest request body [`Negotiated`] reads, matching ntex's JSON extractor.
+const BODY_LIMIT: usize = 32 * 1024;
+
+/// A representation the API can read and write. JSON is always available; the binary
+/// formats are compiled in with the `msgpack` and `cbor` features.
+///
+/// Both binary formats carry the same data model as the JSON API: responses are transcoded
+/// from their JSON form and request bodies are turned into JSON values before they are
+/// deserialized, so ids and timestamps stay strings whatever the format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentFormat {
+    Json,
+    #[cfg(feature = "msgpack")]
+    MessagePack,
+    #[cfg(feature = "cbor")]
+    Cbor,
+}
+
+impl ContentFormat {
+    /// Formats in order of preference, for when `Accept` rates several of them equally.
+    pub fn supported() -> Vec<Self> {
+        #[allow(unused_mut)]
+        let mut formats = vec![Self::Json];
+        #[cfg(feature = "msgpack")]
+        formats.push(Self::MessagePack);
+        #[cfg(feature = "cbor")]
+        formats.push(Self::Cbor);
+        formats
+    }
+
+    pub fn media_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            #[cfg(feature = "msgpack")]
+            Self::MessagePack => "application/msgpack",
+            #[cfg(feature = "cbor")]
+            Self::Cbor => "application/cbor",
+        }
+    }
+
+    /// The format named by a media type, ignoring parameters such as `charset`. MessagePack
+    /// also answers to the unregistered `x-msgpack` and `vnd.msgpack` names clients still send.
+    pub fn from_media_type(media_type: &str) -> Option<Self> {
+        let essence = media_type.split(';').next().unwrap_or("").trim();
+        Self::supported().into_iter().find(|format| {
+            essence.eq_ignore_ascii_case(format.media_type())
+                || format
+                    .aliases()
+                    .iter()
+                    .any(|alias| essence.eq_ignore_ascii_case(alias))
+        })
+    }
+
+    fn aliases(self) -> &'static [&'static str] {
+        match self {
+            Self::Json => &[],
+            #[cfg(feature = "msgpack")]
+            Self::MessagePack => &["application/x-msgpack", "application/vnd.msgpack"],
+            #[cfg(feature = "cbor")]
+            Self::Cbor => &[],
+        }
+    }
+
+    /// Picks the response format for an `Accept` header. A missing or empty header means
+    /// JSON; otherwise the supported format with the highest quality wins, where each format
+    /// takes the quality of the most specific range that matches it.
+    pub fn negotiate(accept: Option<&str>) -> Result<Self, ApiError> {
+        let accept = match accept.map(str::trim) {
+            None | Some("") => return Ok(Self::Json),
+            Some(accept) => accept,
+        };
+        let ranges: Vec<MediaRange> = accept.split(',').filter_map(MediaRange::parse).collect();
+
+        let mut best: Option<(Self, f32)> = None;
+        for format in Self::supported() {
+            let quality = ranges
+                .iter()
+                .filter_map(|range| range.specificity(format).map(|rank| (rank, range.quality)))
+                .max_by_key(|(rank, _)| *rank)
+                .map(|(_, quality)| quality)
+                .unwrap_or(0.0);
+            if quality > 0.0 && best.is_none_or(|(_, best)| quality > best) {
+                best = Some((format, quality));
+            }
+        }
+
+        best.map(|(format, _)| format).ok_or_else(|| {
+            ApiError::not_acceptable(&format!(
+                "Cannot respond with any of '{}'; available: {}",
+                accept,
+                Self::media_types()
+            ))
+        })
+    }
+
+    /// Writes a JSON value in this format.
+    pub fn encode(self, value: &Value) -> Result<Vec<u8>, String> {
+        match self {
+            Self::Json => serde_json::to_vec(value).map_err(|e| e.to_string()),
+            #[cfg(feature = "msgpack")]
+            Self::MessagePack => rmp_serde::to_vec_named(value).map_err(|e| e.to_string()),
+            #[cfg(feature = "cbor")]
+            Self::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(value, &mut bytes).map_err(|e| e.to_string())?;
+                Ok(bytes)
+            }
+        }
+    }
+
+    /// Reads a document in this format as a JSON value.
+    pub fn decode(self, bytes: &[u8]) -> Result<Value, String> {
+        match self {
+            Self::Json => serde_json::from_slice(bytes).map_err(|e| e.to_string()),
+            #[cfg(feature = "msgpack")]
+            Self::MessagePack => rmp_serde::from_slice(bytes).map_err(|e| e.to_string()),
+            #[cfg(feature = "cbor")]
+            Self::Cbor => ciborium::from_reader(bytes).map_err(|e| e.to_string()),
+        }
+    }
+
+    fn media_types() -> String {
+        Self::supported()
+            .iter()
+            .map(|format| format.media_type())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// One entry of an `Accept` header, such as `application/*;q=0.5`.
+struct MediaRange<'a> {
+    kind: &'a str,
+    subtype: &'a str,
+    quality: f32,
+}
+
+impl<'a> MediaRange<'a> {
+    fn parse(entry: &'a str) -> Option<Self> {
+        let mut parts = entry.split(';');
+        let (kind, subtype) = parts.next()?.trim().split_once('/')?;
+        let quality = parts
+            .filter_map(|param| param.split_once('='))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("q"))
+            .and_then(|(_, value)| value.trim().parse::<f32>().ok())
+            .map_or(1.0, |quality| quality.clamp(0.0, 1.0));
+        Some(Self {
+            kind: kind.trim(),
+            subtype: subtype.trim(),
+            quality,
+        })
+    }
+
+    /// How closely this range names the format: 2 for the media type itself, 1 for
+    /// `type/*` and 0 for `*/*`, or `None` when it does not match.
+    fn specificity(&self, format: ContentFormat) -> Option<u8> {
+        let (kind, _) = format.media_type().split_once('/')?;
+        if self.kind == "*" && self.subtype == "*" {
+            return Some(0);
+        }
+        if !self.kind.eq_ignore_ascii_case(kind) {
+            return None;
+        }
+        if self.subtype == "*" {
+            return Some(1);
+        }
+        let media_type = format!("{}/{}", self.kind, self.subtype);
+        (ContentFormat::from_media_type(&media_type) == Some(format)).then_some(2)
+    }
+}
+
+/// Request body extractor that decodes by `Content-Type`: JSON, or MessagePack and CBOR when
+/// those features are enabled. A body without a `Content-Type` is read as JSON; any other
+/// type is rejected with 415 Unsupported Media Type.
+#[derive(Debug)]
+pub struct Negotiated<T>(pub T);
+
+impl<T> Negotiated<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Negotiated<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: DeserializeOwned> FromRequest<DefaultError> for Negotiated<T> {
+    type Error = ApiError;
+
+    async fn from_request(req: &HttpRequest, payload: &mut Payload) -> Result<Self, Self::Error> {
+        let content_type = match req.headers().get(header::CONTENT_TYPE) {
+            None => None,
+            Some(value) => Some(value.to_str().unwrap_or("")),
+        };
+        let format = match content_type {
+            None => ContentFormat::Json,
+            Some(content_type) => {
+                ContentFormat::from_media_type(content_type).ok_or_else(|| {
+                    ApiError::unsupported_media_type(&format!(
+                        "Content-Type '{}' is not supported; send one of {}",
+                        content_type,
+                        ContentFormat::media_types()
+                    ))
+                })?
+            }
+        };
+
+        let mut body = Vec::new();
+        while let Some(chunk) = payload.recv().await {
+            let chunk = chunk.map_err(|e| {
+                ApiError::bad_request(&format!("Failed to read request body: {}", e))
+            })?;
+            if body.len() + chunk.len() > BODY_LIMIT {
+                return Err(ApiError::bad_request(&format!(
+                    "Request body exceeds {} bytes",
+                    BODY_LIMIT
+                )));
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        let value = format
+            .decode(&body)
+            .map_err(|e| ApiError::bad_request(&format!("Invalid request body: {}", e)))?;
+        serde_json::from_value(value)
+            .map(Negotiated)
+            .map_err(|e| ApiError::bad_request(&format!("Invalid request body: {}", e)))
+    }
+}
@@ -0,0 +1,205 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use common::{bearer, jwt_verifier, services};
+use ntex::web::{test, App};
+use rust_mvc_api::middleware::{ApiKeyAuth, JwtAuth};
+use rust_mvc_api::repositories::ApiKeyRepository;
+use rust_mvc_api::routes::configure_routes;
+use rust_mvc_api::services::{ApiKeyService, QuotaPolicy};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use uuid::Uuid;
+
+macro_rules! api_key_app {
+    ($services:expr, $api_keys:expr) => {
+        test::init_service(
+            App::new()
+                .state($services.projects.clone())
+                .state($api_keys.clone())
+                .wrap(JwtAuth::new(Some(jwt_verifier())))
+                .wrap(ApiKeyAuth::new($api_keys.clone()))
+                .configure(configure_routes),
+        )
+        .await
+    };
+}
+
+fn get(uri: &str, headers: &[(&str, &str)]) -> ntex::http::Request {
+    headers
+        .iter()
+        .fold(test::TestRequest::get().uri(uri), |req, (name, value)| {
+            req.header(*name, *value)
+        })
+        .to_request()
+}
+
+#[ntex::test]
+async fn keys_are_minted_listed_and_revoked() {
+    let services = services(QuotaPolicy::default());
+    let api_keys = Arc::new(ApiKeyService::new(Arc::new(ApiKeyRepository::new())));
+    let app = api_key_app!(services, api_keys);
+    let admin = bearer("acme", "alice", "admin");
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/admin/api-keys")
+        .header("Authorization", admin.as_str())
+        .set_json(&json!({ "name": "ci", "scopes": ["admin"] }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status().as_u16(), 201);
+    let body: Value = serde_json::from_slice(&test::read_body(resp).await).unwrap();
+    let key = body["data"]["key"].as_str().unwrap().to_string();
+    let id = body["data"]["api_key"]["id"].as_str().unwrap().to_string();
+    let secret = key.split_once('.').unwrap().1.to_string();
+    assert_eq!(body["data"]["api_key"]["tenant_id"], "acme");
+
+    // The key works on its own, in its own tenant
+    let resp = test::call_service(
+        &app,
+        get("/api/v1/admin/api-keys", &[("X-API-Key", key.as_str())]),
+    )
+    .await;
+    assert_eq!(resp.status().as_u16(), 200);
+    let raw = test::read_body(resp).await;
+    let body: Value = serde_json::from_slice(&raw).unwrap();
+    assert_eq!(body["data"][0]["id"], id.as_str());
+    assert_eq!(body["data"][0]["name"], "ci");
+    assert!(body["data"][0]["last_used_at"].is_string());
+    // Listings never carry the key, its secret or its hash
+    let raw = String::from_utf8(raw.to_vec()).unwrap();
+    assert!(!raw.contains(&secret));
+    assert!(body["data"][0].get("key").is_none());
+    assert!(body["data"][0].get("key_hash").is_none());
+    assert!(body["data"][0].get("salt").is_none());
+
+    let resp = test::call_service(
+        &app,
+        get(
+            "/api/v1/admin/api-keys",
+            &[("X-API-Key", key.as_str()), ("X-Tenant-Id", "globex")],
+        ),
+    )
+    .await;
+    assert_eq!(resp.status().as_u16(), 403);
+    let resp = test::call_service(
+        &app,
+        get(
+            "/api/v1/admin/api-keys",
+            &[("X-API-Key", key.as_str()), ("X-Tenant-Id", "acme")],
+        ),
+    )
+    .await;
+    assert_eq!(resp.status().as_u16(), 200);
+
+    // Only admins manage keys
+    let resp = test::call_service(
+        &app,
+        get(
+            "/api/v1/admin/api-keys",
+            &[("Authorization", bearer("acme", "bob", "").as_str())],
+        ),
+    )
+    .await;
+    assert_eq!(resp.status().as_u16(), 403);
+
+    let req = test::TestRequest::delete()
+        .uri(&format!("/api/v1/admin/api-keys/{}", id))
+        .header("Authorization", admin.as_str())
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status().as_u16(), 200);
+    let body: Value = serde_json::from_slice(&test::read_body(resp).await).unwrap();
+    assert!(body["data"]["revoked_at"].is_string());
+
+    let resp = test::call_service(
+        &app,
+        get("/api/v1/projects", &[("X-API-Key", key.as_str())]),
+    )
+    .await;
+    assert_eq!(resp.status().as_u16(), 401);
+    let body: Value = serde_json::from_slice(&test::read_body(resp).await).unwrap();
+    assert!(body["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("revoked or expired"));
+
+    // Another tenant's admin cannot revoke it
+    let req = test::TestRequest::delete()
+        .uri(&format!("/api/v1/admin/api-keys/{}", id))
+        .header("Authorization", bearer("globex", "carol", "admin").as_str())
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status().as_u16(), 404);
+}
+
+#[ntex::test]
+async fn expired_keys_are_refused_and_keys_win_over_bearer_tokens() {
+    let services = services(QuotaPolicy::default());
+    let repository = Arc::new(ApiKeyRepository::new());
+    let api_keys = Arc::new(ApiKeyService::new(repository.clone()));
+    let app = api_key_app!(services, api_keys);
+    let acme = common::admin("acme");
+
+    let created = api_keys
+        .create_key(
+            &acme,
+            rust_mvc_api::models::ApiKeyCreate {
+                name: "deploy".to_string(),
+                scopes: vec!["admin".to_string()],
+                expires_at: Some(Utc::now() + Duration::hours(1)),
+            },
+        )
+        .await
+        .unwrap();
+    let key = created.key;
+
+    // A valid key is used even next to a bearer token that would fail on its own
+    let resp = test::call_service(
+        &app,
+        get(
+            "/api/v1/admin/api-keys",
+            &[
+                ("X-API-Key", key.as_str()),
+                ("Authorization", "Bearer not-a-jwt"),
+            ],
+        ),
+    )
+    .await;
+    assert_eq!(resp.status().as_u16(), 200);
+    // An invalid key is refused even next to a valid admin token
+    let unknown = format!("{}.{}", Uuid::new_v4().simple(), "0".repeat(64));
+    let resp = test::call_service(
+        &app,
+        get(
+            "/api/v1/admin/api-keys",
+            &[
+                ("X-API-Key", unknown.as_str()),
+                ("Authorization", bearer("acme", "alice", "admin").as_str()),
+            ],
+        ),
+    )
+    .await;
+    assert_eq!(resp.status().as_u16(), 401);
+
+    // Let the key lapse
+    let mut lapsed = repository.find_by_id(&created.api_key.id).unwrap().unwrap();
+    lapsed.expires_at = Some(Utc::now() - Duration::minutes(1));
+    repository.create(lapsed).unwrap();
+    let resp = test::call_service(
+        &app,
+        get("/api/v1/projects", &[("X-API-Key", key.as_str())]),
+    )
+    .await;
+    assert_eq!(resp.status().as_u16(), 401);
+
+    // Expiry must be in the future when minting
+    let req = test::TestRequest::post()
+        .uri("/api/v1/admin/api-keys")
+        .header("Authorization", bearer("acme", "alice", "admin").as_str())
+        .set_json(&json!({
+            "name": "stale",
+            "expires_at": (Utc::now() - Duration::minutes(1)).to_rfc3339()
+        }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status().as_u16(), 400);
+}
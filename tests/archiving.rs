@@ -0,0 +1,145 @@
+mod common;
+
+use common::{admin, admin_bearer, jwt_verifier, member, project, services, task, TestServices};
+use ntex::web::{test, App};
+use rust_mvc_api::middleware::JwtAuth;
+use rust_mvc_api::models::{ProjectMemberUpdate, ProjectRole, ProjectUpdate, TaskUpdate};
+use rust_mvc_api::routes::configure_routes;
+use rust_mvc_api::services::QuotaPolicy;
+use rust_mvc_api::views::ApiError;
+
+#[ntex::test]
+async fn archived_projects_and_their_tasks_are_read_only() {
+    let services = services(QuotaPolicy::default());
+    let alice = member("acme", "alice");
+    let bob = member("acme", "bob");
+    let rockets = services
+        .projects
+        .create_project(&alice, project("Rockets"))
+        .await
+        .unwrap();
+    services
+        .projects
+        .set_member(
+            &alice,
+            rockets.id,
+            "bob".to_string(),
+            ProjectMemberUpdate {
+                role: ProjectRole::Editor,
+            },
+        )
+        .await
+        .unwrap();
+    let fuel = services
+        .tasks
+        .create_task(&bob, task(rockets.id, "Fuel"))
+        .await
+        .unwrap();
+
+    let result = services.projects.archive_project(&bob, rockets.id).await;
+    assert!(matches!(result, Err(ApiError::Forbidden { .. })));
+    let archived = services
+        .projects
+        .archive_project(&alice, rockets.id)
+        .await
+        .unwrap();
+    assert!(archived.archived);
+
+    // Still readable, but gone from the default listing.
+    assert!(services
+        .projects
+        .list_projects(&bob, false)
+        .await
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        services
+            .projects
+            .list_projects(&bob, true)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+    services.tasks.get_task(&bob, fuel.id).await.unwrap();
+
+    let done = TaskUpdate {
+        title: None,
+        description: None,
+        done: Some(true),
+        column_id: None,
+        due_at: None,
+    };
+    let result = services.tasks.update_task(&bob, fuel.id, done).await;
+    assert!(matches!(result, Err(ApiError::Conflict { .. })));
+    let result = services
+        .tasks
+        .create_task(&bob, task(rockets.id, "Paint"))
+        .await;
+    assert!(matches!(result, Err(ApiError::Conflict { .. })));
+    let result = services.tasks.delete_task(&bob, fuel.id).await;
+    assert!(matches!(result, Err(ApiError::Conflict { .. })));
+    let result = services
+        .projects
+        .update_project(
+            &alice,
+            rockets.id,
+            ProjectUpdate {
+                name: Some("Moon rockets".to_string()),
+                description: None,
+            },
+        )
+        .await;
+    assert!(matches!(result, Err(ApiError::Conflict { .. })));
+
+    let unarchived = services
+        .projects
+        .unarchive_project(&alice, rockets.id)
+        .await
+        .unwrap();
+    assert!(!unarchived.archived);
+    services.tasks.delete_task(&bob, fuel.id).await.unwrap();
+}
+
+#[ntex::test]
+async fn listing_can_include_archived_projects() {
+    let TestServices {
+        projects, tasks, ..
+    } = services(QuotaPolicy::default());
+    let acme = admin("acme");
+    projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    let old = projects
+        .create_project(&acme, project("Old rockets"))
+        .await
+        .unwrap();
+    projects.archive_project(&acme, old.id).await.unwrap();
+    // Archiving twice is a no-op.
+    projects.archive_project(&acme, old.id).await.unwrap();
+
+    let app = test::init_service(
+        App::new()
+            .state(projects)
+            .state(tasks)
+            .wrap(JwtAuth::new(Some(jwt_verifier())))
+            .configure(configure_routes),
+    )
+    .await;
+
+    let req = test::TestRequest::get()
+        .uri("/api/v1/projects")
+        .header("Authorization", admin_bearer("acme"))
+        .to_request();
+    let body: serde_json::Value = test::read_response_json(&app, req).await;
+    assert_eq!(body["data"].as_array().unwrap().len(), 1);
+    assert_eq!(body["data"][0]["name"], "Rockets");
+
+    let req = test::TestRequest::get()
+        .uri("/api/v1/projects?include_archived=true")
+        .header("Authorization", admin_bearer("acme"))
+        .to_request();
+    let body: serde_json::Value = test::read_response_json(&app, req).await;
+    assert_eq!(body["data"].as_array().unwrap().len(), 2);
+}
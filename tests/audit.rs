@@ -0,0 +1,252 @@
+mod common;
+
+use common::{admin, admin_bearer, bearer, jwt_verifier, member, project, services, task};
+use ntex::web::{test, App};
+use rust_mvc_api::middleware::{JwtAuth, RequestIdentifier};
+use rust_mvc_api::models::{
+    AuditAction, AuditEntry, AuditFilter, EntityType, ProjectMemberUpdate, ProjectRole,
+    ProjectUpdate, TaskUpdate,
+};
+use rust_mvc_api::routes::configure_routes;
+use rust_mvc_api::services::QuotaPolicy;
+use rust_mvc_api::views::ApiError;
+use serde_json::{json, Value};
+
+#[ntex::test]
+async fn mutations_are_audited_with_field_diffs() {
+    let services = services(QuotaPolicy::default());
+    let acme = admin("acme");
+    let alice = member("acme", "alice");
+
+    let rockets = services
+        .projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    services
+        .projects
+        .update_project(
+            &acme,
+            rockets.id,
+            ProjectUpdate {
+                name: Some("Moon rockets".to_string()),
+                description: None,
+            },
+        )
+        .await
+        .unwrap();
+    services
+        .projects
+        .set_member(
+            &acme,
+            rockets.id,
+            "alice".to_string(),
+            ProjectMemberUpdate {
+                role: ProjectRole::Editor,
+            },
+        )
+        .await
+        .unwrap();
+    let fuel = services
+        .tasks
+        .create_task(&alice, task(rockets.id, "Fuel"))
+        .await
+        .unwrap();
+    services
+        .tasks
+        .update_task(
+            &alice,
+            fuel.id,
+            TaskUpdate {
+                title: None,
+                description: None,
+                done: Some(true),
+                column_id: None,
+                due_at: None,
+            },
+        )
+        .await
+        .unwrap();
+    services.tasks.delete_task(&alice, fuel.id).await.unwrap();
+    // Rejected mutations leave no trace
+    assert!(services
+        .tasks
+        .create_task(&alice, task(rockets.id, " "))
+        .await
+        .is_err());
+
+    let entries = services
+        .audit
+        .list_entries(&acme, &AuditFilter::default())
+        .await
+        .unwrap();
+    let summary: Vec<(AuditAction, EntityType, &str)> = entries
+        .iter()
+        .map(|entry| (entry.action, entry.entity_type, entry.actor.as_str()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (AuditAction::Create, EntityType::Project, "admin@acme"),
+            (AuditAction::Create, EntityType::ProjectMember, "admin@acme"),
+            (AuditAction::Update, EntityType::Project, "admin@acme"),
+            (AuditAction::Create, EntityType::ProjectMember, "admin@acme"),
+            (AuditAction::Create, EntityType::Task, "alice"),
+            (AuditAction::Update, EntityType::Task, "alice"),
+            (AuditAction::Delete, EntityType::Task, "alice"),
+        ]
+    );
+
+    // Updates only carry the fields that changed
+    let renamed = &entries[2].changes;
+    assert_eq!(renamed["name"].before, json!("Rockets"));
+    assert_eq!(renamed["name"].after, json!("Moon rockets"));
+    assert!(!renamed.contains_key("description"));
+    assert!(!renamed.contains_key("id"));
+    assert_eq!(entries[5].changes["done"].before, json!(false));
+    assert_eq!(entries[5].changes["done"].after, json!(true));
+    // Creates and deletes diff against nothing
+    assert_eq!(entries[4].changes["title"].before, Value::Null);
+    assert_eq!(entries[6].changes["title"].after, Value::Null);
+
+    // Filters combine, and the limit keeps the oldest entries
+    let filtered = |filter: AuditFilter| {
+        let audit = services.audit.clone();
+        let acme = acme.clone();
+        async move { audit.list_entries(&acme, &filter).await.unwrap() }
+    };
+    let task_entries = filtered(AuditFilter {
+        entity_type: Some(EntityType::Task),
+        ..AuditFilter::default()
+    })
+    .await;
+    assert_eq!(task_entries.len(), 3);
+    assert!(task_entries.iter().all(|entry| entry.entity_id == fuel.id));
+    let by_alice = filtered(AuditFilter {
+        actor: Some("alice".to_string()),
+        limit: Some(2),
+        ..AuditFilter::default()
+    })
+    .await;
+    assert_eq!(by_alice.len(), 2);
+    assert_eq!(by_alice[0].action, AuditAction::Create);
+    let project_entries = filtered(AuditFilter {
+        entity_id: Some(rockets.id),
+        entity_type: Some(EntityType::Project),
+        ..AuditFilter::default()
+    })
+    .await;
+    assert_eq!(project_entries.len(), 2);
+    let window = filtered(AuditFilter {
+        from: Some(entries[4].timestamp),
+        to: Some(entries[6].timestamp),
+        ..AuditFilter::default()
+    })
+    .await;
+    assert!(window
+        .iter()
+        .all(|entry| entry.timestamp >= entries[4].timestamp
+            && entry.timestamp < entries[6].timestamp));
+    assert!(window.iter().any(|entry| entry.id == entries[4].id));
+    assert!(!window.iter().any(|entry| entry.id == entries[6].id));
+    let result = services
+        .audit
+        .list_entries(
+            &acme,
+            &AuditFilter {
+                from: entries[6].timestamp.into(),
+                to: entries[0].timestamp.into(),
+                ..AuditFilter::default()
+            },
+        )
+        .await;
+    assert!(matches!(result, Err(ApiError::ValidationError { .. })));
+
+    // Other tenants see none of it, and non-admins see nothing at all
+    assert!(services
+        .audit
+        .list_entries(&admin("globex"), &AuditFilter::default())
+        .await
+        .unwrap()
+        .is_empty());
+    let result = services
+        .audit
+        .list_entries(&alice, &AuditFilter::default())
+        .await;
+    assert!(matches!(result, Err(ApiError::Forbidden { .. })));
+}
+
+#[ntex::test]
+async fn audit_log_is_listed_and_exported_over_http_by_admins() {
+    let services = services(QuotaPolicy::default());
+    let app = test::init_service(
+        App::new()
+            .state(services.projects.clone())
+            .state(services.audit.clone())
+            .wrap(JwtAuth::new(Some(jwt_verifier())))
+            .wrap(RequestIdentifier)
+            .configure(configure_routes),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/projects")
+        .header("Authorization", admin_bearer("acme"))
+        .header("X-Request-Id", "req-42")
+        .set_json(&json!({ "name": "Rockets" }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status().as_u16(), 201);
+
+    let get = |uri: &str, authorization: String| {
+        test::TestRequest::get()
+            .uri(uri)
+            .header("Authorization", authorization)
+            .to_request()
+    };
+    let resp = test::call_service(
+        &app,
+        get("/api/v1/audit?entity_type=project", admin_bearer("acme")),
+    )
+    .await;
+    assert_eq!(resp.status().as_u16(), 200);
+    let body: Value = serde_json::from_slice(&test::read_body(resp).await).unwrap();
+    let listed = body["data"].as_array().unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0]["action"], "create");
+    assert_eq!(listed[0]["request_id"], "req-42");
+
+    let resp = test::call_service(&app, get("/api/v1/audit/export", admin_bearer("acme"))).await;
+    assert_eq!(resp.status().as_u16(), 200);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/x-ndjson"
+    );
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert!(body.ends_with('\n'));
+    let exported: Vec<AuditEntry> = body
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(exported.len(), 2);
+    assert_eq!(
+        exported[0].id.to_string(),
+        listed[0]["id"].as_str().unwrap()
+    );
+    assert_eq!(exported[1].entity_type, EntityType::ProjectMember);
+
+    let resp = test::call_service(
+        &app,
+        get(
+            "/api/v1/audit?from=2030-01-01T00:00:00Z&to=2020-01-01T00:00:00Z",
+            admin_bearer("acme"),
+        ),
+    )
+    .await;
+    assert_eq!(resp.status().as_u16(), 400);
+
+    // The audit log is admin-only, listed or exported
+    for uri in ["/api/v1/audit", "/api/v1/audit/export"] {
+        let resp = test::call_service(&app, get(uri, bearer("acme", "alice", ""))).await;
+        assert_eq!(resp.status().as_u16(), 403, "{}", uri);
+    }
+}
@@ -0,0 +1,311 @@
+mod common;
+
+use common::{admin, admin_bearer, jwt_verifier, member, project, services, task, TestServices};
+use futures::stream;
+use ntex::util::Bytes;
+use ntex::web::{test, App};
+use rust_mvc_api::middleware::JwtAuth;
+use rust_mvc_api::models::{
+    Backup, BackupFormat, ProjectMemberUpdate, ProjectRole, RecurringTaskCreate, RestoreMode,
+    TimerStart,
+};
+use rust_mvc_api::routes::configure_routes;
+use rust_mvc_api::services::{BackupService, QuotaPolicy, TenantQuota};
+use rust_mvc_api::views::ApiError;
+use serde_json::Value;
+use std::convert::Infallible;
+
+fn upload(text: &str) -> stream::Iter<std::vec::IntoIter<Result<Bytes, Infallible>>> {
+    let chunks: Vec<Result<Bytes, Infallible>> = text
+        .as_bytes()
+        .chunks(13)
+        .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+        .collect();
+    stream::iter(chunks)
+}
+
+/// Acme with a project, a live and a trashed task, a member, a recurring task and a running
+/// timer.
+async fn seeded() -> TestServices {
+    let services = services(QuotaPolicy::default());
+    let acme = admin("acme");
+    let rockets = services
+        .projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    let fuel = services
+        .tasks
+        .create_task(&acme, task(rockets.id, "Fuel"))
+        .await
+        .unwrap();
+    let paint = services
+        .tasks
+        .create_task(&acme, task(rockets.id, "Paint"))
+        .await
+        .unwrap();
+    services.tasks.delete_task(&acme, paint.id).await.unwrap();
+    services
+        .projects
+        .set_member(
+            &acme,
+            rockets.id,
+            "alice".to_string(),
+            ProjectMemberUpdate {
+                role: ProjectRole::Editor,
+            },
+        )
+        .await
+        .unwrap();
+    services
+        .recurring
+        .create_recurring_task(
+            &acme,
+            RecurringTaskCreate {
+                project_id: rockets.id,
+                title: "Standup".to_string(),
+                description: None,
+                rrule: "FREQ=DAILY".to_string(),
+                starts_at: None,
+            },
+        )
+        .await
+        .unwrap();
+    services
+        .time
+        .start_timer(
+            &member("acme", "alice"),
+            TimerStart {
+                task_id: fuel.id,
+                note: None,
+            },
+        )
+        .await
+        .unwrap();
+    services
+}
+
+/// A backup as JSON, without the instant it was taken.
+fn contents(backup: &Backup) -> Value {
+    let mut value = serde_json::to_value(backup).unwrap();
+    value.as_object_mut().unwrap().remove("created_at");
+    value
+}
+
+#[ntex::test]
+async fn backups_round_trip_into_a_fresh_store() {
+    let source = seeded().await;
+    let acme = admin("acme");
+    let backup = source.backups.export(&acme).await.unwrap();
+    assert_eq!(
+        (
+            backup.projects.len(),
+            backup.tasks.len(),
+            backup.members.len(),
+            backup.recurring_tasks.len(),
+            backup.time_entries.len()
+        ),
+        (1, 2, 2, 1, 1)
+    );
+    assert_eq!(backup.recurring_tasks[0].created_by_scopes, ["admin"]);
+
+    let ndjson = backup.to_ndjson().unwrap();
+    let first: Value = serde_json::from_str(ndjson.lines().next().unwrap()).unwrap();
+    assert_eq!(first["type"], "header");
+    assert_eq!(first["data"]["version"], 1);
+    let json = serde_json::to_string(&backup).unwrap();
+
+    for (text, format) in [(ndjson, BackupFormat::Ndjson), (json, BackupFormat::Json)] {
+        let parsed = BackupService::read_backup(upload(&text), format)
+            .await
+            .unwrap();
+        assert_eq!(contents(&parsed), contents(&backup));
+
+        let target = services(QuotaPolicy::default());
+        let report = target
+            .backups
+            .restore(&acme, parsed, RestoreMode::Merge)
+            .await
+            .unwrap();
+        assert_eq!((report.projects, report.tasks), (1, 2));
+        let restored = target.backups.export(&acme).await.unwrap();
+        assert_eq!(contents(&restored), contents(&backup));
+
+        // The restored data is live: trash, timers and membership all carry over.
+        assert_eq!(target.trash.list_trash(&acme).await.unwrap().tasks.len(), 1);
+        let alice = member("acme", "alice");
+        assert!(target.time.current_timer(&alice).await.unwrap().is_some());
+        assert_eq!(
+            target.tasks.list_tasks(&alice).await.unwrap()[0].id,
+            backup.tasks[0].id
+        );
+    }
+
+    let truncated = BackupService::read_backup(
+        upload("{\"type\":\"project\",\"data\":{}}\n"),
+        BackupFormat::Ndjson,
+    )
+    .await;
+    assert!(matches!(truncated, Err(ApiError::BadRequest { .. })));
+}
+
+#[ntex::test]
+async fn purges_take_dependent_records_out_of_later_backups() {
+    let source = seeded().await;
+    let acme = admin("acme");
+    let round_trip = |expected: (usize, usize, usize, usize)| {
+        let backups = source.backups.clone();
+        let acme = acme.clone();
+        async move {
+            let backup = backups.export(&acme).await.unwrap();
+            assert_eq!(
+                (
+                    backup.projects.len(),
+                    backup.tasks.len(),
+                    backup.recurring_tasks.len(),
+                    backup.time_entries.len()
+                ),
+                expected
+            );
+            let target = services(QuotaPolicy::default());
+            target
+                .backups
+                .restore(&acme, backup.clone(), RestoreMode::Merge)
+                .await
+                .unwrap();
+            let restored = target.backups.export(&acme).await.unwrap();
+            assert_eq!(contents(&restored), contents(&backup));
+        }
+    };
+
+    // Purging the timed task takes its time entry with it
+    let fuel = source.tasks.list_tasks(&acme).await.unwrap()[0].clone();
+    source.tasks.delete_task(&acme, fuel.id).await.unwrap();
+    source.trash.purge_task(&acme, fuel.id).await.unwrap();
+    round_trip((1, 1, 1, 0)).await;
+
+    // Purging the project takes its recurring task with it
+    source
+        .projects
+        .delete_project(&acme, fuel.project_id)
+        .await
+        .unwrap();
+    source
+        .trash
+        .purge_project(&acme, fuel.project_id)
+        .await
+        .unwrap();
+    round_trip((0, 0, 0, 0)).await;
+}
+
+#[ntex::test]
+async fn restores_are_checked_before_anything_is_written() {
+    let source = seeded().await;
+    let acme = admin("acme");
+    let backup = source.backups.export(&acme).await.unwrap();
+
+    let target = services(QuotaPolicy::default());
+    let mut broken = backup.clone();
+    broken.projects.clear();
+    broken.tasks.push(broken.tasks[0].clone());
+    let result = target
+        .backups
+        .restore(&acme, broken, RestoreMode::Merge)
+        .await;
+    let Err(ApiError::ValidationError { message }) = result else {
+        panic!("expected a validation error, got {:?}", result);
+    };
+    assert!(message.contains("appears more than once"), "{}", message);
+    assert!(message.contains("refers to missing project"), "{}", message);
+    assert!(target.backups.export(&acme).await.unwrap().tasks.is_empty());
+
+    // The ids are taken in acme's store, so globex cannot claim them.
+    let globex = admin("globex");
+    let mut stolen = backup.clone();
+    for project in &mut stolen.projects {
+        project.tenant_id = "globex".to_string();
+    }
+    let result = source
+        .backups
+        .restore(&globex, stolen, RestoreMode::Merge)
+        .await;
+    let Err(ApiError::ValidationError { message }) = result else {
+        panic!("expected a validation error, got {:?}", result);
+    };
+    assert!(
+        message.contains("already exists in another tenant"),
+        "{}",
+        message
+    );
+    assert!(message.contains("belongs to tenant 'acme'"), "{}", message);
+
+    let mut future = backup.clone();
+    future.version = 2;
+    let result = target
+        .backups
+        .restore(&acme, future, RestoreMode::Merge)
+        .await;
+    assert!(matches!(result, Err(ApiError::ValidationError { .. })));
+    let result = target
+        .backups
+        .restore(&member("acme", "alice"), backup.clone(), RestoreMode::Merge)
+        .await;
+    assert!(matches!(result, Err(ApiError::Forbidden { .. })));
+
+    let capped = services(QuotaPolicy::new(TenantQuota {
+        max_projects: None,
+        max_tasks: Some(0),
+    }));
+    let result = capped
+        .backups
+        .restore(&acme, backup, RestoreMode::Merge)
+        .await;
+    assert!(matches!(result, Err(ApiError::Conflict { .. })));
+}
+
+#[ntex::test]
+async fn replace_drops_what_the_archive_does_not_hold() {
+    let services = seeded().await;
+    let acme = admin("acme");
+    let app = test::init_service(
+        App::new()
+            .state(services.backups.clone())
+            .wrap(JwtAuth::new(Some(jwt_verifier())))
+            .configure(configure_routes),
+    )
+    .await;
+
+    let req = test::TestRequest::get()
+        .uri("/api/v1/admin/backup")
+        .header("Authorization", admin_bearer("acme"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/x-ndjson"
+    );
+    let archive = test::read_body(resp).await;
+
+    let rovers = services
+        .projects
+        .create_project(&acme, project("Rovers"))
+        .await
+        .unwrap();
+    for (mode, projects) in [("merge", 2), ("replace", 1)] {
+        let req = test::TestRequest::post()
+            .uri(&format!("/api/v1/admin/restore?mode={}", mode))
+            .header("Authorization", admin_bearer("acme"))
+            .set_payload(archive.clone())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success(), "{}", mode);
+        let body: Value = serde_json::from_slice(&test::read_body(resp).await).unwrap();
+        assert_eq!(body["data"]["mode"], mode);
+        assert_eq!(body["data"]["projects"], 1);
+
+        let listed = services.projects.list_projects(&acme, true).await.unwrap();
+        assert_eq!(listed.len(), projects, "{}", mode);
+    }
+    let result = services.projects.get_project(&acme, rovers.id).await;
+    assert!(matches!(result, Err(ApiError::NotFound { .. })));
+}
@@ -0,0 +1,291 @@
+mod common;
+
+use chrono::{TimeZone, Utc};
+use common::{admin, admin_bearer, jwt_verifier, member, project, services, task, TestServices};
+use ntex::web::{test, App};
+use rust_mvc_api::middleware::{CalendarTokenAuth, JwtAuth};
+use rust_mvc_api::models::{
+    CalendarComponent, CalendarTokenCreate, Principal, ProjectMemberUpdate, ProjectRole,
+    TaskCreate, TaskUpdate,
+};
+use rust_mvc_api::routes::configure_routes;
+use rust_mvc_api::services::QuotaPolicy;
+use rust_mvc_api::views::{ical_line, ical_text, task_calendar, ApiError};
+
+#[test]
+fn text_is_escaped_and_long_lines_folded() {
+    assert_eq!(
+        ical_text("Fuel; oxidiser, and\\or\r\nwater"),
+        "Fuel\\; oxidiser\\, and\\\\or\\nwater"
+    );
+    assert_eq!(ical_line("SUMMARY", "Fuel"), "SUMMARY:Fuel\r\n");
+
+    let folded = ical_line("SUMMARY", &"é".repeat(50));
+    let lines: Vec<&str> = folded.trim_end_matches("\r\n").split("\r\n").collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines.iter().all(|line| line.len() <= 75));
+    assert!(lines[1].starts_with(' '));
+    assert_eq!(
+        lines.concat().replacen(' ', "", 1),
+        format!("SUMMARY:{}", "é".repeat(50))
+    );
+}
+
+#[ntex::test]
+async fn project_feed_lists_tasks_with_due_dates() {
+    let TestServices {
+        projects,
+        tasks,
+        calendar,
+        ..
+    } = services(QuotaPolicy::default());
+    let acme = admin("acme");
+    let rockets = projects
+        .create_project(&acme, project("Rockets, Inc."))
+        .await
+        .unwrap();
+    let due_at = Utc.with_ymd_and_hms(2024, 3, 1, 9, 0, 0).unwrap();
+    let fuel = tasks
+        .create_task(
+            &acme,
+            TaskCreate {
+                due_at: Some(due_at),
+                ..task(rockets.id, "Fuel")
+            },
+        )
+        .await
+        .unwrap();
+    let fuel = tasks
+        .update_task(
+            &acme,
+            fuel.id,
+            TaskUpdate {
+                title: None,
+                description: None,
+                done: Some(true),
+                column_id: None,
+                due_at: None,
+            },
+        )
+        .await
+        .unwrap();
+    tasks
+        .create_task(&acme, task(rockets.id, "Someday"))
+        .await
+        .unwrap();
+
+    let (_, due) = calendar.project_calendar(&acme, rockets.id).await.unwrap();
+    let events = task_calendar("Rockets", &due, CalendarComponent::Event, Utc::now());
+    assert!(events.contains(&format!("BEGIN:VEVENT\r\nUID:{}\r\n", fuel.id)));
+    assert!(events.contains("DTSTART:20240301T090000Z\r\n"));
+    assert!(!events.contains("VTODO"));
+
+    let app = test::init_service(
+        App::new()
+            .state(calendar)
+            .wrap(JwtAuth::new(Some(jwt_verifier())))
+            .configure(configure_routes),
+    )
+    .await;
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/projects/{}/calendar.ics", rockets.id))
+        .header("Authorization", admin_bearer("acme"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "text/calendar; charset=utf-8"
+    );
+    let body = test::read_body(resp).await;
+    let ics = String::from_utf8(body.to_vec()).unwrap();
+    assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+    assert!(ics.ends_with("END:VCALENDAR\r\n"));
+    assert!(ics.contains("X-WR-CALNAME:Rockets\\, Inc.\r\n"));
+    assert_eq!(ics.matches("BEGIN:VTODO").count(), 1);
+    for line in [
+        format!("UID:{}", fuel.id),
+        "SUMMARY:Fuel".to_string(),
+        "DUE:20240301T090000Z".to_string(),
+        "STATUS:COMPLETED".to_string(),
+        format!("LAST-MODIFIED:{}", fuel.updated_at.format("%Y%m%dT%H%M%SZ")),
+    ] {
+        assert!(ics.contains(&format!("\r\n{}\r\n", line)), "{}", line);
+    }
+}
+
+#[ntex::test]
+async fn tokens_open_their_owners_feeds_only() {
+    let TestServices {
+        projects,
+        tasks,
+        calendar,
+        ..
+    } = services(QuotaPolicy::default());
+    let acme = admin("acme");
+    let alice = member("acme", "alice");
+    let due_at = Utc.with_ymd_and_hms(2024, 3, 1, 9, 0, 0).unwrap();
+    for (owner, name) in [(&alice, "Rockets"), (&acme, "Rovers")] {
+        let project = projects.create_project(owner, project(name)).await.unwrap();
+        tasks
+            .create_task(
+                owner,
+                TaskCreate {
+                    due_at: Some(due_at),
+                    ..task(project.id, &format!("{} task", name))
+                },
+            )
+            .await
+            .unwrap();
+    }
+    let created = calendar
+        .create_token(
+            &alice,
+            CalendarTokenCreate {
+                name: "Phone".to_string(),
+                expires_at: None,
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        created.feed_url,
+        format!("/api/v1/calendar.ics?token={}", created.token)
+    );
+
+    let app = test::init_service(
+        App::new()
+            .state(calendar.clone())
+            .wrap(JwtAuth::new(Some(jwt_verifier())))
+            .wrap(CalendarTokenAuth::new(calendar.clone()))
+            .configure(configure_routes),
+    )
+    .await;
+    let req = test::TestRequest::get()
+        .uri(&format!("{}&component=event", created.feed_url))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+    let body = test::read_body(resp).await;
+    let ics = String::from_utf8(body.to_vec()).unwrap();
+    assert!(ics.contains("SUMMARY:Rockets task\r\n"));
+    assert!(!ics.contains("Rovers"));
+    assert!(ics.contains("BEGIN:VEVENT"));
+
+    let req = test::TestRequest::get()
+        .uri("/api/v1/calendar.ics?token=0.bogus")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 401);
+
+    let tokens = calendar.list_tokens(&alice).await.unwrap();
+    assert_eq!(tokens.len(), 1);
+    assert!(tokens[0].last_used_at.is_some());
+    assert!(calendar.list_tokens(&acme).await.unwrap().is_empty());
+    calendar
+        .delete_token(&alice, created.calendar_token.id)
+        .await
+        .unwrap();
+    let req = test::TestRequest::get().uri(&created.feed_url).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 401);
+}
+
+#[ntex::test]
+async fn tokens_resolve_access_when_used_and_expire() {
+    let TestServices {
+        projects,
+        tasks,
+        calendar,
+        ..
+    } = services(QuotaPolicy::default());
+    let acme = admin("acme");
+    let alice = member("acme", "alice");
+    let bob = member("acme", "bob");
+    let due_at = Utc.with_ymd_and_hms(2024, 3, 1, 9, 0, 0).unwrap();
+    let mut ids = Vec::new();
+    for (owner, name) in [(&acme, "Rovers"), (&bob, "Probes")] {
+        let project = projects.create_project(owner, project(name)).await.unwrap();
+        tasks
+            .create_task(
+                owner,
+                TaskCreate {
+                    due_at: Some(due_at),
+                    ..task(project.id, name)
+                },
+            )
+            .await
+            .unwrap();
+        ids.push(project.id);
+    }
+    projects
+        .set_member(
+            &acme,
+            ids[0],
+            "alice".to_string(),
+            ProjectMemberUpdate {
+                role: ProjectRole::Viewer,
+            },
+        )
+        .await
+        .unwrap();
+    let mint = |owner: Principal, expires_at| {
+        let calendar = calendar.clone();
+        async move {
+            calendar
+                .create_token(
+                    &owner,
+                    CalendarTokenCreate {
+                        name: "Phone".to_string(),
+                        expires_at,
+                    },
+                )
+                .await
+        }
+    };
+    let feed = |token: String| {
+        let calendar = calendar.clone();
+        async move {
+            let owner = calendar.authenticate(&token).await?;
+            let titles: Vec<String> = calendar
+                .user_calendar(&owner)
+                .await?
+                .into_iter()
+                .map(|task| task.title)
+                .collect();
+            Ok::<_, ApiError>(titles)
+        }
+    };
+
+    // Membership changes after minting apply to the token right away
+    let token = mint(alice.clone(), None).await.unwrap().token;
+    assert_eq!(feed(token.clone()).await.unwrap(), ["Rovers"]);
+    projects
+        .remove_member(&acme, ids[0], "alice")
+        .await
+        .unwrap();
+    assert!(feed(token).await.unwrap().is_empty());
+
+    // An admin's token does not carry the admin scope
+    let token = mint(acme.clone(), None).await.unwrap().token;
+    assert_eq!(feed(token).await.unwrap(), ["Rovers"]);
+
+    assert!(matches!(
+        mint(bob.clone(), Some(Utc::now() - chrono::Duration::minutes(1))).await,
+        Err(ApiError::ValidationError { .. })
+    ));
+    let created = mint(
+        bob.clone(),
+        Some(Utc::now() + chrono::Duration::milliseconds(300)),
+    )
+    .await
+    .unwrap();
+    assert!(created.calendar_token.expires_at.is_some());
+    assert_eq!(feed(created.token.clone()).await.unwrap(), ["Probes"]);
+    ntex::time::sleep(ntex::time::Millis(400)).await;
+    match feed(created.token).await {
+        Err(ApiError::Unauthorized { message }) => {
+            assert_eq!(message, "Calendar token has expired")
+        }
+        other => panic!("expected 401, got {:?}", other),
+    }
+}
@@ -0,0 +1,232 @@
+mod common;
+
+use common::{admin, member, project, services, task};
+use rust_mvc_api::models::{
+    ChangeFeed, ChangeOperation, EntityType, ProjectMemberUpdate, ProjectRole, TaskUpdate,
+};
+use rust_mvc_api::services::QuotaPolicy;
+use rust_mvc_api::views::ApiError;
+use uuid::Uuid;
+
+fn summary(feed: &ChangeFeed) -> Vec<(u64, EntityType, Uuid, ChangeOperation)> {
+    feed.changes
+        .iter()
+        .map(|change| {
+            (
+                change.seq,
+                change.entity_type,
+                change.entity_id,
+                change.operation,
+            )
+        })
+        .collect()
+}
+
+#[ntex::test]
+async fn changes_are_ordered_compacted_and_paged() {
+    let services = services(QuotaPolicy::default());
+    let acme = admin("acme");
+
+    let rockets = services
+        .projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    let fuel = services
+        .tasks
+        .create_task(&acme, task(rockets.id, "Fuel"))
+        .await
+        .unwrap();
+    let paint = services
+        .tasks
+        .create_task(&acme, task(rockets.id, "Paint"))
+        .await
+        .unwrap();
+    services
+        .tasks
+        .update_task(
+            &acme,
+            fuel.id,
+            TaskUpdate {
+                title: None,
+                description: None,
+                done: Some(true),
+                column_id: None,
+                due_at: None,
+            },
+        )
+        .await
+        .unwrap();
+    services.tasks.delete_task(&acme, paint.id).await.unwrap();
+
+    let feed = services.changes.list_changes(&acme, 0, None).await.unwrap();
+    assert_eq!(
+        summary(&feed),
+        vec![
+            (1, EntityType::Project, rockets.id, ChangeOperation::Upsert),
+            (4, EntityType::Task, fuel.id, ChangeOperation::Upsert),
+            (5, EntityType::Task, paint.id, ChangeOperation::Delete),
+        ]
+    );
+    assert_eq!(feed.changes[1].data.as_ref().unwrap()["done"], true);
+    assert!(feed.changes[2].data.is_none());
+    assert_eq!(feed.next_since, 5);
+    assert!(!feed.has_more);
+
+    let first = services
+        .changes
+        .list_changes(&acme, 0, Some(2))
+        .await
+        .unwrap();
+    assert_eq!(first.changes.len(), 2);
+    assert!(first.has_more);
+    assert_eq!(first.next_since, 4);
+    let rest = services
+        .changes
+        .list_changes(&acme, first.next_since, Some(2))
+        .await
+        .unwrap();
+    assert_eq!(
+        summary(&rest),
+        vec![(5, EntityType::Task, paint.id, ChangeOperation::Delete)]
+    );
+    assert!(!rest.has_more);
+
+    let idle = services
+        .changes
+        .list_changes(&acme, rest.next_since, None)
+        .await
+        .unwrap();
+    assert!(idle.changes.is_empty());
+    assert_eq!(idle.next_since, 5);
+
+    assert!(matches!(
+        services.changes.list_changes(&acme, 0, Some(0)).await,
+        Err(ApiError::ValidationError { .. })
+    ));
+}
+
+#[ntex::test]
+async fn changes_respect_tenants_and_membership() {
+    let services = services(QuotaPolicy::default());
+    let alice = member("acme", "alice");
+    let bob = member("acme", "bob");
+
+    let secret = services
+        .projects
+        .create_project(&alice, project("Secret"))
+        .await
+        .unwrap();
+    let hidden = services
+        .tasks
+        .create_task(&alice, task(secret.id, "Hidden"))
+        .await
+        .unwrap();
+    let own = services
+        .projects
+        .create_project(&bob, project("Own"))
+        .await
+        .unwrap();
+    services
+        .projects
+        .create_project(&admin("globex"), project("Elsewhere"))
+        .await
+        .unwrap();
+
+    let feed = services.changes.list_changes(&bob, 0, None).await.unwrap();
+    assert_eq!(
+        summary(&feed),
+        vec![(3, EntityType::Project, own.id, ChangeOperation::Upsert)]
+    );
+    // The head of the sequence moves past other tenants' changes as well.
+    assert_eq!(feed.next_since, 4);
+
+    // Deleting a project leaves tombstones for it and its tasks, for its members only.
+    services
+        .projects
+        .delete_project(&alice, secret.id)
+        .await
+        .unwrap();
+    let feed = services
+        .changes
+        .list_changes(&bob, feed.next_since, None)
+        .await
+        .unwrap();
+    assert!(feed.changes.is_empty());
+    let feed = services
+        .changes
+        .list_changes(&alice, 0, None)
+        .await
+        .unwrap();
+    let mut tombstones: Vec<_> = feed
+        .changes
+        .iter()
+        .map(|change| (change.entity_type, change.entity_id, change.operation))
+        .collect();
+    tombstones.sort_by_key(|(entity_type, _, _)| *entity_type == EntityType::Task);
+    assert_eq!(
+        tombstones,
+        vec![
+            (EntityType::Project, secret.id, ChangeOperation::Delete),
+            (EntityType::Task, hidden.id, ChangeOperation::Delete),
+        ]
+    );
+
+    let globex = services
+        .changes
+        .list_changes(&admin("globex"), 0, None)
+        .await
+        .unwrap();
+    assert_eq!(globex.changes.len(), 1);
+}
+
+#[ntex::test]
+async fn new_members_receive_the_project_from_their_cursor() {
+    let services = services(QuotaPolicy::default());
+    let alice = member("acme", "alice");
+    let bob = member("acme", "bob");
+
+    let rockets = services
+        .projects
+        .create_project(&alice, project("Rockets"))
+        .await
+        .unwrap();
+    let fuel = services
+        .tasks
+        .create_task(&alice, task(rockets.id, "Fuel"))
+        .await
+        .unwrap();
+    let feed = services.changes.list_changes(&bob, 0, None).await.unwrap();
+    assert!(feed.changes.is_empty());
+
+    services
+        .projects
+        .set_member(
+            &alice,
+            rockets.id,
+            "bob".to_string(),
+            ProjectMemberUpdate {
+                role: ProjectRole::Viewer,
+            },
+        )
+        .await
+        .unwrap();
+    let feed = services
+        .changes
+        .list_changes(&bob, feed.next_since, None)
+        .await
+        .unwrap();
+    let mut entities: Vec<_> = feed
+        .changes
+        .iter()
+        .map(|change| (change.entity_type, change.entity_id, change.operation))
+        .collect();
+    entities.sort_by_key(|(entity_type, _, _)| *entity_type == EntityType::Task);
+    assert_eq!(
+        entities,
+        vec![
+            (EntityType::Project, rockets.id, ChangeOperation::Upsert),
+            (EntityType::Task, fuel.id, ChangeOperation::Upsert),
+        ]
+    );
+}
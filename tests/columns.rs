@@ -0,0 +1,311 @@
+mod common;
+
+use common::{admin, admin_bearer, jwt_verifier, project, services, task, TestServices};
+use ntex::http::StatusCode;
+use ntex::web::{test, App};
+use rust_mvc_api::middleware::JwtAuth;
+use rust_mvc_api::models::{Column, ColumnDefinition, EventKind, TaskCreate, TaskUpdate};
+use rust_mvc_api::routes::configure_routes;
+use rust_mvc_api::services::QuotaPolicy;
+use rust_mvc_api::views::ApiError;
+use serde_json::json;
+use uuid::Uuid;
+
+fn column(name: &str, wip_limit: Option<usize>, done: bool) -> ColumnDefinition {
+    ColumnDefinition {
+        id: None,
+        name: name.to_string(),
+        wip_limit,
+        done,
+    }
+}
+
+fn keep(column: &Column) -> ColumnDefinition {
+    ColumnDefinition {
+        id: Some(column.id),
+        name: column.name.clone(),
+        wip_limit: column.wip_limit,
+        done: column.done,
+    }
+}
+
+fn to_column(id: Uuid) -> TaskUpdate {
+    TaskUpdate {
+        title: None,
+        description: None,
+        done: None,
+        column_id: Some(id),
+        due_at: None,
+    }
+}
+
+fn mark_done(done: bool) -> TaskUpdate {
+    TaskUpdate {
+        title: None,
+        description: None,
+        done: Some(done),
+        due_at: None,
+        column_id: None,
+    }
+}
+
+#[ntex::test]
+async fn the_done_column_drives_the_done_flag() {
+    let services = services(QuotaPolicy::default());
+    let acme = admin("acme");
+    let rockets = services
+        .projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    let columns = services
+        .projects
+        .set_columns(
+            &acme,
+            rockets.id,
+            vec![
+                column("Backlog", None, false),
+                column("Doing", Some(2), false),
+                column("Done", None, true),
+            ],
+        )
+        .await
+        .unwrap();
+    let [backlog, doing, done] = [&columns[0], &columns[1], &columns[2]];
+
+    // New tasks start in the first column.
+    let fuel = services
+        .tasks
+        .create_task(&acme, task(rockets.id, "Fuel"))
+        .await
+        .unwrap();
+    assert_eq!(fuel.column_id, Some(backlog.id));
+    assert!(!fuel.done);
+
+    let fuel = services
+        .tasks
+        .update_task(&acme, fuel.id, to_column(done.id))
+        .await
+        .unwrap();
+    assert!(fuel.done);
+
+    // Clients that only know about `done` move tasks in and out of the done column.
+    let fuel = services
+        .tasks
+        .update_task(&acme, fuel.id, mark_done(false))
+        .await
+        .unwrap();
+    assert_eq!(fuel.column_id, Some(backlog.id));
+    assert!(!fuel.done);
+    let fuel = services
+        .tasks
+        .update_task(&acme, fuel.id, mark_done(true))
+        .await
+        .unwrap();
+    assert_eq!(fuel.column_id, Some(done.id));
+
+    let result = services
+        .tasks
+        .update_task(&acme, fuel.id, to_column(Uuid::new_v4()))
+        .await;
+    assert!(matches!(result, Err(ApiError::ValidationError { .. })));
+
+    // A column with tasks cannot be dropped from the board.
+    let result = services
+        .projects
+        .set_columns(&acme, rockets.id, vec![keep(backlog), keep(doing)])
+        .await;
+    assert!(matches!(result, Err(ApiError::Conflict { .. })));
+}
+
+#[ntex::test]
+async fn marking_a_column_done_updates_the_tasks_in_it() {
+    let services = services(QuotaPolicy::default());
+    let acme = admin("acme");
+    let mut events = services.events.subscribe("test");
+    let rockets = services
+        .projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    let columns = services
+        .projects
+        .set_columns(
+            &acme,
+            rockets.id,
+            vec![
+                column("Backlog", None, false),
+                column("Shipped", None, false),
+            ],
+        )
+        .await
+        .unwrap();
+    let fuel = services
+        .tasks
+        .create_task(
+            &acme,
+            TaskCreate {
+                column_id: Some(columns[1].id),
+                ..task(rockets.id, "Fuel")
+            },
+        )
+        .await
+        .unwrap();
+    assert!(!fuel.done);
+    while events.try_recv().is_ok() {}
+
+    let shipped = ColumnDefinition {
+        done: true,
+        ..keep(&columns[1])
+    };
+    services
+        .projects
+        .set_columns(&acme, rockets.id, vec![keep(&columns[0]), shipped])
+        .await
+        .unwrap();
+    assert!(services.tasks.get_task(&acme, fuel.id).await.unwrap().done);
+    let kinds: Vec<EventKind> = std::iter::from_fn(|| events.try_recv().ok())
+        .map(|envelope| envelope.event.kind())
+        .collect();
+    assert_eq!(kinds, [EventKind::ProjectUpdated, EventKind::TaskUpdated]);
+
+    // Moving the done flag to another column reopens the task.
+    let backlog = ColumnDefinition {
+        done: true,
+        ..keep(&columns[0])
+    };
+    services
+        .projects
+        .set_columns(&acme, rockets.id, vec![backlog, keep(&columns[1])])
+        .await
+        .unwrap();
+    assert!(!services.tasks.get_task(&acme, fuel.id).await.unwrap().done);
+}
+
+#[ntex::test]
+async fn wip_limits_are_enforced() {
+    let services = services(QuotaPolicy::default());
+    let acme = admin("acme");
+    let rockets = services
+        .projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    let columns = services
+        .projects
+        .set_columns(
+            &acme,
+            rockets.id,
+            vec![
+                column("Backlog", None, false),
+                column("Doing", Some(1), false),
+            ],
+        )
+        .await
+        .unwrap();
+    let doing = columns[1].id;
+
+    let fuel = services
+        .tasks
+        .create_task(
+            &acme,
+            TaskCreate {
+                column_id: Some(doing),
+                ..task(rockets.id, "Fuel")
+            },
+        )
+        .await
+        .unwrap();
+    let paint = services
+        .tasks
+        .create_task(&acme, task(rockets.id, "Paint"))
+        .await
+        .unwrap();
+
+    let result = services
+        .tasks
+        .update_task(&acme, paint.id, to_column(doing))
+        .await;
+    assert!(matches!(result, Err(ApiError::Conflict { .. })));
+    let result = services
+        .tasks
+        .create_task(
+            &acme,
+            TaskCreate {
+                column_id: Some(doing),
+                ..task(rockets.id, "Launch")
+            },
+        )
+        .await;
+    assert!(matches!(result, Err(ApiError::Conflict { .. })));
+
+    // Staying in a full column is fine; leaving it makes room.
+    services
+        .tasks
+        .update_task(&acme, fuel.id, to_column(doing))
+        .await
+        .unwrap();
+    services.tasks.delete_task(&acme, fuel.id).await.unwrap();
+    services
+        .tasks
+        .update_task(&acme, paint.id, to_column(doing))
+        .await
+        .unwrap();
+}
+
+#[ntex::test]
+async fn boards_are_validated_over_http() {
+    let TestServices {
+        projects, tasks, ..
+    } = services(QuotaPolicy::default());
+    let acme = admin("acme");
+    let rockets = projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+
+    let app = test::init_service(
+        App::new()
+            .state(projects)
+            .state(tasks)
+            .wrap(JwtAuth::new(Some(jwt_verifier())))
+            .configure(configure_routes),
+    )
+    .await;
+    let uri = format!("/api/v1/projects/{}/columns", rockets.id);
+
+    let req = test::TestRequest::put()
+        .uri(&uri)
+        .header("Authorization", admin_bearer("acme"))
+        .set_json(&json!([
+            {"name": "Done", "done": true},
+            {"name": "Shipped", "done": true}
+        ]))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let req = test::TestRequest::put()
+        .uri(&uri)
+        .header("Authorization", admin_bearer("acme"))
+        .set_json(&json!([
+            {"name": "To do", "wip_limit": 5},
+            {"name": "Done", "done": true}
+        ]))
+        .to_request();
+    let body: serde_json::Value = test::read_response_json(&app, req).await;
+    assert_eq!(body["data"][0]["wip_limit"], 5);
+
+    let req = test::TestRequest::get()
+        .uri(&uri)
+        .header("Authorization", admin_bearer("acme"))
+        .to_request();
+    let body: serde_json::Value = test::read_response_json(&app, req).await;
+    let names: Vec<_> = body["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|column| column["name"].clone())
+        .collect();
+    assert_eq!(names, [json!("To do"), json!("Done")]);
+}
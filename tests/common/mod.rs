@@ -0,0 +1,223 @@
+#![allow(dead_code)]
+
+use chrono::{Duration, Utc};
+use jsonwebtoken::{encode, EncodingKey, Header};
+use rust_mvc_api::config::Config;
+use rust_mvc_api::middleware::JwtVerifier;
+use rust_mvc_api::models::{Principal, ProjectCreate, TaskCreate};
+use rust_mvc_api::repositories::{
+    AuditRepository, CalendarTokenRepository, ChangeLog, MembershipRepository, ProjectRepository,
+    RecurringTaskRepository, ReminderRepository, RevisionRepository, TaskRepository,
+    TimeEntryRepository, WebhookDeliveryRepository, WebhookRepository,
+};
+use rust_mvc_api::services::{
+    AuditService, BackupService, CalendarService, ChangeService, EventBus, EventStream,
+    ProjectService, QuotaPolicy, RecurringTaskService, ReminderService, RetryPolicy, TaskService,
+    TimeService, TrashDependents, TrashService, WebhookService,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Services wired against fresh in-memory repositories.
+pub struct TestServices {
+    pub projects: Arc<ProjectService>,
+    pub tasks: Arc<TaskService>,
+    pub audit: Arc<AuditService>,
+    pub changes: Arc<ChangeService>,
+    pub events: Arc<EventBus>,
+    /// Not attached to `events`; tests push the envelopes they want streamed.
+    pub stream: Arc<EventStream>,
+    /// Gives up after three attempts, using the default backoff.
+    pub webhooks: Arc<WebhookService>,
+    /// Keeps deleted items for seven days; the purge job is not started.
+    pub trash: Arc<TrashService>,
+    pub time: Arc<TimeService>,
+    /// The scheduler is not started; tests call `run_due` themselves.
+    pub recurring: Arc<RecurringTaskService>,
+    /// Sends due-soon reminders a day ahead; the job is not started.
+    pub reminders: Arc<ReminderService>,
+    pub calendar: Arc<CalendarService>,
+    pub backups: Arc<BackupService>,
+}
+
+pub fn services(quotas: QuotaPolicy) -> TestServices {
+    let change_log = Arc::new(ChangeLog::new());
+    let project_repository = Arc::new(ProjectRepository::new(change_log.clone()));
+    let task_repository = Arc::new(TaskRepository::new(change_log.clone()));
+    let membership_repository = Arc::new(MembershipRepository::new());
+    let audit = Arc::new(AuditService::new(
+        Arc::new(AuditRepository::new()),
+        Arc::new(RevisionRepository::new()),
+    ));
+    let time_entry_repository = Arc::new(TimeEntryRepository::new());
+    let recurring_task_repository = Arc::new(RecurringTaskRepository::new());
+    let events = Arc::new(EventBus::new());
+
+    let projects = Arc::new(ProjectService::new(
+        project_repository.clone(),
+        task_repository.clone(),
+        membership_repository.clone(),
+        quotas.clone(),
+        audit.as_ref().clone(),
+        events.clone(),
+    ));
+    let stream = Arc::new(EventStream::new(membership_repository.clone()));
+    let changes = Arc::new(ChangeService::new(
+        change_log,
+        membership_repository.clone(),
+    ));
+    let webhooks = Arc::new(WebhookService::new(
+        Arc::new(WebhookRepository::new()),
+        Arc::new(WebhookDeliveryRepository::new()),
+        project_repository.clone(),
+        RetryPolicy {
+            max_attempts: 3,
+            timeout: std::time::Duration::from_secs(1),
+            ..RetryPolicy::default()
+        },
+    ));
+    let reminder_repository = Arc::new(ReminderRepository::new());
+    let trash = Arc::new(TrashService::new(
+        project_repository.clone(),
+        task_repository.clone(),
+        TrashDependents {
+            memberships: membership_repository.clone(),
+            time_entries: time_entry_repository.clone(),
+            recurring_tasks: recurring_task_repository.clone(),
+            reminders: reminder_repository.clone(),
+        },
+        quotas.clone(),
+        audit.as_ref().clone(),
+        events.clone(),
+        Duration::days(7),
+    ));
+    let time = Arc::new(TimeService::new(
+        time_entry_repository.clone(),
+        task_repository.clone(),
+        project_repository.clone(),
+        membership_repository.clone(),
+    ));
+    let reminders = Arc::new(ReminderService::new(
+        reminder_repository,
+        task_repository.clone(),
+        project_repository.clone(),
+        membership_repository.clone(),
+        events.clone(),
+        Duration::hours(24),
+    ));
+    let calendar = Arc::new(CalendarService::new(
+        Arc::new(CalendarTokenRepository::new()),
+        task_repository.clone(),
+        project_repository.clone(),
+        membership_repository.clone(),
+    ));
+    let backups = Arc::new(BackupService::new(
+        project_repository.clone(),
+        task_repository.clone(),
+        membership_repository.clone(),
+        recurring_task_repository.clone(),
+        time_entry_repository,
+        quotas.clone(),
+    ));
+    let tasks = Arc::new(TaskService::new(
+        task_repository,
+        project_repository.clone(),
+        membership_repository.clone(),
+        quotas,
+        audit.as_ref().clone(),
+        events.clone(),
+    ));
+    let recurring = Arc::new(RecurringTaskService::new(
+        recurring_task_repository,
+        project_repository,
+        membership_repository,
+        tasks.clone(),
+    ));
+
+    TestServices {
+        projects,
+        tasks,
+        audit,
+        changes,
+        events,
+        stream,
+        webhooks,
+        trash,
+        time,
+        recurring,
+        reminders,
+        calendar,
+        backups,
+    }
+}
+
+pub fn admin(tenant_id: &str) -> Principal {
+    Principal {
+        subject: format!("admin@{}", tenant_id),
+        name: None,
+        scopes: vec!["admin".to_string()],
+        tenant_id: tenant_id.to_string(),
+        request_id: None,
+    }
+}
+
+pub fn member(tenant_id: &str, subject: &str) -> Principal {
+    Principal {
+        subject: subject.to_string(),
+        name: None,
+        scopes: vec![],
+        tenant_id: tenant_id.to_string(),
+        request_id: None,
+    }
+}
+
+pub fn project(name: &str) -> ProjectCreate {
+    ProjectCreate {
+        name: name.to_string(),
+        description: None,
+    }
+}
+
+pub fn task(project_id: Uuid, title: &str) -> TaskCreate {
+    TaskCreate {
+        project_id,
+        title: title.to_string(),
+        description: None,
+        column_id: None,
+        due_at: None,
+        occurrence: None,
+    }
+}
+
+/// HS256 secret that [`jwt_verifier`] trusts and [`bearer`] signs with.
+pub const JWT_SECRET: &str = "test-secret";
+
+pub fn jwt_verifier() -> Arc<JwtVerifier> {
+    let config = Config {
+        jwt_secret: Some(JWT_SECRET.to_string()),
+        ..Config::default()
+    };
+    Arc::new(JwtVerifier::from_config(&config).unwrap().unwrap())
+}
+
+/// An `Authorization` value for `subject` in `tenant_id`, valid for an hour.
+pub fn bearer(tenant_id: &str, subject: &str, scope: &str) -> String {
+    let claims = serde_json::json!({
+        "sub": subject,
+        "tenant": tenant_id,
+        "scope": scope,
+        "exp": Utc::now().timestamp() + 3600,
+    });
+    let token = encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(JWT_SECRET.as_bytes()),
+    )
+    .unwrap();
+    format!("Bearer {}", token)
+}
+
+/// An `Authorization` value for the same caller as [`admin`].
+pub fn admin_bearer(tenant_id: &str) -> String {
+    bearer(tenant_id, &format!("admin@{}", tenant_id), "admin")
+}
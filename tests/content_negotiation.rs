@@ -0,0 +1,219 @@
+mod common;
+
+use common::{admin, admin_bearer, jwt_verifier, services};
+use ntex::web::{test, App};
+use rust_mvc_api::middleware::{ContentNegotiation, JwtAuth};
+use rust_mvc_api::routes::configure_routes;
+use rust_mvc_api::services::QuotaPolicy;
+use rust_mvc_api::views::{ApiError, ContentFormat};
+use serde_json::Value;
+
+macro_rules! negotiation_app {
+    ($services:expr) => {
+        test::init_service(
+            App::new()
+                .state($services.projects.clone())
+                .state($services.tasks.clone())
+                .wrap(JwtAuth::new(Some(jwt_verifier())))
+                .wrap(ContentNegotiation)
+                .configure(configure_routes),
+        )
+        .await
+    };
+}
+
+#[test]
+fn accept_headers_pick_the_best_supported_format() {
+    for accept in [
+        None,
+        Some(""),
+        Some("*/*"),
+        Some("application/*, text/html"),
+    ] {
+        assert_eq!(
+            ContentFormat::negotiate(accept).unwrap(),
+            ContentFormat::Json
+        );
+    }
+    for accept in ["text/html", "application/json;q=0, text/*", "image/*"] {
+        let result = ContentFormat::negotiate(Some(accept));
+        assert!(
+            matches!(result, Err(ApiError::NotAcceptable { .. })),
+            "{}",
+            accept
+        );
+    }
+
+    #[cfg(feature = "msgpack")]
+    {
+        let format =
+            ContentFormat::negotiate(Some("application/json;q=0.5, application/x-msgpack"));
+        assert_eq!(format.unwrap(), ContentFormat::MessagePack);
+        assert_eq!(
+            ContentFormat::negotiate(Some("application/*")).unwrap(),
+            ContentFormat::Json
+        );
+    }
+    #[cfg(feature = "cbor")]
+    {
+        let format = ContentFormat::negotiate(Some("application/cbor, application/json;q=0.9"));
+        assert_eq!(format.unwrap(), ContentFormat::Cbor);
+    }
+}
+
+#[ntex::test]
+async fn unsupported_formats_get_406_and_415() {
+    let services = services(QuotaPolicy::default());
+    let app = negotiation_app!(services);
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/projects")
+        .header("Authorization", admin_bearer("acme"))
+        .header("Content-Type", "text/plain")
+        .set_payload("Rockets")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status().as_u16(), 415);
+    let body: Value = serde_json::from_slice(&test::read_body(resp).await).unwrap();
+    assert_eq!(body["error"]["code"], "UNSUPPORTED_MEDIA_TYPE");
+
+    // Refused before the handler runs, so nothing is created
+    let req = test::TestRequest::post()
+        .uri("/api/v1/projects")
+        .header("Authorization", admin_bearer("acme"))
+        .header("Accept", "text/html")
+        .set_json(&serde_json::json!({ "name": "Rockets" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status().as_u16(), 406);
+    let body: Value = serde_json::from_slice(&test::read_body(resp).await).unwrap();
+    assert_eq!(body["error"]["code"], "NOT_ACCEPTABLE");
+    let acme = admin("acme");
+    assert!(services
+        .projects
+        .list_projects(&acme, true)
+        .await
+        .unwrap()
+        .is_empty());
+
+    // Without a Content-Type the body is read as JSON
+    let req = test::TestRequest::post()
+        .uri("/api/v1/projects")
+        .header("Authorization", admin_bearer("acme"))
+        .set_payload(r#"{"name":"Rockets"}"#)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status().as_u16(), 201);
+    assert_eq!(resp.headers().get("vary").unwrap(), "accept");
+    let body: Value = serde_json::from_slice(&test::read_body(resp).await).unwrap();
+    let project_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    let req = test::TestRequest::get()
+        .uri("/api/v1/projects")
+        .header("Authorization", admin_bearer("acme"))
+        .header("Accept", "text/html")
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status().as_u16(), 406);
+
+    // Errors and non-JSON representations are left alone
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/projects/{}", uuid::Uuid::new_v4()))
+        .header("Authorization", admin_bearer("acme"))
+        .header("Accept", "text/html")
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status().as_u16(), 404);
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/projects/{}/tasks.csv", project_id))
+        .header("Authorization", admin_bearer("acme"))
+        .header("Accept", "text/csv")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status().as_u16(), 200);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "text/csv; charset=utf-8"
+    );
+}
+
+#[cfg(feature = "msgpack")]
+#[ntex::test]
+async fn projects_round_trip_as_messagepack() {
+    let services = services(QuotaPolicy::default());
+    let app = negotiation_app!(services);
+
+    let body = rmp_serde::to_vec_named(&serde_json::json!({
+        "name": "Rockets",
+        "description": "To the moon"
+    }))
+    .unwrap();
+    let req = test::TestRequest::post()
+        .uri("/api/v1/projects")
+        .header("Authorization", admin_bearer("acme"))
+        .header("Content-Type", "application/msgpack")
+        .header("Accept", "application/vnd.msgpack")
+        .set_payload(body)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status().as_u16(), 201);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/msgpack"
+    );
+    let body: Value = rmp_serde::from_slice(&test::read_body(resp).await).unwrap();
+    assert_eq!(body["success"], true);
+    assert_eq!(body["data"]["name"], "Rockets");
+    assert_eq!(body["data"]["description"], "To the moon");
+
+    // Errors are encoded the same way
+    let req = test::TestRequest::post()
+        .uri("/api/v1/projects")
+        .header("Authorization", admin_bearer("acme"))
+        .header("Content-Type", "application/x-msgpack")
+        .header("Accept", "application/msgpack")
+        .set_payload(vec![0xc1])
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status().as_u16(), 400);
+    let body: Value = rmp_serde::from_slice(&test::read_body(resp).await).unwrap();
+    assert_eq!(body["error"]["code"], "BAD_REQUEST");
+}
+
+#[cfg(feature = "cbor")]
+#[ntex::test]
+async fn tasks_round_trip_as_cbor() {
+    let services = services(QuotaPolicy::default());
+    let rockets = services
+        .projects
+        .create_project(&admin("acme"), common::project("Rockets"))
+        .await
+        .unwrap();
+    let app = negotiation_app!(services);
+
+    let mut body = Vec::new();
+    ciborium::into_writer(
+        &serde_json::json!({
+            "project_id": rockets.id,
+            "title": "Fuel",
+            "due_at": "2024-03-01T09:00:00Z"
+        }),
+        &mut body,
+    )
+    .unwrap();
+    let req = test::TestRequest::post()
+        .uri("/api/v1/tasks")
+        .header("Authorization", admin_bearer("acme"))
+        .header("Content-Type", "application/cbor")
+        .header("Accept", "application/cbor")
+        .set_payload(body)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status().as_u16(), 201);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/cbor"
+    );
+    let body: Value = ciborium::from_reader(&test::read_body(resp).await[..]).unwrap();
+    assert_eq!(body["data"]["title"], "Fuel");
+    assert_eq!(body["data"]["project_id"], rockets.id.to_string());
+    assert_eq!(body["data"]["due_at"], "2024-03-01T09:00:00Z");
+}
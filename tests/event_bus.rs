@@ -0,0 +1,105 @@
+mod common;
+
+use common::{admin, project, services, task};
+use rust_mvc_api::models::{DomainEvent, EventKind, TaskUpdate};
+use rust_mvc_api::services::QuotaPolicy;
+
+#[ntex::test]
+async fn service_mutations_publish_events() {
+    let services = services(QuotaPolicy::default());
+    let mut events = services.events.subscribe("test");
+    let acme = admin("acme");
+
+    let created = services
+        .projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    let fuel = services
+        .tasks
+        .create_task(&acme, task(created.id, "Fuel"))
+        .await
+        .unwrap();
+    services
+        .tasks
+        .update_task(
+            &acme,
+            fuel.id,
+            TaskUpdate {
+                title: None,
+                description: None,
+                done: Some(true),
+                column_id: None,
+                due_at: None,
+            },
+        )
+        .await
+        .unwrap();
+    services
+        .projects
+        .delete_project(&acme, created.id)
+        .await
+        .unwrap();
+
+    let kinds: Vec<EventKind> = std::iter::from_fn(|| events.try_recv().ok())
+        .map(|envelope| {
+            assert_eq!(envelope.tenant_id, "acme");
+            assert_eq!(envelope.actor, acme.subject);
+            envelope.event.kind()
+        })
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            EventKind::ProjectCreated,
+            EventKind::TaskCreated,
+            EventKind::TaskUpdated,
+            EventKind::ProjectDeleted,
+        ]
+    );
+}
+
+#[ntex::test]
+async fn failed_mutations_publish_nothing() {
+    let services = services(QuotaPolicy::default());
+    let mut events = services.events.subscribe("test");
+
+    let result = services
+        .projects
+        .create_project(&admin("acme"), project("  "))
+        .await;
+    assert!(result.is_err());
+    assert!(events.try_recv().is_err());
+}
+
+#[ntex::test]
+async fn project_deletion_reports_cascaded_tasks() {
+    let services = services(QuotaPolicy::default());
+    let acme = admin("acme");
+
+    let created = services
+        .projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    services
+        .tasks
+        .create_task(&acme, task(created.id, "Fuel"))
+        .await
+        .unwrap();
+
+    let mut events = services.events.subscribe("test");
+    services
+        .projects
+        .delete_project(&acme, created.id)
+        .await
+        .unwrap();
+
+    let envelope = events.try_recv().unwrap();
+    match envelope.event {
+        DomainEvent::ProjectDeleted {
+            ref cascaded_tasks, ..
+        } => assert_eq!(cascaded_tasks.len(), 1),
+        ref other => panic!("unexpected event {:?}", other.kind()),
+    }
+}
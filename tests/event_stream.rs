@@ -0,0 +1,213 @@
+mod common;
+
+use common::{admin, member, project, services, task, TestServices};
+use futures::FutureExt;
+use rust_mvc_api::models::{
+    EventEnvelope, EventFilter, EventKind, ProjectMemberUpdate, ProjectRole,
+};
+use rust_mvc_api::services::{EventSubscription, QuotaPolicy, StreamItem};
+use rust_mvc_api::views::ApiError;
+use std::sync::Arc;
+use tokio::sync::mpsc::Receiver;
+
+/// Streams every published event, as the stream's event bus subscription would.
+fn stream_published(services: &TestServices, events: &mut Receiver<Arc<EventEnvelope>>) {
+    while let Ok(envelope) = events.try_recv() {
+        services.stream.push(envelope);
+    }
+}
+
+/// Items that are ready without waiting, as `(id, kind)` with id 0 for a reset.
+fn ready(subscription: &mut EventSubscription) -> Vec<(u64, Option<EventKind>)> {
+    std::iter::from_fn(|| subscription.recv().now_or_never().flatten())
+        .map(|item| match item {
+            StreamItem::Event(event) => (event.id, Some(event.envelope.event.kind())),
+            StreamItem::Reset => (0, None),
+        })
+        .collect()
+}
+
+#[ntex::test]
+async fn subscriptions_resume_after_last_event_id() {
+    let services = services(QuotaPolicy::default());
+    let mut events = services.events.subscribe("test");
+    let acme = admin("acme");
+
+    let created = services
+        .projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    services
+        .tasks
+        .create_task(&acme, task(created.id, "Fuel"))
+        .await
+        .unwrap();
+    stream_published(&services, &mut events);
+
+    let mut fresh = services
+        .stream
+        .subscribe(&acme, EventFilter::default(), None)
+        .unwrap();
+    let mut resumed = services
+        .stream
+        .subscribe(&acme, EventFilter::default(), Some(1))
+        .unwrap();
+    assert_eq!(ready(&mut fresh), vec![]);
+    assert_eq!(ready(&mut resumed), vec![(2, Some(EventKind::TaskCreated))]);
+
+    services
+        .tasks
+        .create_task(&acme, task(created.id, "Launch"))
+        .await
+        .unwrap();
+    stream_published(&services, &mut events);
+    assert_eq!(ready(&mut fresh), vec![(3, Some(EventKind::TaskCreated))]);
+    assert_eq!(ready(&mut resumed), vec![(3, Some(EventKind::TaskCreated))]);
+}
+
+#[ntex::test]
+async fn unknown_or_evicted_event_ids_reset_the_client() {
+    let services = services(QuotaPolicy::default());
+    let mut events = services.events.subscribe("test");
+    let acme = admin("acme");
+
+    services
+        .projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    let envelope = events.try_recv().unwrap();
+    let mut subscription = services
+        .stream
+        .subscribe(&acme, EventFilter::default(), Some(99))
+        .unwrap();
+    assert_eq!(ready(&mut subscription), vec![(0, None)]);
+    let mut overflowing = services
+        .stream
+        .subscribe(&acme, EventFilter::default(), Some(u64::MAX))
+        .unwrap();
+    assert_eq!(ready(&mut overflowing), vec![(0, None)]);
+
+    for _ in 0..1500 {
+        services.stream.push(envelope.clone());
+    }
+    let mut evicted = services
+        .stream
+        .subscribe(&acme, EventFilter::default(), Some(1))
+        .unwrap();
+    assert_eq!(ready(&mut evicted), vec![(0, None)]);
+    let mut recent = services
+        .stream
+        .subscribe(&acme, EventFilter::default(), Some(1499))
+        .unwrap();
+    assert_eq!(
+        ready(&mut recent),
+        vec![(1500, Some(EventKind::ProjectCreated))]
+    );
+}
+
+#[ntex::test]
+async fn subscriptions_only_see_matching_readable_events() {
+    let services = services(QuotaPolicy::default());
+    let mut events = services.events.subscribe("test");
+    let alice = member("acme", "alice");
+    let bob = member("acme", "bob");
+
+    let mut all = services
+        .stream
+        .subscribe(&alice, EventFilter::default(), None)
+        .unwrap();
+
+    let own = services
+        .projects
+        .create_project(&alice, project("Rockets"))
+        .await
+        .unwrap();
+    let foreign = services
+        .projects
+        .create_project(&bob, project("Secret"))
+        .await
+        .unwrap();
+    services
+        .tasks
+        .create_task(&bob, task(foreign.id, "Hidden"))
+        .await
+        .unwrap();
+    let other_tenant = services
+        .projects
+        .create_project(&admin("globex"), project("Rockets"))
+        .await
+        .unwrap();
+    services
+        .tasks
+        .create_task(&admin("globex"), task(other_tenant.id, "Elsewhere"))
+        .await
+        .unwrap();
+    services
+        .tasks
+        .create_task(&alice, task(own.id, "Fuel"))
+        .await
+        .unwrap();
+    stream_published(&services, &mut events);
+
+    assert_eq!(
+        ready(&mut all),
+        vec![
+            (1, Some(EventKind::ProjectCreated)),
+            (6, Some(EventKind::TaskCreated)),
+        ]
+    );
+
+    assert!(matches!(
+        services.stream.subscribe(
+            &alice,
+            EventFilter {
+                project_id: Some(foreign.id),
+                event_types: vec![],
+            },
+            None,
+        ),
+        Err(ApiError::Forbidden { .. })
+    ));
+
+    // A viewer still hears about the deletion that removes its membership.
+    services
+        .projects
+        .set_member(
+            &bob,
+            foreign.id,
+            "alice".to_string(),
+            ProjectMemberUpdate {
+                role: ProjectRole::Viewer,
+            },
+        )
+        .await
+        .unwrap();
+    let mut deletions = services
+        .stream
+        .subscribe(
+            &alice,
+            EventFilter {
+                project_id: Some(foreign.id),
+                event_types: vec![EventKind::ProjectDeleted],
+            },
+            None,
+        )
+        .unwrap();
+    services
+        .tasks
+        .create_task(&bob, task(foreign.id, "Visible"))
+        .await
+        .unwrap();
+    services
+        .projects
+        .delete_project(&bob, foreign.id)
+        .await
+        .unwrap();
+    stream_published(&services, &mut events);
+    assert_eq!(
+        ready(&mut deletions),
+        vec![(8, Some(EventKind::ProjectDeleted))]
+    );
+}
@@ -0,0 +1,264 @@
+#![cfg(feature = "graphql")]
+
+mod common;
+
+use async_graphql::dataloader::DataLoader;
+use async_graphql::Request;
+use common::{admin, member, project, services, task, TestServices};
+use ntex::web::{test, App};
+use rust_mvc_api::graphql::{
+    build_schema, ProjectTasksLoader, MAX_QUERY_COMPLEXITY, MAX_QUERY_DEPTH,
+};
+use rust_mvc_api::middleware::JwtAuth;
+use rust_mvc_api::models::{Principal, ProjectMemberUpdate, ProjectRole, TaskUpdate};
+use rust_mvc_api::routes::configure_routes;
+use rust_mvc_api::services::QuotaPolicy;
+use serde_json::{json, Value};
+
+/// Runs a query as `principal` the way the endpoint does, returning the JSON response.
+async fn execute(services: &TestServices, principal: &Principal, query: &str) -> Value {
+    let schema = build_schema(services.projects.clone(), services.tasks.clone());
+    let loader = DataLoader::new(
+        ProjectTasksLoader::new(services.tasks.clone(), principal.clone()),
+        ntex::rt::spawn,
+    );
+    let response = schema
+        .execute(Request::new(query).data(principal.clone()).data(loader))
+        .await;
+    serde_json::to_value(&response).unwrap()
+}
+
+#[ntex::test]
+async fn projects_resolve_their_tasks_in_one_batch() {
+    let services = services(QuotaPolicy::default());
+    let acme = admin("acme");
+    let mut ids = Vec::new();
+    for (name, titles) in [
+        ("Rockets", &["Fuel", "Paint"][..]),
+        ("Rovers", &["Wheels"][..]),
+        ("Probes", &[][..]),
+    ] {
+        let created = services
+            .projects
+            .create_project(&acme, project(name))
+            .await
+            .unwrap();
+        for title in titles {
+            services
+                .tasks
+                .create_task(&acme, task(created.id, title))
+                .await
+                .unwrap();
+        }
+        ids.push(created.id);
+    }
+    let paint = services
+        .tasks
+        .list_tasks_by_project(&acme, ids[0])
+        .await
+        .unwrap()[1]
+        .clone();
+    services
+        .tasks
+        .update_task(
+            &acme,
+            paint.id,
+            TaskUpdate {
+                title: None,
+                description: None,
+                done: Some(true),
+                column_id: None,
+                due_at: None,
+            },
+        )
+        .await
+        .unwrap();
+
+    let batch = services
+        .tasks
+        .list_tasks_by_projects(&acme, &ids)
+        .await
+        .unwrap();
+    assert_eq!(batch[&ids[0]].len(), 2);
+    assert_eq!(batch[&ids[1]].len(), 1);
+    assert!(!batch.contains_key(&ids[2]));
+
+    let response = execute(
+        &services,
+        &acme,
+        "{ projects { name tasks { title done } open: tasks(done: false) { title } } }",
+    )
+    .await;
+    let mut projects = response["data"]["projects"].as_array().unwrap().clone();
+    projects.sort_by_key(|project| project["name"].as_str().unwrap().to_string());
+    assert_eq!(
+        Value::Array(projects),
+        json!([
+            { "name": "Probes", "tasks": [], "open": [] },
+            {
+                "name": "Rockets",
+                "tasks": [
+                    { "title": "Fuel", "done": false },
+                    { "title": "Paint", "done": true }
+                ],
+                "open": [{ "title": "Fuel" }]
+            },
+            { "name": "Rovers", "tasks": [{ "title": "Wheels", "done": false }], "open": [{ "title": "Wheels" }] }
+        ])
+    );
+
+    // Members only see the projects they belong to, nested or not
+    services
+        .projects
+        .set_member(
+            &acme,
+            ids[1],
+            "alice".to_string(),
+            ProjectMemberUpdate {
+                role: ProjectRole::Viewer,
+            },
+        )
+        .await
+        .unwrap();
+    let alice = member("acme", "alice");
+    let query = format!(
+        r#"{{ projects {{ name tasks {{ title }} }} tasks(filter: {{ done: false }}) {{ title }} project(id: "{}") {{ name }} }}"#,
+        ids[0]
+    );
+    let response = execute(&services, &alice, &query).await;
+    assert_eq!(
+        response["data"]["projects"],
+        json!([{ "name": "Rovers", "tasks": [{ "title": "Wheels" }] }])
+    );
+    assert_eq!(response["data"]["tasks"], json!([{ "title": "Wheels" }]));
+    assert_eq!(response["errors"][0]["path"], json!(["project"]));
+    assert_eq!(response["errors"][0]["extensions"]["code"], "FORBIDDEN");
+}
+
+#[ntex::test]
+async fn oversized_queries_are_rejected_but_introspection_is_not() {
+    let services = services(QuotaPolicy::default());
+    let acme = admin("acme");
+
+    // GraphiQL loads the schema with the standard introspection query
+    let body = execute(
+        &services,
+        &acme,
+        include_str!("fixtures/introspection.graphql"),
+    )
+    .await;
+    assert!(body.get("errors").is_none(), "{}", body);
+    assert!(!body["data"]["__schema"]["types"]
+        .as_array()
+        .unwrap()
+        .is_empty());
+
+    let deep = format!(
+        "{{ __schema {{ types {{ fields {{ type {} name {} }} }} }} }}",
+        "{ ofType ".repeat(MAX_QUERY_DEPTH),
+        "}".repeat(MAX_QUERY_DEPTH)
+    );
+    let body = execute(&services, &acme, &deep).await;
+    assert_eq!(body["data"], Value::Null);
+    assert!(body["errors"][0]["message"]
+        .as_str()
+        .unwrap()
+        .contains("nested too deep"));
+
+    let wide = format!(
+        "{{ {} }}",
+        (0..MAX_QUERY_COMPLEXITY)
+            .map(|i| format!("p{}: projects {{ id }}", i))
+            .collect::<Vec<_>>()
+            .join(" ")
+    );
+    let body = execute(&services, &acme, &wide).await;
+    assert_eq!(body["data"], Value::Null);
+    assert!(body["errors"][0]["message"]
+        .as_str()
+        .unwrap()
+        .contains("too complex"));
+}
+
+#[ntex::test]
+async fn mutations_go_through_the_services_over_http() {
+    let services = services(QuotaPolicy::default());
+    let app = test::init_service(
+        App::new()
+            .state(services.tasks.clone())
+            .state(build_schema(
+                services.projects.clone(),
+                services.tasks.clone(),
+            ))
+            .wrap(JwtAuth::new(None))
+            .configure(configure_routes),
+    )
+    .await;
+
+    let post = |query: String| {
+        test::TestRequest::post()
+            .uri("/graphql")
+            .header("X-Tenant-Id", "acme")
+            .set_json(&json!({ "query": query }))
+            .to_request()
+    };
+    let resp = test::call_service(
+        &app,
+        post(r#"mutation { createProject(input: { name: "Rockets" }) { id name } }"#.to_string()),
+    )
+    .await;
+    assert!(resp.status().is_success());
+    let body: Value = serde_json::from_slice(&test::read_body(resp).await).unwrap();
+    let project_id = body["data"]["createProject"]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let resp = test::call_service(
+        &app,
+        post(format!(
+            r#"mutation {{ createTask(input: {{ projectId: "{}", title: "Fuel", dueAt: "2024-03-01T09:00:00Z" }}) {{ title dueAt }} }}"#,
+            project_id
+        )),
+    )
+    .await;
+    let body: Value = serde_json::from_slice(&test::read_body(resp).await).unwrap();
+    assert_eq!(
+        body["data"]["createTask"],
+        json!({ "title": "Fuel", "dueAt": "2024-03-01T09:00:00+00:00" })
+    );
+
+    // Service errors keep their REST error codes
+    let resp = test::call_service(
+        &app,
+        post(format!(
+            r#"mutation {{ createTask(input: {{ projectId: "{}", title: "" }}) {{ id }} }}"#,
+            project_id
+        )),
+    )
+    .await;
+    let body: Value = serde_json::from_slice(&test::read_body(resp).await).unwrap();
+    assert_eq!(body["errors"][0]["extensions"]["code"], "VALIDATION_ERROR");
+
+    let resp = test::call_service(
+        &app,
+        post(format!(
+            r#"mutation {{ deleteProject(id: "{}") }}"#,
+            project_id
+        )),
+    )
+    .await;
+    let body: Value = serde_json::from_slice(&test::read_body(resp).await).unwrap();
+    assert_eq!(body["data"]["deleteProject"], true);
+    let acme = admin("acme");
+    assert!(services
+        .projects
+        .list_projects(&acme, true)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let req = test::TestRequest::get().uri("/graphiql").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.headers().get("content-type").unwrap(), "text/html");
+}
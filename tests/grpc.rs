@@ -0,0 +1,373 @@
+#![cfg(feature = "grpc")]
+
+mod common;
+
+use common::{admin, admin_bearer, jwt_verifier, services, TestServices};
+use rust_mvc_api::config::AuthMode;
+use rust_mvc_api::grpc::proto::projects_client::ProjectsClient;
+use rust_mvc_api::grpc::proto::tasks_client::TasksClient;
+use rust_mvc_api::grpc::proto::{
+    CreateProjectRequest, CreateTaskRequest, GetProjectRequest, ListTasksRequest,
+    UpdateTaskRequest, WatchTasksRequest,
+};
+use rust_mvc_api::grpc::{GrpcAuth, GrpcServer, ERROR_CODE_METADATA};
+use rust_mvc_api::models::ApiKeyCreate;
+use rust_mvc_api::repositories::ApiKeyRepository;
+use rust_mvc_api::services::{ApiKeyService, QuotaPolicy};
+use rust_mvc_api::views::ApiError;
+use std::sync::Arc;
+use tonic::metadata::MetadataMap;
+use tonic::transport::Channel;
+use tonic::{Code, Request, Status};
+
+/// Serves the gRPC API over `services` on an ephemeral port and connects a channel to it.
+async fn connect(services: &TestServices, api_keys: Arc<ApiKeyService>) -> Channel {
+    let address = GrpcServer::new(
+        services.projects.clone(),
+        services.tasks.clone(),
+        services.stream.clone(),
+        GrpcAuth::new(AuthMode::Jwt, Some(jwt_verifier()), api_keys),
+    )
+    .spawn("127.0.0.1:0")
+    .unwrap();
+    Channel::from_shared(format!("http://{}", address))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap()
+}
+
+/// A request from the tenant's admin, authenticated like [`admin`].
+fn as_admin<T>(tenant_id: &str, message: T) -> Request<T> {
+    let mut request = Request::new(message);
+    request
+        .metadata_mut()
+        .insert("authorization", admin_bearer(tenant_id).parse().unwrap());
+    request
+}
+
+fn error_code(status: &Status) -> &str {
+    status
+        .metadata()
+        .get(ERROR_CODE_METADATA)
+        .unwrap()
+        .to_str()
+        .unwrap()
+}
+
+#[ntex::test]
+async fn calls_share_the_services_and_map_errors_to_status_codes() {
+    let services = services(QuotaPolicy::default());
+    let api_keys = Arc::new(ApiKeyService::new(Arc::new(ApiKeyRepository::new())));
+    let channel = connect(&services, api_keys.clone()).await;
+    let mut projects = ProjectsClient::new(channel.clone());
+    let mut tasks = TasksClient::new(channel);
+
+    let created = projects
+        .create_project(as_admin(
+            "acme",
+            CreateProjectRequest {
+                name: "Rockets".to_string(),
+                description: Some("To the moon".to_string()),
+            },
+        ))
+        .await
+        .unwrap()
+        .into_inner();
+    let stored = services
+        .projects
+        .list_projects(&admin("acme"), false)
+        .await
+        .unwrap();
+    assert_eq!(stored[0].id.to_string(), created.id);
+    assert_eq!(stored[0].description.as_deref(), Some("To the moon"));
+
+    let task = tasks
+        .create_task(as_admin(
+            "acme",
+            CreateTaskRequest {
+                project_id: created.id.clone(),
+                title: "Fuel".to_string(),
+                description: None,
+                column_id: None,
+                due_at: Some(prost_types::Timestamp {
+                    seconds: 1_709_283_600,
+                    nanos: 0,
+                }),
+            },
+        ))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(task.due_at.unwrap().seconds, 1_709_283_600);
+    assert_eq!(
+        services
+            .tasks
+            .get_task(&admin("acme"), task.id.parse().unwrap())
+            .await
+            .unwrap()
+            .due_at
+            .unwrap()
+            .to_rfc3339(),
+        "2024-03-01T09:00:00+00:00"
+    );
+    let listed = tasks
+        .list_tasks(as_admin(
+            "acme",
+            ListTasksRequest {
+                project_id: Some(created.id.clone()),
+            },
+        ))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(listed.tasks, vec![task.clone()]);
+
+    // Tenants stay isolated, as over HTTP
+    let status = projects
+        .get_project(as_admin(
+            "globex",
+            GetProjectRequest {
+                id: created.id.clone(),
+            },
+        ))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+    assert_eq!(error_code(&status), "NOT_FOUND");
+
+    let status = projects
+        .get_project(as_admin(
+            "acme",
+            GetProjectRequest {
+                id: "rockets".to_string(),
+            },
+        ))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert_eq!(error_code(&status), "BAD_REQUEST");
+
+    let status = tasks
+        .update_task(as_admin(
+            "acme",
+            UpdateTaskRequest {
+                id: task.id.clone(),
+                title: Some(" ".to_string()),
+                description: None,
+                done: None,
+                column_id: None,
+                due_at: None,
+            },
+        ))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert_eq!(error_code(&status), "VALIDATION_ERROR");
+
+    // API keys authenticate from metadata and stay bound to their tenant
+    let key = api_keys
+        .create_key(
+            &admin("acme"),
+            ApiKeyCreate {
+                name: "ci".to_string(),
+                scopes: Vec::new(),
+                expires_at: None,
+            },
+        )
+        .await
+        .unwrap()
+        .key;
+    let with_key = |key: &str, tenant_id: &str| {
+        let mut request = Request::new(GetProjectRequest {
+            id: created.id.clone(),
+        });
+        request
+            .metadata_mut()
+            .insert("x-tenant-id", tenant_id.parse().unwrap());
+        request
+            .metadata_mut()
+            .insert("x-api-key", key.parse().unwrap());
+        request
+    };
+    let status = projects
+        .get_project(with_key(&key, "globex"))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+    assert_eq!(error_code(&status), "FORBIDDEN");
+    let status = projects
+        .get_project(with_key("not-a-key", "acme"))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+    assert_eq!(error_code(&status), "UNAUTHORIZED");
+}
+
+#[ntex::test]
+async fn calls_without_credentials_need_anonymous_mode() {
+    let api_keys = Arc::new(ApiKeyService::new(Arc::new(ApiKeyRepository::new())));
+    let mut metadata = MetadataMap::new();
+    metadata.insert("x-tenant-id", "acme".parse().unwrap());
+
+    for auth in [
+        GrpcAuth::new(AuthMode::Jwt, Some(jwt_verifier()), api_keys.clone()),
+        GrpcAuth::new(AuthMode::Jwt, None, api_keys.clone()),
+    ] {
+        let result = auth.authenticate(&metadata).await;
+        assert!(matches!(result, Err(ApiError::Unauthorized { .. })));
+    }
+
+    let principal = GrpcAuth::new(AuthMode::Anonymous, None, api_keys)
+        .authenticate(&metadata)
+        .await
+        .unwrap();
+    assert_eq!(principal.tenant_id, "acme");
+    assert!(principal.scopes.is_empty());
+}
+
+#[ntex::test]
+async fn watch_tasks_streams_changes_and_resumes() {
+    let services = services(QuotaPolicy::default());
+    services.stream.start(&services.events);
+    let api_keys = Arc::new(ApiKeyService::new(Arc::new(ApiKeyRepository::new())));
+    let mut tasks = TasksClient::new(connect(&services, api_keys).await);
+    let acme = admin("acme");
+
+    let rockets = services
+        .projects
+        .create_project(&acme, common::project("Rockets"))
+        .await
+        .unwrap();
+    let rovers = services
+        .projects
+        .create_project(&acme, common::project("Rovers"))
+        .await
+        .unwrap();
+    let mut changes = tasks
+        .watch_tasks(as_admin(
+            "acme",
+            WatchTasksRequest {
+                project_id: Some(rockets.id.to_string()),
+                after_sequence: None,
+            },
+        ))
+        .await
+        .unwrap()
+        .into_inner();
+
+    let wheels = services
+        .tasks
+        .create_task(&acme, common::task(rovers.id, "Wheels"))
+        .await
+        .unwrap();
+    let paint = services
+        .tasks
+        .create_task(&acme, common::task(rockets.id, "Paint"))
+        .await
+        .unwrap();
+    services.tasks.delete_task(&acme, paint.id).await.unwrap();
+
+    // Project events and other projects' tasks are left out
+    let created = changes.message().await.unwrap().unwrap();
+    assert_eq!(created.r#type, "task_created");
+    assert_eq!(created.task.as_ref().unwrap().title, "Paint");
+    assert_eq!(created.actor, acme.subject);
+    let deleted = changes.message().await.unwrap().unwrap();
+    assert_eq!(deleted.r#type, "task_deleted");
+    assert_eq!(deleted.sequence, created.sequence + 1);
+    assert_ne!(wheels.project_id, rockets.id);
+
+    // Resuming replays what came after the given sequence
+    let mut resumed = tasks
+        .watch_tasks(as_admin(
+            "acme",
+            WatchTasksRequest {
+                project_id: None,
+                after_sequence: Some(created.sequence - 2),
+            },
+        ))
+        .await
+        .unwrap()
+        .into_inner();
+    let replayed = resumed.message().await.unwrap().unwrap();
+    assert_eq!(replayed.task.unwrap().title, "Wheels");
+    assert_eq!(resumed.message().await.unwrap().unwrap(), created);
+
+    // A sequence the server never reached ends the stream so the client reloads
+    let mut stale = tasks
+        .watch_tasks(as_admin(
+            "acme",
+            WatchTasksRequest {
+                project_id: None,
+                after_sequence: Some(1_000),
+            },
+        ))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(stale.message().await.unwrap_err().code(), Code::Aborted);
+}
+
+#[ntex::test]
+async fn watch_tasks_reports_tasks_cascaded_with_their_project() {
+    let services = services(QuotaPolicy::default());
+    services.stream.start(&services.events);
+    let api_keys = Arc::new(ApiKeyService::new(Arc::new(ApiKeyRepository::new())));
+    let mut tasks = TasksClient::new(connect(&services, api_keys).await);
+    let acme = admin("acme");
+
+    let rockets = services
+        .projects
+        .create_project(&acme, common::project("Rockets"))
+        .await
+        .unwrap();
+    let mut ids = Vec::new();
+    for title in ["Fuel", "Paint"] {
+        let task = services
+            .tasks
+            .create_task(&acme, common::task(rockets.id, title))
+            .await
+            .unwrap();
+        ids.push(task.id.to_string());
+    }
+    let mut changes = tasks
+        .watch_tasks(as_admin(
+            "acme",
+            WatchTasksRequest {
+                project_id: Some(rockets.id.to_string()),
+                after_sequence: None,
+            },
+        ))
+        .await
+        .unwrap()
+        .into_inner();
+
+    services
+        .projects
+        .delete_project(&acme, rockets.id)
+        .await
+        .unwrap();
+    services
+        .trash
+        .restore_project(&acme, rockets.id)
+        .await
+        .unwrap();
+
+    for kind in ["task_deleted", "task_restored"] {
+        let mut seen = Vec::new();
+        let mut sequences = Vec::new();
+        for _ in &ids {
+            let change = changes.message().await.unwrap().unwrap();
+            assert_eq!(change.r#type, kind);
+            seen.push(change.task.unwrap().id);
+            sequences.push(change.sequence);
+        }
+        seen.sort();
+        let mut expected = ids.clone();
+        expected.sort();
+        assert_eq!(seen, expected, "{}", kind);
+        // One project event, so one sequence for all of its tasks
+        assert!(sequences.iter().all(|sequence| *sequence == sequences[0]));
+    }
+}
@@ -0,0 +1,278 @@
+mod common;
+
+use chrono::Utc;
+use common::{admin, services};
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use ntex::web::{test, App};
+use rust_mvc_api::config::Config;
+use rust_mvc_api::middleware::{JwtAuth, JwtVerifier};
+use rust_mvc_api::models::DEFAULT_TENANT;
+use rust_mvc_api::routes::configure_routes;
+use rust_mvc_api::services::QuotaPolicy;
+use rust_mvc_api::views::ApiError;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+const SECRET: &str = "test-secret";
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+fn fixture(name: &str) -> String {
+    format!("{}/{}", FIXTURES, name)
+}
+
+fn token_claims(extra: Value) -> Value {
+    let mut claims = json!({
+        "sub": "alice",
+        "tenant": "acme",
+        "exp": Utc::now().timestamp() + 3600,
+    });
+    claims
+        .as_object_mut()
+        .unwrap()
+        .extend(extra.as_object().unwrap().clone());
+    claims
+}
+
+fn hs256(claims: &Value) -> String {
+    encode(
+        &Header::new(Algorithm::HS256),
+        claims,
+        &EncodingKey::from_secret(SECRET.as_bytes()),
+    )
+    .unwrap()
+}
+
+fn rs256(claims: &Value, kid: Option<&str>) -> String {
+    let pem = std::fs::read(fixture("jwt_rsa_private.pem")).unwrap();
+    let mut header = Header::new(Algorithm::RS256);
+    header.kid = kid.map(str::to_string);
+    encode(&header, claims, &EncodingKey::from_rsa_pem(&pem).unwrap()).unwrap()
+}
+
+fn verifier(config: Config) -> JwtVerifier {
+    JwtVerifier::from_config(&config).unwrap().unwrap()
+}
+
+fn message(err: ApiError) -> String {
+    match err {
+        ApiError::Unauthorized { message } => message,
+        other => panic!("expected 401, got {:?}", other),
+    }
+}
+
+#[test]
+fn hs256_and_rs256_tokens_verify_against_their_keys() {
+    assert!(JwtVerifier::from_config(&Config::default())
+        .unwrap()
+        .is_none());
+    let verifier = verifier(Config {
+        jwt_secret: Some(SECRET.to_string()),
+        jwt_public_key_path: Some(fixture("jwt_rsa_public.pem")),
+        ..Config::default()
+    });
+
+    let claims = verifier
+        .verify(&hs256(&token_claims(json!({ "scope": "admin reports" }))))
+        .unwrap();
+    assert_eq!(claims.sub, "alice");
+    let principal = claims.into_principal("acme".to_string());
+    assert_eq!(principal.scopes, vec!["admin", "reports"]);
+    assert!(principal.is_admin());
+
+    let claims = verifier
+        .verify(&rs256(&token_claims(json!({})), None))
+        .unwrap();
+    assert_eq!(claims.tenant.as_deref(), Some("acme"));
+
+    // A token signed with another secret, or tampered with, is refused
+    let forged = encode(
+        &Header::new(Algorithm::HS256),
+        &token_claims(json!({})),
+        &EncodingKey::from_secret(b"other-secret"),
+    )
+    .unwrap();
+    assert_eq!(
+        message(verifier.verify(&forged).unwrap_err()),
+        "Invalid bearer token"
+    );
+    let token = hs256(&token_claims(json!({})));
+    let tampered = format!("{}x", token);
+    assert!(verifier.verify(&tampered).is_err());
+
+    // Only the configured algorithms are accepted
+    let hs_only = self::verifier(Config {
+        jwt_secret: Some(SECRET.to_string()),
+        ..Config::default()
+    });
+    assert_eq!(
+        message(
+            hs_only
+                .verify(&rs256(&token_claims(json!({})), None))
+                .unwrap_err()
+        ),
+        "Unsupported token signing key"
+    );
+}
+
+#[test]
+fn jwks_keys_are_picked_by_kid() {
+    let verifier = verifier(Config {
+        jwt_jwks_path: Some(fixture("jwks.json")),
+        ..Config::default()
+    });
+    let claims = verifier
+        .verify(&rs256(&token_claims(json!({})), Some("test-key")))
+        .unwrap();
+    assert_eq!(claims.sub, "alice");
+    for kid in [Some("rotated-key"), None] {
+        assert_eq!(
+            message(
+                verifier
+                    .verify(&rs256(&token_claims(json!({})), kid))
+                    .unwrap_err()
+            ),
+            "Unsupported token signing key"
+        );
+    }
+
+    // A key that no token could select fails loading instead of being dropped
+    let mut jwks: Value =
+        serde_json::from_str(&std::fs::read_to_string(fixture("jwks.json")).unwrap()).unwrap();
+    jwks["keys"][0].as_object_mut().unwrap().remove("kid");
+    let path = std::env::temp_dir().join(format!("jwks-{}.json", uuid::Uuid::new_v4()));
+    std::fs::write(&path, jwks.to_string()).unwrap();
+    let result = JwtVerifier::from_config(&Config {
+        jwt_jwks_path: Some(path.to_string_lossy().into_owned()),
+        ..Config::default()
+    });
+    std::fs::remove_file(&path).unwrap();
+    assert!(result.err().unwrap().contains("without a kid"));
+}
+
+#[ntex::test]
+async fn requests_need_a_valid_bearer_token() {
+    let services = services(QuotaPolicy::default());
+    let verifier = Arc::new(verifier(Config {
+        jwt_secret: Some(SECRET.to_string()),
+        jwt_issuer: Some("https://issuer.example".to_string()),
+        jwt_audience: Some("tasks-api".to_string()),
+        ..Config::default()
+    }));
+    let app = test::init_service(
+        App::new()
+            .state(services.projects.clone())
+            .wrap(JwtAuth::new(Some(verifier)))
+            .configure(configure_routes),
+    )
+    .await;
+    let valid = json!({ "iss": "https://issuer.example", "aud": "tasks-api" });
+
+    let get = |authorization: Option<String>| {
+        let mut req = test::TestRequest::get().uri("/api/v1/projects");
+        if let Some(authorization) = authorization {
+            req = req.header("Authorization", authorization);
+        }
+        req.to_request()
+    };
+    let resp = test::call_service(
+        &app,
+        get(Some(format!(
+            "Bearer {}",
+            hs256(&token_claims(valid.clone()))
+        ))),
+    )
+    .await;
+    assert_eq!(resp.status().as_u16(), 200);
+
+    let mut expired = token_claims(valid.clone());
+    expired["exp"] = json!(Utc::now().timestamp() - 3600);
+    let mut wrong_issuer = token_claims(valid.clone());
+    wrong_issuer["iss"] = json!("https://elsewhere.example");
+    let mut wrong_audience = token_claims(valid.clone());
+    wrong_audience["aud"] = json!("billing-api");
+    for (authorization, expected) in [
+        (
+            Some(format!("Bearer {}", hs256(&expired))),
+            "Token has expired",
+        ),
+        (
+            Some(format!("Bearer {}", hs256(&wrong_issuer))),
+            "Invalid bearer token",
+        ),
+        (
+            Some(format!("Bearer {}", hs256(&wrong_audience))),
+            "Invalid bearer token",
+        ),
+        (None, "Missing bearer token"),
+        (Some("Bearer ".to_string()), "Missing bearer token"),
+        (Some(format!("Basic {}", SECRET)), "Missing bearer token"),
+        (
+            Some("Bearer not-a-jwt".to_string()),
+            "Malformed bearer token",
+        ),
+    ] {
+        let resp = test::call_service(&app, get(authorization.clone())).await;
+        assert_eq!(resp.status().as_u16(), 401, "{:?}", authorization);
+        let body: Value = serde_json::from_slice(&test::read_body(resp).await).unwrap();
+        assert_eq!(body["error"]["code"], "UNAUTHORIZED");
+        assert!(
+            body["error"]["message"]
+                .as_str()
+                .unwrap()
+                .ends_with(expected),
+            "{:?}: {}",
+            authorization,
+            body
+        );
+    }
+
+    // Health checks and API docs stay public
+    let req = test::TestRequest::get().uri("/health").to_request();
+    assert_eq!(test::call_service(&app, req).await.status().as_u16(), 200);
+    #[cfg(feature = "openapi")]
+    for uri in ["/docs", "/openapi.json"] {
+        let req = test::TestRequest::get().uri(uri).to_request();
+        assert_eq!(test::call_service(&app, req).await.status().as_u16(), 200);
+    }
+}
+
+#[ntex::test]
+async fn tokens_without_a_tenant_claim_stay_in_the_default_tenant() {
+    let services = services(QuotaPolicy::default());
+    let verifier = Arc::new(verifier(Config {
+        jwt_secret: Some(SECRET.to_string()),
+        ..Config::default()
+    }));
+    let app = test::init_service(
+        App::new()
+            .state(services.projects.clone())
+            .wrap(JwtAuth::new(Some(verifier)))
+            .configure(configure_routes),
+    )
+    .await;
+    let mut claims = token_claims(json!({ "scope": "admin" }));
+    claims.as_object_mut().unwrap().remove("tenant");
+    let authorization = format!("Bearer {}", hs256(&claims));
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/projects")
+        .header("Authorization", authorization.as_str())
+        .set_json(&json!({ "name": "Rockets" }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status().as_u16(), 201);
+    let owner = admin(DEFAULT_TENANT);
+    let projects = services
+        .projects
+        .list_projects(&owner, false)
+        .await
+        .unwrap();
+    assert_eq!(projects.len(), 1);
+
+    // An admin token cannot reach another tenant by naming it.
+    let req = test::TestRequest::get()
+        .uri("/api/v1/projects")
+        .header("Authorization", authorization.as_str())
+        .header("X-Tenant-Id", "acme")
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status().as_u16(), 403);
+}
@@ -0,0 +1,224 @@
+mod common;
+
+use common::{bearer, jwt_verifier, services};
+use ntex::web::{test, App};
+use rust_mvc_api::middleware::JwtAuth;
+use rust_mvc_api::repositories::ApiKeyRepository;
+use rust_mvc_api::routes::configure_routes;
+use rust_mvc_api::services::{ApiKeyService, QuotaPolicy};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+macro_rules! roles_app {
+    ($services:expr, $auth:expr) => {
+        test::init_service(
+            App::new()
+                .state($services.projects.clone())
+                .state($services.tasks.clone())
+                .state($services.audit.clone())
+                .state($services.backups.clone())
+                .state(Arc::new(ApiKeyService::new(Arc::new(
+                    ApiKeyRepository::new(),
+                ))))
+                .wrap($auth)
+                .configure(configure_routes),
+        )
+        .await
+    };
+}
+
+/// Sends a request as `subject` in the `acme` tenant, or without credentials for `None`.
+fn request(
+    method: &str,
+    uri: &str,
+    subject: Option<&str>,
+    body: Option<Value>,
+) -> test::TestRequest {
+    let req = match method {
+        "GET" => test::TestRequest::get(),
+        "POST" => test::TestRequest::post(),
+        "PUT" => test::TestRequest::put(),
+        "DELETE" => test::TestRequest::delete(),
+        _ => unreachable!(),
+    }
+    .uri(uri);
+    let req = match subject {
+        Some(subject) => req.header("Authorization", bearer("acme", subject, "")),
+        None => req.header("X-Tenant-Id", "acme"),
+    };
+    match body {
+        Some(body) => req.set_json(&body),
+        None => req,
+    }
+}
+
+#[ntex::test]
+async fn project_roles_allow_and_deny_by_rank() {
+    let services = services(QuotaPolicy::default());
+    let app = roles_app!(services, JwtAuth::new(Some(jwt_verifier())));
+    let call = |method: &str, uri: &str, subject: &str, body: Option<Value>| {
+        request(method, uri, Some(subject), body).to_request()
+    };
+
+    // The creator owns the project and hands out the other roles
+    let resp = test::call_service(
+        &app,
+        call(
+            "POST",
+            "/api/v1/projects",
+            "olivia",
+            Some(json!({ "name": "Rockets" })),
+        ),
+    )
+    .await;
+    assert_eq!(resp.status().as_u16(), 201);
+    let body: Value = serde_json::from_slice(&test::read_body(resp).await).unwrap();
+    let project = format!("/api/v1/projects/{}", body["data"]["id"].as_str().unwrap());
+    for (subject, role) in [("victor", "viewer"), ("edith", "editor")] {
+        let resp = test::call_service(
+            &app,
+            call(
+                "PUT",
+                &format!("{}/members/{}", project, subject),
+                "olivia",
+                Some(json!({ "role": role })),
+            ),
+        )
+        .await;
+        assert_eq!(resp.status().as_u16(), 200);
+    }
+    let resp = test::call_service(
+        &app,
+        call(
+            "POST",
+            "/api/v1/tasks",
+            "olivia",
+            Some(json!({ "project_id": body["data"]["id"], "title": "Fuel" })),
+        ),
+    )
+    .await;
+    let task: Value = serde_json::from_slice(&test::read_body(resp).await).unwrap();
+    let task = format!("/api/v1/tasks/{}", task["data"]["id"].as_str().unwrap());
+
+    // Expected statuses for the viewer, the editor, the owner and a non-member
+    let subjects = ["victor", "edith", "olivia", "mallory"];
+    let checks: Vec<(&str, String, Option<Value>, [u16; 4])> = vec![
+        ("GET", project.clone(), None, [200, 200, 200, 403]),
+        ("GET", task.clone(), None, [200, 200, 200, 403]),
+        (
+            "GET",
+            format!("{}/members", project),
+            None,
+            [200, 200, 200, 403],
+        ),
+        (
+            "POST",
+            "/api/v1/tasks".to_string(),
+            Some(json!({ "project_id": body["data"]["id"], "title": "Paint" })),
+            [403, 201, 201, 403],
+        ),
+        (
+            "PUT",
+            task.clone(),
+            Some(json!({ "done": true })),
+            [403, 200, 200, 403],
+        ),
+        (
+            "PUT",
+            project.clone(),
+            Some(json!({ "description": "To the moon" })),
+            [403, 200, 200, 403],
+        ),
+        (
+            "PUT",
+            format!("{}/members/mallory", project),
+            Some(json!({ "role": "viewer" })),
+            [403, 403, 200, 403],
+        ),
+    ];
+    for (method, uri, body, expected) in checks {
+        for (subject, expected) in subjects.iter().zip(expected) {
+            // Membership changes are made by the owner last, so earlier checks see the
+            // outsider as a non-member
+            let resp = test::call_service(&app, call(method, &uri, subject, body.clone())).await;
+            assert_eq!(
+                resp.status().as_u16(),
+                expected,
+                "{} {} as {}",
+                method,
+                uri,
+                subject
+            );
+        }
+    }
+
+    // The added viewer can now read but still not write
+    let resp = test::call_service(&app, call("GET", &project, "mallory", None)).await;
+    assert_eq!(resp.status().as_u16(), 200);
+    let resp = test::call_service(&app, call("DELETE", &task, "mallory", None)).await;
+    assert_eq!(resp.status().as_u16(), 403);
+
+    // Only owners delete the project; editors may delete tasks
+    let resp = test::call_service(&app, call("DELETE", &project, "edith", None)).await;
+    assert_eq!(resp.status().as_u16(), 403);
+    let resp = test::call_service(&app, call("DELETE", &task, "edith", None)).await;
+    assert_eq!(resp.status().as_u16(), 204);
+    let resp = test::call_service(&app, call("DELETE", &project, "olivia", None)).await;
+    assert_eq!(resp.status().as_u16(), 204);
+}
+
+#[ntex::test]
+async fn anonymous_mode_grants_no_admin_rights() {
+    let services = services(QuotaPolicy::default());
+    let rockets = services
+        .projects
+        .create_project(&common::admin("acme"), common::project("Rockets"))
+        .await
+        .unwrap();
+    let app = roles_app!(services, JwtAuth::new(None));
+
+    // Administrative endpoints are closed to anonymous callers
+    for uri in [
+        "/api/v1/admin/api-keys",
+        "/api/v1/admin/backup",
+        "/api/v1/audit",
+    ] {
+        let resp = test::call_service(&app, request("GET", uri, None, None).to_request()).await;
+        assert_eq!(resp.status().as_u16(), 403, "{}", uri);
+    }
+    let req = request(
+        "POST",
+        "/api/v1/admin/api-keys",
+        None,
+        Some(json!({ "name": "ci", "scopes": ["admin"] })),
+    )
+    .to_request();
+    assert_eq!(test::call_service(&app, req).await.status().as_u16(), 403);
+
+    // Other callers' projects stay out of reach
+    let uri = format!("/api/v1/projects/{}", rockets.id);
+    let resp = test::call_service(&app, request("GET", &uri, None, None).to_request()).await;
+    assert_eq!(resp.status().as_u16(), 403);
+    let resp = test::call_service(
+        &app,
+        request("GET", "/api/v1/projects", None, None).to_request(),
+    )
+    .await;
+    let body: Value = serde_json::from_slice(&test::read_body(resp).await).unwrap();
+    assert_eq!(body["data"], json!([]));
+
+    // Anonymous callers still own what they create
+    let req = request(
+        "POST",
+        "/api/v1/projects",
+        None,
+        Some(json!({ "name": "Scratch" })),
+    )
+    .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status().as_u16(), 201);
+    let body: Value = serde_json::from_slice(&test::read_body(resp).await).unwrap();
+    let uri = format!("/api/v1/projects/{}", body["data"]["id"].as_str().unwrap());
+    let resp = test::call_service(&app, request("DELETE", &uri, None, None).to_request()).await;
+    assert_eq!(resp.status().as_u16(), 204);
+}
@@ -0,0 +1,276 @@
+mod common;
+
+use common::{admin, project, services, task};
+use rust_mvc_api::models::{DomainEvent, Task, TaskMove};
+use rust_mvc_api::services::rank::{self, MAX_RANK_LEN};
+use rust_mvc_api::services::{QuotaPolicy, TenantQuota};
+use rust_mvc_api::views::ApiError;
+use uuid::Uuid;
+
+fn titles(tasks: &[Task]) -> Vec<&str> {
+    tasks.iter().map(|task| task.title.as_str()).collect()
+}
+
+fn after(id: Uuid) -> TaskMove {
+    TaskMove {
+        before: None,
+        after: Some(id),
+    }
+}
+
+#[test]
+fn rank_keys_sort_between_their_neighbours() {
+    let mut keys = vec![rank::between(None, None)];
+    for i in 0..200 {
+        // Alternate between the front, the back and a tight gap in the middle.
+        let key = match i % 3 {
+            0 => rank::between(None, Some(&keys[0])),
+            1 => rank::between(Some(&keys[keys.len() - 1]), None),
+            _ => {
+                let mid = keys.len() / 2;
+                rank::between(Some(&keys[mid - 1]), Some(&keys[mid]))
+            }
+        };
+        keys.push(key);
+        keys.sort();
+    }
+    assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(keys.iter().all(|key| !key.ends_with('0')));
+
+    let spread = rank::spread(1000);
+    assert_eq!(spread.len(), 1000);
+    assert!(spread.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(spread
+        .iter()
+        .all(|key| !key.ends_with('0') && key.len() <= 3));
+}
+
+#[ntex::test]
+async fn tasks_are_listed_and_moved_in_rank_order() {
+    let services = services(QuotaPolicy::default());
+    let acme = admin("acme");
+    let rockets = services
+        .projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    let mut ids = Vec::new();
+    for title in ["A", "B", "C", "D"] {
+        let created = services
+            .tasks
+            .create_task(&acme, task(rockets.id, title))
+            .await
+            .unwrap();
+        ids.push(created.id);
+    }
+    let list = || services.tasks.list_tasks_by_project(&acme, rockets.id);
+    assert_eq!(titles(&list().await.unwrap()), ["A", "B", "C", "D"]);
+
+    services
+        .tasks
+        .move_task(&acme, ids[3], after(ids[0]))
+        .await
+        .unwrap();
+    assert_eq!(titles(&list().await.unwrap()), ["A", "D", "B", "C"]);
+
+    services
+        .tasks
+        .move_task(
+            &acme,
+            ids[0],
+            TaskMove {
+                before: Some(ids[2]),
+                after: None,
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(titles(&list().await.unwrap()), ["D", "B", "A", "C"]);
+
+    services
+        .tasks
+        .move_task(
+            &acme,
+            ids[2],
+            TaskMove {
+                before: Some(ids[1]),
+                after: Some(ids[3]),
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(titles(&list().await.unwrap()), ["D", "C", "B", "A"]);
+
+    // Neighbours must be adjacent, in the same project and not the task itself.
+    let result = services
+        .tasks
+        .move_task(
+            &acme,
+            ids[0],
+            TaskMove {
+                before: Some(ids[1]),
+                after: Some(ids[3]),
+            },
+        )
+        .await;
+    assert!(matches!(result, Err(ApiError::ValidationError { .. })));
+    let elsewhere = services
+        .projects
+        .create_project(&acme, project("Rovers"))
+        .await
+        .unwrap();
+    let wheels = services
+        .tasks
+        .create_task(&acme, task(elsewhere.id, "Wheels"))
+        .await
+        .unwrap();
+    let result = services
+        .tasks
+        .move_task(&acme, ids[0], after(wheels.id))
+        .await;
+    assert!(matches!(result, Err(ApiError::ValidationError { .. })));
+    let result = services.tasks.move_task(&acme, ids[0], after(ids[0])).await;
+    assert!(matches!(result, Err(ApiError::ValidationError { .. })));
+    let result = services
+        .tasks
+        .move_task(&acme, ids[0], TaskMove::default())
+        .await;
+    assert!(matches!(result, Err(ApiError::ValidationError { .. })));
+}
+
+#[ntex::test]
+async fn long_ranks_are_rebalanced() {
+    let services = services(QuotaPolicy::default());
+    let acme = admin("acme");
+    let rockets = services
+        .projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    let mut ids = Vec::new();
+    for title in ["First", "Ping", "Pong", "Last"] {
+        let created = services
+            .tasks
+            .create_task(&acme, task(rockets.id, title))
+            .await
+            .unwrap();
+        ids.push(created.id);
+    }
+    let mut events = services.events.subscribe("test");
+
+    // Each move halves the gap behind "First" until the keys have to be respread.
+    for i in 0..300 {
+        let moving = ids[1 + i % 2];
+        services
+            .tasks
+            .move_task(&acme, moving, after(ids[0]))
+            .await
+            .unwrap();
+    }
+
+    let tasks = services
+        .tasks
+        .list_tasks_by_project(&acme, rockets.id)
+        .await
+        .unwrap();
+    assert_eq!(titles(&tasks), ["First", "Pong", "Ping", "Last"]);
+    assert!(tasks.iter().all(|task| task.rank.len() <= MAX_RANK_LEN));
+
+    // Respread keys are saved like moves: published, and kept as the latest revision
+    let last = tasks[3].id;
+    let published = std::iter::from_fn(|| events.try_recv().ok())
+        .filter(|envelope| {
+            matches!(&envelope.event, DomainEvent::TaskUpdated { after, .. } if after.id == last)
+        })
+        .count();
+    assert!(published > 0);
+    for task in &tasks {
+        let revisions = services
+            .tasks
+            .list_task_revisions(&acme, task.id)
+            .await
+            .unwrap();
+        assert_eq!(revisions.last().unwrap().data["rank"], task.rank.as_str());
+    }
+}
+
+#[ntex::test]
+async fn creates_over_quota_do_not_rebalance() {
+    let services = services(QuotaPolicy::new(TenantQuota {
+        max_projects: None,
+        max_tasks: Some(2),
+    }));
+    let acme = admin("acme");
+    let rockets = services
+        .projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    let mut ids = Vec::new();
+    for title in ["Ping", "Pong"] {
+        let created = services
+            .tasks
+            .create_task(&acme, task(rockets.id, title))
+            .await
+            .unwrap();
+        ids.push(created.id);
+    }
+
+    // Moving to the end lengthens the keys until the next append would need a rebalance.
+    for i in 0.. {
+        let tasks = services
+            .tasks
+            .list_tasks_by_project(&acme, rockets.id)
+            .await
+            .unwrap();
+        if rank::between(Some(&tasks[1].rank), None).len() > MAX_RANK_LEN {
+            break;
+        }
+        services
+            .tasks
+            .move_task(&acme, ids[i % 2], after(ids[(i + 1) % 2]))
+            .await
+            .unwrap();
+    }
+    let before = services
+        .tasks
+        .list_tasks_by_project(&acme, rockets.id)
+        .await
+        .unwrap();
+    let mut events = services.events.subscribe("test");
+
+    let result = services
+        .tasks
+        .create_task(&acme, task(rockets.id, "Launch"))
+        .await;
+    assert!(matches!(result, Err(ApiError::Conflict { .. })));
+    assert!(events.try_recv().is_err());
+    let tasks = services
+        .tasks
+        .list_tasks_by_project(&acme, rockets.id)
+        .await
+        .unwrap();
+    let ranks =
+        |tasks: &[Task]| -> Vec<String> { tasks.iter().map(|task| task.rank.clone()).collect() };
+    assert_eq!(ranks(&tasks), ranks(&before));
+
+    // With room again, the new task is stored first and the project respread after it.
+    services
+        .tasks
+        .delete_task(&acme, before[0].id)
+        .await
+        .unwrap();
+    let launch = services
+        .tasks
+        .create_task(&acme, task(rockets.id, "Launch"))
+        .await
+        .unwrap();
+    assert!(launch.rank.len() <= MAX_RANK_LEN);
+    let tasks = services
+        .tasks
+        .list_tasks_by_project(&acme, rockets.id)
+        .await
+        .unwrap();
+    assert_eq!(titles(&tasks), [before[1].title.as_str(), "Launch"]);
+    assert!(tasks.iter().all(|task| task.rank.len() <= MAX_RANK_LEN));
+}
@@ -0,0 +1,264 @@
+mod common;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use common::{admin, project, services, task};
+use rust_mvc_api::models::{RecurrenceRule, RecurringTaskCreate, TaskCreate, TaskOccurrence};
+use rust_mvc_api::services::QuotaPolicy;
+use rust_mvc_api::views::ApiError;
+use uuid::Uuid;
+
+fn at(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(year, month, day, 9, 0, 0).unwrap()
+}
+
+fn expand(rule: &str, start: DateTime<Utc>, limit: usize) -> Vec<DateTime<Utc>> {
+    let rule: RecurrenceRule = rule.parse().unwrap();
+    rule.occurrences(start).take(limit).collect()
+}
+
+fn template(project_id: Uuid, rrule: &str, starts_at: DateTime<Utc>) -> RecurringTaskCreate {
+    RecurringTaskCreate {
+        project_id,
+        title: "Rotate certs".to_string(),
+        description: None,
+        rrule: rrule.to_string(),
+        starts_at: Some(starts_at),
+    }
+}
+
+#[test]
+fn rules_expand_to_their_occurrences() {
+    // 2024-01-01 is a Monday.
+    assert_eq!(
+        expand(
+            "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH;COUNT=5",
+            at(2024, 1, 1),
+            10
+        ),
+        [
+            at(2024, 1, 1),
+            at(2024, 1, 4),
+            at(2024, 1, 15),
+            at(2024, 1, 18),
+            at(2024, 1, 29),
+        ]
+    );
+    assert_eq!(
+        expand("FREQ=MONTHLY;BYDAY=-1FR;UNTIL=20240426", at(2024, 1, 1), 10),
+        [
+            at(2024, 1, 26),
+            at(2024, 2, 23),
+            at(2024, 3, 29),
+            at(2024, 4, 26),
+        ]
+    );
+    // Months without a 31st are skipped.
+    assert_eq!(
+        expand("FREQ=MONTHLY", at(2024, 1, 31), 3),
+        [at(2024, 1, 31), at(2024, 3, 31), at(2024, 5, 31)]
+    );
+    assert_eq!(
+        expand(
+            "FREQ=DAILY;INTERVAL=3;BYDAY=MO,TU,WE,TH,FR",
+            at(2024, 1, 1),
+            3
+        ),
+        [at(2024, 1, 1), at(2024, 1, 4), at(2024, 1, 10)]
+    );
+
+    let rule: RecurrenceRule = "RRULE:freq=weekly;interval=2;byday=mo,fr;count=3"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        rule.to_string(),
+        "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,FR;COUNT=3"
+    );
+    let rule: RecurrenceRule = "FREQ=MONTHLY;BYDAY=+1MO;UNTIL=20241231".parse().unwrap();
+    assert_eq!(
+        rule.to_string(),
+        "FREQ=MONTHLY;BYDAY=1MO;UNTIL=20241231T235959Z"
+    );
+
+    for invalid in [
+        "INTERVAL=2",
+        "FREQ=YEARLY",
+        "FREQ=DAILY;INTERVAL=0",
+        "FREQ=DAILY;COUNT=2;UNTIL=20240101",
+        "FREQ=WEEKLY;BYDAY=1MO",
+        "FREQ=MONTHLY;BYDAY=6MO",
+        "FREQ=DAILY;BYHOUR=9",
+    ] {
+        assert!(invalid.parse::<RecurrenceRule>().is_err(), "{}", invalid);
+    }
+}
+
+#[ntex::test]
+async fn due_occurrences_become_tasks_once() {
+    let services = services(QuotaPolicy::default());
+    let acme = admin("acme");
+    let ops = services
+        .projects
+        .create_project(&acme, project("Ops"))
+        .await
+        .unwrap();
+    let now = Utc::now();
+    let weekly = services
+        .recurring
+        .create_recurring_task(
+            &acme,
+            template(ops.id, "FREQ=WEEKLY", now - Duration::days(15)),
+        )
+        .await
+        .unwrap();
+    assert_eq!(weekly.next_at, Some(now - Duration::days(15)));
+
+    // Missed occurrences are skipped; only the latest one is created.
+    assert_eq!(services.recurring.run_due(now).await.unwrap(), 1);
+    assert_eq!(services.recurring.run_due(now).await.unwrap(), 0);
+    let tasks = services
+        .tasks
+        .list_tasks_by_project(&acme, ops.id)
+        .await
+        .unwrap();
+    assert_eq!(tasks.len(), 1);
+    let occurrence = tasks[0].occurrence.unwrap();
+    assert_eq!(occurrence.recurring_task_id, weekly.id);
+    assert_eq!(occurrence.occurs_at, now - Duration::days(1));
+    let weekly = services
+        .recurring
+        .get_recurring_task(&acme, weekly.id)
+        .await
+        .unwrap();
+    assert_eq!(weekly.next_at, Some(now + Duration::days(6)));
+
+    // Creating the same occurrence again, as after a crash mid-run, returns the same task.
+    let again = services
+        .tasks
+        .create_task(
+            &acme,
+            TaskCreate {
+                occurrence: Some(occurrence),
+                ..task(ops.id, "Rotate certs")
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(again.id, tasks[0].id);
+    let other = services
+        .tasks
+        .create_task(
+            &acme,
+            TaskCreate {
+                occurrence: Some(TaskOccurrence {
+                    occurs_at: now + Duration::days(6),
+                    ..occurrence
+                }),
+                ..task(ops.id, "Rotate certs")
+            },
+        )
+        .await
+        .unwrap();
+    assert_ne!(other.id, tasks[0].id);
+}
+
+#[ntex::test]
+async fn huge_intervals_run_out_instead_of_overflowing() {
+    let start = at(2024, 3, 1);
+    for rule in [
+        "FREQ=DAILY;INTERVAL=100000000",
+        "FREQ=WEEKLY;INTERVAL=4294967295;BYDAY=MO,FR",
+        "FREQ=MONTHLY;INTERVAL=4294967295",
+    ] {
+        let occurrences = expand(rule, start, 3);
+        assert!(occurrences.len() <= 2, "{}", rule);
+        assert!(occurrences.iter().all(|at| *at >= start), "{}", rule);
+    }
+    assert_eq!(expand("FREQ=DAILY;INTERVAL=100000000", start, 3), [start]);
+
+    // The scheduler creates the one occurrence and then retires the template
+    let services = services(QuotaPolicy::default());
+    let acme = admin("acme");
+    let ops = services
+        .projects
+        .create_project(&acme, project("Ops"))
+        .await
+        .unwrap();
+    let now = Utc::now();
+    let created = services
+        .recurring
+        .create_recurring_task(
+            &acme,
+            template(
+                ops.id,
+                "FREQ=DAILY;INTERVAL=100000000",
+                now - Duration::hours(1),
+            ),
+        )
+        .await
+        .unwrap();
+    assert_eq!(services.recurring.run_due(now).await.unwrap(), 1);
+    let stored = services
+        .recurring
+        .get_recurring_task(&acme, created.id)
+        .await
+        .unwrap();
+    assert_eq!(stored.next_at, None);
+}
+
+#[ntex::test]
+async fn archived_projects_skip_occurrences() {
+    let services = services(QuotaPolicy::default());
+    let acme = admin("acme");
+    let ops = services
+        .projects
+        .create_project(&acme, project("Ops"))
+        .await
+        .unwrap();
+    let now = Utc::now();
+    services
+        .recurring
+        .create_recurring_task(&acme, template(ops.id, "FREQ=DAILY;COUNT=2", now))
+        .await
+        .unwrap();
+    services
+        .projects
+        .archive_project(&acme, ops.id)
+        .await
+        .unwrap();
+
+    assert_eq!(services.recurring.run_due(now).await.unwrap(), 0);
+    services
+        .projects
+        .unarchive_project(&acme, ops.id)
+        .await
+        .unwrap();
+    assert_eq!(
+        services
+            .recurring
+            .run_due(now + Duration::days(1))
+            .await
+            .unwrap(),
+        1
+    );
+    // The rule has run out.
+    assert_eq!(
+        services
+            .recurring
+            .run_due(now + Duration::days(30))
+            .await
+            .unwrap(),
+        0
+    );
+    let tasks = services
+        .tasks
+        .list_tasks_by_project(&acme, ops.id)
+        .await
+        .unwrap();
+    assert_eq!(tasks.len(), 1);
+
+    let result = services
+        .recurring
+        .create_recurring_task(&acme, template(ops.id, "FREQ=HOURLY", now))
+        .await;
+    assert!(matches!(result, Err(ApiError::ValidationError { .. })));
+}
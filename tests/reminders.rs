@@ -0,0 +1,224 @@
+mod common;
+
+use chrono::{DateTime, Duration, Utc};
+use common::{admin, admin_bearer, jwt_verifier, member, project, services, task, TestServices};
+use ntex::web::{test, App};
+use rust_mvc_api::middleware::JwtAuth;
+use rust_mvc_api::models::{DomainEvent, EventKind, OverdueCount, TaskCreate, TaskUpdate};
+use rust_mvc_api::routes::configure_routes;
+use rust_mvc_api::services::QuotaPolicy;
+use uuid::Uuid;
+
+fn due(project_id: Uuid, title: &str, due_at: DateTime<Utc>) -> TaskCreate {
+    TaskCreate {
+        due_at: Some(due_at),
+        ..task(project_id, title)
+    }
+}
+
+fn reschedule(due_at: DateTime<Utc>) -> TaskUpdate {
+    TaskUpdate {
+        title: None,
+        description: None,
+        done: None,
+        column_id: None,
+        due_at: Some(due_at),
+    }
+}
+
+#[ntex::test]
+async fn reminders_fire_once_per_due_date() {
+    let services = services(QuotaPolicy::default());
+    let mut events = services.events.subscribe("test");
+    let acme = admin("acme");
+    let rockets = services
+        .projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    let now = Utc::now();
+    let fuel = services
+        .tasks
+        .create_task(&acme, due(rockets.id, "Fuel", now + Duration::hours(2)))
+        .await
+        .unwrap();
+    let wheels = services
+        .tasks
+        .create_task(&acme, due(rockets.id, "Wheels", now - Duration::hours(1)))
+        .await
+        .unwrap();
+    services
+        .tasks
+        .create_task(&acme, due(rockets.id, "Paint", now + Duration::days(3)))
+        .await
+        .unwrap();
+    let done = services
+        .tasks
+        .create_task(&acme, due(rockets.id, "Launch", now - Duration::days(1)))
+        .await
+        .unwrap();
+    services
+        .tasks
+        .update_task(
+            &acme,
+            done.id,
+            TaskUpdate {
+                title: None,
+                description: None,
+                done: Some(true),
+                column_id: None,
+                due_at: None,
+            },
+        )
+        .await
+        .unwrap();
+    while events.try_recv().is_ok() {}
+
+    assert_eq!(services.reminders.send_due(now).unwrap(), 2);
+    assert_eq!(services.reminders.send_due(now).unwrap(), 0);
+    let mut reminders: Vec<(EventKind, Uuid)> = std::iter::from_fn(|| events.try_recv().ok())
+        .map(|envelope| {
+            assert_eq!(envelope.actor, "system:reminders");
+            assert_eq!(envelope.event.project_id(), rockets.id);
+            let task_id = match &envelope.event {
+                DomainEvent::TaskDueSoon { task } | DomainEvent::TaskOverdue { task } => task.id,
+                other => panic!("unexpected event {:?}", other.kind()),
+            };
+            (envelope.event.kind(), task_id)
+        })
+        .collect();
+    reminders.sort_by_key(|(kind, _)| kind.as_str());
+    assert_eq!(
+        reminders,
+        [
+            (EventKind::TaskDueSoon, fuel.id),
+            (EventKind::TaskOverdue, wheels.id)
+        ]
+    );
+
+    // Moving the due date re-arms the reminders for the new date.
+    services
+        .tasks
+        .update_task(&acme, fuel.id, reschedule(now + Duration::hours(30)))
+        .await
+        .unwrap();
+    assert_eq!(services.reminders.send_due(now).unwrap(), 0);
+    assert_eq!(
+        services
+            .reminders
+            .send_due(now + Duration::hours(10))
+            .unwrap(),
+        1
+    );
+    assert_eq!(
+        services
+            .reminders
+            .send_due(now + Duration::hours(31))
+            .unwrap(),
+        1
+    );
+
+    // Archived projects get no reminders.
+    services
+        .projects
+        .archive_project(&acme, rockets.id)
+        .await
+        .unwrap();
+    assert_eq!(
+        services
+            .reminders
+            .send_due(now + Duration::days(3))
+            .unwrap(),
+        0
+    );
+}
+
+#[ntex::test]
+async fn lists_and_counts_overdue_tasks() {
+    let TestServices {
+        projects,
+        tasks,
+        reminders,
+        ..
+    } = services(QuotaPolicy::default());
+    let acme = admin("acme");
+    let alice = member("acme", "alice");
+    let rockets = projects
+        .create_project(&alice, project("Rockets"))
+        .await
+        .unwrap();
+    let rovers = projects
+        .create_project(&acme, project("Rovers"))
+        .await
+        .unwrap();
+    let now = Utc::now();
+    let fuel = tasks
+        .create_task(&alice, due(rockets.id, "Fuel", now - Duration::hours(1)))
+        .await
+        .unwrap();
+    let wheels = tasks
+        .create_task(&acme, due(rovers.id, "Wheels", now - Duration::days(2)))
+        .await
+        .unwrap();
+    let tyres = tasks
+        .create_task(&acme, due(rovers.id, "Tyres", now - Duration::days(1)))
+        .await
+        .unwrap();
+    tasks
+        .create_task(&acme, due(rovers.id, "Paint", now + Duration::hours(1)))
+        .await
+        .unwrap();
+    tasks
+        .create_task(&acme, task(rovers.id, "Polish"))
+        .await
+        .unwrap();
+
+    let overdue = reminders.list_overdue_tasks(&acme, None).await.unwrap();
+    let ids: Vec<Uuid> = overdue.iter().map(|task| task.id).collect();
+    assert_eq!(ids, [wheels.id, tyres.id, fuel.id]);
+    let overdue = reminders.list_overdue_tasks(&alice, None).await.unwrap();
+    let ids: Vec<Uuid> = overdue.iter().map(|task| task.id).collect();
+    assert_eq!(ids, [fuel.id]);
+
+    let app = test::init_service(
+        App::new()
+            .state(reminders)
+            .wrap(JwtAuth::new(Some(jwt_verifier())))
+            .configure(configure_routes),
+    )
+    .await;
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/tasks/overdue?project_id={}", rovers.id))
+        .header("Authorization", admin_bearer("acme"))
+        .to_request();
+    let body: serde_json::Value = test::read_response_json(&app, req).await;
+    let titles: Vec<&str> = body["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|task| task["title"].as_str().unwrap())
+        .collect();
+    assert_eq!(titles, ["Wheels", "Tyres"]);
+
+    let req = test::TestRequest::get()
+        .uri("/api/v1/tasks/overdue/counts")
+        .header("Authorization", admin_bearer("acme"))
+        .to_request();
+    let body: serde_json::Value = test::read_response_json(&app, req).await;
+    assert_eq!(
+        body["data"],
+        serde_json::to_value([
+            OverdueCount {
+                project_id: rovers.id,
+                project_name: "Rovers".to_string(),
+                overdue: 2,
+            },
+            OverdueCount {
+                project_id: rockets.id,
+                project_name: "Rockets".to_string(),
+                overdue: 1,
+            },
+        ])
+        .unwrap()
+    );
+}
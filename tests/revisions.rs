@@ -0,0 +1,214 @@
+mod common;
+
+use common::{admin, admin_bearer, jwt_verifier, member, project, services, task, TestServices};
+use ntex::http::StatusCode;
+use ntex::web::{test, App};
+use rust_mvc_api::middleware::JwtAuth;
+use rust_mvc_api::models::{ProjectUpdate, TaskUpdate};
+use rust_mvc_api::routes::configure_routes;
+use rust_mvc_api::services::QuotaPolicy;
+use rust_mvc_api::views::ApiError;
+use serde_json::json;
+
+fn rename(title: &str) -> TaskUpdate {
+    TaskUpdate {
+        title: Some(title.to_string()),
+        description: None,
+        done: None,
+        column_id: None,
+        due_at: None,
+    }
+}
+
+#[ntex::test]
+async fn task_history_can_be_diffed_and_reverted() {
+    let services = services(QuotaPolicy::default());
+    let acme = admin("acme");
+    let rockets = services
+        .projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    let fuel = services
+        .tasks
+        .create_task(&acme, task(rockets.id, "Fuel"))
+        .await
+        .unwrap();
+    services
+        .tasks
+        .update_task(&acme, fuel.id, rename("Fuel up"))
+        .await
+        .unwrap();
+    services
+        .tasks
+        .update_task(
+            &acme,
+            fuel.id,
+            TaskUpdate {
+                title: Some("Fuel up twice".to_string()),
+                description: Some("Both stages".to_string()),
+                done: None,
+                due_at: None,
+                column_id: None,
+            },
+        )
+        .await
+        .unwrap();
+
+    let revisions = services
+        .tasks
+        .list_task_revisions(&acme, fuel.id)
+        .await
+        .unwrap();
+    let titles: Vec<_> = revisions
+        .iter()
+        .map(|revision| (revision.number, revision.data["title"].clone()))
+        .collect();
+    assert_eq!(
+        titles,
+        [
+            (1, json!("Fuel")),
+            (2, json!("Fuel up")),
+            (3, json!("Fuel up twice")),
+        ]
+    );
+    assert!(revisions
+        .iter()
+        .all(|revision| revision.actor == acme.subject));
+
+    let diff = services
+        .tasks
+        .diff_task_revisions(&acme, fuel.id, 1, None)
+        .await
+        .unwrap();
+    assert_eq!(diff.to, 3);
+    assert_eq!(diff.changes["title"].before, json!("Fuel"));
+    assert_eq!(diff.changes["title"].after, json!("Fuel up twice"));
+    assert_eq!(diff.changes["description"].after, json!("Both stages"));
+    let diff = services
+        .tasks
+        .diff_task_revisions(&acme, fuel.id, 1, Some(2))
+        .await
+        .unwrap();
+    assert!(!diff.changes.contains_key("description"));
+
+    // Reverting writes a fourth revision rather than rewriting history.
+    let reverted = services.tasks.revert_task(&acme, fuel.id, 1).await.unwrap();
+    assert_eq!(reverted.title, "Fuel");
+    assert_eq!(reverted.description, None);
+    let latest = services
+        .tasks
+        .get_task_revision(&acme, fuel.id, 4)
+        .await
+        .unwrap();
+    assert_eq!(latest.data["title"], json!("Fuel"));
+    assert_eq!(
+        services
+            .tasks
+            .get_task_revision(&acme, fuel.id, 3)
+            .await
+            .unwrap()
+            .data["title"],
+        json!("Fuel up twice")
+    );
+
+    let result = services.tasks.get_task_revision(&acme, fuel.id, 5).await;
+    assert!(matches!(result, Err(ApiError::NotFound { .. })));
+    let result = services
+        .tasks
+        .list_task_revisions(&admin("globex"), fuel.id)
+        .await;
+    assert!(matches!(result, Err(ApiError::NotFound { .. })));
+}
+
+#[ntex::test]
+async fn project_reverts_need_editor_access() {
+    let services = services(QuotaPolicy::default());
+    let alice = member("acme", "alice");
+    let rockets = services
+        .projects
+        .create_project(&alice, project("Rockets"))
+        .await
+        .unwrap();
+    services
+        .projects
+        .update_project(
+            &alice,
+            rockets.id,
+            ProjectUpdate {
+                name: Some("Moon rockets".to_string()),
+                description: None,
+            },
+        )
+        .await
+        .unwrap();
+
+    let result = services
+        .projects
+        .revert_project(&member("acme", "mallory"), rockets.id, 1)
+        .await;
+    assert!(matches!(result, Err(ApiError::Forbidden { .. })));
+
+    let reverted = services
+        .projects
+        .revert_project(&alice, rockets.id, 1)
+        .await
+        .unwrap();
+    assert_eq!(reverted.name, "Rockets");
+    let revisions = services
+        .projects
+        .list_project_revisions(&alice, rockets.id)
+        .await
+        .unwrap();
+    assert_eq!(revisions.len(), 3);
+}
+
+#[ntex::test]
+async fn revision_routes_resolve_diff_before_numbers() {
+    let TestServices {
+        projects, tasks, ..
+    } = services(QuotaPolicy::default());
+    let acme = admin("acme");
+    let rockets = projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    let fuel = tasks
+        .create_task(&acme, task(rockets.id, "Fuel"))
+        .await
+        .unwrap();
+    tasks
+        .update_task(&acme, fuel.id, rename("Fuel up"))
+        .await
+        .unwrap();
+
+    let app = test::init_service(
+        App::new()
+            .state(projects)
+            .state(tasks)
+            .wrap(JwtAuth::new(Some(jwt_verifier())))
+            .configure(configure_routes),
+    )
+    .await;
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/tasks/{}/revisions/diff?from=1", fuel.id))
+        .header("Authorization", admin_bearer("acme"))
+        .to_request();
+    let body: serde_json::Value = test::read_response_json(&app, req).await;
+    assert_eq!(body["data"]["changes"]["title"]["after"], json!("Fuel up"));
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/api/v1/tasks/{}/revisions/1/revert", fuel.id))
+        .header("Authorization", admin_bearer("acme"))
+        .to_request();
+    let body: serde_json::Value = test::read_response_json(&app, req).await;
+    assert_eq!(body["data"]["title"], json!("Fuel"));
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/tasks/{}/revisions/3", fuel.id))
+        .header("Authorization", admin_bearer("acme"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+}
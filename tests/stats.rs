@@ -0,0 +1,176 @@
+mod common;
+
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use common::{admin, member, project, services, task};
+use rust_mvc_api::models::{
+    EntityType, ProjectStatsQuery, Revision, Task, TaskHistory, TaskUpdate,
+};
+use rust_mvc_api::services::QuotaPolicy;
+use rust_mvc_api::views::ApiError;
+use serde_json::json;
+use uuid::Uuid;
+
+fn day(day: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 3, day, 12, 0, 0).unwrap()
+}
+
+fn revision(task: &Task, number: u32, at: DateTime<Utc>, done: bool) -> Revision {
+    Revision {
+        number,
+        tenant_id: task.tenant_id.clone(),
+        entity_type: EntityType::Task,
+        entity_id: task.id,
+        actor: "alice".to_string(),
+        updated_at: at,
+        data: json!({ "title": task.title, "done": done }),
+    }
+}
+
+fn mark_done(done: bool) -> TaskUpdate {
+    TaskUpdate {
+        title: None,
+        description: None,
+        done: Some(done),
+        column_id: None,
+        due_at: None,
+    }
+}
+
+#[test]
+fn history_replays_done_changes() {
+    let mut fuel = Task::new("acme".to_string(), Uuid::new_v4(), "Fuel".to_string(), None);
+    fuel.created_at = day(1);
+    fuel.done = true;
+    fuel.deleted_at = Some(day(9));
+    let history = TaskHistory::new(
+        &fuel,
+        &[
+            revision(&fuel, 1, day(1), false),
+            revision(&fuel, 2, day(2), true),
+            // Renamed while done
+            revision(&fuel, 3, day(3), true),
+            revision(&fuel, 4, day(4), false),
+            revision(&fuel, 5, day(6), true),
+        ],
+    );
+
+    assert!(!history.exists_at(day(1) - Duration::hours(1)));
+    assert!(history.exists_at(day(8)));
+    assert!(!history.exists_at(day(9)));
+    let done: Vec<bool> = (1..=7).map(|d| history.is_done_at(day(d))).collect();
+    assert_eq!(done, [false, true, true, false, false, true, true]);
+    assert_eq!(history.completed_at(), Some(day(6)));
+
+    // Without revisions the current state holds from creation on.
+    let history = TaskHistory::new(&fuel, &[]);
+    assert!(history.is_done_at(day(1)));
+    assert_eq!(history.completed_at(), Some(day(1)));
+}
+
+#[ntex::test]
+async fn stats_count_tasks_and_replay_today() {
+    let services = services(QuotaPolicy::default());
+    let acme = admin("acme");
+    let rockets = services
+        .projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    let mut ids = Vec::new();
+    for title in ["Fuel", "Wheels", "Paint", "Launch"] {
+        let created = services
+            .tasks
+            .create_task(&acme, task(rockets.id, title))
+            .await
+            .unwrap();
+        ids.push(created.id);
+    }
+    for id in &ids[..2] {
+        services
+            .tasks
+            .update_task(&acme, *id, mark_done(true))
+            .await
+            .unwrap();
+    }
+    // Done, then reopened
+    services
+        .tasks
+        .update_task(&acme, ids[2], mark_done(true))
+        .await
+        .unwrap();
+    services
+        .tasks
+        .update_task(&acme, ids[2], mark_done(false))
+        .await
+        .unwrap();
+    services.tasks.delete_task(&acme, ids[3]).await.unwrap();
+
+    let stats = services
+        .projects
+        .project_stats(&acme, rockets.id, &ProjectStatsQuery::default())
+        .await
+        .unwrap();
+    assert_eq!((stats.total, stats.open, stats.done), (3, 1, 2));
+    assert_eq!(stats.completion_percent, 66.67);
+    assert!(stats
+        .average_hours_to_done
+        .is_some_and(|hours| hours < 0.01));
+    // The project was created today, so the default burndown starts today.
+    assert_eq!(stats.burndown.len(), 1);
+    let today = &stats.burndown[0];
+    assert_eq!(today.date, Utc::now().date_naive());
+    assert_eq!((today.total, today.open, today.done), (3, 1, 2));
+
+    let today = Utc::now().date_naive();
+    let stats = services
+        .projects
+        .project_stats(
+            &acme,
+            rockets.id,
+            &ProjectStatsQuery {
+                from: Some(today - Duration::days(2)),
+                to: None,
+            },
+        )
+        .await
+        .unwrap();
+    let totals: Vec<(NaiveDate, usize)> = stats
+        .burndown
+        .iter()
+        .map(|point| (point.date, point.total))
+        .collect();
+    assert_eq!(
+        totals,
+        [
+            (today - Duration::days(2), 0),
+            (today - Duration::days(1), 0),
+            (today, 3)
+        ]
+    );
+
+    for query in [
+        ProjectStatsQuery {
+            from: Some(today + Duration::days(1)),
+            to: None,
+        },
+        ProjectStatsQuery {
+            from: Some(today - Duration::days(400)),
+            to: None,
+        },
+    ] {
+        let result = services
+            .projects
+            .project_stats(&acme, rockets.id, &query)
+            .await;
+        assert!(matches!(result, Err(ApiError::ValidationError { .. })));
+    }
+    let result = services
+        .projects
+        .project_stats(
+            &member("acme", "mallory"),
+            rockets.id,
+            &ProjectStatsQuery::default(),
+        )
+        .await;
+    assert!(matches!(result, Err(ApiError::Forbidden { .. })));
+}
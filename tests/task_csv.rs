@@ -0,0 +1,399 @@
+mod common;
+
+use common::{admin, admin_bearer, jwt_verifier, member, project, services, TestServices};
+use futures::stream;
+use ntex::util::Bytes;
+use ntex::web::{test, App};
+use rust_mvc_api::middleware::JwtAuth;
+use rust_mvc_api::models::ColumnDefinition;
+use rust_mvc_api::routes::configure_routes;
+use rust_mvc_api::services::{rank, QuotaPolicy, TenantQuota};
+use rust_mvc_api::views::{ApiError, CsvReader, CsvRow, MAX_FIELD_LEN, MAX_RECORD_LEN};
+use serde_json::Value;
+use std::convert::Infallible;
+
+fn column(name: &str, wip_limit: Option<usize>, done: bool) -> ColumnDefinition {
+    ColumnDefinition {
+        id: None,
+        name: name.to_string(),
+        wip_limit,
+        done,
+    }
+}
+
+fn upload(csv: &str) -> stream::Iter<std::vec::IntoIter<Result<Bytes, Infallible>>> {
+    // Small chunks split rows and quoted fields across reads
+    let chunks: Vec<Result<Bytes, Infallible>> = csv
+        .as_bytes()
+        .chunks(7)
+        .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+        .collect();
+    stream::iter(chunks)
+}
+
+fn read_all(reader: &mut CsvReader, input: &[u8], chunk_size: usize) -> Vec<CsvRow> {
+    let mut rows: Vec<CsvRow> = input
+        .chunks(chunk_size)
+        .flat_map(|chunk| reader.feed(chunk))
+        .collect();
+    rows.extend(reader.finish());
+    rows
+}
+
+#[test]
+fn reader_parses_quotes_and_line_numbers_across_chunks() {
+    let input =
+        b"title,description\r\nFuel,\"Mix, then \"\"stir\"\"\nslowly\"\r\n\r\nWheels,\n\"Open";
+    let fields = |values: &[&str]| Ok(values.iter().map(|value| value.to_string()).collect());
+    let expected = vec![
+        CsvRow {
+            line: 1,
+            fields: fields(&["title", "description"]),
+        },
+        CsvRow {
+            line: 2,
+            fields: fields(&["Fuel", "Mix, then \"stir\"\nslowly"]),
+        },
+        CsvRow {
+            line: 5,
+            fields: fields(&["Wheels", ""]),
+        },
+        CsvRow {
+            line: 6,
+            fields: Err("Quoted field is not closed".to_string()),
+        },
+    ];
+
+    for chunk_size in [1, 2, 5, input.len()] {
+        let rows = read_all(&mut CsvReader::new(), input, chunk_size);
+        assert_eq!(rows, expected, "chunks of {}", chunk_size);
+    }
+    assert_eq!(
+        read_all(&mut CsvReader::new(), b"a,b\n\xff,c", 3)[1].fields,
+        Err("Row is not valid UTF-8".to_string())
+    );
+}
+
+#[test]
+fn reader_rejects_oversized_fields_and_rows_but_keeps_reading() {
+    let long_field = format!("a,\"{}\nb\"\n", "x".repeat(MAX_FIELD_LEN));
+    let wide_row = format!(
+        "{}\n",
+        vec!["y".repeat(1024); MAX_RECORD_LEN / 1024].join(",")
+    );
+    let input = format!("title\n{}{}after,it\n", long_field, wide_row);
+
+    for chunk_size in [1000, input.len()] {
+        let rows = read_all(&mut CsvReader::new(), input.as_bytes(), chunk_size);
+        let summary: Vec<(usize, Result<usize, String>)> = rows
+            .into_iter()
+            .map(|row| (row.line, row.fields.map(|fields| fields.len())))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (1, Ok(1)),
+                (
+                    2,
+                    Err(format!("Field is longer than {} bytes", MAX_FIELD_LEN))
+                ),
+                (
+                    4,
+                    Err(format!("Row is longer than {} bytes", MAX_RECORD_LEN))
+                ),
+                (5, Ok(2)),
+            ],
+            "chunks of {}",
+            chunk_size
+        );
+    }
+}
+
+#[test]
+fn appended_ranks_keep_a_fixed_width() {
+    let mut key = rank::between(None, None);
+    for _ in 0..100_000 {
+        let next = rank::after(&key).unwrap();
+        assert!(next > key && next.len() <= 4, "{} after {}", next, key);
+        key = next;
+    }
+    assert_eq!(rank::after("zzzz"), None);
+    assert_eq!(rank::after("zzzzzzz"), None);
+    assert_eq!(rank::after("Vz").as_deref(), Some("Vz01"));
+}
+
+#[ntex::test]
+async fn dry_run_reports_row_errors_with_line_numbers() {
+    let TestServices {
+        projects, tasks, ..
+    } = services(QuotaPolicy::default());
+    let acme = admin("acme");
+    let rockets = projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    projects
+        .set_columns(
+            &acme,
+            rockets.id,
+            vec![column("Backlog", None, false), column("Done", None, true)],
+        )
+        .await
+        .unwrap();
+
+    let csv = format!(
+        "Title,Owner,Column,Due\n\
+         Fuel,alice,backlog,2024-03-01\n\
+         ,bob,,\n\
+         Wheels,,Shipping,\n\
+         Paint,,,next week\n\
+         \"Launch\nwindow\",,Done,2024-03-01T09:00:00+02:00\n\
+         {},,,\n\
+         Extra,,,,surplus\n",
+        "x".repeat(201)
+    );
+    let report = tasks
+        .import_tasks(&acme, rockets.id, upload(&csv), true)
+        .await
+        .unwrap();
+    assert!(report.dry_run);
+    assert_eq!(report.ignored_columns, ["Owner"]);
+    assert_eq!((report.rows, report.imported, report.failed), (7, 2, 5));
+    let errors: Vec<(usize, &str)> = report
+        .errors
+        .iter()
+        .map(|error| (error.line, error.code.as_str()))
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            (3, "VALIDATION_ERROR"),
+            (4, "VALIDATION_ERROR"),
+            (5, "VALIDATION_ERROR"),
+            (8, "VALIDATION_ERROR"),
+            (9, "VALIDATION_ERROR"),
+        ]
+    );
+    assert!(report.errors[0].message.contains("title cannot be empty"));
+    assert!(report.errors[1].message.contains("'Shipping'"));
+    assert!(report.errors[2].message.contains("'next week'"));
+    assert!(report.errors[4].message.contains("has 4"));
+    // Nothing is created on a dry run
+    assert!(tasks
+        .list_tasks_by_project(&acme, rockets.id)
+        .await
+        .unwrap()
+        .is_empty());
+
+    for csv in ["description,due_at\nFuel,\n", "", "\n\n"] {
+        let result = tasks
+            .import_tasks(&acme, rockets.id, upload(csv), true)
+            .await;
+        assert!(matches!(result, Err(ApiError::ValidationError { .. })));
+    }
+    let result = tasks
+        .import_tasks(
+            &member("acme", "mallory"),
+            rockets.id,
+            upload("title\nFuel\n"),
+            true,
+        )
+        .await;
+    assert!(matches!(result, Err(ApiError::Forbidden { .. })));
+}
+
+#[ntex::test]
+async fn import_and_export_round_trip_over_http() {
+    let TestServices {
+        projects, tasks, ..
+    } = services(QuotaPolicy::default());
+    let acme = admin("acme");
+    let rockets = projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    projects
+        .set_columns(
+            &acme,
+            rockets.id,
+            vec![
+                column("Backlog", None, false),
+                column("Doing", Some(1), false),
+                column("Done", None, true),
+            ],
+        )
+        .await
+        .unwrap();
+
+    let app = test::init_service(
+        App::new()
+            .state(tasks.clone())
+            .wrap(JwtAuth::new(Some(jwt_verifier())))
+            .configure(configure_routes),
+    )
+    .await;
+    let uri = format!("/api/v1/projects/{}/tasks.csv", rockets.id);
+    let req = test::TestRequest::post()
+        .uri(&uri)
+        .header("Authorization", admin_bearer("acme"))
+        .header("Content-Type", "text/csv")
+        .set_payload(
+            "title,description,column,due_at\r\n\
+             Fuel,\"Mix, then stir\",Doing,2024-03-01\r\n\
+             Wheels,,Doing,\r\n\
+             Paint,,Done,\r\n\
+             \"=HYPERLINK(\"\"https://example.com\"\")\",@everyone,Backlog,\r\n",
+        )
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+    let body: Value = serde_json::from_slice(&test::read_body(resp).await).unwrap();
+    let report = &body["data"];
+    assert_eq!(report["dry_run"], false);
+    assert_eq!(
+        (report["imported"].as_u64(), report["failed"].as_u64()),
+        (Some(3), Some(1))
+    );
+    // Real imports also apply the board's WIP limits
+    assert_eq!(report["errors"][0]["line"], 3);
+    assert_eq!(report["errors"][0]["code"], "CONFLICT");
+
+    let req = test::TestRequest::get()
+        .uri(&uri)
+        .header("Authorization", admin_bearer("acme"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "text/csv; charset=utf-8"
+    );
+    let body = test::read_body(resp).await;
+    let csv = String::from_utf8(body.to_vec()).unwrap();
+    let rows = read_all(&mut CsvReader::new(), csv.as_bytes(), 64);
+    let fields: Vec<Vec<String>> = rows.into_iter().map(|row| row.fields.unwrap()).collect();
+    assert_eq!(
+        fields[0],
+        [
+            "id",
+            "title",
+            "description",
+            "done",
+            "column",
+            "due_at",
+            "created_at",
+            "updated_at"
+        ]
+    );
+    assert_eq!(fields.len(), 4);
+    assert_eq!(
+        fields[1][1..6],
+        [
+            "Fuel",
+            "Mix, then stir",
+            "false",
+            "Doing",
+            "2024-03-01T00:00:00+00:00"
+        ]
+    );
+    assert_eq!(fields[2][1..6], ["Paint", "", "true", "Done", ""]);
+    // Formula-like text is exported as text, and imports back unchanged
+    assert_eq!(
+        fields[3][1..3],
+        ["'=HYPERLINK(\"https://example.com\")", "'@everyone"]
+    );
+    let copy = projects
+        .create_project(&acme, project("Copy"))
+        .await
+        .unwrap();
+    projects
+        .set_columns(
+            &acme,
+            copy.id,
+            vec![
+                column("Backlog", None, false),
+                column("Doing", Some(1), false),
+                column("Done", None, true),
+            ],
+        )
+        .await
+        .unwrap();
+    let report = tasks
+        .import_tasks(&acme, copy.id, upload(&csv), false)
+        .await
+        .unwrap();
+    assert_eq!((report.imported, report.failed), (3, 0));
+    let copied = tasks.list_tasks_by_project(&acme, copy.id).await.unwrap();
+    assert_eq!(copied[2].title, "=HYPERLINK(\"https://example.com\")");
+    assert_eq!(copied[2].description.as_deref(), Some("@everyone"));
+}
+
+#[ntex::test]
+async fn large_imports_keep_file_order_within_quota_and_wip_limits() {
+    let TestServices {
+        projects, tasks, ..
+    } = services(QuotaPolicy::new(TenantQuota {
+        max_projects: None,
+        max_tasks: Some(1200),
+    }));
+    let acme = admin("acme");
+    let rockets = projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    projects
+        .set_columns(
+            &acme,
+            rockets.id,
+            vec![
+                column("Backlog", None, false),
+                column("Doing", Some(3), false),
+            ],
+        )
+        .await
+        .unwrap();
+    tasks
+        .create_task(&acme, common::task(rockets.id, "Existing"))
+        .await
+        .unwrap();
+
+    let mut csv = "title,column\n".to_string();
+    for i in 0..1500 {
+        let column = if i % 100 == 0 { "Doing" } else { "" };
+        csv.push_str(&format!("Task {},{}\n", i, column));
+    }
+    let report = tasks
+        .import_tasks(&acme, rockets.id, upload(&csv), false)
+        .await
+        .unwrap();
+    // Every row for Doing after the third hits its WIP limit, and the quota leaves room
+    // for 1199 tasks, the last from line 1210
+    assert_eq!(
+        (report.rows, report.imported, report.failed),
+        (1500, 1199, 301)
+    );
+    assert_eq!(report.errors.len(), 100);
+    assert_eq!(report.errors[0].line, 302);
+    assert!(report.errors[0].message.contains("WIP limit of 3"));
+    assert_eq!(report.errors[1].line, 402);
+    let quota_errors: Vec<usize> = report
+        .errors
+        .iter()
+        .filter(|error| error.message.contains("quota of 1200"))
+        .map(|error| error.line)
+        .collect();
+    assert_eq!(quota_errors[0], 1211);
+    assert!(quota_errors.windows(2).all(|pair| pair[0] < pair[1]));
+
+    let listed = tasks
+        .list_tasks_by_project(&acme, rockets.id)
+        .await
+        .unwrap();
+    assert_eq!(listed.len(), 1200);
+    assert_eq!(listed[0].title, "Existing");
+    let numbers: Vec<usize> = listed[1..]
+        .iter()
+        .map(|task| task.title["Task ".len()..].parse().unwrap())
+        .collect();
+    assert!(numbers.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(listed.iter().all(|task| task.rank.len() <= 4));
+}
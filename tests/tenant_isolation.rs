@@ -0,0 +1,198 @@
+mod common;
+
+use common::{admin, project, services, task, TestServices};
+use futures::executor::block_on;
+use ntex::http::StatusCode;
+use ntex::web::{test, App};
+use rust_mvc_api::middleware::JwtAuth;
+use rust_mvc_api::routes::configure_routes;
+use rust_mvc_api::services::{QuotaPolicy, TenantQuota};
+use rust_mvc_api::views::ApiError;
+
+#[ntex::test]
+async fn cross_tenant_project_reads_are_not_found() {
+    let projects = services(QuotaPolicy::default()).projects;
+    let acme = admin("acme");
+    let globex = admin("globex");
+
+    let created = projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+
+    // Even an admin of another tenant cannot tell the project exists
+    let result = projects.get_project(&globex, created.id).await;
+    assert!(matches!(result, Err(ApiError::NotFound { .. })));
+
+    let result = projects.delete_project(&globex, created.id).await;
+    assert!(matches!(result, Err(ApiError::NotFound { .. })));
+
+    assert!(projects
+        .list_projects(&globex, false)
+        .await
+        .unwrap()
+        .is_empty());
+    assert_eq!(projects.list_projects(&acme, false).await.unwrap().len(), 1);
+}
+
+#[ntex::test]
+async fn cross_tenant_task_reads_are_not_found() {
+    let TestServices {
+        projects, tasks, ..
+    } = services(QuotaPolicy::default());
+    let acme = admin("acme");
+    let globex = admin("globex");
+
+    let created = projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    let fuel = tasks
+        .create_task(&acme, task(created.id, "Fuel"))
+        .await
+        .unwrap();
+
+    let result = tasks.get_task(&globex, fuel.id).await;
+    assert!(matches!(result, Err(ApiError::NotFound { .. })));
+
+    let result = tasks.list_tasks_by_project(&globex, created.id).await;
+    assert!(matches!(result, Err(ApiError::NotFound { .. })));
+
+    // Tasks cannot be attached to another tenant's project either
+    let result = tasks
+        .create_task(&globex, task(created.id, "Sabotage"))
+        .await;
+    assert!(matches!(result, Err(ApiError::NotFound { .. })));
+
+    assert!(tasks.list_tasks(&globex).await.unwrap().is_empty());
+}
+
+#[ntex::test]
+async fn tenant_quotas_limit_projects_and_tasks() {
+    let quotas = QuotaPolicy::new(TenantQuota {
+        max_projects: Some(1),
+        max_tasks: None,
+    })
+    .with_override(
+        "globex",
+        TenantQuota {
+            max_projects: Some(2),
+            max_tasks: Some(1),
+        },
+    );
+    let TestServices {
+        projects, tasks, ..
+    } = services(quotas);
+    let acme = admin("acme");
+    let globex = admin("globex");
+
+    projects
+        .create_project(&acme, project("One"))
+        .await
+        .unwrap();
+    let result = projects.create_project(&acme, project("Two")).await;
+    assert!(matches!(result, Err(ApiError::Conflict { .. })));
+
+    // Quotas are counted per tenant
+    let created = projects
+        .create_project(&globex, project("One"))
+        .await
+        .unwrap();
+    projects
+        .create_project(&globex, project("Two"))
+        .await
+        .unwrap();
+
+    tasks
+        .create_task(&globex, task(created.id, "First"))
+        .await
+        .unwrap();
+    let result = tasks.create_task(&globex, task(created.id, "Second")).await;
+    assert!(matches!(result, Err(ApiError::Conflict { .. })));
+}
+
+#[test]
+fn concurrent_creates_stay_within_quota() {
+    let TestServices {
+        projects, tasks, ..
+    } = services(QuotaPolicy::new(TenantQuota {
+        max_projects: Some(3),
+        max_tasks: Some(5),
+    }));
+    let acme = admin("acme");
+
+    // Every create races the others between its checks and its insert
+    let created = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..16)
+            .map(|i| {
+                let (projects, acme) = (&projects, &acme);
+                scope.spawn(move || {
+                    block_on(projects.create_project(acme, project(&format!("P{}", i))))
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .filter_map(|handle| handle.join().unwrap().ok())
+            .collect::<Vec<_>>()
+    });
+    assert_eq!(created.len(), 3);
+
+    let project_id = created[0].id;
+    let results = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..16)
+            .map(|i| {
+                let (tasks, acme) = (&tasks, &acme);
+                scope.spawn(move || {
+                    block_on(tasks.create_task(acme, task(project_id, &format!("T{}", i))))
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 5);
+    assert!(results
+        .iter()
+        .filter_map(|result| result.as_ref().err())
+        .all(|err| matches!(err, ApiError::Conflict { .. })));
+}
+
+#[ntex::test]
+async fn tenant_header_scopes_http_requests() {
+    let TestServices {
+        projects, tasks, ..
+    } = services(QuotaPolicy::default());
+    let app = test::init_service(
+        App::new()
+            .state(projects)
+            .state(tasks)
+            .wrap(JwtAuth::new(None))
+            .configure(configure_routes),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/projects")
+        .header("X-Tenant-Id", "acme")
+        .set_json(&serde_json::json!({ "name": "Rockets" }))
+        .to_request();
+    let body: serde_json::Value = test::read_response_json(&app, req).await;
+    let id = body["data"]["id"].as_str().unwrap().to_string();
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/projects/{}", id))
+        .header("X-Tenant-Id", "globex")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/projects/{}", id))
+        .header("X-Tenant-Id", "acme")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+}
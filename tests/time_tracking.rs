@@ -0,0 +1,236 @@
+mod common;
+
+use chrono::{Duration, TimeZone, Utc};
+use common::{admin, admin_bearer, jwt_verifier, member, project, services, task, TestServices};
+use ntex::web::{test, App};
+use rust_mvc_api::middleware::JwtAuth;
+use rust_mvc_api::models::{
+    ProjectMemberUpdate, ProjectRole, TimeEntryCreate, TimeReportGroup, TimeReportQuery, TimerStart,
+};
+use rust_mvc_api::routes::configure_routes;
+use rust_mvc_api::services::QuotaPolicy;
+use rust_mvc_api::views::ApiError;
+use uuid::Uuid;
+
+fn booking(task_id: Uuid, started_at: chrono::DateTime<Utc>, minutes: u32) -> TimeEntryCreate {
+    TimeEntryCreate {
+        task_id,
+        started_at: Some(started_at),
+        ended_at: None,
+        duration_minutes: Some(minutes),
+        note: None,
+    }
+}
+
+#[ntex::test]
+async fn timers_do_not_overlap_per_user() {
+    let services = services(QuotaPolicy::default());
+    let alice = member("acme", "alice");
+    let bob = member("acme", "bob");
+    let rockets = services
+        .projects
+        .create_project(&alice, project("Rockets"))
+        .await
+        .unwrap();
+    services
+        .projects
+        .set_member(
+            &alice,
+            rockets.id,
+            "bob".to_string(),
+            ProjectMemberUpdate {
+                role: ProjectRole::Editor,
+            },
+        )
+        .await
+        .unwrap();
+    let fuel = services
+        .tasks
+        .create_task(&alice, task(rockets.id, "Fuel"))
+        .await
+        .unwrap();
+    let start = |task_id| TimerStart {
+        task_id,
+        note: None,
+    };
+
+    let timer = services
+        .time
+        .start_timer(&alice, start(fuel.id))
+        .await
+        .unwrap();
+    assert!(timer.is_running());
+    let result = services.time.start_timer(&alice, start(fuel.id)).await;
+    assert!(matches!(result, Err(ApiError::Conflict { .. })));
+    // Other users keep their own timers.
+    services
+        .time
+        .start_timer(&bob, start(fuel.id))
+        .await
+        .unwrap();
+
+    let stopped = services.time.stop_timer(&alice).await.unwrap();
+    assert_eq!(stopped.id, timer.id);
+    assert!(!stopped.is_running());
+    assert!(services.time.current_timer(&alice).await.unwrap().is_none());
+    let result = services.time.stop_timer(&alice).await;
+    assert!(matches!(result, Err(ApiError::NotFound { .. })));
+    services
+        .time
+        .start_timer(&alice, start(fuel.id))
+        .await
+        .unwrap();
+
+    let result = services
+        .time
+        .start_timer(&member("acme", "mallory"), start(fuel.id))
+        .await;
+    assert!(matches!(result, Err(ApiError::Forbidden { .. })));
+}
+
+#[ntex::test]
+async fn entries_need_a_valid_span() {
+    let services = services(QuotaPolicy::default());
+    let acme = admin("acme");
+    let rockets = services
+        .projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    let fuel = services
+        .tasks
+        .create_task(&acme, task(rockets.id, "Fuel"))
+        .await
+        .unwrap();
+    let now = Utc::now();
+
+    let entry = services
+        .time
+        .create_entry(&acme, booking(fuel.id, now - Duration::hours(3), 90))
+        .await
+        .unwrap();
+    assert_eq!(
+        entry.ended_at,
+        Some(now - Duration::hours(3) + Duration::minutes(90))
+    );
+
+    let invalid = [
+        TimeEntryCreate {
+            ended_at: Some(now - Duration::hours(4)),
+            duration_minutes: None,
+            ..booking(fuel.id, now - Duration::hours(3), 0)
+        },
+        booking(fuel.id, now - Duration::minutes(10), 60),
+        booking(fuel.id, now - Duration::days(3), 25 * 60),
+        TimeEntryCreate {
+            started_at: None,
+            duration_minutes: None,
+            ..booking(fuel.id, now, 0)
+        },
+    ];
+    for create in invalid {
+        let result = services.time.create_entry(&acme, create).await;
+        assert!(matches!(result, Err(ApiError::ValidationError { .. })));
+    }
+}
+
+#[ntex::test]
+async fn reports_total_hours_within_the_period() {
+    let TestServices {
+        projects,
+        tasks,
+        time,
+        ..
+    } = services(QuotaPolicy::default());
+    let acme = admin("acme");
+    let rockets = projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    let rovers = projects
+        .create_project(&acme, project("Rovers, \"small\""))
+        .await
+        .unwrap();
+    let fuel = tasks
+        .create_task(&acme, task(rockets.id, "Fuel"))
+        .await
+        .unwrap();
+    let wheels = tasks
+        .create_task(&acme, task(rovers.id, "Wheels"))
+        .await
+        .unwrap();
+
+    let day = Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+    time.create_entry(&acme, booking(fuel.id, day + Duration::hours(9), 120))
+        .await
+        .unwrap();
+    // Half of this entry falls before the period.
+    time.create_entry(&acme, booking(fuel.id, day - Duration::hours(1), 120))
+        .await
+        .unwrap();
+    time.create_entry(&acme, booking(wheels.id, day + Duration::hours(10), 30))
+        .await
+        .unwrap();
+    time.create_entry(&acme, booking(wheels.id, day + Duration::days(2), 60))
+        .await
+        .unwrap();
+
+    let report = time
+        .report(
+            &acme,
+            &TimeReportQuery {
+                from: Some(day),
+                to: Some(day + Duration::days(1)),
+                ..TimeReportQuery::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(report.total_hours, 3.5);
+    let rows: Vec<_> = report
+        .rows
+        .iter()
+        .map(|row| (row.name.as_str(), row.hours, row.entries))
+        .collect();
+    assert_eq!(rows, [("Rockets", 3.0, 2), ("Rovers, \"small\"", 0.5, 1)]);
+
+    let by_user = time
+        .report(
+            &acme,
+            &TimeReportQuery {
+                group_by: TimeReportGroup::User,
+                ..TimeReportQuery::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(by_user.rows.len(), 1);
+    assert_eq!(by_user.rows[0].key, acme.subject);
+    assert_eq!(by_user.rows[0].hours, 5.5);
+
+    let app = test::init_service(
+        App::new()
+            .state(time)
+            .wrap(JwtAuth::new(Some(jwt_verifier())))
+            .configure(configure_routes),
+    )
+    .await;
+    let req = test::TestRequest::get()
+        .uri("/api/v1/time/report.csv?from=2024-03-01T00:00:00Z&to=2024-03-02T00:00:00Z")
+        .header("Authorization", admin_bearer("acme"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "text/csv; charset=utf-8"
+    );
+    let body = test::read_body(resp).await;
+    let csv = String::from_utf8(body.to_vec()).unwrap();
+    assert_eq!(
+        csv,
+        format!(
+            "key,name,hours,entries\r\n{},Rockets,3.00,2\r\n{},\"Rovers, \"\"small\"\"\",0.50,1\r\n",
+            rockets.id, rovers.id
+        )
+    );
+}
@@ -0,0 +1,255 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use common::{admin, member, project, services, task};
+use rust_mvc_api::models::{EventKind, ProjectMemberUpdate, ProjectRole};
+use rust_mvc_api::services::{QuotaPolicy, TenantQuota};
+use rust_mvc_api::views::ApiError;
+
+#[ntex::test]
+async fn restoring_a_project_brings_back_its_cascaded_tasks() {
+    let services = services(QuotaPolicy::default());
+    let acme = admin("acme");
+    let mut events = services.events.subscribe("test");
+
+    let rockets = services
+        .projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    let fuel = services
+        .tasks
+        .create_task(&acme, task(rockets.id, "Fuel"))
+        .await
+        .unwrap();
+    let paint = services
+        .tasks
+        .create_task(&acme, task(rockets.id, "Paint"))
+        .await
+        .unwrap();
+
+    // Paint goes first on its own, then the project takes fuel with it.
+    services.tasks.delete_task(&acme, paint.id).await.unwrap();
+    services
+        .projects
+        .delete_project(&acme, rockets.id)
+        .await
+        .unwrap();
+
+    let result = services.projects.get_project(&acme, rockets.id).await;
+    assert!(matches!(result, Err(ApiError::NotFound { .. })));
+    assert!(services.tasks.list_tasks(&acme).await.unwrap().is_empty());
+
+    let trash = services.trash.list_trash(&acme).await.unwrap();
+    assert_eq!(trash.projects.len(), 1);
+    assert!(trash.projects[0].deleted_at.is_some());
+    assert_eq!(trash.tasks.len(), 2);
+
+    let result = services.trash.restore_task(&acme, fuel.id).await;
+    assert!(matches!(result, Err(ApiError::Conflict { .. })));
+
+    let restored = services
+        .trash
+        .restore_project(&acme, rockets.id)
+        .await
+        .unwrap();
+    assert!(restored.deleted_at.is_none());
+    let tasks = services
+        .tasks
+        .list_tasks_by_project(&acme, rockets.id)
+        .await
+        .unwrap();
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].id, fuel.id);
+
+    // The separately deleted task stays in the trash until restored itself.
+    let trash = services.trash.list_trash(&acme).await.unwrap();
+    assert!(trash.projects.is_empty());
+    assert_eq!(trash.tasks.len(), 1);
+    assert_eq!(trash.tasks[0].id, paint.id);
+    services.trash.restore_task(&acme, paint.id).await.unwrap();
+    services.tasks.get_task(&acme, paint.id).await.unwrap();
+
+    let kinds: Vec<EventKind> = std::iter::from_fn(|| events.try_recv().ok())
+        .map(|envelope| envelope.event.kind())
+        .collect();
+    assert_eq!(
+        &kinds[kinds.len() - 2..],
+        [EventKind::ProjectRestored, EventKind::TaskRestored]
+    );
+}
+
+#[ntex::test]
+async fn restoring_requires_ownership_and_quota() {
+    let services = services(QuotaPolicy::new(TenantQuota {
+        max_projects: Some(1),
+        max_tasks: None,
+    }));
+    let alice = member("acme", "alice");
+    let bob = member("acme", "bob");
+
+    let rockets = services
+        .projects
+        .create_project(&alice, project("Rockets"))
+        .await
+        .unwrap();
+    services
+        .projects
+        .set_member(
+            &alice,
+            rockets.id,
+            "bob".to_string(),
+            ProjectMemberUpdate {
+                role: ProjectRole::Editor,
+            },
+        )
+        .await
+        .unwrap();
+    services
+        .projects
+        .delete_project(&alice, rockets.id)
+        .await
+        .unwrap();
+
+    // Memberships survive the deletion, so both still see the project in the trash.
+    assert_eq!(
+        services
+            .trash
+            .list_trash(&bob)
+            .await
+            .unwrap()
+            .projects
+            .len(),
+        1
+    );
+    let outsider = member("acme", "carol");
+    assert!(services
+        .trash
+        .list_trash(&outsider)
+        .await
+        .unwrap()
+        .projects
+        .is_empty());
+
+    let result = services.trash.restore_project(&bob, rockets.id).await;
+    assert!(matches!(result, Err(ApiError::Forbidden { .. })));
+
+    // The freed quota slot was taken in the meantime.
+    let rovers = services
+        .projects
+        .create_project(&alice, project("Rovers"))
+        .await
+        .unwrap();
+    let result = services.trash.restore_project(&alice, rockets.id).await;
+    assert!(matches!(result, Err(ApiError::Conflict { .. })));
+
+    services
+        .projects
+        .delete_project(&alice, rovers.id)
+        .await
+        .unwrap();
+    services
+        .trash
+        .restore_project(&alice, rockets.id)
+        .await
+        .unwrap();
+    services
+        .projects
+        .get_project(&bob, rockets.id)
+        .await
+        .unwrap();
+}
+
+#[ntex::test]
+async fn a_project_over_the_task_quota_stays_in_the_trash_with_its_tasks() {
+    let services = services(QuotaPolicy::new(TenantQuota {
+        max_projects: None,
+        max_tasks: Some(2),
+    }));
+    let acme = admin("acme");
+
+    let rockets = services
+        .projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    for title in ["Fuel", "Paint"] {
+        services
+            .tasks
+            .create_task(&acme, task(rockets.id, title))
+            .await
+            .unwrap();
+    }
+    services
+        .projects
+        .delete_project(&acme, rockets.id)
+        .await
+        .unwrap();
+
+    let rovers = services
+        .projects
+        .create_project(&acme, project("Rovers"))
+        .await
+        .unwrap();
+    services
+        .tasks
+        .create_task(&acme, task(rovers.id, "Wheels"))
+        .await
+        .unwrap();
+
+    let result = services.trash.restore_project(&acme, rockets.id).await;
+    assert!(matches!(result, Err(ApiError::Conflict { .. })));
+    let trash = services.trash.list_trash(&acme).await.unwrap();
+    assert_eq!(trash.projects.len(), 1);
+    assert_eq!(trash.tasks.len(), 2);
+    let result = services.projects.get_project(&acme, rockets.id).await;
+    assert!(matches!(result, Err(ApiError::NotFound { .. })));
+}
+
+#[ntex::test]
+async fn purged_items_are_gone_for_good() {
+    let services = services(QuotaPolicy::default());
+    let acme = admin("acme");
+
+    let rockets = services
+        .projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    let fuel = services
+        .tasks
+        .create_task(&acme, task(rockets.id, "Fuel"))
+        .await
+        .unwrap();
+    services.tasks.delete_task(&acme, fuel.id).await.unwrap();
+
+    // Only trashed items can be purged.
+    let result = services.trash.purge_project(&acme, rockets.id).await;
+    assert!(matches!(result, Err(ApiError::NotFound { .. })));
+
+    services.trash.purge_task(&acme, fuel.id).await.unwrap();
+    let result = services.trash.restore_task(&acme, fuel.id).await;
+    assert!(matches!(result, Err(ApiError::NotFound { .. })));
+
+    services
+        .tasks
+        .create_task(&acme, task(rockets.id, "Paint"))
+        .await
+        .unwrap();
+    services
+        .projects
+        .delete_project(&acme, rockets.id)
+        .await
+        .unwrap();
+
+    // Nothing is old enough yet; a week later the project and its task go.
+    assert_eq!(services.trash.purge_expired(Utc::now()).unwrap(), 0);
+    let later = Utc::now() + Duration::days(8);
+    assert_eq!(services.trash.purge_expired(later).unwrap(), 2);
+
+    let trash = services.trash.list_trash(&acme).await.unwrap();
+    assert!(trash.projects.is_empty());
+    assert!(trash.tasks.is_empty());
+    let result = services.trash.restore_project(&acme, rockets.id).await;
+    assert!(matches!(result, Err(ApiError::NotFound { .. })));
+}
@@ -0,0 +1,596 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use common::{admin, project, services, task, TestServices};
+use ntex::time::{sleep, Millis};
+use ntex::util::Bytes;
+use ntex::web::types::State;
+use ntex::web::{self, test, App, HttpRequest, HttpResponse};
+use rust_mvc_api::config::Config;
+use rust_mvc_api::models::{
+    DeliveryStatus, EventEnvelope, EventKind, Principal, WebhookCreate, WebhookUpdate,
+};
+use rust_mvc_api::services::webhook_service::{
+    sign, DELIVERY_ID_HEADER, EVENT_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER,
+};
+use rust_mvc_api::services::{QuotaPolicy, RetryPolicy};
+use rust_mvc_api::views::ApiError;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::mpsc::Receiver;
+use uuid::Uuid;
+
+const SECRET: &str = "0123456789abcdef0123456789abcdef";
+
+#[derive(Debug)]
+struct Received {
+    delivery_id: String,
+    event: String,
+    timestamp: String,
+    signature: String,
+    body: Bytes,
+}
+
+/// Stand-in receiver: records every request and answers with queued status codes, 200 once
+/// the queue is empty.
+#[derive(Default)]
+struct Inbox {
+    received: Mutex<Vec<Received>>,
+    statuses: Mutex<VecDeque<u16>>,
+}
+
+impl Inbox {
+    fn respond_with(&self, statuses: &[u16]) {
+        self.statuses.lock().unwrap().extend(statuses);
+    }
+
+    fn received(&self) -> Vec<Received> {
+        std::mem::take(&mut *self.received.lock().unwrap())
+    }
+}
+
+async fn hook(req: HttpRequest, body: Bytes, inbox: State<Arc<Inbox>>) -> HttpResponse {
+    let header = |name: &str| {
+        req.headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string()
+    };
+    inbox.received.lock().unwrap().push(Received {
+        delivery_id: header(DELIVERY_ID_HEADER),
+        event: header(EVENT_HEADER),
+        timestamp: header(TIMESTAMP_HEADER),
+        signature: header(SIGNATURE_HEADER),
+        body,
+    });
+
+    let status = inbox.statuses.lock().unwrap().pop_front().unwrap_or(200);
+    HttpResponse::build(ntex::http::StatusCode::from_u16(status).unwrap()).finish()
+}
+
+/// Answers only after the test services' one-second delivery timeout.
+async fn slow_hook() -> HttpResponse {
+    sleep(Millis(3_000)).await;
+    HttpResponse::Ok().finish()
+}
+
+fn receiver() -> (test::TestServer, Arc<Inbox>) {
+    let inbox = Arc::new(Inbox::default());
+    let state = inbox.clone();
+    let server = test::server(move || {
+        App::new()
+            .state(state.clone())
+            .route("/hook", web::post().to(hook))
+            .route("/slow", web::post().to(slow_hook))
+    });
+    (server, inbox)
+}
+
+fn webhook(url: String, event_types: Vec<EventKind>, project_id: Option<Uuid>) -> WebhookCreate {
+    WebhookCreate {
+        url,
+        event_types,
+        project_id,
+        secret: Some(SECRET.to_string()),
+    }
+}
+
+/// Hands every published event to the webhook service, as its event bus subscription would.
+fn enqueue_published(services: &TestServices, events: &mut Receiver<Arc<EventEnvelope>>) -> usize {
+    std::iter::from_fn(|| events.try_recv().ok())
+        .map(|envelope| services.webhooks.enqueue(&envelope).unwrap())
+        .sum()
+}
+
+#[ntex::test]
+async fn deliveries_are_signed_and_logged() {
+    let services = services(QuotaPolicy::default());
+    let mut events = services.events.subscribe("test");
+    let (server, inbox) = receiver();
+    let acme = admin("acme");
+
+    let created = services
+        .webhooks
+        .create_webhook(&acme, webhook(server.url("/hook"), vec![], None))
+        .await
+        .unwrap();
+    assert_eq!(created.secret, SECRET);
+
+    services
+        .projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    assert_eq!(enqueue_published(&services, &mut events), 1);
+    assert_eq!(services.webhooks.deliver_due(Utc::now()).await.unwrap(), 1);
+
+    let received = inbox.received();
+    assert_eq!(received.len(), 1);
+    let request = &received[0];
+    assert_eq!(request.event, "project_created");
+    assert_eq!(
+        request.signature,
+        sign(SECRET, &request.timestamp, &request.body)
+    );
+    let payload: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+    assert_eq!(payload["tenant_id"], "acme");
+    assert_eq!(payload["event"]["type"], "project_created");
+    assert_eq!(payload["event"]["project"]["name"], "Rockets");
+
+    let log = services
+        .webhooks
+        .list_deliveries(&acme, created.webhook.id, None)
+        .await
+        .unwrap();
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].id.to_string(), request.delivery_id);
+    assert_eq!(log[0].status, DeliveryStatus::Delivered);
+    assert_eq!(log[0].attempts, 1);
+    assert_eq!(log[0].last_status_code, Some(200));
+
+    // Delivered entries are not sent again.
+    assert_eq!(services.webhooks.deliver_due(Utc::now()).await.unwrap(), 0);
+}
+
+#[ntex::test]
+async fn failed_deliveries_back_off_then_dead_letter() {
+    let services = services(QuotaPolicy::default());
+    let mut events = services.events.subscribe("test");
+    let (server, inbox) = receiver();
+    inbox.respond_with(&[500, 503, 500]);
+    let acme = admin("acme");
+
+    let created = services
+        .webhooks
+        .create_webhook(&acme, webhook(server.url("/hook"), vec![], None))
+        .await
+        .unwrap();
+    let id = created.webhook.id;
+    services
+        .projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    enqueue_published(&services, &mut events);
+
+    let start = Utc::now();
+    assert_eq!(services.webhooks.deliver_due(start).await.unwrap(), 1);
+    let delivery = services
+        .webhooks
+        .list_deliveries(&acme, id, None)
+        .await
+        .unwrap()
+        .remove(0);
+    assert_eq!(delivery.status, DeliveryStatus::Pending);
+    assert_eq!(delivery.last_status_code, Some(500));
+    assert_eq!(
+        delivery.next_attempt_at,
+        Some(start + Duration::seconds(10))
+    );
+
+    // Nothing is due until the backoff has elapsed, and the delay doubles per attempt.
+    assert_eq!(services.webhooks.deliver_due(start).await.unwrap(), 0);
+    let second = start + Duration::seconds(10);
+    assert_eq!(services.webhooks.deliver_due(second).await.unwrap(), 1);
+    let delivery = services
+        .webhooks
+        .list_deliveries(&acme, id, None)
+        .await
+        .unwrap()
+        .remove(0);
+    assert_eq!(delivery.attempts, 2);
+    assert_eq!(
+        delivery.next_attempt_at,
+        Some(second + Duration::seconds(20))
+    );
+
+    let third = second + Duration::seconds(20);
+    assert_eq!(services.webhooks.deliver_due(third).await.unwrap(), 1);
+    let dead = services
+        .webhooks
+        .list_deliveries(&acme, id, Some(DeliveryStatus::DeadLettered))
+        .await
+        .unwrap();
+    assert_eq!(dead.len(), 1);
+    assert_eq!(dead[0].attempts, 3);
+    assert_eq!(dead[0].next_attempt_at, None);
+    assert_eq!(inbox.received().len(), 3);
+
+    // A dead letter can be queued again once the receiver is healthy.
+    let requeued = services
+        .webhooks
+        .retry_delivery(&acme, id, dead[0].id)
+        .await
+        .unwrap();
+    assert_eq!(requeued.status, DeliveryStatus::Pending);
+    assert_eq!(services.webhooks.deliver_due(Utc::now()).await.unwrap(), 1);
+    let delivered = services
+        .webhooks
+        .list_deliveries(&acme, id, Some(DeliveryStatus::Delivered))
+        .await
+        .unwrap();
+    assert_eq!(delivered.len(), 1);
+    assert!(matches!(
+        services
+            .webhooks
+            .retry_delivery(&acme, id, dead[0].id)
+            .await,
+        Err(ApiError::Conflict { .. })
+    ));
+}
+
+#[ntex::test]
+async fn unreachable_receivers_are_retried() {
+    let services = services(QuotaPolicy::default());
+    let mut events = services.events.subscribe("test");
+    let acme = admin("acme");
+
+    let created = services
+        .webhooks
+        .create_webhook(
+            &acme,
+            webhook("http://127.0.0.1:1/hook".to_string(), vec![], None),
+        )
+        .await
+        .unwrap();
+    services
+        .projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    enqueue_published(&services, &mut events);
+
+    assert_eq!(services.webhooks.deliver_due(Utc::now()).await.unwrap(), 1);
+    let delivery = services
+        .webhooks
+        .list_deliveries(&acme, created.webhook.id, None)
+        .await
+        .unwrap()
+        .remove(0);
+    assert_eq!(delivery.status, DeliveryStatus::Pending);
+    assert_eq!(delivery.last_status_code, None);
+    assert!(delivery.last_error.is_some());
+}
+
+#[ntex::test]
+async fn slow_receivers_time_out_without_holding_up_others() {
+    let services = services(QuotaPolicy::default());
+    let mut events = services.events.subscribe("test");
+    let (server, inbox) = receiver();
+    let acme = admin("acme");
+
+    let mut slow = Vec::new();
+    for _ in 0..3 {
+        let created = services
+            .webhooks
+            .create_webhook(&acme, webhook(server.url("/slow"), vec![], None))
+            .await
+            .unwrap();
+        slow.push(created.webhook.id);
+    }
+    let fast = services
+        .webhooks
+        .create_webhook(&acme, webhook(server.url("/hook"), vec![], None))
+        .await
+        .unwrap();
+    services
+        .projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    assert_eq!(enqueue_published(&services, &mut events), 4);
+
+    // Sent one after another, the slow receivers alone would take three timeouts
+    let started = Instant::now();
+    assert_eq!(services.webhooks.deliver_due(Utc::now()).await.unwrap(), 4);
+    assert!(started.elapsed() < std::time::Duration::from_millis(2_500));
+
+    assert_eq!(inbox.received().len(), 1);
+    let delivered = services
+        .webhooks
+        .list_deliveries(&acme, fast.webhook.id, None)
+        .await
+        .unwrap();
+    assert_eq!(delivered[0].status, DeliveryStatus::Delivered);
+    for id in slow {
+        let delivery = services
+            .webhooks
+            .list_deliveries(&acme, id, None)
+            .await
+            .unwrap()
+            .remove(0);
+        assert_eq!(delivery.status, DeliveryStatus::Pending);
+        assert_eq!(delivery.attempts, 1);
+        assert!(delivery.last_error.is_some());
+    }
+}
+
+#[ntex::test]
+async fn deleting_a_webhook_mid_attempt_drops_its_delivery() {
+    let services = services(QuotaPolicy::default());
+    let mut events = services.events.subscribe("test");
+    let (server, _inbox) = receiver();
+    let acme = admin("acme");
+
+    let created = services
+        .webhooks
+        .create_webhook(&acme, webhook(server.url("/slow"), vec![], None))
+        .await
+        .unwrap();
+    services
+        .projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    assert_eq!(enqueue_published(&services, &mut events), 1);
+
+    let (attempted, deleted) = futures::join!(services.webhooks.deliver_due(Utc::now()), async {
+        sleep(Millis(200)).await;
+        services
+            .webhooks
+            .delete_webhook(&acme, created.webhook.id)
+            .await
+    });
+    assert_eq!(attempted.unwrap(), 1);
+    deleted.unwrap();
+
+    // The failed attempt was not written back as a retry for the deleted webhook.
+    let later = Utc::now() + Duration::days(1);
+    assert_eq!(services.webhooks.deliver_due(later).await.unwrap(), 0);
+}
+
+#[ntex::test]
+async fn finished_deliveries_are_pruned_after_the_retention_period() {
+    let services = services(QuotaPolicy::default());
+    let mut events = services.events.subscribe("test");
+    let (server, _inbox) = receiver();
+    let acme = admin("acme");
+
+    let delivered = services
+        .webhooks
+        .create_webhook(&acme, webhook(server.url("/hook"), vec![], None))
+        .await
+        .unwrap();
+    let paused = services
+        .webhooks
+        .create_webhook(&acme, webhook(server.url("/hook"), vec![], None))
+        .await
+        .unwrap();
+    services
+        .projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    assert_eq!(enqueue_published(&services, &mut events), 2);
+
+    // Pausing one webhook keeps its delivery pending.
+    services
+        .webhooks
+        .update_webhook(
+            &acme,
+            paused.webhook.id,
+            WebhookUpdate {
+                url: None,
+                event_types: None,
+                project_id: None,
+                secret: None,
+                active: Some(false),
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(services.webhooks.deliver_due(Utc::now()).await.unwrap(), 2);
+
+    assert_eq!(services.webhooks.prune_deliveries(Utc::now()).unwrap(), 0);
+    let later = Utc::now() + Duration::days(8);
+    assert_eq!(services.webhooks.prune_deliveries(later).unwrap(), 1);
+    let log = |id| services.webhooks.list_deliveries(&acme, id, None);
+    assert!(log(delivered.webhook.id).await.unwrap().is_empty());
+
+    // Pending retries are kept however old they are.
+    let pending = log(paused.webhook.id).await.unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].status, DeliveryStatus::Pending);
+}
+
+#[test]
+fn configured_delays_are_capped() {
+    let policy = RetryPolicy::from_config(&Config {
+        webhook_retry_base_secs: Some(u64::MAX),
+        webhook_retry_max_secs: Some(u64::MAX),
+        webhook_timeout_secs: Some(u64::MAX),
+        ..Config::default()
+    });
+    assert_eq!(policy.max_delay, Duration::days(30));
+    assert_eq!(policy.backoff(1), Duration::days(30));
+    assert_eq!(policy.backoff(u32::MAX), Duration::days(30));
+    assert_eq!(policy.timeout.as_secs(), 30 * 24 * 60 * 60);
+
+    let policy = RetryPolicy::from_config(&Config {
+        webhook_retry_base_secs: Some(5),
+        webhook_retry_max_secs: Some(60),
+        ..Config::default()
+    });
+    assert_eq!(policy.backoff(1), Duration::seconds(5));
+    assert_eq!(policy.backoff(3), Duration::seconds(20));
+    assert_eq!(policy.backoff(10), Duration::seconds(60));
+}
+
+#[ntex::test]
+async fn subscriptions_filter_by_event_type_project_and_tenant() {
+    let services = services(QuotaPolicy::default());
+    let mut events = services.events.subscribe("test");
+    let (server, inbox) = receiver();
+    let acme = admin("acme");
+    let globex = admin("globex");
+
+    let rockets = services
+        .projects
+        .create_project(&acme, project("Rockets"))
+        .await
+        .unwrap();
+    let boosters = services
+        .projects
+        .create_project(&acme, project("Boosters"))
+        .await
+        .unwrap();
+    let created = services
+        .webhooks
+        .create_webhook(
+            &acme,
+            webhook(
+                server.url("/hook"),
+                vec![EventKind::TaskCreated],
+                Some(rockets.id),
+            ),
+        )
+        .await
+        .unwrap();
+    enqueue_published(&services, &mut events);
+
+    services
+        .tasks
+        .create_task(&acme, task(rockets.id, "Fuel"))
+        .await
+        .unwrap();
+    services
+        .tasks
+        .create_task(&acme, task(boosters.id, "Paint"))
+        .await
+        .unwrap();
+    let other = services
+        .projects
+        .create_project(&globex, project("Rockets"))
+        .await
+        .unwrap();
+    services
+        .tasks
+        .create_task(&globex, task(other.id, "Sabotage"))
+        .await
+        .unwrap();
+
+    assert_eq!(enqueue_published(&services, &mut events), 1);
+    services.webhooks.deliver_due(Utc::now()).await.unwrap();
+    let received = inbox.received();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].event, "task_created");
+
+    // Disabled webhooks stop receiving events.
+    services
+        .webhooks
+        .update_webhook(
+            &acme,
+            created.webhook.id,
+            WebhookUpdate {
+                url: None,
+                event_types: None,
+                project_id: None,
+                secret: None,
+                active: Some(false),
+            },
+        )
+        .await
+        .unwrap();
+    services
+        .tasks
+        .create_task(&acme, task(rockets.id, "Launch"))
+        .await
+        .unwrap();
+    assert_eq!(enqueue_published(&services, &mut events), 0);
+}
+
+#[ntex::test]
+async fn webhook_management_is_validated() {
+    let services = services(QuotaPolicy::default());
+    let acme = admin("acme");
+    let viewer = Principal {
+        scopes: vec![],
+        ..admin("acme")
+    };
+
+    assert!(matches!(
+        services
+            .webhooks
+            .create_webhook(
+                &viewer,
+                webhook("http://localhost/hook".to_string(), vec![], None)
+            )
+            .await,
+        Err(ApiError::Forbidden { .. })
+    ));
+    assert!(matches!(
+        services
+            .webhooks
+            .create_webhook(
+                &acme,
+                webhook("ftp://localhost/hook".to_string(), vec![], None)
+            )
+            .await,
+        Err(ApiError::ValidationError { .. })
+    ));
+    assert!(matches!(
+        services
+            .webhooks
+            .create_webhook(
+                &acme,
+                webhook(
+                    "http://localhost/hook".to_string(),
+                    vec![],
+                    Some(Uuid::new_v4())
+                )
+            )
+            .await,
+        Err(ApiError::NotFound { .. })
+    ));
+
+    let generated = services
+        .webhooks
+        .create_webhook(
+            &acme,
+            WebhookCreate {
+                secret: None,
+                ..webhook("https://example.com/hook".to_string(), vec![], None)
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(generated.secret.len(), 64);
+    let listed =
+        serde_json::to_value(services.webhooks.list_webhooks(&acme).await.unwrap()).unwrap();
+    assert!(listed[0].get("secret").is_none());
+
+    services
+        .webhooks
+        .delete_webhook(&acme, generated.webhook.id)
+        .await
+        .unwrap();
+    assert!(matches!(
+        services
+            .webhooks
+            .list_deliveries(&acme, generated.webhook.id, None)
+            .await,
+        Err(ApiError::NotFound { .. })
+    ));
+}
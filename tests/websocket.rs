@@ -0,0 +1,146 @@
+mod common;
+
+use common::{admin, admin_bearer, jwt_verifier, project, services, task};
+use ntex::time::{timeout, Millis};
+use ntex::util::{ByteString, Bytes};
+use ntex::web::{test, App};
+use ntex::ws::{Frame, Message, WsClient, WsSink};
+use rust_mvc_api::middleware::JwtAuth;
+use rust_mvc_api::models::DEFAULT_TENANT;
+use rust_mvc_api::routes::configure_routes;
+use rust_mvc_api::services::QuotaPolicy;
+use serde_json::{json, Value};
+
+type Frames = ntex::channel::mpsc::Receiver<Result<Frame, ntex::ws::error::WsError<()>>>;
+
+async fn send(sink: &WsSink, message: Value) {
+    sink.send(Message::Text(ByteString::from(message.to_string())))
+        .await
+        .unwrap();
+}
+
+async fn next(frames: &Frames) -> Value {
+    match timeout(Millis(2_000), frames.recv()).await {
+        Ok(Some(Ok(Frame::Text(text)))) => serde_json::from_slice(&text).unwrap(),
+        other => panic!("expected a text frame, got {:?}", other),
+    }
+}
+
+#[ntex::test]
+async fn websocket_subscriptions_and_task_mutations() {
+    let services = services(QuotaPolicy::default());
+    services.stream.start(&services.events);
+    let owner = admin(DEFAULT_TENANT);
+    let rockets = services
+        .projects
+        .create_project(&owner, project("Rockets"))
+        .await
+        .unwrap();
+    let boosters = services
+        .projects
+        .create_project(&owner, project("Boosters"))
+        .await
+        .unwrap();
+
+    let (projects, tasks, stream) = (
+        services.projects.clone(),
+        services.tasks.clone(),
+        services.stream.clone(),
+    );
+    let server = test::server(move || {
+        App::new()
+            .state(projects.clone())
+            .state(tasks.clone())
+            .state(stream.clone())
+            .wrap(JwtAuth::new(Some(jwt_verifier())))
+            .configure(configure_routes)
+    });
+    let connection = WsClient::build(server.url("/api/v1/ws"))
+        .address(server.addr())
+        .header("Authorization", admin_bearer(DEFAULT_TENANT))
+        .finish()
+        .unwrap()
+        .connect()
+        .await
+        .unwrap()
+        .seal();
+    let sink = connection.sink();
+    let frames = connection.receiver();
+
+    send(
+        &sink,
+        json!({ "id": "1", "type": "subscribe", "project_id": rockets.id }),
+    )
+    .await;
+    assert_eq!(
+        next(&frames).await,
+        json!({ "type": "ack", "id": "1", "data": { "project_id": rockets.id } })
+    );
+
+    // Changes to other projects are not sent.
+    services
+        .tasks
+        .create_task(&owner, task(boosters.id, "Paint"))
+        .await
+        .unwrap();
+
+    send(
+        &sink,
+        json!({
+            "id": "2",
+            "type": "create_task",
+            "task": { "project_id": rockets.id, "title": "Fuel" }
+        }),
+    )
+    .await;
+    let mut replies = [next(&frames).await, next(&frames).await];
+    replies.sort_by_key(|frame| frame["type"].as_str().unwrap().to_string());
+    let (ack, event) = (&replies[0], &replies[1]);
+    assert_eq!(ack["id"], "2");
+    assert_eq!(ack["data"]["title"], "Fuel");
+    assert_eq!(event["type"], "event");
+    assert_eq!(event["event"]["event"]["type"], "task_created");
+    assert_eq!(event["event"]["event"]["task"]["id"], ack["data"]["id"]);
+
+    send(
+        &sink,
+        json!({
+            "id": "3",
+            "type": "update_task",
+            "task_id": rockets.id,
+            "changes": { "done": true }
+        }),
+    )
+    .await;
+    let error = next(&frames).await;
+    assert_eq!(error["type"], "error");
+    assert_eq!(error["id"], "3");
+    assert_eq!(error["error"]["code"], "NOT_FOUND");
+
+    send(&sink, json!({ "id": "4", "type": "launch" })).await;
+    let error = next(&frames).await;
+    assert_eq!(error["type"], "error");
+    assert_eq!(error["id"], Value::Null);
+    assert_eq!(error["error"]["code"], "BAD_REQUEST");
+
+    sink.send(Message::Binary(Bytes::from_static(b"\x00")))
+        .await
+        .unwrap();
+    assert_eq!(next(&frames).await["error"]["code"], "BAD_REQUEST");
+
+    send(
+        &sink,
+        json!({ "id": "5", "type": "unsubscribe", "project_id": rockets.id }),
+    )
+    .await;
+    assert_eq!(next(&frames).await["type"], "ack");
+    send(
+        &sink,
+        json!({ "id": "6", "type": "delete_task", "task_id": ack["data"]["id"] }),
+    )
+    .await;
+    assert_eq!(
+        next(&frames).await,
+        json!({ "type": "ack", "id": "6", "data": { "task_id": ack["data"]["id"] } })
+    );
+}